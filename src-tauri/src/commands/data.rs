@@ -0,0 +1,73 @@
+// 数据管理模块 - 数据备份与恢复、数据目录迁移、工作区导入导出
+
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+use crate::commands::{fetch_scheduler, git_stage, project, stats, tasks, toolbox};
+use crate::storage::backup::{self, BackupInfo};
+use crate::storage::relocation::{self, RelocationReport};
+use crate::storage::workspace::{
+    self, ConflictStrategy, ImportMode, WorkspaceExportReport, WorkspaceImportReport,
+};
+
+/// 数据库内容被整体替换后，重新加载各模块的内存缓存
+pub(crate) async fn reload_all_caches(app: &AppHandle) -> Result<(), String> {
+    project::reload_projects()?;
+    stats::reload_stats_cache()?;
+    tasks::reload_runs()?;
+    git_stage::reload_discarded()?;
+    fetch_scheduler::reload_statuses()?;
+    toolbox::forwarder::reload_rules().await?;
+    toolbox::server::reload_servers().await?;
+    toolbox::downloader::reload_tasks().await?;
+    // 内存中的 Netcat 会话不重新加载的话，下次保存会覆盖恢复的数据
+    if let Some(netcat) = app.try_state::<toolbox::netcat::NetcatState>() {
+        netcat.reload_sessions().await?;
+    }
+    Ok(())
+}
+
+/// 获取备份列表（最新的在前）
+#[tauri::command]
+pub fn list_backups() -> Result<Vec<BackupInfo>, String> {
+    backup::list_backups()
+}
+
+/// 立即创建一份备份
+#[tauri::command]
+pub fn create_backup() -> Result<BackupInfo, String> {
+    backup::create_backup(backup::REASON_MANUAL)
+}
+
+/// 从备份恢复数据，返回恢复前自动创建的备份（可用于撤销）
+#[tauri::command]
+pub async fn restore_backup(app: AppHandle, file_name: String) -> Result<BackupInfo, String> {
+    let safety = backup::restore_backup(&file_name)?;
+    reload_all_caches(&app).await?;
+    Ok(safety)
+}
+
+/// 把数据目录迁移到新位置（`target_dir` 为新的数据根目录），完成后需要重启应用
+#[tauri::command]
+pub fn move_data_directory(target_dir: String) -> Result<RelocationReport, String> {
+    relocation::move_data_directory(Path::new(&target_dir))
+}
+
+/// 把整个工作区（项目、设置、工具箱规则、Claude 配置档案）导出为归档文件
+#[tauri::command]
+pub fn export_workspace(path: String) -> Result<WorkspaceExportReport, String> {
+    workspace::export_workspace(Path::new(&path))
+}
+
+/// 从归档导入工作区，`on_conflict` 只在合并模式下生效，默认保留现有数据
+#[tauri::command]
+pub async fn import_workspace(
+    app: AppHandle,
+    path: String,
+    mode: ImportMode,
+    on_conflict: Option<ConflictStrategy>,
+) -> Result<WorkspaceImportReport, String> {
+    let report = workspace::import_workspace(Path::new(&path), mode, on_conflict.unwrap_or_default())?;
+    reload_all_caches(&app).await?;
+    Ok(report)
+}
@@ -0,0 +1,419 @@
+// 项目环境变量 - 每个项目可以有多组命名的环境变量（local、staging、test 等），
+// 可与项目中的 .env 文件互相同步；选中的一组在打开编辑器、终端和运行脚本时注入。
+//
+// 敏感值只在本机数据库和写出的 .env 文件中保存，返回给界面时隐去，日志中只记录变量名。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::project;
+use crate::storage::atomic::write_atomic;
+use crate::storage::{keys, load_setting, paths, save_setting, EnvProfile, EnvVar, ProjectEnv};
+
+/// 变量名包含这些词时，从 .env 文件读入后默认作为敏感值
+const SECRET_KEY_HINTS: &[&str] = &["SECRET", "TOKEN", "PASSWORD", "PASSWD", "PRIVATE", "API_KEY", "ACCESS_KEY", "CREDENTIAL"];
+
+/// 返回给界面的变量，敏感值不包含内容
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvVarView {
+    pub key: String,
+    pub value: Option<String>,
+    pub secret: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvProfileView {
+    pub name: String,
+    pub vars: Vec<EnvVarView>,
+    pub file: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectEnvView {
+    pub active: Option<String>,
+    pub profiles: Vec<EnvProfileView>,
+}
+
+impl From<&ProjectEnv> for ProjectEnvView {
+    fn from(env: &ProjectEnv) -> Self {
+        Self {
+            active: env.active.clone(),
+            profiles: env
+                .profiles
+                .iter()
+                .map(|profile| EnvProfileView {
+                    name: profile.name.clone(),
+                    file: profile.file.clone(),
+                    vars: profile
+                        .vars
+                        .iter()
+                        .map(|var| EnvVarView {
+                            key: var.key.clone(),
+                            value: (!var.secret).then(|| var.value.clone()),
+                            secret: var.secret,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 保存时提交的变量，敏感值不修改时 value 传 None，沿用已保存的值
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvVarInput {
+    pub key: String,
+    pub value: Option<String>,
+    pub secret: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvProfileInput {
+    pub name: String,
+    /// 重命名时填原来的名称
+    pub original_name: Option<String>,
+    pub vars: Vec<EnvVarInput>,
+    pub file: Option<String>,
+}
+
+fn load_all() -> Result<HashMap<String, ProjectEnv>, String> {
+    Ok(load_setting(keys::PROJECT_ENV)?.unwrap_or_default())
+}
+
+fn save_all(all: &HashMap<String, ProjectEnv>) -> Result<(), String> {
+    save_setting(keys::PROJECT_ENV, all).map_err(|e| format!("保存环境变量失败: {}", e))
+}
+
+/// 修改一个项目的环境变量配置并保存
+fn update_env(project_id: &str, apply: impl FnOnce(&mut ProjectEnv) -> Result<(), String>) -> Result<ProjectEnvView, String> {
+    let mut all = load_all()?;
+    let env = all.entry(project_id.to_string()).or_default();
+    apply(env)?;
+    let view = ProjectEnvView::from(&*env);
+    save_all(&all)?;
+    Ok(view)
+}
+
+fn project_dir(project_id: &str) -> Result<String, String> {
+    project::get_projects()?
+        .into_iter()
+        .find(|p| p.id == project_id)
+        .map(|p| p.path)
+        .ok_or_else(|| "项目不存在".to_string())
+}
+
+/// .env 文件只能是项目根目录下以 .env 开头的文件
+fn validate_env_file_name(file: &str) -> Result<(), String> {
+    if !file.starts_with(".env") || file.contains(['/', '\\']) || file.contains("..") {
+        return Err(format!("无效的 .env 文件名: {}", file));
+    }
+    Ok(())
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_uppercase();
+    SECRET_KEY_HINTS.iter().any(|hint| key.contains(hint))
+}
+
+/// 解析 .env 内容：支持注释、export 前缀、单双引号（双引号内处理 \n 等转义）
+fn parse_dotenv(content: &str) -> Vec<(String, String)> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, raw)) = line.split_once('=') else { continue };
+        let key = key.trim();
+        if key.is_empty() {
+            continue;
+        }
+
+        let raw = raw.trim();
+        let value = if let Some(inner) = raw.strip_prefix('"').and_then(|r| r.rfind('"').map(|end| &r[..end])) {
+            unescape_double_quoted(inner)
+        } else if let Some(inner) = raw.strip_prefix('\'').and_then(|r| r.rfind('\'').map(|end| &r[..end])) {
+            inner.to_string()
+        } else {
+            // 未加引号的值，# 之后是行尾注释
+            raw.split(" #").next().unwrap_or("").trim().to_string()
+        };
+
+        match vars.iter_mut().find(|(k, _)| k == key) {
+            Some(existing) => existing.1 = value,
+            None => vars.push((key.to_string(), value)),
+        }
+    }
+    vars
+}
+
+fn unescape_double_quoted(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// 生成 .env 内容，包含空白、引号、# 或换行的值加双引号
+fn format_dotenv(vars: &[EnvVar]) -> String {
+    let mut content = String::new();
+    for var in vars {
+        let needs_quotes = var.value.is_empty()
+            || var.value.contains(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '#' | '\\'));
+        if needs_quotes {
+            let escaped = var.value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\r', "\\r");
+            content.push_str(&format!("{}=\"{}\"\n", var.key, escaped));
+        } else {
+            content.push_str(&format!("{}={}\n", var.key, var.value));
+        }
+    }
+    content
+}
+
+/// 项目当前选中的环境变量（未选中时为空）
+pub(crate) fn env_for_project(project_id: &str) -> Vec<(String, String)> {
+    let all = match load_all() {
+        Ok(all) => all,
+        Err(e) => {
+            log::warn!("读取项目环境变量失败: {}", e);
+            return Vec::new();
+        }
+    };
+    all.get(project_id)
+        .and_then(|env| {
+            let active = env.active.as_ref()?;
+            env.profiles.iter().find(|p| &p.name == active)
+        })
+        .map(|profile| profile.vars.iter().map(|v| (v.key.clone(), v.value.clone())).collect())
+        .unwrap_or_default()
+}
+
+/// 按路径找到项目后返回其选中的环境变量（打开编辑器、终端时只知道路径）
+pub(crate) fn env_for_path(path: &str) -> Vec<(String, String)> {
+    match project::get_projects() {
+        Ok(projects) => projects
+            .iter()
+            .find(|p| paths::same_path(&p.path, path))
+            .map(|p| env_for_project(&p.id))
+            .unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// 删除项目后清理其环境变量（其中可能有敏感值）
+pub(crate) fn forget_projects(project_ids: &[String]) {
+    let result = load_all().and_then(|mut all| {
+        let before = all.len();
+        all.retain(|id, _| !project_ids.contains(id));
+        if all.len() == before {
+            return Ok(());
+        }
+        save_all(&all)
+    });
+    if let Err(e) = result {
+        log::warn!("清理已删除项目的环境变量失败: {}", e);
+    }
+}
+
+#[tauri::command]
+pub fn get_project_env(project_id: String) -> Result<ProjectEnvView, String> {
+    let all = load_all()?;
+    Ok(all.get(&project_id).map(ProjectEnvView::from).unwrap_or(ProjectEnvView {
+        active: None,
+        profiles: Vec::new(),
+    }))
+}
+
+/// 新增或修改一组环境变量
+#[tauri::command]
+pub fn save_env_profile(project_id: String, profile: EnvProfileInput) -> Result<ProjectEnvView, String> {
+    let name = profile.name.trim().to_string();
+    if name.is_empty() {
+        return Err("配置名称不能为空".to_string());
+    }
+    if let Some(file) = &profile.file {
+        validate_env_file_name(file)?;
+    }
+    for (index, var) in profile.vars.iter().enumerate() {
+        if var.key.trim().is_empty() || var.key.contains(['=', '\0']) {
+            return Err(format!("变量名无效: {}", var.key));
+        }
+        if profile.vars[..index].iter().any(|other| other.key == var.key) {
+            return Err(format!("变量名重复: {}", var.key));
+        }
+    }
+
+    update_env(&project_id, |env| {
+        let original = profile.original_name.as_deref().unwrap_or(&name);
+        if original != name && env.profiles.iter().any(|p| p.name == name) {
+            return Err(format!("配置名称已存在: {}", name));
+        }
+        let existing = env.profiles.iter().position(|p| p.name == original);
+        let previous = existing.map(|index| env.profiles[index].vars.clone()).unwrap_or_default();
+
+        let mut vars = Vec::new();
+        for input in profile.vars {
+            let value = match input.value {
+                Some(value) => value,
+                None => previous
+                    .iter()
+                    .find(|v| v.key == input.key)
+                    .map(|v| v.value.clone())
+                    .ok_or_else(|| format!("变量 {} 缺少值", input.key))?,
+            };
+            vars.push(EnvVar { key: input.key, value, secret: input.secret });
+        }
+
+        let updated = EnvProfile { name: name.clone(), vars, file: profile.file };
+        match existing {
+            Some(index) => env.profiles[index] = updated,
+            None => env.profiles.push(updated),
+        }
+        if env.active.as_deref() == Some(original) {
+            env.active = Some(name.clone());
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn delete_env_profile(project_id: String, name: String) -> Result<ProjectEnvView, String> {
+    update_env(&project_id, |env| {
+        env.profiles.retain(|p| p.name != name);
+        if env.active.as_deref() == Some(name.as_str()) {
+            env.active = None;
+        }
+        Ok(())
+    })
+}
+
+/// 选择注入的环境变量配置，传 None 表示不注入
+#[tauri::command]
+pub fn set_active_env(project_id: String, name: Option<String>) -> Result<ProjectEnvView, String> {
+    update_env(&project_id, |env| {
+        if let Some(name) = &name {
+            if !env.profiles.iter().any(|p| &p.name == name) {
+                return Err(format!("配置不存在: {}", name));
+            }
+        }
+        env.active = name;
+        Ok(())
+    })
+}
+
+/// 项目根目录下的 .env 文件
+#[tauri::command]
+pub fn list_env_files(project_id: String) -> Result<Vec<String>, String> {
+    let dir = project_dir(&project_id)?;
+    let entries = fs::read_dir(&dir).map_err(|e| format!("读取项目目录失败: {}", e))?;
+    let mut files: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with(".env"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// 从 .env 文件读入一组环境变量（同名配置整体替换），并与该文件关联
+///
+/// 未指定配置名时按文件名命名：.env.staging -> staging，.env -> default
+#[tauri::command]
+pub fn import_env_file(project_id: String, file: String, profile_name: Option<String>) -> Result<ProjectEnvView, String> {
+    validate_env_file_name(&file)?;
+    let dir = project_dir(&project_id)?;
+    let content = fs::read_to_string(Path::new(&dir).join(&file)).map_err(|e| format!("读取 {} 失败: {}", file, e))?;
+
+    let name = profile_name.unwrap_or_else(|| match file.trim_start_matches(".env").trim_start_matches('.') {
+        "" => "default".to_string(),
+        suffix => suffix.to_string(),
+    });
+    let vars: Vec<EnvVar> = parse_dotenv(&content)
+        .into_iter()
+        .map(|(key, value)| EnvVar { secret: is_secret_key(&key), key, value })
+        .collect();
+    log::info!("从 {} 读入 {} 个环境变量: {}", file, vars.len(), vars.iter().map(|v| v.key.as_str()).collect::<Vec<_>>().join(", "));
+
+    update_env(&project_id, |env| {
+        let profile = EnvProfile { name: name.clone(), vars, file: Some(file.clone()) };
+        match env.profiles.iter_mut().find(|p| p.name == name) {
+            Some(existing) => *existing = profile,
+            None => env.profiles.push(profile),
+        }
+        Ok(())
+    })
+}
+
+/// 把一组环境变量写回关联的 .env 文件（未关联时写到 .env.<配置名>）
+#[tauri::command]
+pub fn write_env_file(project_id: String, profile_name: String) -> Result<ProjectEnvView, String> {
+    let dir = project_dir(&project_id)?;
+    update_env(&project_id, |env| {
+        let profile = env
+            .profiles
+            .iter_mut()
+            .find(|p| p.name == profile_name)
+            .ok_or_else(|| format!("配置不存在: {}", profile_name))?;
+        let file = profile.file.clone().unwrap_or_else(|| format!(".env.{}", profile.name));
+        validate_env_file_name(&file)?;
+
+        write_atomic(&Path::new(&dir).join(&file), format_dotenv(&profile.vars))?;
+        log::info!("已写入 {}（{} 个变量）", file, profile.vars.len());
+        profile.file = Some(file);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quotes_comments_and_export() {
+        let content = "# comment\nexport A=1\nB=\"two words\\nline\"\nC='raw \\n'\nD=plain # note\nA=override\n";
+        let vars = parse_dotenv(content);
+        assert_eq!(
+            vars,
+            vec![
+                ("A".to_string(), "override".to_string()),
+                ("B".to_string(), "two words\nline".to_string()),
+                ("C".to_string(), "raw \\n".to_string()),
+                ("D".to_string(), "plain".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn formatted_values_parse_back() {
+        let vars = vec![
+            EnvVar { key: "URL".to_string(), value: "http://a/b?c=1".to_string(), secret: false },
+            EnvVar { key: "MSG".to_string(), value: "say \"hi\" # now\nbye".to_string(), secret: true },
+            EnvVar { key: "WIN".to_string(), value: "C:\\new\\dir".to_string(), secret: false },
+        ];
+        let parsed = parse_dotenv(&format_dotenv(&vars));
+        assert_eq!(parsed[0].1, vars[0].value);
+        assert_eq!(parsed[1].1, vars[1].value);
+        assert_eq!(parsed[2].1, vars[2].value);
+    }
+}
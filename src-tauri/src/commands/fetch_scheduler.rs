@@ -0,0 +1,360 @@
+// 定时获取远程更新 - 按设置的间隔在后台 fetch 项目并更新领先/落后计数，
+// 当前分支落后远程或远程出现新标签时发送通知，并在托盘提示中显示落后的项目数
+
+use chrono::Timelike;
+use futures::stream::{self, StreamExt};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{watch, Notify};
+
+use crate::commands::git_batch::{self, GitOutput};
+use crate::commands::project;
+use crate::commands::settings::{self, NotificationInput};
+use crate::storage::{
+    current_iso_time, keys, load_cache, load_setting, save_cache, save_setting, FetchSchedule, Project, QuietHours,
+};
+
+/// 托盘图标 ID，用于更新托盘提示
+pub const TRAY_ID: &str = "main";
+const TRAY_TOOLTIP: &str = "CodeShelf - 代码书架";
+
+const REMOTE_STATUS_EVENT: &str = "remote-status-event";
+
+/// 同时 fetch 的项目数
+const FETCH_CONCURRENCY: usize = 4;
+
+/// 检查是否到了 fetch 时间的间隔
+const TICK: Duration = Duration::from_secs(60);
+
+/// 项目当前分支与远程的差异（最近一次 fetch 后）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteStatus {
+    pub project_id: String,
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    /// 上游分支指向的提交，用于判断远程是否有新提交
+    pub upstream_head: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    pub checked_at: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RemoteStatusEvent {
+    Updated { statuses: Vec<RemoteStatus> },
+}
+
+/// 各项目的远程状态（按项目 ID，启动时从缓存读取）
+static STATUSES: Lazy<Mutex<HashMap<String, RemoteStatus>>> = Lazy::new(|| Mutex::new(load_statuses()));
+
+fn load_statuses() -> HashMap<String, RemoteStatus> {
+    let statuses: Vec<RemoteStatus> = match load_cache(keys::REMOTE_STATUS) {
+        Ok(statuses) => statuses.unwrap_or_default(),
+        Err(e) => {
+            log::warn!("读取远程状态缓存失败: {}", e);
+            Vec::new()
+        }
+    };
+    statuses.into_iter().map(|s| (s.project_id.clone(), s)).collect()
+}
+
+/// 数据库被整体替换（如恢复备份）后重新加载远程状态
+pub fn reload_statuses() -> Result<(), String> {
+    let statuses = load_statuses();
+    *STATUSES.lock().map_err(|e| e.to_string())? = statuses;
+    Ok(())
+}
+
+/// 修改设置后唤醒调度循环
+static WAKE: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// 上一轮定时 fetch 的开始时间
+static LAST_ROUND: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+/// 是否有一轮 fetch 正在进行
+static ROUND_RUNNING: AtomicBool = AtomicBool::new(false);
+
+fn load_schedule() -> Result<FetchSchedule, String> {
+    let schedule = load_setting::<FetchSchedule>(keys::FETCH_SCHEDULE)
+        .map_err(|e| format!("读取定时 fetch 设置失败: {}", e))?;
+    Ok(schedule.unwrap_or_default())
+}
+
+/// 解析 HH:MM，返回当天的分钟数
+fn parse_time(text: &str) -> Option<u32> {
+    let (hour, minute) = text.trim().split_once(':')?;
+    let (hour, minute) = (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?);
+    (hour < 24 && minute < 60).then_some(hour * 60 + minute)
+}
+
+fn in_quiet_hours(quiet: &QuietHours, now_minutes: u32) -> bool {
+    let (Some(start), Some(end)) = (parse_time(&quiet.start), parse_time(&quiet.end)) else {
+        return false;
+    };
+    if start <= end {
+        start <= now_minutes && now_minutes < end
+    } else {
+        now_minutes >= start || now_minutes < end
+    }
+}
+
+fn is_quiet_now(schedule: &FetchSchedule) -> bool {
+    let now = chrono::Local::now();
+    schedule
+        .quiet_hours
+        .as_ref()
+        .map(|quiet| in_quiet_hours(quiet, now.hour() * 60 + now.minute()))
+        .unwrap_or(false)
+}
+
+fn emit(app: &AppHandle, event: RemoteStatusEvent) {
+    if let Err(e) = app.emit(REMOTE_STATUS_EVENT, &event) {
+        log::warn!("发送远程状态事件失败: {}", e);
+    }
+}
+
+/// 托盘提示中显示落后远程的项目数
+fn update_tray(app: &AppHandle, behind_count: usize) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else { return };
+    let tooltip = if behind_count > 0 {
+        format!("{}\n{} 个项目落后远程", TRAY_TOOLTIP, behind_count)
+    } else {
+        TRAY_TOOLTIP.to_string()
+    };
+    if let Err(e) = tray.set_tooltip(Some(tooltip)) {
+        log::warn!("更新托盘提示失败: {}", e);
+    }
+}
+
+async fn notify(title: String, message: String) {
+    let input = NotificationInput { notification_type: "info".to_string(), title, message };
+    if let Err(e) = settings::add_notification(input).await {
+        log::warn!("发送通知失败: {}", e);
+    }
+}
+
+fn stdout_if_ok(output: Option<GitOutput>) -> Option<String> {
+    output.filter(|o| o.success && !o.stdout.is_empty()).map(|o| o.stdout)
+}
+
+/// fetch 一个项目并计算当前分支与上游的差异，同时返回 fetch 后新出现的标签
+async fn check_project(project: &Project, cancel: &mut watch::Receiver<bool>) -> (RemoteStatus, Vec<String>) {
+    let path = project.path.as_str();
+    let mut status = RemoteStatus {
+        project_id: project.id.clone(),
+        branch: None,
+        upstream: None,
+        upstream_head: None,
+        ahead: 0,
+        behind: 0,
+        checked_at: current_iso_time(),
+        error: None,
+    };
+
+    let tag_list = |output: Option<String>| -> HashSet<String> {
+        output.map(|o| o.lines().map(str::to_string).collect()).unwrap_or_default()
+    };
+    let tags_before = tag_list(stdout_if_ok(git_batch::git(path, &["tag", "-l"], cancel).await.ok().flatten()));
+
+    match git_batch::git(path, &["fetch", "--all", "--tags"], cancel).await {
+        Ok(Some(output)) if output.success => {}
+        Ok(Some(output)) => status.error = Some(git_batch::last_line(&output.stderr)),
+        Ok(None) => status.error = Some("已取消".to_string()),
+        Err(e) => status.error = Some(e),
+    }
+
+    let run = |args: &'static [&'static str]| {
+        let mut cancel = cancel.clone();
+        async move { stdout_if_ok(git_batch::git(path, args, &mut cancel).await.ok().flatten()) }
+    };
+    status.branch = run(&["rev-parse", "--abbrev-ref", "HEAD"]).await;
+    status.upstream = run(&["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"]).await;
+    if status.upstream.is_some() {
+        status.upstream_head = run(&["rev-parse", "@{u}"]).await;
+        if let Some(counts) = run(&["rev-list", "--left-right", "--count", "HEAD...@{u}"]).await {
+            let mut parts = counts.split_whitespace().map(|n| n.parse().unwrap_or(0));
+            status.ahead = parts.next().unwrap_or(0);
+            status.behind = parts.next().unwrap_or(0);
+        }
+    }
+
+    let mut new_tags: Vec<String> = if status.error.is_none() {
+        tag_list(run(&["tag", "-l"]).await).difference(&tags_before).cloned().collect()
+    } else {
+        Vec::new()
+    };
+    new_tags.sort();
+    (status, new_tags)
+}
+
+/// 执行一轮 fetch，更新缓存的远程状态并按设置发送通知
+async fn run_round(app: &AppHandle, schedule: &FetchSchedule, project_ids: &[String]) -> Result<Vec<RemoteStatus>, String> {
+    if ROUND_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("正在检查远程更新".to_string());
+    }
+
+    let projects: Vec<Project> = match project::get_projects() {
+        Ok(projects) => projects
+            .into_iter()
+            .filter(|p| project_ids.is_empty() || project_ids.contains(&p.id))
+            .filter(|p| Path::new(&p.path).join(".git").exists())
+            .collect(),
+        Err(e) => {
+            ROUND_RUNNING.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
+    };
+
+    // 定时 fetch 不会被取消，发送端需要保持到本轮结束
+    let (_cancel_tx, cancel_rx) = watch::channel(false);
+    let results: Vec<(Project, RemoteStatus, Vec<String>)> = stream::iter(projects)
+        .map(|project| {
+            let mut cancel = cancel_rx.clone();
+            async move {
+                let (status, new_tags) = check_project(&project, &mut cancel).await;
+                (project, status, new_tags)
+            }
+        })
+        .buffer_unordered(FETCH_CONCURRENCY)
+        .collect()
+        .await;
+    ROUND_RUNNING.store(false, Ordering::SeqCst);
+
+    let previous = STATUSES.lock().map_err(|e| e.to_string())?.clone();
+    let mut notifications = Vec::new();
+    for (project, status, new_tags) in &results {
+        let remote_moved = previous.get(&project.id).map(|p| p.upstream_head != status.upstream_head).unwrap_or(true);
+        if schedule.notify_behind && status.behind > 0 && remote_moved {
+            notifications.push((
+                format!("{} 落后远程", project.name),
+                format!(
+                    "{} 落后 {} {} 个提交",
+                    status.branch.as_deref().unwrap_or("HEAD"),
+                    status.upstream.as_deref().unwrap_or("上游分支"),
+                    status.behind
+                ),
+            ));
+        }
+        if schedule.notify_tags && !new_tags.is_empty() {
+            notifications.push((format!("{} 有新标签", project.name), new_tags.join(", ")));
+        }
+    }
+
+    let statuses: Vec<RemoteStatus> = results.into_iter().map(|(_, status, _)| status).collect();
+    let behind_count = {
+        let mut all = STATUSES.lock().map_err(|e| e.to_string())?;
+        for status in &statuses {
+            all.insert(status.project_id.clone(), status.clone());
+        }
+        if let Err(e) = save_cache(keys::REMOTE_STATUS, &all.values().collect::<Vec<_>>()) {
+            log::warn!("保存远程状态缓存失败: {}", e);
+        }
+        all.values().filter(|s| s.behind > 0).count()
+    };
+
+    for (title, message) in notifications {
+        notify(title, message).await;
+    }
+    log::info!("已检查 {} 个项目的远程更新，{} 个项目落后远程", statuses.len(), behind_count);
+    update_tray(app, behind_count);
+    emit(app, RemoteStatusEvent::Updated { statuses: statuses.clone() });
+    Ok(statuses)
+}
+
+/// 启动后台调度：到达间隔且不在免打扰时段时执行一轮 fetch
+pub fn spawn_fetch_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match load_schedule() {
+                Ok(schedule) if schedule.enabled && !is_quiet_now(&schedule) => {
+                    let interval = Duration::from_secs(schedule.interval_minutes as u64 * 60);
+                    let due = LAST_ROUND
+                        .lock()
+                        .map(|last| last.map(|at| at.elapsed() >= interval).unwrap_or(true))
+                        .unwrap_or(false);
+                    if due {
+                        if let Ok(mut last) = LAST_ROUND.lock() {
+                            *last = Some(Instant::now());
+                        }
+                        if let Err(e) = run_round(&app, &schedule, &schedule.project_ids).await {
+                            log::warn!("定时 fetch 失败: {}", e);
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("{}", e),
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(TICK) => {}
+                _ = WAKE.notified() => {}
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_fetch_schedule() -> Result<FetchSchedule, String> {
+    load_schedule()
+}
+
+#[tauri::command]
+pub fn set_fetch_schedule(schedule: FetchSchedule) -> Result<FetchSchedule, String> {
+    if schedule.interval_minutes < 5 {
+        return Err("fetch 间隔至少为 5 分钟".to_string());
+    }
+    if let Some(quiet) = &schedule.quiet_hours {
+        if parse_time(&quiet.start).is_none() || parse_time(&quiet.end).is_none() {
+            return Err("免打扰时段格式应为 HH:MM".to_string());
+        }
+    }
+    save_setting(keys::FETCH_SCHEDULE, &schedule).map_err(|e| format!("保存定时 fetch 设置失败: {}", e))?;
+    WAKE.notify_one();
+    Ok(schedule)
+}
+
+/// 立即 fetch 指定项目（未指定时使用定时设置中的项目），不受免打扰时段限制
+#[tauri::command]
+pub async fn fetch_remotes_now(app: AppHandle, project_ids: Option<Vec<String>>) -> Result<Vec<RemoteStatus>, String> {
+    let schedule = load_schedule()?;
+    let project_ids = project_ids.unwrap_or_else(|| schedule.project_ids.clone());
+    run_round(&app, &schedule, &project_ids).await
+}
+
+/// 缓存的各项目远程状态
+#[tauri::command]
+pub fn get_remote_statuses() -> Result<Vec<RemoteStatus>, String> {
+    Ok(STATUSES.lock().map_err(|e| e.to_string())?.values().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quiet(start: &str, end: &str) -> QuietHours {
+        QuietHours { start: start.to_string(), end: end.to_string() }
+    }
+
+    #[test]
+    fn quiet_hours_can_wrap_midnight() {
+        let night = quiet("22:00", "07:30");
+        assert!(in_quiet_hours(&night, 23 * 60));
+        assert!(in_quiet_hours(&night, 7 * 60 + 29));
+        assert!(!in_quiet_hours(&night, 7 * 60 + 30));
+        assert!(!in_quiet_hours(&night, 12 * 60));
+
+        let lunch = quiet("12:00", "13:00");
+        assert!(in_quiet_hours(&lunch, 12 * 60 + 30));
+        assert!(!in_quiet_hours(&lunch, 13 * 60));
+        assert!(!in_quiet_hours(&quiet("25:00", "07:00"), 3 * 60));
+    }
+}
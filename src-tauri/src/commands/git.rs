@@ -1,6 +1,12 @@
 use serde::{Deserialize, Serialize};
 use std::process::Command;
 
+use crate::commands::git_backend::{self, LogQuery};
+use crate::commands::git_stash::{self, StashApplyResult};
+pub(crate) use crate::commands::git_backend::run_git_command;
+use crate::commands::repo_scan::{scan_repos, ScanConfig, ScanOptions};
+use crate::storage::ProjectStack;
+
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 
@@ -41,6 +47,27 @@ pub struct CommitInfo {
     pub parent_hashes: Option<Vec<String>>,
 }
 
+#[cfg(test)]
+impl CommitInfo {
+    /// 测试用的提交：标题与哈希相同，只指定父提交
+    pub(crate) fn fixture(hash: &str, parents: &[&str]) -> Self {
+        CommitInfo {
+            hash: hash.to_string(),
+            short_hash: hash.to_string(),
+            message: hash.to_string(),
+            author: "Alice".to_string(),
+            email: "a@example.com".to_string(),
+            date: "2024-05-01T10:00:00+08:00".to_string(),
+            body: None,
+            files_changed: None,
+            insertions: None,
+            deletions: None,
+            refs: None,
+            parent_hashes: Some(parents.iter().map(|p| p.to_string()).collect()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommitFileChange {
     pub insertions: u32,
@@ -64,360 +91,271 @@ pub struct RemoteInfo {
     pub push_url: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GitRepo {
-    pub path: String,
-    pub name: String,
-}
+/// 运行 git 时设置的环境变量：输出英文提示，GitError::classify 只识别英文的错误信息
+pub(crate) const GIT_ENGLISH_ENV: [(&str, &str); 2] = [("LC_ALL", "C"), ("LANGUAGE", "")];
 
-fn run_git_command(path: &str, args: &[&str]) -> Result<String, String> {
-    #[cfg(target_os = "windows")]
-    let output = Command::new("git")
-        .args(["-C", path])
-        .args(args)
-        .creation_flags(CREATE_NO_WINDOW)
-        .output()
-        .map_err(|e| e.to_string())?;
-
-    #[cfg(not(target_os = "windows"))]
-    let output = Command::new("git")
-        .args(["-C", path])
-        .args(args)
-        .output()
-        .map_err(|e| e.to_string())?;
-
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
-    } else {
-        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
-    }
-}
-
-/// 解析 git status --porcelain 输出中的文件路径
-/// 处理引号包裹的路径（包含空格或特殊字符时）
-fn unquote_git_path(path: &str) -> String {
-    let path = path.trim();
-    if path.starts_with('"') && path.ends_with('"') && path.len() >= 2 {
-        // 去除引号并处理转义字符
-        let inner = &path[1..path.len()-1];
-        inner
-            .replace("\\n", "\n")
-            .replace("\\t", "\t")
-            .replace("\\\\", "\\")
-            .replace("\\\"", "\"")
-    } else {
-        path.to_string()
+/// 用户输入的引用名、远程仓库名不能以 - 开头，否则会被 git 当作选项
+pub(crate) fn reject_option_like(kind: &str, value: &str) -> Result<(), GitError> {
+    if value.starts_with('-') {
+        return Err(GitError::Failed(format!("无效的{}: {}", kind, value)));
     }
+    Ok(())
 }
 
-#[tauri::command]
-pub async fn scan_directory(path: String, depth: Option<u32>) -> Result<Vec<GitRepo>, String> {
-    let mut repos = Vec::new();
-    let scan_depth = depth.unwrap_or(3);
-    scan_for_repos(&path, &mut repos, scan_depth)?;
-    Ok(repos)
-}
-
-fn scan_for_repos(path: &str, repos: &mut Vec<GitRepo>, depth: u32) -> Result<(), String> {
-    if depth == 0 {
-        return Ok(());
+/// Git 操作失败的原因，前端根据 code 提供对应的处理方式（如配置凭据、先拉取再推送）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitError {
+    /// 目录不是 Git 仓库
+    NotARepo,
+    /// 当前分支没有设置上游分支
+    NoUpstream,
+    /// 远程仓库需要认证或凭据无效
+    AuthRequired(String),
+    /// 存在冲突，或远程有本地没有的提交（需要先拉取合并）
+    MergeConflict(String),
+    /// 无法连接远程仓库
+    NetworkUnreachable(String),
+    /// 工作区有未提交的修改，操作会覆盖它们
+    DirtyWorktree(String),
+    /// 当前处于分离 HEAD 状态，不在任何分支上
+    DetachedHead,
+    /// 找不到 git 命令
+    GitNotInstalled,
+    /// 其他错误，内容为 git 的输出或错误说明
+    Failed(String),
+}
+
+const AUTH_HINTS: &[&str] = &[
+    "authentication failed",
+    "permission denied (publickey",
+    " denied to ",
+    "could not read username",
+    "could not read password",
+    "terminal prompts disabled",
+    "host key verification failed",
+    "the requested url returned error: 403",
+    "the requested url returned error: 401",
+];
+
+const NETWORK_HINTS: &[&str] = &[
+    "could not resolve host",
+    "could not resolve hostname",
+    "connection timed out",
+    "operation timed out",
+    "connection refused",
+    "network is unreachable",
+    "failed to connect",
+    "could not connect to server",
+    "unable to access",
+];
+
+const NO_UPSTREAM_HINTS: &[&str] = &["no upstream configured", "has no upstream branch", "no tracking information"];
+
+const DETACHED_HINTS: &[&str] = &["you are not currently on a branch", "head does not point to a branch"];
+
+const DIRTY_HINTS: &[&str] = &[
+    "would be overwritten",
+    "please commit your changes or stash them",
+    "you have unstaged changes",
+    "your index contains uncommitted changes",
+];
+
+const CONFLICT_HINTS: &[&str] = &[
+    "conflict",
+    "unmerged files",
+    "not possible to fast-forward",
+    "diverging branches",
+    "non-fast-forward",
+    "[rejected]",
+    "fetch first",
+];
+
+impl GitError {
+    /// 给前端使用的错误码
+    pub fn code(&self) -> &'static str {
+        match self {
+            GitError::NotARepo => "notARepo",
+            GitError::NoUpstream => "noUpstream",
+            GitError::AuthRequired(_) => "authRequired",
+            GitError::MergeConflict(_) => "mergeConflict",
+            GitError::NetworkUnreachable(_) => "networkUnreachable",
+            GitError::DirtyWorktree(_) => "dirtyWorktree",
+            GitError::DetachedHead => "detachedHead",
+            GitError::GitNotInstalled => "gitNotInstalled",
+            GitError::Failed(_) => "failed",
+        }
     }
 
-    let entries = std::fs::read_dir(path).map_err(|e| e.to_string())?;
-
-    for entry in entries.flatten() {
-        let entry_path = entry.path();
-        if entry_path.is_dir() {
-            let dir_name = entry_path.file_name().unwrap().to_string_lossy().to_string();
-
-            // Skip hidden directories except .git
-            if dir_name.starts_with('.') && dir_name != ".git" {
-                continue;
-            }
-
-            if dir_name == ".git" {
-                // Found a git repo, add the parent directory
-                if let Some(parent) = entry_path.parent() {
-                    let repo_name = parent
-                        .file_name()
-                        .map(|n| n.to_string_lossy().to_string())
-                        .unwrap_or_else(|| "Unknown".to_string());
-                    repos.push(GitRepo {
-                        path: parent.to_string_lossy().to_string(),
-                        name: repo_name,
-                    });
-                }
-            } else {
-                // Continue scanning subdirectories
-                scan_for_repos(&entry_path.to_string_lossy(), repos, depth - 1)?;
-            }
+    /// 简短的说明
+    pub fn message(&self) -> &str {
+        match self {
+            GitError::NotARepo => "不是 Git 仓库",
+            GitError::NoUpstream => "当前分支没有设置上游分支",
+            GitError::AuthRequired(_) => "远程仓库需要认证",
+            GitError::MergeConflict(_) => "存在冲突，需要先合并",
+            GitError::NetworkUnreachable(_) => "无法连接远程仓库",
+            GitError::DirtyWorktree(_) => "工作区有未提交的修改",
+            GitError::DetachedHead => "当前不在任何分支上（分离 HEAD）",
+            GitError::GitNotInstalled => "未安装 Git",
+            GitError::Failed(message) => message,
         }
     }
 
-    Ok(())
-}
-
-#[tauri::command]
-pub async fn get_git_status(path: String) -> Result<GitStatus, String> {
-    // Get current branch
-    let branch = run_git_command(&path, &["rev-parse", "--abbrev-ref", "HEAD"])
-        .unwrap_or_else(|_| "unknown".to_string());
-
-    // Get status with -uall to show all untracked files recursively
-    let status_output = run_git_command(&path, &["status", "--porcelain", "-uall"])?;
-
-    let mut staged = Vec::new();
-    let mut unstaged = Vec::new();
-    let mut untracked = Vec::new();
-
-    for line in status_output.lines() {
-        if line.len() < 3 {
-            continue;
+    /// git 的原始输出
+    pub fn detail(&self) -> Option<&str> {
+        match self {
+            GitError::AuthRequired(detail)
+            | GitError::MergeConflict(detail)
+            | GitError::NetworkUnreachable(detail)
+            | GitError::DirtyWorktree(detail) => Some(detail.as_str()).filter(|d| !d.is_empty()),
+            _ => None,
         }
-        let status = &line[0..2];
-        // 跳过状态码后的所有空白字符，更稳健地获取文件路径
-        let file_part = line[2..].trim_start();
-        let file = unquote_git_path(file_part);
+    }
 
-        if file.is_empty() {
-            continue;
+    /// 按 git 的输出判断失败原因（合并冲突的提示写在标准输出中）
+    pub(crate) fn classify(stderr: &str, stdout: &str) -> Self {
+        let detail = stderr.trim().to_string();
+        let text = stderr.to_lowercase();
+        let matches = |hints: &[&str]| hints.iter().any(|hint| text.contains(hint));
+
+        if text.contains("not a git repository") {
+            GitError::NotARepo
+        } else if matches(AUTH_HINTS) {
+            GitError::AuthRequired(detail)
+        } else if matches(NETWORK_HINTS) {
+            GitError::NetworkUnreachable(detail)
+        } else if matches(NO_UPSTREAM_HINTS) {
+            GitError::NoUpstream
+        } else if matches(DETACHED_HINTS) {
+            GitError::DetachedHead
+        } else if matches(DIRTY_HINTS) {
+            GitError::DirtyWorktree(detail)
+        } else if matches(CONFLICT_HINTS) {
+            GitError::MergeConflict(detail)
+        } else if stdout.contains("CONFLICT (") {
+            // 合并时 stderr 只有拉取进度，冲突的文件列在 stdout 中
+            GitError::MergeConflict(stdout.trim().to_string())
+        } else {
+            GitError::Failed(detail)
         }
+    }
 
-        match status.chars().next() {
-            Some('?') => untracked.push(file),
-            Some(' ') => unstaged.push(file),
-            Some(_) => {
-                if status.chars().nth(1) == Some(' ') {
-                    staged.push(file);
-                } else {
-                    staged.push(file.clone());
-                    unstaged.push(file);
-                }
-            }
-            None => {}
+    /// git 进程退出码非 0 时的错误
+    pub(crate) fn from_output(output: &std::process::Output) -> Self {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        match Self::classify(&stderr, &stdout) {
+            GitError::Failed(detail) if detail.is_empty() => GitError::Failed(match output.status.code() {
+                Some(code) => format!("git 命令执行失败（退出码 {}）", code),
+                None => "git 进程被终止".to_string(),
+            }),
+            error => error,
         }
     }
 
-    // Get ahead/behind
-    let (ahead, behind) = get_ahead_behind(&path);
-
-    Ok(GitStatus {
-        branch,
-        is_clean: staged.is_empty() && unstaged.is_empty() && untracked.is_empty(),
-        staged,
-        unstaged,
-        untracked,
-        ahead,
-        behind,
-    })
-}
-
-fn get_ahead_behind(path: &str) -> (u32, u32) {
-    let output = run_git_command(path, &["rev-list", "--left-right", "--count", "HEAD...@{upstream}"]);
-
-    if let Ok(result) = output {
-        let parts: Vec<&str> = result.split_whitespace().collect();
-        if parts.len() == 2 {
-            let ahead = parts[0].parse().unwrap_or(0);
-            let behind = parts[1].parse().unwrap_or(0);
-            return (ahead, behind);
+    /// 启动 git 进程失败时的错误
+    pub(crate) fn from_io(error: std::io::Error) -> Self {
+        if error.kind() == std::io::ErrorKind::NotFound {
+            GitError::GitNotInstalled
+        } else {
+            GitError::Failed(format!("执行 git 命令失败: {}", error))
         }
     }
-    (0, 0)
 }
 
-/// 解析分支/标签引用
-fn parse_refs(refs_str: &str) -> Option<Vec<String>> {
-    let refs_str = refs_str.trim();
-    if refs_str.is_empty() {
-        return None;
+impl std::fmt::Display for GitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.detail() {
+            Some(detail) => write!(f, "{}: {}", self.message(), detail),
+            None => f.write_str(self.message()),
+        }
     }
+}
 
-    let refs: Vec<String> = refs_str
-        .split(',')
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
-        .collect();
+impl std::error::Error for GitError {}
 
-    if refs.is_empty() {
-        None
-    } else {
-        Some(refs)
+/// 供返回 String 错误的调用方直接使用 `?`
+impl From<GitError> for String {
+    fn from(error: GitError) -> Self {
+        error.to_string()
     }
 }
 
-/// 解析父提交哈希
-fn parse_parent_hashes(hashes_str: &str) -> Option<Vec<String>> {
-    let hashes_str = hashes_str.trim();
-    if hashes_str.is_empty() {
-        return None;
-    }
-
-    let hashes: Vec<String> = hashes_str
-        .split_whitespace()
-        .map(|s| s.to_string())
-        .filter(|s| !s.is_empty())
-        .collect();
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GitErrorBody<'a> {
+    code: &'static str,
+    message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<&'a str>,
+}
 
-    if hashes.is_empty() {
-        None
-    } else {
-        Some(hashes)
+/// 前端收到 { code, message, detail }
+impl Serialize for GitError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GitErrorBody { code: self.code(), message: self.message(), detail: self.detail() }.serialize(serializer)
     }
 }
 
-/// 获取单个提交的统计信息
-fn get_commit_stats_sync(path: &str, commit_hash: &str) -> Option<(u32, u32, u32)> {
-    let args = vec![
-        "show",
-        "--numstat",
-        "--format=",
-        commit_hash,
-    ];
-
-    let output = run_git_command(path, &args).ok()?;
+/// 仓库类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepoKind {
+    Normal,
+    /// 裸仓库（没有工作区）
+    Bare,
+    /// git worktree 创建的工作树（.git 是指向主仓库的文件）
+    Worktree,
+    /// 子模块（.git 是指向父仓库 modules/ 的文件）
+    Submodule,
+    /// 不是 Git 仓库，只识别到项目清单文件
+    Plain,
+}
 
-    let mut files_changed = 0u32;
-    let mut insertions = 0u32;
-    let mut deletions = 0u32;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitRepo {
+    pub path: String,
+    pub name: String,
+    pub kind: RepoKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stack: Option<ProjectStack>,
+}
 
-    for line in output.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
+/// 扫描目录下的仓库（一次性返回结果，实时推送见 repo_scan::scan_repositories）
+#[tauri::command]
+pub async fn scan_directory(path: String, depth: Option<u32>) -> Result<Vec<GitRepo>, String> {
+    let config = ScanConfig::load(ScanOptions { depth, ..Default::default() })?;
+    let report = tokio::task::spawn_blocking(move || scan_repos(&path, &config, |_| {}))
+        .await
+        .map_err(|e| format!("扫描任务异常: {}", e))??;
 
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 3 {
-            files_changed += 1;
-            // 解析新增行数 (可能是 "-" 表示二进制文件)
-            if let Ok(add) = parts[0].parse::<u32>() {
-                insertions += add;
-            }
-            // 解析删除行数
-            if let Ok(del) = parts[1].parse::<u32>() {
-                deletions += del;
-            }
-        }
+    for error in &report.errors {
+        log::warn!("扫描时无法读取 {}: {}", error.path.as_deref().unwrap_or("-"), error.message);
     }
-
-    Some((files_changed, insertions, deletions))
+    Ok(report.repos)
 }
 
 #[tauri::command]
-pub async fn get_commit_history(path: String, limit: Option<u32>, ref_name: Option<String>) -> Result<Vec<CommitInfo>, String> {
-    let limit_str = limit.unwrap_or(50).to_string();
-
-    // 使用 %x1f (Unit Separator) 作为字段分隔符，%x1e (Record Separator) 作为提交分隔符
-    // 这样可以避免提交信息中的特殊字符干扰解析
-    let format = [
-        "%H",   // hash - 完整哈希
-        "%h",   // short_hash - 短哈希
-        "%s",   // message - 提交标题
-        "%an",  // author - 作者名
-        "%ae",  // email - 作者邮箱
-        "%aI",  // date - ISO 8601 格式日期
-        "%b",   // body - 完整提交信息体
-        "%D",   // refs - 分支/标签引用
-        "%P",   // parent_hashes - 父提交哈希
-    ]
-    .join("%x1f");
-
-    let mut args = vec![
-        "log".to_string(),
-        format!("-{}", limit_str),
-        format!("--format=%x1e{}", format),
-    ];
-
-    // 如果指定了 ref_name（如 origin/main），则获取该引用的提交历史
-    if let Some(ref_name) = ref_name {
-        args.push(ref_name);
-    }
-
-    let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
-    let output = run_git_command(&path, &args_ref)?;
-
-    // 解析提交信息
-    let commits: Vec<CommitInfo> = output
-        .split('\x1e')
-        .filter(|s| !s.trim().is_empty())
-        .filter_map(|record| {
-            let parts: Vec<&str> = record.split('\x1f').collect();
-            if parts.len() >= 9 {
-                let hash = parts[0].trim().to_string();
-
-                // 获取统计信息
-                let stats = get_commit_stats_sync(&path, &hash);
-
-                Some(CommitInfo {
-                    hash,
-                    short_hash: parts[1].trim().to_string(),
-                    message: parts[2].trim().to_string(),
-                    author: parts[3].trim().to_string(),
-                    email: parts[4].trim().to_string(),
-                    date: parts[5].trim().to_string(),
-                    body: {
-                        let body = parts[6].trim();
-                        if body.is_empty() {
-                            None
-                        } else {
-                            Some(body.to_string())
-                        }
-                    },
-                    refs: parse_refs(parts[7]),
-                    parent_hashes: parse_parent_hashes(parts[8]),
-                    files_changed: stats.map(|s| s.0),
-                    insertions: stats.map(|s| s.1),
-                    deletions: stats.map(|s| s.2),
-                })
-            } else {
-                None
-            }
-        })
-        .collect();
+pub async fn get_git_status(path: String) -> Result<GitStatus, GitError> {
+    git_backend::read(|backend| backend.status(&path))
+}
 
-    Ok(commits)
+#[tauri::command]
+pub async fn get_commit_history(path: String, limit: Option<u32>, ref_name: Option<String>) -> Result<Vec<CommitInfo>, GitError> {
+    let query = LogQuery {
+        limit: limit.unwrap_or(50) as usize,
+        // 如果指定了 ref_name（如 origin/main），则获取该引用的提交历史
+        rev: ref_name.as_deref(),
+        with_stats: true,
+        ..Default::default()
+    };
+    git_backend::read(|backend| backend.log(&path, &query))
 }
 
 /// 获取单个提交的详细信息（用于按需加载）
 #[tauri::command]
-pub async fn get_commit_detail(path: String, commit_hash: String) -> Result<CommitInfo, String> {
-    let format = [
-        "%H", "%h", "%s", "%an", "%ae", "%aI", "%b", "%D", "%P",
-    ]
-    .join("%x1f");
-
-    let args = vec!["show", "--format", &format, "-s", &commit_hash];
-    let output = run_git_command(&path, &args)?;
-
-    let parts: Vec<&str> = output.trim().split('\x1f').collect();
-    if parts.len() < 9 {
-        return Err("Invalid commit format".to_string());
-    }
-
-    let stats = get_commit_stats_sync(&path, &commit_hash);
-
-    Ok(CommitInfo {
-        hash: parts[0].trim().to_string(),
-        short_hash: parts[1].trim().to_string(),
-        message: parts[2].trim().to_string(),
-        author: parts[3].trim().to_string(),
-        email: parts[4].trim().to_string(),
-        date: parts[5].trim().to_string(),
-        body: {
-            let body = parts[6].trim();
-            if body.is_empty() {
-                None
-            } else {
-                Some(body.to_string())
-            }
-        },
-        refs: parse_refs(parts[7]),
-        parent_hashes: parse_parent_hashes(parts[8]),
-        files_changed: stats.map(|s| s.0),
-        insertions: stats.map(|s| s.1),
-        deletions: stats.map(|s| s.2),
-    })
+pub async fn get_commit_detail(path: String, commit_hash: String) -> Result<CommitInfo, GitError> {
+    git_backend::read(|backend| backend.commit(&path, &commit_hash))
 }
 
 /// 获取提交的文件变更列表
@@ -425,38 +363,8 @@ pub async fn get_commit_detail(path: String, commit_hash: String) -> Result<Comm
 pub async fn get_commit_files(
     path: String,
     commit_hash: String,
-) -> Result<Vec<CommitFileChange>, String> {
-    let args = vec![
-        "show",
-        "--numstat",
-        "--format=",
-        commit_hash.as_str(),
-    ];
-
-    let output = run_git_command(&path, &args)?;
-
-    let files: Vec<CommitFileChange> = output
-        .lines()
-        .filter_map(|line| {
-            let line = line.trim();
-            if line.is_empty() {
-                return None;
-            }
-
-            let parts: Vec<&str> = line.split('\t').collect();
-            if parts.len() >= 3 {
-                Some(CommitFileChange {
-                    insertions: parts[0].parse().unwrap_or(0),
-                    deletions: parts[1].parse().unwrap_or(0),
-                    filename: parts[2..].join("\t"),
-                })
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    Ok(files)
+) -> Result<Vec<CommitFileChange>, GitError> {
+    git_backend::read(|backend| backend.commit_files(&path, &commit_hash))
 }
 
 /// 搜索提交历史
@@ -466,180 +374,110 @@ pub async fn search_commits(
     query: String,
     search_type: Option<String>,
     limit: Option<u32>,
-) -> Result<Vec<CommitInfo>, String> {
-    let limit_str = limit.unwrap_or(50).to_string();
-    let format = [
-        "%H", "%h", "%s", "%an", "%ae", "%aI", "%b", "%D", "%P",
-    ]
-    .join("%x1f");
-
-    let mut args = vec![
-        "log".to_string(),
-        format!("-{}", limit_str),
-        format!("--format=%x1e{}", format),
-    ];
-
-    // 根据搜索类型添加参数
+) -> Result<Vec<CommitInfo>, GitError> {
+    let mut log_query = LogQuery {
+        limit: limit.unwrap_or(50) as usize,
+        ..Default::default()
+    };
+
+    // 根据搜索类型设置条件
     match search_type.as_deref() {
-        Some("author") => {
-            args.push(format!("--author={}", query));
-        }
-        Some("message") => {
-            args.push(format!("--grep={}", query));
-        }
+        Some("author") => log_query.author = Some(&query),
         Some("hash") => {
             // 直接查找特定提交
             return get_commit_detail(path, query).await.map(|c| vec![c]);
         }
-        _ => {
-            // 默认搜索提交信息
-            args.push(format!("--grep={}", query));
-        }
+        // 默认搜索提交信息
+        _ => log_query.grep = Some(&query),
     }
 
-    let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
-    let output = run_git_command(&path, &args_ref)?;
-
-    let commits: Vec<CommitInfo> = output
-        .split('\x1e')
-        .filter(|s| !s.trim().is_empty())
-        .filter_map(|record| {
-            let parts: Vec<&str> = record.split('\x1f').collect();
-            if parts.len() >= 9 {
-                Some(CommitInfo {
-                    hash: parts[0].trim().to_string(),
-                    short_hash: parts[1].trim().to_string(),
-                    message: parts[2].trim().to_string(),
-                    author: parts[3].trim().to_string(),
-                    email: parts[4].trim().to_string(),
-                    date: parts[5].trim().to_string(),
-                    body: {
-                        let body = parts[6].trim();
-                        if body.is_empty() {
-                            None
-                        } else {
-                            Some(body.to_string())
-                        }
-                    },
-                    refs: parse_refs(parts[7]),
-                    parent_hashes: parse_parent_hashes(parts[8]),
-                    files_changed: None,
-                    insertions: None,
-                    deletions: None,
-                })
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    Ok(commits)
+    git_backend::read(|backend| backend.log(&path, &log_query))
 }
 
 #[tauri::command]
-pub async fn get_branches(path: String) -> Result<Vec<BranchInfo>, String> {
-    let output = run_git_command(&path, &["branch", "-a", "-vv"])?;
-
-    let branches: Vec<BranchInfo> = output
-        .lines()
-        .map(|line| {
-            let is_current = line.starts_with('*');
-            let line = line.trim_start_matches(['*', ' '].as_ref());
-            let parts: Vec<&str> = line.split_whitespace().collect();
-
-            let name = parts.first().unwrap_or(&"").to_string();
-            let is_remote = name.starts_with("remotes/");
-
-            // Extract upstream from [origin/branch] format
-            let upstream = line
-                .find('[')
-                .and_then(|start| {
-                    line[start..].find(']').map(|end| {
-                        line[start + 1..start + end].split(':').next().unwrap_or("").to_string()
-                    })
-                });
-
-            BranchInfo {
-                name: name.trim_start_matches("remotes/").to_string(),
-                is_current,
-                is_remote,
-                upstream,
-            }
-        })
-        .collect();
+pub async fn get_branches(path: String) -> Result<Vec<BranchInfo>, GitError> {
+    git_backend::read(|backend| backend.branches(&path))
+}
 
-    Ok(branches)
+#[tauri::command]
+pub async fn get_remotes(path: String) -> Result<Vec<RemoteInfo>, GitError> {
+    git_backend::read(|backend| backend.remotes(&path))
 }
 
 #[tauri::command]
-pub async fn get_remotes(path: String) -> Result<Vec<RemoteInfo>, String> {
-    let output = run_git_command(&path, &["remote", "-v"])?;
-
-    let mut remotes: std::collections::HashMap<String, RemoteInfo> = std::collections::HashMap::new();
-
-    for line in output.lines() {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 2 {
-            let name = parts[0].to_string();
-            let url = parts[1].to_string();
-            let remote_type = parts.get(2).unwrap_or(&"");
-
-            let entry = remotes.entry(name.clone()).or_insert(RemoteInfo {
-                name,
-                url: url.clone(),
-                fetch_url: None,
-                push_url: None,
-            });
+pub async fn add_remote(path: String, name: String, url: String) -> Result<(), GitError> {
+    run_git_command(&path, &["remote", "add", &name, &url])?;
+    Ok(())
+}
 
-            if remote_type.contains("fetch") {
-                entry.fetch_url = Some(url);
-            } else if remote_type.contains("push") {
-                entry.push_url = Some(url);
-            }
+/// 拆分远程地址中的主机名和仓库路径（去掉协议、用户名、端口和 .git 后缀），本地路径的主机名为空
+///
+/// git@github.com:en-o/codeshelf.git、https://github.com/en-o/codeshelf -> ("github.com", "en-o/codeshelf")
+pub(crate) fn parse_remote_url(url: &str) -> (&str, String) {
+    let url = url.trim();
+    let (host, path) = match url.split_once("://") {
+        // scheme://[user@]host[:port]/path
+        Some((_, rest)) => {
+            let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+            let host = authority.rsplit('@').next().unwrap_or(authority);
+            (host.split(':').next().unwrap_or(host), path.to_string())
         }
-    }
+        // scp 写法 [user@]host:path；没有冒号、冒号前有路径分隔符或是盘符（C:\repo）的是本地路径
+        None => match url.split_once(':') {
+            Some((authority, path)) if !is_local_prefix(authority) => {
+                (authority.rsplit('@').next().unwrap_or(authority), path.to_string())
+            }
+            _ => ("", url.replace('\\', "/")),
+        },
+    };
+    (host, path.trim_matches('/').trim_end_matches(".git").to_string())
+}
 
-    Ok(remotes.into_values().collect())
+/// scp 写法中冒号之前的部分实际是本地路径
+fn is_local_prefix(authority: &str) -> bool {
+    let is_drive = authority.len() == 1 && authority.chars().all(|c| c.is_ascii_alphabetic());
+    is_drive || authority.contains(['/', '\\'])
 }
 
-#[tauri::command]
-pub async fn add_remote(path: String, name: String, url: String) -> Result<(), String> {
-    run_git_command(&path, &["remote", "add", &name, &url])?;
-    Ok(())
+/// 规范化远程地址，让 SSH 和 HTTPS 写法得到同一个结果（用于比较，不区分大小写）
+pub(crate) fn normalize_remote_url(url: &str) -> String {
+    let (host, path) = parse_remote_url(url);
+    format!("{}/{}", host, path).to_lowercase()
 }
 
 #[tauri::command]
-pub async fn verify_remote_url(url: String) -> Result<(), String> {
+pub async fn verify_remote_url(url: String) -> Result<(), GitError> {
     // 使用 git ls-remote 验证远程仓库 URL 是否有效 (hide console window on Windows)
     #[cfg(target_os = "windows")]
     let output = std::process::Command::new("git")
         .args(&["ls-remote", "--exit-code", &url])
+        .envs(GIT_ENGLISH_ENV)
         .creation_flags(CREATE_NO_WINDOW)
         .output()
-        .map_err(|e| format!("执行 git 命令失败: {}", e))?;
+        .map_err(GitError::from_io)?;
 
     #[cfg(not(target_os = "windows"))]
     let output = std::process::Command::new("git")
         .args(&["ls-remote", "--exit-code", &url])
+        .envs(GIT_ENGLISH_ENV)
         .output()
-        .map_err(|e| format!("执行 git 命令失败: {}", e))?;
+        .map_err(GitError::from_io)?;
 
     if output.status.success() {
         Ok(())
     } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(format!("无法连接到远程仓库: {}", stderr.trim()))
+        Err(GitError::from_output(&output))
     }
 }
 
 #[tauri::command]
-pub async fn remove_remote(path: String, name: String) -> Result<(), String> {
+pub async fn remove_remote(path: String, name: String) -> Result<(), GitError> {
     run_git_command(&path, &["remote", "remove", &name])?;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn git_push(path: String, remote: String, branch: String, force: bool) -> Result<String, String> {
+pub async fn git_push(path: String, remote: String, branch: String, force: bool) -> Result<String, GitError> {
     let mut args = vec!["push", &remote, &branch];
     if force {
         args.push("--force");
@@ -648,12 +486,12 @@ pub async fn git_push(path: String, remote: String, branch: String, force: bool)
 }
 
 #[tauri::command]
-pub async fn git_pull(path: String, remote: String, branch: String) -> Result<String, String> {
+pub async fn git_pull(path: String, remote: String, branch: String) -> Result<String, GitError> {
     run_git_command(&path, &["pull", &remote, &branch])
 }
 
 #[tauri::command]
-pub async fn git_fetch(path: String, remote: Option<String>) -> Result<String, String> {
+pub async fn git_fetch(path: String, remote: Option<String>) -> Result<String, GitError> {
     match remote {
         Some(r) => run_git_command(&path, &["fetch", &r]),
         None => run_git_command(&path, &["fetch", "--all"]),
@@ -661,7 +499,7 @@ pub async fn git_fetch(path: String, remote: Option<String>) -> Result<String, S
 }
 
 #[tauri::command]
-pub async fn git_clone(url: String, target_dir: String, repo_name: String) -> Result<String, String> {
+pub async fn git_clone(url: String, target_dir: String, repo_name: String) -> Result<String, GitError> {
     use std::path::PathBuf;
 
     let target_path = PathBuf::from(&target_dir).join(&repo_name);
@@ -669,27 +507,29 @@ pub async fn git_clone(url: String, target_dir: String, repo_name: String) -> Re
 
     // Check if directory already exists
     if target_path.exists() {
-        return Err(format!("Directory '{}' already exists", repo_name));
+        return Err(GitError::Failed(format!("Directory '{}' already exists", repo_name)));
     }
 
     // Clone the repository (hide console window on Windows)
     #[cfg(target_os = "windows")]
     let output = Command::new("git")
         .args(&["clone", &url, &target_path_str])
+        .envs(GIT_ENGLISH_ENV)
         .creation_flags(CREATE_NO_WINDOW)
         .output()
-        .map_err(|e| format!("Failed to execute git clone: {}", e))?;
+        .map_err(GitError::from_io)?;
 
     #[cfg(not(target_os = "windows"))]
     let output = Command::new("git")
         .args(&["clone", &url, &target_path_str])
+        .envs(GIT_ENGLISH_ENV)
         .output()
-        .map_err(|e| format!("Failed to execute git clone: {}", e))?;
+        .map_err(GitError::from_io)?;
 
     if output.status.success() {
         Ok(target_path_str)
     } else {
-        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        Err(GitError::from_output(&output))
     }
 }
 
@@ -700,7 +540,7 @@ pub async fn sync_to_remote(
     target_remote: String,
     sync_all_branches: bool,
     force: bool,
-) -> Result<String, String> {
+) -> Result<String, GitError> {
     // First, fetch all branches from source remote to ensure we have latest refs
     run_git_command(&path, &["fetch", &source_remote, "--prune"])?;
 
@@ -728,7 +568,7 @@ pub async fn sync_to_remote(
             .collect();
 
         if branches.is_empty() {
-            return Err("No branches found to sync".to_string());
+            return Err(GitError::Failed("No branches found to sync".to_string()));
         }
 
         // Sort branches to push default branch first (important for new repos)
@@ -782,13 +622,47 @@ pub async fn sync_to_remote(
     }
 }
 
+/// 切换分支的结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckoutResult {
+    pub output: String,
+    /// 切换前自动储藏了修改时，切换后重新应用的结果（有冲突时储藏保留在列表中）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_stash: Option<StashApplyResult>,
+}
+
+/// 切换分支，auto_stash 为 true 时先储藏未提交的修改，切换后再重新应用
 #[tauri::command]
-pub async fn checkout_branch(path: String, branch: String) -> Result<String, String> {
-    run_git_command(&path, &["checkout", &branch])
+pub async fn checkout_branch(path: String, branch: String, auto_stash: Option<bool>) -> Result<CheckoutResult, GitError> {
+    if !auto_stash.unwrap_or(false) {
+        let output = run_git_command(&path, &["checkout", &branch])?;
+        return Ok(CheckoutResult { output, auto_stash: None });
+    }
+
+    let message = format!("切换到 {} 前自动储藏", branch);
+    let stashed = git_stash::push_stash(&path, Some(&message), true, &[])?;
+    let output = match run_git_command(&path, &["checkout", &branch]) {
+        Ok(output) => output,
+        Err(e) => {
+            // 切换失败时把修改放回原分支
+            if let Some(stash) = &stashed {
+                if let Err(pop_error) = git_stash::pop_stash_commit(&path, stash) {
+                    log::warn!("切换分支失败后恢复储藏失败: {}", pop_error);
+                }
+            }
+            return Err(e);
+        }
+    };
+    let auto_stash = match &stashed {
+        Some(stash) => Some(git_stash::pop_stash_commit(&path, stash)?),
+        None => None,
+    };
+    Ok(CheckoutResult { output, auto_stash })
 }
 
 #[tauri::command]
-pub async fn create_branch(path: String, branch: String, checkout: bool) -> Result<String, String> {
+pub async fn create_branch(path: String, branch: String, checkout: bool) -> Result<String, GitError> {
     if checkout {
         // Create and checkout the new branch
         run_git_command(&path, &["checkout", "-b", &branch])
@@ -799,7 +673,7 @@ pub async fn create_branch(path: String, branch: String, checkout: bool) -> Resu
 }
 
 #[tauri::command]
-pub async fn git_add(path: String, files: Vec<String>) -> Result<String, String> {
+pub async fn git_add(path: String, files: Vec<String>) -> Result<String, GitError> {
     if files.is_empty() {
         // Add all changes
         run_git_command(&path, &["add", "-A"])
@@ -812,7 +686,7 @@ pub async fn git_add(path: String, files: Vec<String>) -> Result<String, String>
 }
 
 #[tauri::command]
-pub async fn git_unstage(path: String, files: Vec<String>) -> Result<String, String> {
+pub async fn git_unstage(path: String, files: Vec<String>) -> Result<String, GitError> {
     if files.is_empty() {
         run_git_command(&path, &["reset", "HEAD"])
     } else {
@@ -823,17 +697,17 @@ pub async fn git_unstage(path: String, files: Vec<String>) -> Result<String, Str
 }
 
 #[tauri::command]
-pub async fn git_commit(path: String, message: String) -> Result<String, String> {
+pub async fn git_commit(path: String, message: String) -> Result<String, GitError> {
     if message.trim().is_empty() {
-        return Err("提交信息不能为空".to_string());
+        return Err(GitError::Failed("提交信息不能为空".to_string()));
     }
     run_git_command(&path, &["commit", "-m", &message])
 }
 
 #[tauri::command]
-pub async fn git_add_and_commit(path: String, files: Vec<String>, message: String) -> Result<String, String> {
+pub async fn git_add_and_commit(path: String, files: Vec<String>, message: String) -> Result<String, GitError> {
     if message.trim().is_empty() {
-        return Err("提交信息不能为空".to_string());
+        return Err(GitError::Failed("提交信息不能为空".to_string()));
     }
 
     // First add files
@@ -844,12 +718,177 @@ pub async fn git_add_and_commit(path: String, files: Vec<String>, message: Strin
 }
 
 #[tauri::command]
-pub async fn is_git_repo(path: String) -> Result<bool, String> {
+pub async fn is_git_repo(path: String) -> Result<bool, GitError> {
     let git_dir = std::path::Path::new(&path).join(".git");
     Ok(git_dir.exists())
 }
 
 #[tauri::command]
-pub async fn git_init(path: String) -> Result<String, String> {
+pub async fn git_init(path: String) -> Result<String, GitError> {
     run_git_command(&path, &["init"])
 }
+
+/// 标签列表的格式：%1f 分隔名称、对象类型、指向的提交、标签对象、附注标题、创建者和时间
+const TAG_FORMAT: &str = "--format=%(refname:short)%1f%(objecttype)%1f%(*objectname)%1f%(objectname)%1f%(contents:subject)%1f%(taggername)%1f%(creatordate:iso-strict)";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagInfo {
+    pub name: String,
+    /// 标签指向的提交
+    pub commit: String,
+    /// 附注标签（git tag -a）
+    pub annotated: bool,
+    /// 附注标签的说明（第一行）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tagger: Option<String>,
+    /// 附注标签的创建时间，轻量标签为提交时间
+    pub date: String,
+}
+
+fn parse_tags(output: &str) -> Vec<TagInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\x1f').collect();
+            let [name, object_type, peeled, object, subject, tagger, date] = fields.as_slice() else {
+                return None;
+            };
+            let annotated = *object_type == "tag";
+            let non_empty = |s: &str| Some(s.to_string()).filter(|s| !s.is_empty());
+            Some(TagInfo {
+                name: name.to_string(),
+                commit: if annotated { peeled.to_string() } else { object.to_string() },
+                annotated,
+                message: non_empty(subject).filter(|_| annotated),
+                tagger: non_empty(tagger).filter(|_| annotated),
+                date: date.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// 标签列表，最新创建的在前
+#[tauri::command]
+pub async fn get_tags(path: String) -> Result<Vec<TagInfo>, GitError> {
+    let output = run_git_command(&path, &["for-each-ref", "refs/tags", "--sort=-creatordate", TAG_FORMAT])?;
+    Ok(parse_tags(&output))
+}
+
+/// 创建标签，有说明时创建附注标签，否则创建轻量标签；target 默认为 HEAD
+#[tauri::command]
+pub async fn create_tag(path: String, name: String, target: Option<String>, message: Option<String>) -> Result<(), GitError> {
+    if name.trim().is_empty() {
+        return Err(GitError::Failed("标签名不能为空".to_string()));
+    }
+    reject_option_like("标签名", &name)?;
+    if let Some(target) = target.as_deref() {
+        reject_option_like("目标提交", target)?;
+    }
+    let mut args = vec!["tag"];
+    if let Some(message) = message.as_deref().filter(|m| !m.trim().is_empty()) {
+        args.extend(["-a", "-m", message]);
+    }
+    args.push(&name);
+    if let Some(target) = target.as_deref() {
+        args.push(target);
+    }
+    run_git_command(&path, &args).map(|_| ())
+}
+
+/// 删除本地标签，指定 remote 时同时删除远程仓库中的标签
+#[tauri::command]
+pub async fn delete_tag(path: String, name: String, remote: Option<String>) -> Result<(), GitError> {
+    reject_option_like("标签名", &name)?;
+    if let Some(remote) = remote.as_deref() {
+        reject_option_like("远程仓库名", remote)?;
+        let tag_ref = format!("refs/tags/{}", name);
+        run_git_command(&path, &["push", remote, "--delete", &tag_ref])?;
+    }
+    run_git_command(&path, &["tag", "-d", &name]).map(|_| ())
+}
+
+/// 推送标签到远程仓库，不指定名称时推送全部标签
+#[tauri::command]
+pub async fn push_tags(path: String, remote: String, name: Option<String>) -> Result<String, GitError> {
+    reject_option_like("远程仓库名", &remote)?;
+    match name {
+        Some(name) => run_git_command(&path, &["push", &remote, &format!("refs/tags/{}", name)]),
+        None => run_git_command(&path, &["push", &remote, "--tags"]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_option_like_names() {
+        assert!(reject_option_like("标签名", "--delete").is_err());
+        assert!(reject_option_like("标签名", "v1.0-rc").is_ok());
+    }
+
+    #[test]
+    fn parses_annotated_and_lightweight_tags() {
+        let output = "v1.1\x1ftag\x1fc2\x1ft2\x1f版本 1.1\x1fAlice\x1f2024-05-02T10:00:00+08:00\n\
+                      v1.0\x1fcommit\x1f\x1fc1\x1ffix: typo\x1f\x1f2024-05-01T10:00:00+08:00";
+        let tags = parse_tags(output);
+        assert_eq!(tags.len(), 2);
+        assert!(tags[0].annotated);
+        assert_eq!((tags[0].commit.as_str(), tags[0].message.as_deref()), ("c2", Some("版本 1.1")));
+        assert!(!tags[1].annotated);
+        assert_eq!((tags[1].commit.as_str(), tags[1].message.as_deref()), ("c1", None));
+    }
+
+    #[test]
+    fn normalizes_remote_urls() {
+        let expected = "github.com/en-o/codeshelf";
+        for url in [
+            "git@github.com:en-o/codeshelf.git",
+            "https://github.com/en-o/codeshelf",
+            "ssh://git@github.com:22/en-o/codeshelf.git/",
+            "https://user@GitHub.com/En-o/CodeShelf.git",
+        ] {
+            assert_eq!(normalize_remote_url(url), expected, "{}", url);
+        }
+        // 本地路径不会被当成 scp 写法的主机名
+        assert_eq!(normalize_remote_url("C:\\repo\\app.git"), "/c:/repo/app");
+        assert_eq!(normalize_remote_url("C:/repo/app"), "/c:/repo/app");
+        assert_eq!(normalize_remote_url("/srv/git/app.git"), "/srv/git/app");
+        assert_eq!(normalize_remote_url("./a:b"), "/./a:b");
+        // 拆分时保留原来的大小写
+        assert_eq!(parse_remote_url("git@gitlab.example.com:Team/App.git"), ("gitlab.example.com", "Team/App".to_string()));
+    }
+
+    #[test]
+    fn classifies_git_failures() {
+        assert_eq!(
+            GitError::classify("fatal: not a git repository (or any of the parent directories): .git", ""),
+            GitError::NotARepo
+        );
+        assert_eq!(
+            GitError::classify("fatal: no upstream configured for branch 'main'", ""),
+            GitError::NoUpstream
+        );
+        assert_eq!(GitError::classify("fatal: HEAD does not point to a branch", ""), GitError::DetachedHead);
+        assert!(matches!(
+            GitError::classify("fatal: unable to access 'https://example.com/': The requested URL returned error: 403", ""),
+            GitError::AuthRequired(_)
+        ));
+        assert!(matches!(
+            GitError::classify("fatal: unable to access 'https://example.com/': Could not resolve host: example.com", ""),
+            GitError::NetworkUnreachable(_)
+        ));
+        assert!(matches!(
+            GitError::classify("error: Your local changes to the following files would be overwritten by merge:", ""),
+            GitError::DirtyWorktree(_)
+        ));
+        assert_eq!(
+            GitError::classify("", "CONFLICT (content): Merge conflict in a.txt\nAutomatic merge failed"),
+            GitError::MergeConflict("CONFLICT (content): Merge conflict in a.txt\nAutomatic merge failed".to_string())
+        );
+        assert_eq!(GitError::classify("error: pathspec 'x' did not match", ""), GitError::Failed("error: pathspec 'x' did not match".to_string()));
+    }
+}
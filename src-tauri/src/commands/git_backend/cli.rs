@@ -0,0 +1,591 @@
+// 调用 git 命令的实现：作为 libgit2 读取失败时的后备，以及推送、拉取等远程操作的执行方式
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use super::{GitBackend, LogQuery};
+use crate::commands::git::{
+    BranchInfo, CommitFileChange, CommitInfo, GitError, GitStatus, RemoteInfo, GIT_ENGLISH_ENV,
+};
+use crate::commands::git_branch::{BranchDetail, BranchOverview};
+use crate::commands::git_diff::{self, DiffFileStatus, DiffTarget, FileDiff, MAX_DIFF_FILE_BYTES, MAX_DIFF_LINES};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+// Windows: CREATE_NO_WINDOW flag to hide console window
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// 提交格式：%x1f (Unit Separator) 分隔字段，避免提交信息中的特殊字符干扰解析
+const COMMIT_FORMAT: &str = "%H%x1f%h%x1f%s%x1f%an%x1f%ae%x1f%aI%x1f%b%x1f%D%x1f%P";
+
+/// 本地分支列表的格式：%1f 分隔分支名、是否当前分支、上游分支、与上游的差异、提交哈希、提交时间和标题
+const BRANCH_FORMAT: &str = "--format=%(refname:short)%1f%(HEAD)%1f%(upstream:short)%1f%(upstream:track)%1f%(objectname)%1f%(committerdate:iso-strict)%1f%(contents:subject)";
+
+/// 空树的对象 ID，根提交和它比较
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+pub(crate) fn run_git_command(path: &str, args: &[&str]) -> Result<String, GitError> {
+    let stdout = run_git_bytes(path, args)?;
+    Ok(String::from_utf8_lossy(&stdout).trim().to_string())
+}
+
+/// 运行 git 命令并返回原始输出（读取文件内容等不能去掉首尾空白的场合）
+pub(crate) fn run_git_bytes(path: &str, args: &[&str]) -> Result<Vec<u8>, GitError> {
+    let mut cmd = Command::new("git");
+    cmd.args(["-C", path]).args(args).envs(GIT_ENGLISH_ENV);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    let output = cmd.output().map_err(GitError::from_io)?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(GitError::from_output(&output))
+    }
+}
+
+/// 运行 git 命令并把 input 写入标准输入（如 git apply 读取补丁）
+pub(crate) fn run_git_with_input(path: &str, args: &[&str], input: &str) -> Result<String, GitError> {
+    let mut cmd = Command::new("git");
+    cmd.args(["-C", path])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .envs(GIT_ENGLISH_ENV);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    let mut child = cmd.spawn().map_err(GitError::from_io)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| GitError::Failed(format!("写入 git 输入失败: {}", e)))?;
+    }
+    let output = child.wait_with_output().map_err(GitError::from_io)?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(GitError::from_output(&output))
+    }
+}
+
+/// git 命令的版本，未安装时为 None
+pub(super) fn cli_version() -> Option<String> {
+    let mut cmd = Command::new("git");
+    cmd.arg("--version");
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    let output = cmd.output().ok().filter(|o| o.status.success())?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().replace("git version ", ""))
+}
+
+/// 解析 git status --porcelain 输出中的文件路径
+/// 处理引号包裹的路径（包含空格或特殊字符时）
+fn unquote_git_path(path: &str) -> String {
+    let path = path.trim();
+    if path.starts_with('"') && path.ends_with('"') && path.len() >= 2 {
+        // 去除引号并处理转义字符
+        let inner = &path[1..path.len()-1];
+        inner
+            .replace("\\n", "\n")
+            .replace("\\t", "\t")
+            .replace("\\\\", "\\")
+            .replace("\\\"", "\"")
+    } else {
+        path.to_string()
+    }
+}
+
+/// 解析分支/标签引用
+fn parse_refs(refs_str: &str) -> Option<Vec<String>> {
+    let refs: Vec<String> = refs_str
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    (!refs.is_empty()).then_some(refs)
+}
+
+/// 解析父提交哈希
+fn parse_parent_hashes(hashes_str: &str) -> Option<Vec<String>> {
+    let hashes: Vec<String> = hashes_str.split_whitespace().map(|s| s.to_string()).collect();
+    (!hashes.is_empty()).then_some(hashes)
+}
+
+/// 解析 COMMIT_FORMAT 格式的一条记录
+fn parse_commit(record: &str) -> Option<CommitInfo> {
+    let parts: Vec<&str> = record.split('\x1f').collect();
+    if parts.len() < 9 {
+        return None;
+    }
+    let body = parts[6].trim();
+    Some(CommitInfo {
+        hash: parts[0].trim().to_string(),
+        short_hash: parts[1].trim().to_string(),
+        message: parts[2].trim().to_string(),
+        author: parts[3].trim().to_string(),
+        email: parts[4].trim().to_string(),
+        date: parts[5].trim().to_string(),
+        body: (!body.is_empty()).then(|| body.to_string()),
+        refs: parse_refs(parts[7]),
+        parent_hashes: parse_parent_hashes(parts[8]),
+        files_changed: None,
+        insertions: None,
+        deletions: None,
+    })
+}
+
+/// 解析 --numstat 输出（二进制文件的行数为 "-"，按 0 计）
+fn parse_numstat(output: &str) -> Vec<CommitFileChange> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.trim().split('\t').collect();
+            (parts.len() >= 3).then(|| CommitFileChange {
+                insertions: parts[0].parse().unwrap_or(0),
+                deletions: parts[1].parse().unwrap_or(0),
+                filename: parts[2..].join("\t"),
+            })
+        })
+        .collect()
+}
+
+/// 填入提交的文件数和增删行数
+fn fill_stats(path: &str, commit: &mut CommitInfo) {
+    if let Ok(output) = run_git_command(path, &["show", "--numstat", "--format=", &commit.hash]) {
+        let files = parse_numstat(&output);
+        commit.files_changed = Some(files.len() as u32);
+        commit.insertions = Some(files.iter().map(|f| f.insertions).sum());
+        commit.deletions = Some(files.iter().map(|f| f.deletions).sum());
+    }
+}
+
+/// 对象的大小（字节），对象不存在时（如新增文件的旧版本）为 None
+fn blob_size(path: &str, spec: &str) -> Option<u64> {
+    run_git_command(path, &["cat-file", "-s", spec]).ok()?.trim().parse().ok()
+}
+
+/// 未跟踪文件没有可比较的版本，整个文件作为新增
+fn untracked_file_diff(path: &str, file: &str) -> Result<FileDiff, GitError> {
+    let full_path = Path::new(path).join(file);
+    let read_error = |e: std::io::Error| GitError::Failed(format!("读取 {} 失败: {}", file, e));
+    let metadata = std::fs::metadata(&full_path).map_err(read_error)?;
+    // 未跟踪的嵌套仓库仍列为 dir/，没有可显示的内容
+    if metadata.is_dir() {
+        return Ok(FileDiff::unchanged(file));
+    }
+    let size = metadata.len();
+
+    let mut diff = if size > MAX_DIFF_FILE_BYTES {
+        let mut diff = FileDiff::unchanged(file);
+        diff.truncated = true;
+        diff
+    } else {
+        let bytes = std::fs::read(&full_path).map_err(read_error)?;
+        // 与 git 相同，开头 8000 字节内有 NUL 视为二进制文件
+        if bytes.iter().take(8000).any(|b| *b == 0) {
+            let mut diff = FileDiff::unchanged(file);
+            diff.binary = true;
+            diff
+        } else {
+            let content = String::from_utf8_lossy(&bytes);
+            // 保留 CRLF 文件行尾的 \r
+            let lines: Vec<&str> = content.split_inclusive('\n').map(|l| l.strip_suffix('\n').unwrap_or(l)).collect();
+            let mut text = format!("--- /dev/null\n+++ b/{}\n@@ -0,0 +1,{} @@\n", file, lines.len());
+            for line in &lines {
+                text.push('+');
+                text.push_str(line);
+                text.push('\n');
+            }
+            if !content.is_empty() && !content.ends_with('\n') {
+                text.push_str("\\ No newline at end of file\n");
+            }
+            git_diff::parse_unified_diff(&text, MAX_DIFF_LINES)
+        }
+    };
+    diff.status = DiffFileStatus::Added;
+    diff.old_path = None;
+    diff.new_path = Some(file.to_string());
+    Ok(diff)
+}
+
+pub(super) struct CliBackend;
+
+impl GitBackend for CliBackend {
+    fn status(&self, path: &str) -> Result<GitStatus, GitError> {
+        let branch = run_git_command(path, &["rev-parse", "--abbrev-ref", "HEAD"])
+            .unwrap_or_else(|_| "unknown".to_string());
+
+        // -uall 逐个列出未跟踪目录中的文件，而不是只列出 dir/
+        let status_output = run_git_command(path, &["status", "--porcelain", "-uall"])?;
+
+        let mut staged = Vec::new();
+        let mut unstaged = Vec::new();
+        let mut untracked = Vec::new();
+
+        for line in status_output.lines() {
+            if line.len() < 3 {
+                continue;
+            }
+            let status = &line[0..2];
+            // 跳过状态码后的所有空白字符，更稳健地获取文件路径
+            let file = unquote_git_path(line[2..].trim_start());
+            if file.is_empty() {
+                continue;
+            }
+
+            match status.chars().next() {
+                Some('?') => untracked.push(file),
+                Some(' ') => unstaged.push(file),
+                Some(_) => {
+                    if status.chars().nth(1) == Some(' ') {
+                        staged.push(file);
+                    } else {
+                        staged.push(file.clone());
+                        unstaged.push(file);
+                    }
+                }
+                None => {}
+            }
+        }
+
+        let (ahead, behind) = super::counts_or_zero(path, self.ahead_behind(path));
+        Ok(GitStatus {
+            branch,
+            is_clean: staged.is_empty() && unstaged.is_empty() && untracked.is_empty(),
+            staged,
+            unstaged,
+            untracked,
+            ahead,
+            behind,
+        })
+    }
+
+    fn ahead_behind(&self, path: &str) -> Result<(u32, u32), GitError> {
+        let output = run_git_command(path, &["rev-list", "--left-right", "--count", "HEAD...@{upstream}"])?;
+        let mut parts = output.split_whitespace().map(|n| n.parse().unwrap_or(0));
+        Ok((parts.next().unwrap_or(0), parts.next().unwrap_or(0)))
+    }
+
+    fn log(&self, path: &str, query: &LogQuery) -> Result<Vec<CommitInfo>, GitError> {
+        // 与 libgit2 的排序相同：按时间倒序，但父提交不会排在子提交之前
+        let mut args = vec![
+            "log".to_string(),
+            "--date-order".to_string(),
+            format!("-{}", query.limit),
+            format!("--format=%x1e{}", COMMIT_FORMAT),
+        ];
+        if query.skip > 0 {
+            args.push(format!("--skip={}", query.skip));
+        }
+        if let Some(author) = query.author {
+            args.push(format!("--author={}", author));
+        }
+        if let Some(grep) = query.grep {
+            args.push(format!("--grep={}", grep));
+        }
+        if query.all_refs {
+            args.extend(["--branches", "--remotes", "--tags"].map(String::from));
+        }
+        // 之后的参数都是引用，以 - 开头的引用名不会被当成选项
+        args.push("--end-of-options".to_string());
+        // 指定引用（如 origin/main）时获取该引用的提交历史
+        if let Some(rev) = query.rev {
+            args.push(rev.to_string());
+        }
+        args.extend(query.revs.iter().cloned());
+        if query.all_refs {
+            args.push("HEAD".to_string());
+        }
+        // 引用和路径同名时避免歧义
+        args.push("--".to_string());
+
+        let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        let output = run_git_command(path, &args_ref)?;
+
+        let mut commits: Vec<CommitInfo> = output
+            .split('\x1e')
+            .filter(|s| !s.trim().is_empty())
+            .filter_map(parse_commit)
+            .collect();
+        if query.with_stats {
+            for commit in &mut commits {
+                fill_stats(path, commit);
+            }
+        }
+        Ok(commits)
+    }
+
+    fn commit(&self, path: &str, rev: &str) -> Result<CommitInfo, GitError> {
+        let output = run_git_command(path, &["show", &format!("--format={}", COMMIT_FORMAT), "-s", "--end-of-options", rev])?;
+        let mut commit = parse_commit(output.trim()).ok_or_else(|| GitError::Failed("Invalid commit format".to_string()))?;
+        fill_stats(path, &mut commit);
+        Ok(commit)
+    }
+
+    fn commit_files(&self, path: &str, rev: &str) -> Result<Vec<CommitFileChange>, GitError> {
+        let output = run_git_command(path, &["show", "--numstat", "--format=", "--end-of-options", rev])?;
+        Ok(parse_numstat(&output))
+    }
+
+    fn branches(&self, path: &str) -> Result<Vec<BranchInfo>, GitError> {
+        let output = run_git_command(path, &["branch", "-a", "-vv"])?;
+
+        let branches = output
+            .lines()
+            .map(|line| {
+                let is_current = line.starts_with('*');
+                let line = line.trim_start_matches(['*', ' '].as_ref());
+                let name = line.split_whitespace().next().unwrap_or("").to_string();
+                let is_remote = name.starts_with("remotes/");
+
+                // Extract upstream from [origin/branch] format
+                let upstream = line.find('[').and_then(|start| {
+                    line[start..]
+                        .find(']')
+                        .map(|end| line[start + 1..start + end].split(':').next().unwrap_or("").to_string())
+                });
+
+                BranchInfo {
+                    name: name.trim_start_matches("remotes/").to_string(),
+                    is_current,
+                    is_remote,
+                    upstream,
+                }
+            })
+            .collect();
+        Ok(branches)
+    }
+
+    fn branch_overview(&self, path: &str) -> Result<BranchOverview, GitError> {
+        let output = run_git_command(path, &["for-each-ref", "refs/heads", BRANCH_FORMAT])?;
+        let rows: Vec<Vec<&str>> = output.lines().map(|line| line.split('\x1f').collect()).collect();
+        let has_local = |name: &str| rows.iter().any(|row| row[0] == name);
+
+        // 默认分支：本地有这个分支时和本地分支比较，否则和远程分支比较
+        let remote_head = run_git_command(path, &["symbolic-ref", "-q", "refs/remotes/origin/HEAD"]).ok();
+        let default_branch = remote_head
+            .as_deref()
+            .and_then(|target| target.strip_prefix("refs/remotes/origin/"))
+            .map(str::to_string)
+            .or_else(|| ["main", "master"].into_iter().find(|name| has_local(name)).map(str::to_string));
+        let compare_ref = default_branch.as_deref().and_then(|name| {
+            if has_local(name) {
+                Some(format!("refs/heads/{}", name))
+            } else {
+                remote_head.clone()
+            }
+        });
+
+        let mut mainline: Option<HashSet<String>> = None;
+        let mut branches = Vec::new();
+        for row in &rows {
+            let [name, head, upstream, track, hash, date, subject] = row.as_slice() else {
+                continue;
+            };
+            let upstream = Some(upstream.to_string()).filter(|u| !u.is_empty());
+            let counts = upstream.as_ref().and_then(|_| parse_track(track));
+            let default_counts = compare_ref.as_deref().and_then(|target| {
+                let range = format!("refs/heads/{}...{}", name, target);
+                let output = run_git_command(path, &["rev-list", "--left-right", "--count", &range]).ok()?;
+                let (ahead, behind) = output.split_once('\t')?;
+                Some((ahead.trim().parse().ok()?, behind.trim().parse().ok()?))
+            });
+            // 与 libgit2 实现相同，指向默认分支主线上提交的分支不算作已合并
+            let merged = match (default_counts, compare_ref.as_deref()) {
+                (Some((0, _)), Some(target)) => {
+                    if mainline.is_none() {
+                        let history = run_git_command(path, &["rev-list", "--first-parent", target])?;
+                        mainline = Some(history.lines().map(str::to_string).collect());
+                    }
+                    !mainline.as_ref().is_some_and(|history| history.contains(*hash))
+                }
+                _ => false,
+            };
+
+            branches.push(BranchDetail {
+                name: name.to_string(),
+                is_current: *head == "*",
+                upstream_gone: upstream.is_some() && counts.is_none(),
+                upstream,
+                ahead: counts.map(|(ahead, _)| ahead),
+                behind: counts.map(|(_, behind)| behind),
+                default_ahead: default_counts.map(|(ahead, _)| ahead),
+                default_behind: default_counts.map(|(_, behind)| behind),
+                merged,
+                last_commit_hash: hash.to_string(),
+                last_commit_message: subject.to_string(),
+                last_commit_date: date.to_string(),
+            });
+        }
+        branches.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(BranchOverview { default_branch, branches })
+    }
+
+    fn remotes(&self, path: &str) -> Result<Vec<RemoteInfo>, GitError> {
+        let output = run_git_command(path, &["remote", "-v"])?;
+        let mut remotes: HashMap<String, RemoteInfo> = HashMap::new();
+
+        for line in output.lines() {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 2 {
+                continue;
+            }
+            let name = parts[0].to_string();
+            let url = parts[1].to_string();
+            let remote_type = parts.get(2).unwrap_or(&"");
+
+            let entry = remotes.entry(name.clone()).or_insert(RemoteInfo {
+                name,
+                url: url.clone(),
+                fetch_url: None,
+                push_url: None,
+            });
+            if remote_type.contains("fetch") {
+                entry.fetch_url = Some(url);
+            } else if remote_type.contains("push") {
+                entry.push_url = Some(url);
+            }
+        }
+        Ok(remotes.into_values().collect())
+    }
+
+    fn root_commits(&self, path: &str) -> Result<Vec<String>, GitError> {
+        // 空仓库没有 HEAD，rev-list 会失败
+        let output = run_git_command(path, &["rev-list", "--max-parents=0", "HEAD"]).unwrap_or_default();
+        Ok(output.lines().map(|line| line.trim().to_string()).filter(|line| !line.is_empty()).collect())
+    }
+
+    fn file_diff(&self, path: &str, target: DiffTarget, file: &str, context_lines: u32) -> Result<FileDiff, GitError> {
+        // 比较的提交放在 --end-of-options 之后，以 - 开头的引用名不会被当成选项
+        let mut base = vec![];
+        match target {
+            DiffTarget::WorkingTree => {
+                if run_git_command(path, &["ls-files", "--error-unmatch", "--", file]).is_err() {
+                    return untracked_file_diff(path, file);
+                }
+            }
+            DiffTarget::Staged => base.push("--cached".to_string()),
+            DiffTarget::Commit(rev) => {
+                let parent = format!("{}^", rev);
+                let parent = match run_git_command(path, &["rev-parse", "--verify", "-q", "--end-of-options", &parent]) {
+                    Ok(_) => parent,
+                    Err(_) => EMPTY_TREE.to_string(),
+                };
+                base.extend(["--end-of-options".to_string(), parent, rev.to_string()]);
+            }
+        }
+        let base: Vec<&str> = base.iter().map(String::as_str).collect();
+
+        // 按路径过滤时 git 只在过滤范围内检测重命名，先找出重命名前后的两个路径
+        let mut args = vec!["-c", "core.quotePath=false", "diff", "--name-status", "-M"];
+        args.extend(&base);
+        let names = run_git_command(path, &args)?;
+        let (status, paths) = names
+            .lines()
+            .map(|line| line.split('\t').collect::<Vec<_>>())
+            .find(|fields| fields[1..].contains(&file))
+            .map(|fields| (fields[0].chars().next(), fields[1..].to_vec()))
+            .unwrap_or_else(|| (None, vec![file]));
+        let (old_path, new_path) = match paths.as_slice() {
+            [old_path, new_path] => (*old_path, *new_path),
+            _ => (file, file),
+        };
+
+        // 与未跟踪文件相同，超过上限的文件不读取内容
+        let new_size = match target {
+            DiffTarget::WorkingTree => std::fs::metadata(Path::new(path).join(new_path)).map(|m| m.len()).ok(),
+            DiffTarget::Staged => blob_size(path, &format!(":{}", new_path)),
+            DiffTarget::Commit(rev) => blob_size(path, &format!("{}:{}", rev, new_path)),
+        };
+        let old_rev = match target {
+            DiffTarget::WorkingTree => "",
+            DiffTarget::Staged => "HEAD",
+            // base 为 ["--end-of-options", 父提交, 提交]
+            DiffTarget::Commit(_) => base[1],
+        };
+        let old_size = blob_size(path, &format!("{}:{}", old_rev, old_path));
+        let too_large = old_size.into_iter().chain(new_size).any(|size| size > MAX_DIFF_FILE_BYTES);
+        let mut diff = if status.is_some() && too_large {
+            let mut diff = FileDiff::unchanged(file);
+            diff.status = match status {
+                Some('A') => DiffFileStatus::Added,
+                Some('D') => DiffFileStatus::Deleted,
+                Some('R') => DiffFileStatus::Renamed,
+                Some('C') => DiffFileStatus::Copied,
+                _ => DiffFileStatus::Modified,
+            };
+            diff.truncated = true;
+            diff
+        } else {
+            let context = format!("-U{}", context_lines);
+            let mut args = vec!["-c", "core.quotePath=false", "diff", "-M", &context];
+            args.extend(&base);
+            args.push("--");
+            args.extend(&paths);
+            let text = run_git_command(path, &args)?;
+            if text.is_empty() {
+                return Ok(FileDiff::unchanged(file));
+            }
+            git_diff::parse_unified_diff(&text, MAX_DIFF_LINES)
+        };
+        diff.old_path = (diff.status != DiffFileStatus::Added).then(|| old_path.to_string());
+        diff.new_path = (diff.status != DiffFileStatus::Deleted).then(|| new_path.to_string());
+        Ok(diff)
+    }
+}
+
+/// 解析 %(upstream:track)："[ahead 1, behind 2]"、"[gone]" 或空（与上游一致）
+///
+/// 返回 None 表示上游分支已删除
+fn parse_track(track: &str) -> Option<(u32, u32)> {
+    let track = track.trim().trim_start_matches('[').trim_end_matches(']');
+    if track == "gone" {
+        return None;
+    }
+    let (mut ahead, mut behind) = (0, 0);
+    for part in track.split(", ") {
+        if let Some(n) = part.strip_prefix("ahead ") {
+            ahead = n.parse().unwrap_or(0);
+        } else if let Some(n) = part.strip_prefix("behind ") {
+            behind = n.parse().unwrap_or(0);
+        }
+    }
+    Some((ahead, behind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commit_records_and_numstat() {
+        let record = "abc123\x1fabc\x1ffix: typo\x1fAlice\x1fa@example.com\x1f2024-05-01T10:00:00+08:00\x1f\n\x1fHEAD -> main, tag: v1.0\x1fp1 p2";
+        let commit = parse_commit(record).unwrap();
+        assert_eq!(commit.message, "fix: typo");
+        assert_eq!(commit.body, None);
+        assert_eq!(commit.refs, Some(vec!["HEAD -> main".to_string(), "tag: v1.0".to_string()]));
+        assert_eq!(commit.parent_hashes, Some(vec!["p1".to_string(), "p2".to_string()]));
+
+        let files = parse_numstat("3\t1\tsrc/main.rs\n-\t-\tlogo.png\n0\t0\tdocs/{a.md => b.md}\n");
+        assert_eq!(files.len(), 3);
+        assert_eq!((files[0].insertions, files[0].deletions), (3, 1));
+        assert_eq!((files[1].insertions, files[1].deletions), (0, 0));
+        assert_eq!(files[2].filename, "docs/{a.md => b.md}");
+    }
+
+    #[test]
+    fn parses_upstream_track() {
+        assert_eq!(parse_track("[ahead 2, behind 1]"), Some((2, 1)));
+        assert_eq!(parse_track("[behind 3]"), Some((0, 3)));
+        assert_eq!(parse_track(""), Some((0, 0)));
+        assert_eq!(parse_track("[gone]"), None);
+    }
+}
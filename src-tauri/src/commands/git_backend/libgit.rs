@@ -0,0 +1,556 @@
+// libgit2 实现：在进程内读取仓库，不启动 git 进程
+
+use chrono::{DateTime, FixedOffset};
+use git2::{
+    Branch, BranchType, Commit, Delta, Diff, DiffFile, DiffFindOptions, DiffOptions, ErrorClass, ErrorCode, Oid, Patch,
+    Repository, Sort, Status, StatusOptions,
+};
+use std::collections::{HashMap, HashSet};
+
+use super::{GitBackend, LogQuery};
+use crate::commands::git::{BranchInfo, CommitFileChange, CommitInfo, GitError, GitStatus, RemoteInfo};
+use crate::commands::git_branch::{BranchDetail, BranchOverview};
+use crate::commands::git_diff::{self, DiffFileStatus, DiffTarget, FileDiff, MAX_DIFF_FILE_BYTES, MAX_DIFF_LINES};
+
+fn err(e: git2::Error) -> GitError {
+    if e.class() == ErrorClass::Repository && e.code() == ErrorCode::NotFound {
+        GitError::NotARepo
+    } else {
+        GitError::Failed(e.message().to_string())
+    }
+}
+
+fn open(path: &str) -> Result<Repository, GitError> {
+    Repository::discover(path).map_err(err)
+}
+
+fn lossy(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).trim().to_string()
+}
+
+/// 与 git log 的 %aI 相同的 ISO 8601 格式（保留作者所在时区）
+fn format_time(time: git2::Time) -> String {
+    let offset = FixedOffset::east_opt(time.offset_minutes() * 60).unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+    DateTime::from_timestamp(time.seconds(), 0)
+        .map(|t| t.with_timezone(&offset).format("%Y-%m-%dT%H:%M:%S%:z").to_string())
+        .unwrap_or_default()
+}
+
+/// 当前分支名：分离 HEAD 时为 "HEAD"，还没有提交时为将要创建的分支名
+fn current_branch(repo: &Repository) -> String {
+    match repo.head() {
+        Ok(head) if head.is_branch() => head.shorthand().unwrap_or("HEAD").to_string(),
+        Ok(_) => "HEAD".to_string(),
+        Err(_) => repo
+            .find_reference("HEAD")
+            .ok()
+            .and_then(|head| head.symbolic_target().map(|t| t.trim_start_matches("refs/heads/").to_string()))
+            .unwrap_or_else(|| "unknown".to_string()),
+    }
+}
+
+/// 默认分支名和用来比较的提交：本地有这个分支时用本地分支，否则用远程分支
+fn default_branch(repo: &Repository) -> Option<(String, Oid)> {
+    let remote_head = repo
+        .find_reference("refs/remotes/origin/HEAD")
+        .ok()
+        .and_then(|head| head.symbolic_target().map(str::to_string));
+    let name = remote_head
+        .as_deref()
+        .and_then(|target| target.strip_prefix("refs/remotes/origin/"))
+        .map(str::to_string)
+        .or_else(|| {
+            ["main", "master"]
+                .into_iter()
+                .find(|name| repo.find_branch(name, BranchType::Local).is_ok())
+                .map(str::to_string)
+        })?;
+    let oid = repo
+        .find_branch(&name, BranchType::Local)
+        .ok()
+        .and_then(|branch| branch.get().target())
+        .or_else(|| remote_head.and_then(|target| repo.refname_to_id(&target).ok()))?;
+    Some((name, oid))
+}
+
+/// 默认分支沿第一个父提交的历史（包括默认分支本身）
+fn first_parent_history(repo: &Repository, tip: Oid) -> Result<HashSet<Oid>, GitError> {
+    let mut walk = repo.revwalk().map_err(err)?;
+    walk.push(tip).map_err(err)?;
+    walk.simplify_first_parent().map_err(err)?;
+    walk.map(|oid| oid.map_err(err)).collect()
+}
+
+/// 各提交上的分支和标签，写法与 git log 的 %D 相同（HEAD -> main, tag: v1.0, origin/main）
+fn decorations(repo: &Repository) -> HashMap<Oid, Vec<String>> {
+    let mut map: HashMap<Oid, Vec<String>> = HashMap::new();
+    let head = repo.head().ok();
+    let head_branch = head.as_ref().filter(|h| h.is_branch()).and_then(|h| h.shorthand().map(str::to_string));
+    if let Some(oid) = head.as_ref().and_then(|h| h.target()) {
+        let label = match &head_branch {
+            Some(branch) => format!("HEAD -> {}", branch),
+            None => "HEAD".to_string(),
+        };
+        map.entry(oid).or_default().push(label);
+    }
+
+    let (mut tags, mut locals, mut remotes) = (Vec::new(), Vec::new(), Vec::new());
+    if let Ok(references) = repo.references() {
+        for reference in references.flatten() {
+            let (Some(name), Some(short)) = (reference.name(), reference.shorthand()) else { continue };
+            let Ok(commit) = reference.peel_to_commit() else { continue };
+            if name.starts_with("refs/tags/") {
+                tags.push((commit.id(), format!("tag: {}", short)));
+            } else if name.starts_with("refs/heads/") {
+                if head_branch.as_deref() != Some(short) {
+                    locals.push((commit.id(), short.to_string()));
+                }
+            } else if name.starts_with("refs/remotes/") {
+                remotes.push((commit.id(), short.to_string()));
+            }
+        }
+    }
+    for (oid, label) in tags.into_iter().chain(locals).chain(remotes) {
+        map.entry(oid).or_default().push(label);
+    }
+    map
+}
+
+/// 提交相对第一个父提交的差异（检测重命名）
+fn commit_diff<'r>(repo: &'r Repository, commit: &Commit, options: Option<&mut DiffOptions>) -> Result<Diff<'r>, GitError> {
+    let tree = commit.tree().map_err(err)?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree().map_err(err)?),
+        Err(_) => None,
+    };
+    let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), options).map_err(err)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true))).map_err(err)?;
+    Ok(diff)
+}
+
+fn delta_path(file: DiffFile) -> Option<String> {
+    file.path().map(|p| p.to_string_lossy().to_string())
+}
+
+/// 按比较目标生成差异并检测重命名
+fn target_diff<'r>(repo: &'r Repository, target: DiffTarget, options: &mut DiffOptions) -> Result<Diff<'r>, GitError> {
+    match target {
+        DiffTarget::WorkingTree => {
+            options.include_untracked(true).recurse_untracked_dirs(true).show_untracked_content(true);
+            let mut diff = repo.diff_index_to_workdir(None, Some(options)).map_err(err)?;
+            // 未暂存的重命名表现为删除旧文件加未跟踪的新文件
+            diff.find_similar(Some(DiffFindOptions::new().renames(true).for_untracked(true))).map_err(err)?;
+            Ok(diff)
+        }
+        DiffTarget::Staged => {
+            // 还没有提交时和空树比较
+            let head_tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+            let mut diff = repo.diff_tree_to_index(head_tree.as_ref(), None, Some(options)).map_err(err)?;
+            diff.find_similar(Some(DiffFindOptions::new().renames(true))).map_err(err)?;
+            Ok(diff)
+        }
+        DiffTarget::Commit(rev) => {
+            let commit = repo.revparse_single(rev).and_then(|obj| obj.peel_to_commit()).map_err(err)?;
+            commit_diff(repo, &commit, Some(options))
+        }
+    }
+}
+
+/// 按路径过滤后看不到重命名的另一半，在完整的差异中找出重命名前后的另一个路径
+fn rename_counterpart(repo: &Repository, target: DiffTarget, file: &str) -> Result<Option<String>, GitError> {
+    let mut options = DiffOptions::new();
+    options.max_size(MAX_DIFF_FILE_BYTES as i64);
+    let diff = target_diff(repo, target, &mut options)?;
+    let counterpart = diff.deltas().filter(|delta| delta.status() == Delta::Renamed).find_map(|delta| {
+        let (old_path, new_path) = (delta_path(delta.old_file()), delta_path(delta.new_file()));
+        if old_path.as_deref() == Some(file) {
+            new_path
+        } else if new_path.as_deref() == Some(file) {
+            old_path
+        } else {
+            None
+        }
+    });
+    Ok(counterpart)
+}
+
+fn commit_info(repo: &Repository, commit: &Commit, decorations: &HashMap<Oid, Vec<String>>, with_stats: bool) -> CommitInfo {
+    let hash = commit.id().to_string();
+    let short_hash = commit
+        .as_object()
+        .short_id()
+        .ok()
+        .and_then(|id| id.as_str().map(str::to_string))
+        .unwrap_or_else(|| hash[..7].to_string());
+    let author = commit.author();
+    let body = commit.body_bytes().map(lossy).filter(|b| !b.is_empty());
+    let parents: Vec<String> = commit.parent_ids().map(|id| id.to_string()).collect();
+    let stats = if with_stats {
+        commit_diff(repo, commit, None).and_then(|diff| diff.stats().map_err(err)).ok()
+    } else {
+        None
+    };
+
+    CommitInfo {
+        short_hash,
+        message: lossy(commit.summary_bytes().unwrap_or_default()),
+        author: lossy(author.name_bytes()),
+        email: lossy(author.email_bytes()),
+        date: format_time(author.when()),
+        body,
+        refs: decorations.get(&commit.id()).cloned(),
+        parent_hashes: (!parents.is_empty()).then_some(parents),
+        files_changed: stats.as_ref().map(|s| s.files_changed() as u32),
+        insertions: stats.as_ref().map(|s| s.insertions() as u32),
+        deletions: stats.as_ref().map(|s| s.deletions() as u32),
+        hash,
+    }
+}
+
+pub(super) struct LibGitBackend;
+
+impl GitBackend for LibGitBackend {
+    fn status(&self, path: &str) -> Result<GitStatus, GitError> {
+        let repo = open(path)?;
+        let mut options = StatusOptions::new();
+        // 逐个列出未跟踪目录中的文件，与 git status --porcelain -uall 一致
+        options.include_untracked(true).recurse_untracked_dirs(true).include_ignored(false);
+        let statuses = repo.statuses(Some(&mut options)).map_err(err)?;
+
+        let index_changed = Status::INDEX_NEW
+            | Status::INDEX_MODIFIED
+            | Status::INDEX_DELETED
+            | Status::INDEX_RENAMED
+            | Status::INDEX_TYPECHANGE;
+        let worktree_changed = Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE;
+
+        let mut staged = Vec::new();
+        let mut unstaged = Vec::new();
+        let mut untracked = Vec::new();
+        for entry in statuses.iter() {
+            let status = entry.status();
+            let file = String::from_utf8_lossy(entry.path_bytes()).to_string();
+            // 冲突的文件同时出现在暂存区和工作区（与 git status 的 UU 一致）
+            let conflicted = status.contains(Status::CONFLICTED);
+            if conflicted || status.intersects(index_changed) {
+                staged.push(file.clone());
+            }
+            // git rm --cached 后文件既是暂存的删除，又是未跟踪文件
+            if status.contains(Status::WT_NEW) {
+                untracked.push(file);
+            } else if conflicted || status.intersects(worktree_changed) {
+                unstaged.push(file);
+            }
+        }
+
+        let (ahead, behind) = super::counts_or_zero(path, self.ahead_behind(path));
+        Ok(GitStatus {
+            branch: current_branch(&repo),
+            is_clean: staged.is_empty() && unstaged.is_empty() && untracked.is_empty(),
+            staged,
+            unstaged,
+            untracked,
+            ahead,
+            behind,
+        })
+    }
+
+    fn ahead_behind(&self, path: &str) -> Result<(u32, u32), GitError> {
+        let repo = open(path)?;
+        let head = repo.head().map_err(err)?;
+        if !head.is_branch() {
+            return Err(GitError::DetachedHead);
+        }
+        let local = head.target().ok_or(GitError::NoUpstream)?;
+        let upstream = match Branch::wrap(head).upstream() {
+            Ok(upstream) => upstream.get().target().ok_or(GitError::NoUpstream)?,
+            Err(e) if e.code() == ErrorCode::NotFound => return Err(GitError::NoUpstream),
+            Err(e) => return Err(err(e)),
+        };
+        let (ahead, behind) = repo.graph_ahead_behind(local, upstream).map_err(err)?;
+        Ok((ahead as u32, behind as u32))
+    }
+
+    fn log(&self, path: &str, query: &LogQuery) -> Result<Vec<CommitInfo>, GitError> {
+        let repo = open(path)?;
+        let mut walk = repo.revwalk().map_err(err)?;
+        // 按时间倒序，同时保证子提交排在父提交之前（提交图依赖这一点）
+        walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME).map_err(err)?;
+        let unborn = repo.head().is_err_and(|e| e.code() == ErrorCode::UnbornBranch);
+        for rev in query.rev.into_iter().chain(query.revs.iter().map(String::as_str)) {
+            if rev.contains("..") {
+                walk.push_range(rev).map_err(err)?;
+            } else {
+                let commit = repo.revparse_single(rev).and_then(|obj| obj.peel_to_commit()).map_err(err)?;
+                walk.push(commit.id()).map_err(err)?;
+            }
+        }
+        if query.all_refs {
+            // 不指向提交的引用（如指向树的标签）会被忽略
+            for glob in ["refs/heads/*", "refs/remotes/*", "refs/tags/*"] {
+                walk.push_glob(glob).map_err(err)?;
+            }
+            if !unborn {
+                walk.push_head().map_err(err)?;
+            }
+        } else if query.rev.is_none() && query.revs.is_empty() {
+            // 还没有任何提交
+            if unborn {
+                return Ok(Vec::new());
+            }
+            walk.push_head().map_err(err)?;
+        }
+
+        let decorations = decorations(&repo);
+        let mut commits = Vec::new();
+        let mut skipped = 0;
+        for oid in walk {
+            if commits.len() >= query.limit {
+                break;
+            }
+            let commit = repo.find_commit(oid.map_err(err)?).map_err(err)?;
+            if let Some(author) = query.author {
+                let signature = commit.author();
+                let who = format!("{} <{}>", lossy(signature.name_bytes()), lossy(signature.email_bytes()));
+                if !who.contains(author) {
+                    continue;
+                }
+            }
+            if let Some(grep) = query.grep {
+                if !String::from_utf8_lossy(commit.message_bytes()).contains(grep) {
+                    continue;
+                }
+            }
+            if skipped < query.skip {
+                skipped += 1;
+                continue;
+            }
+            commits.push(commit_info(&repo, &commit, &decorations, query.with_stats));
+        }
+        Ok(commits)
+    }
+
+    fn commit(&self, path: &str, rev: &str) -> Result<CommitInfo, GitError> {
+        let repo = open(path)?;
+        let commit = repo.revparse_single(rev).and_then(|obj| obj.peel_to_commit()).map_err(err)?;
+        Ok(commit_info(&repo, &commit, &decorations(&repo), true))
+    }
+
+    fn commit_files(&self, path: &str, rev: &str) -> Result<Vec<CommitFileChange>, GitError> {
+        let repo = open(path)?;
+        let commit = repo.revparse_single(rev).and_then(|obj| obj.peel_to_commit()).map_err(err)?;
+        let diff = commit_diff(&repo, &commit, None)?;
+
+        let mut files = Vec::new();
+        for (index, delta) in diff.deltas().enumerate() {
+            let old = delta_path(delta.old_file()).unwrap_or_default();
+            let new = delta_path(delta.new_file()).unwrap_or_default();
+            let filename = if delta.status() == Delta::Renamed && old != new {
+                format!("{} => {}", old, new)
+            } else if new.is_empty() {
+                old
+            } else {
+                new
+            };
+            // 二进制文件没有行数，按 0 计
+            let (insertions, deletions) = Patch::from_diff(&diff, index)
+                .ok()
+                .flatten()
+                .and_then(|patch| patch.line_stats().ok())
+                .map(|(_, added, removed)| (added as u32, removed as u32))
+                .unwrap_or((0, 0));
+            files.push(CommitFileChange { insertions, deletions, filename });
+        }
+        Ok(files)
+    }
+
+    fn branches(&self, path: &str) -> Result<Vec<BranchInfo>, GitError> {
+        let repo = open(path)?;
+        let mut branches = Vec::new();
+        for kind in [BranchType::Local, BranchType::Remote] {
+            let mut group: Vec<BranchInfo> = repo
+                .branches(Some(kind))
+                .map_err(err)?
+                .flatten()
+                .filter_map(|(branch, _)| {
+                    let name = branch.name().ok().flatten()?.to_string();
+                    let upstream = match kind {
+                        BranchType::Local => branch
+                            .upstream()
+                            .ok()
+                            .and_then(|upstream| upstream.name().ok().flatten().map(str::to_string)),
+                        BranchType::Remote => None,
+                    };
+                    Some(BranchInfo {
+                        name,
+                        is_current: branch.is_head(),
+                        is_remote: kind == BranchType::Remote,
+                        upstream,
+                    })
+                })
+                .collect();
+            group.sort_by(|a, b| a.name.cmp(&b.name));
+            branches.extend(group);
+        }
+        Ok(branches)
+    }
+
+    fn branch_overview(&self, path: &str) -> Result<BranchOverview, GitError> {
+        let repo = open(path)?;
+        let default = default_branch(&repo);
+        let mut mainline: Option<HashSet<Oid>> = None;
+        let mut branches = Vec::new();
+        for (branch, _) in repo.branches(Some(BranchType::Local)).map_err(err)?.flatten() {
+            let (Some(name), Some(oid)) = (branch.name().ok().flatten().map(str::to_string), branch.get().target()) else {
+                continue;
+            };
+            // 配置中的上游分支，远程分支被删除后仍然存在
+            let configured = repo
+                .branch_upstream_name(&format!("refs/heads/{}", name))
+                .ok()
+                .and_then(|upstream| upstream.as_str().map(str::to_string));
+            let upstream_oid = branch.upstream().ok().and_then(|upstream| upstream.get().target());
+            let (ahead, behind) = match upstream_oid.and_then(|upstream| repo.graph_ahead_behind(oid, upstream).ok()) {
+                Some((ahead, behind)) => (Some(ahead as u32), Some(behind as u32)),
+                None => (None, None),
+            };
+            let (default_ahead, default_behind) =
+                match default.as_ref().and_then(|(_, target)| repo.graph_ahead_behind(oid, *target).ok()) {
+                    Some((ahead, behind)) => (Some(ahead as u32), Some(behind as u32)),
+                    None => (None, None),
+                };
+            let commit = repo.find_commit(oid).map_err(err)?;
+            // 指向默认分支主线上的提交（与默认分支相同或更早）的分支没有自己的提交，
+            // 和快进合并后的分支无法区分，不算作已合并
+            let merged = match (default_ahead, &default) {
+                (Some(0), Some((_, target))) => {
+                    if mainline.is_none() {
+                        mainline = Some(first_parent_history(&repo, *target)?);
+                    }
+                    !mainline.as_ref().is_some_and(|history| history.contains(&oid))
+                }
+                _ => false,
+            };
+
+            branches.push(BranchDetail {
+                name,
+                is_current: branch.is_head(),
+                upstream_gone: configured.is_some() && upstream_oid.is_none(),
+                upstream: configured.map(|upstream| {
+                    let short = upstream.strip_prefix("refs/remotes/").or_else(|| upstream.strip_prefix("refs/heads/"));
+                    short.unwrap_or(&upstream).to_string()
+                }),
+                ahead,
+                behind,
+                default_ahead,
+                default_behind,
+                merged,
+                last_commit_hash: oid.to_string(),
+                last_commit_message: lossy(commit.summary_bytes().unwrap_or_default()),
+                last_commit_date: format_time(commit.committer().when()),
+            });
+        }
+        branches.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(BranchOverview { default_branch: default.map(|(name, _)| name), branches })
+    }
+
+    fn remotes(&self, path: &str) -> Result<Vec<RemoteInfo>, GitError> {
+        let repo = open(path)?;
+        let names = repo.remotes().map_err(err)?;
+        let mut remotes = Vec::new();
+        for name in names.iter().flatten() {
+            let remote = repo.find_remote(name).map_err(err)?;
+            let url = remote.url().unwrap_or("").to_string();
+            remotes.push(RemoteInfo {
+                name: name.to_string(),
+                fetch_url: Some(url.clone()),
+                push_url: Some(remote.pushurl().unwrap_or(&url).to_string()),
+                url,
+            });
+        }
+        Ok(remotes)
+    }
+
+    fn root_commits(&self, path: &str) -> Result<Vec<String>, GitError> {
+        let repo = open(path)?;
+        let mut walk = repo.revwalk().map_err(err)?;
+        // 空仓库没有 HEAD
+        if walk.push_head().is_err() {
+            return Ok(Vec::new());
+        }
+        let mut roots = Vec::new();
+        for oid in walk {
+            let commit = repo.find_commit(oid.map_err(err)?).map_err(err)?;
+            if commit.parent_count() == 0 {
+                roots.push(commit.id().to_string());
+            }
+        }
+        Ok(roots)
+    }
+
+    fn file_diff(&self, path: &str, target: DiffTarget, file: &str, context_lines: u32) -> Result<FileDiff, GitError> {
+        let repo = open(path)?;
+        let options = |paths: &[&str]| {
+            let mut options = DiffOptions::new();
+            // 超过上限的文件按二进制处理，不读取内容
+            options.context_lines(context_lines).max_size(MAX_DIFF_FILE_BYTES as i64).disable_pathspec_match(true);
+            for path in paths {
+                options.pathspec(path);
+            }
+            options
+        };
+
+        // 只比较这个文件；新增或删除的文件可能是重命名的一半，再带上另一半的路径比较
+        let mut diff = target_diff(&repo, target, &mut options(&[file]))?;
+        let maybe_renamed = diff
+            .deltas()
+            .next()
+            .is_some_and(|delta| matches!(delta.status(), Delta::Added | Delta::Untracked | Delta::Deleted));
+        if maybe_renamed {
+            if let Some(counterpart) = rename_counterpart(&repo, target, file)? {
+                diff = target_diff(&repo, target, &mut options(&[file, &counterpart]))?;
+            }
+        }
+
+        let found = diff.deltas().enumerate().find(|(_, delta)| {
+            delta_path(delta.new_file()).as_deref() == Some(file) || delta_path(delta.old_file()).as_deref() == Some(file)
+        });
+        let Some((index, delta)) = found else {
+            return Ok(FileDiff::unchanged(file));
+        };
+        let (old_path, new_path) = (delta_path(delta.old_file()), delta_path(delta.new_file()));
+        let workdir_size = match (target, repo.workdir(), &new_path) {
+            (DiffTarget::WorkingTree, Some(workdir), Some(new_path)) => {
+                std::fs::metadata(workdir.join(new_path)).map(|m| m.len()).unwrap_or(0)
+            }
+            _ => 0,
+        };
+        let too_large = [delta.old_file().size(), delta.new_file().size(), workdir_size]
+            .iter()
+            .any(|size| *size > MAX_DIFF_FILE_BYTES);
+        let status = delta.status();
+
+        let text = match Patch::from_diff(&diff, index).map_err(err)? {
+            Some(mut patch) => {
+                let buf = patch.to_buf().map_err(err)?;
+                String::from_utf8_lossy(&buf).to_string()
+            }
+            None => String::new(),
+        };
+        let mut result = git_diff::parse_unified_diff(&text, MAX_DIFF_LINES);
+        if too_large {
+            result.binary = false;
+            result.truncated = true;
+        }
+        result.status = match status {
+            Delta::Added | Delta::Untracked => DiffFileStatus::Added,
+            Delta::Deleted => DiffFileStatus::Deleted,
+            Delta::Renamed => DiffFileStatus::Renamed,
+            Delta::Copied => DiffFileStatus::Copied,
+            _ => result.status,
+        };
+        result.old_path = old_path.filter(|_| result.status != DiffFileStatus::Added);
+        result.new_path = new_path.filter(|_| result.status != DiffFileStatus::Deleted);
+        Ok(result)
+    }
+}
@@ -0,0 +1,229 @@
+// Git 后端 - 读取类操作（状态、提交历史、差异统计、分支和引用）在进程内通过 libgit2 完成，
+// 避免仪表盘、健康检查等批量读取时为每个仓库、每个提交启动 git 进程。
+// push、pull、fetch 等需要凭据或网络的操作仍然调用 git 命令（run_git_command）。
+
+mod cli;
+mod libgit;
+
+pub(crate) use cli::{run_git_bytes, run_git_command, run_git_with_input};
+
+use serde::Serialize;
+
+use crate::commands::git::{BranchInfo, CommitFileChange, CommitInfo, GitError, GitStatus, RemoteInfo};
+use crate::commands::git_branch::BranchOverview;
+use crate::commands::git_diff::{DiffTarget, FileDiff};
+
+/// 提交历史查询条件
+#[derive(Debug, Default)]
+pub(crate) struct LogQuery<'a> {
+    pub limit: usize,
+    /// 起始引用（分支、标签、提交），默认为 HEAD
+    pub rev: Option<&'a str>,
+    /// 另外的起始引用，与 rev 一起遍历
+    pub revs: &'a [String],
+    /// 从所有本地分支、远程分支、标签和 HEAD 开始遍历
+    pub all_refs: bool,
+    /// 跳过前面的提交（在作者、提交信息过滤之后计数）
+    pub skip: usize,
+    /// 作者名或邮箱包含的文本
+    pub author: Option<&'a str>,
+    /// 提交信息包含的文本
+    pub grep: Option<&'a str>,
+    /// 同时统计每个提交的文件数和增删行数
+    pub with_stats: bool,
+}
+
+/// 读取仓库信息的接口
+pub(crate) trait GitBackend {
+    fn status(&self, path: &str) -> Result<GitStatus, GitError>;
+
+    /// 当前分支相对上游分支的 (领先, 落后) 提交数，没有上游时为 (0, 0)
+    fn ahead_behind(&self, path: &str) -> Result<(u32, u32), GitError>;
+
+    fn log(&self, path: &str, query: &LogQuery) -> Result<Vec<CommitInfo>, GitError>;
+
+    /// 单个提交（含差异统计）
+    fn commit(&self, path: &str, rev: &str) -> Result<CommitInfo, GitError>;
+
+    /// 提交相对第一个父提交的文件变更
+    fn commit_files(&self, path: &str, rev: &str) -> Result<Vec<CommitFileChange>, GitError>;
+
+    fn branches(&self, path: &str) -> Result<Vec<BranchInfo>, GitError>;
+
+    /// 本地分支与上游分支、默认分支的关系和最后一次提交
+    fn branch_overview(&self, path: &str) -> Result<BranchOverview, GitError>;
+
+    fn remotes(&self, path: &str) -> Result<Vec<RemoteInfo>, GitError>;
+
+    /// 没有父提交的提交（空仓库时为空）
+    fn root_commits(&self, path: &str) -> Result<Vec<String>, GitError>;
+
+    /// 单个文件的差异，`file` 可以是重命名前或重命名后的路径
+    fn file_diff(&self, path: &str, target: DiffTarget, file: &str, context_lines: u32) -> Result<FileDiff, GitError>;
+}
+
+/// 后端信息（关于页面展示）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBackendInfo {
+    /// 内置 libgit2 的版本
+    pub embedded_version: String,
+    /// git 命令的版本，未安装时为 None（只影响推送、拉取等远程操作）
+    pub cli_version: Option<String>,
+}
+
+/// 用进程内实现读取，失败时（如 libgit2 不支持的仓库扩展）改用 git 命令
+pub(crate) fn read<T>(op: impl Fn(&dyn GitBackend) -> Result<T, GitError>) -> Result<T, GitError> {
+    match op(&libgit::LibGitBackend) {
+        Ok(value) => Ok(value),
+        // 仓库本身的状态，git 命令的结果也一样
+        Err(e @ (GitError::NotARepo | GitError::NoUpstream | GitError::DetachedHead)) => Err(e),
+        Err(e) => {
+            log::debug!("libgit2 读取失败，改用 git 命令: {}", e);
+            op(&cli::CliBackend)
+        }
+    }
+}
+
+/// 没有上游分支或不在分支上时没有领先/落后可言，按 0 计
+pub(crate) fn counts_or_zero(path: &str, counts: Result<(u32, u32), GitError>) -> (u32, u32) {
+    match counts {
+        Ok(counts) => counts,
+        Err(GitError::NoUpstream | GitError::DetachedHead) => (0, 0),
+        Err(e) => {
+            log::warn!("无法计算 {} 与上游分支的差异: {}", path, e);
+            (0, 0)
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_git_backend_info() -> GitBackendInfo {
+    let (major, minor, patch) = git2::Version::get().libgit2_version();
+    GitBackendInfo {
+        embedded_version: format!("libgit2 {}.{}.{}", major, minor, patch),
+        cli_version: cli::cli_version(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::git_diff::{DiffFileStatus, MAX_DIFF_FILE_BYTES};
+    use crate::test_support::TempDir;
+    use std::path::Path;
+
+    /// 暂存新增的文件和删除的文件
+    fn stage(repo: &git2::Repository, added: &[&str], removed: &[&str]) {
+        let mut index = repo.index().unwrap();
+        for file in added {
+            index.add_path(Path::new(file)).unwrap();
+        }
+        for file in removed {
+            index.remove_path(Path::new(file)).unwrap();
+        }
+        index.write().unwrap();
+    }
+
+    fn sorted_status(status: GitStatus) -> (Vec<String>, Vec<String>, Vec<String>) {
+        let sort = |mut files: Vec<String>| {
+            files.sort();
+            files
+        };
+        (sort(status.staged), sort(status.unstaged), sort(status.untracked))
+    }
+
+    #[test]
+    fn backends_report_the_same_status() {
+        let dir = TempDir::new("status");
+        let repo = dir.init_repo(&[("tracked.txt", "one\n")]);
+        dir.write("staged.txt", "new\n");
+        stage(&repo, &["staged.txt"], &[]);
+
+        dir.write("tracked.txt", "two\n");
+        dir.write("notes.md", "");
+        dir.write("generated/a/b.txt", "");
+        dir.write("generated/c.txt", "");
+
+        let path = dir.path().to_string_lossy().to_string();
+        let libgit = sorted_status(libgit::LibGitBackend.status(&path).unwrap());
+        let cli = sorted_status(cli::CliBackend.status(&path).unwrap());
+        assert_eq!(libgit, cli);
+        assert_eq!(libgit.2, vec!["generated/a/b.txt", "generated/c.txt", "notes.md"]);
+    }
+
+    #[test]
+    fn backends_diff_renamed_and_oversized_files() {
+        let lines: String = (0..50).map(|n| format!("line {}\n", n)).collect();
+        let dir = TempDir::new("file-diff");
+        let repo = dir.init_repo(&[("old.txt", &lines), ("big.txt", "small\n"), ("other.txt", "x\n")]);
+        std::fs::rename(dir.path().join("old.txt"), dir.path().join("new.txt")).unwrap();
+        stage(&repo, &["new.txt"], &["old.txt"]);
+        dir.write("big.txt", &"x".repeat(MAX_DIFF_FILE_BYTES as usize + 1));
+
+        let path = dir.path().to_string_lossy().to_string();
+        for backend in [&libgit::LibGitBackend as &dyn GitBackend, &cli::CliBackend] {
+            let renamed = backend.file_diff(&path, DiffTarget::Staged, "new.txt", 3).unwrap();
+            assert_eq!(renamed.status, DiffFileStatus::Renamed);
+            assert_eq!(renamed.old_path.as_deref(), Some("old.txt"));
+            assert_eq!(renamed.new_path.as_deref(), Some("new.txt"));
+
+            let big = backend.file_diff(&path, DiffTarget::WorkingTree, "big.txt", 3).unwrap();
+            assert_eq!((big.status, big.truncated, big.hunks.len()), (DiffFileStatus::Modified, true, 0));
+        }
+    }
+
+    #[test]
+    fn backends_diff_commits() {
+        let dir = TempDir::new("commit-diff");
+        let repo = dir.init_repo(&[("a.txt", "one\n")]);
+        let root = repo.head().unwrap().peel_to_commit().unwrap();
+        dir.write("a.txt", "two\n");
+        stage(&repo, &["a.txt"], &[]);
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let second = repo.commit(Some("HEAD"), &signature, &signature, "second", &tree, &[&root]).unwrap().to_string();
+        let root = root.id().to_string();
+
+        let path = dir.path().to_string_lossy().to_string();
+        for backend in [&libgit::LibGitBackend as &dyn GitBackend, &cli::CliBackend] {
+            let modified = backend.file_diff(&path, DiffTarget::Commit(&second), "a.txt", 3).unwrap();
+            assert_eq!((modified.status, modified.additions, modified.deletions), (DiffFileStatus::Modified, 1, 1));
+            // 根提交与空树比较
+            let added = backend.file_diff(&path, DiffTarget::Commit(&root), "a.txt", 3).unwrap();
+            assert_eq!(added.status, DiffFileStatus::Added);
+            assert_eq!(backend.commit_files(&path, &second).unwrap().len(), 1);
+        }
+    }
+
+    #[test]
+    fn backends_only_count_branches_with_own_commits_as_merged() {
+        let dir = TempDir::new("merged");
+        let repo = dir.init_repo(&[("a.txt", "one\n")]);
+        let root = repo.head().unwrap().peel_to_commit().unwrap();
+        let tree = root.tree().unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let commit = |message: &str, parents: &[&git2::Commit]| {
+            let oid = repo.commit(None, &signature, &signature, message, &tree, parents).unwrap();
+            repo.find_commit(oid).unwrap()
+        };
+        let second = commit("second", &[&root]);
+        let feature = commit("feature", &[&root]);
+        let merge = commit("merge feature", &[&second, &feature]);
+        let wip = commit("wip", &[&merge]);
+        for (name, target) in [("main", &merge), ("fresh", &merge), ("old", &root), ("feature", &feature), ("wip", &wip)] {
+            repo.branch(name, target, true).unwrap();
+        }
+        repo.set_head("refs/heads/main").unwrap();
+
+        let path = dir.path().to_string_lossy().to_string();
+        for backend in [&libgit::LibGitBackend as &dyn GitBackend, &cli::CliBackend] {
+            let overview = backend.branch_overview(&path).unwrap();
+            assert_eq!(overview.default_branch.as_deref(), Some("main"));
+            let merged: Vec<&str> = overview.branches.iter().filter(|b| b.merged).map(|b| b.name.as_str()).collect();
+            assert_eq!(merged, vec!["feature"]);
+            // fresh、old 指向主线上的提交（如快进合并后），没有自己的提交，不算未合并
+            assert_eq!(overview.unmerged_count(), 1);
+        }
+    }
+}
@@ -0,0 +1,431 @@
+// 批量 Git 操作 - 对一批项目并发执行 fetch / pull --ff-only / push，逐个推送进度并汇总结果
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+use tokio::process::Command;
+use tokio::sync::{watch, Semaphore};
+
+use crate::commands::git::{GitError, GIT_ENGLISH_ENV};
+use crate::commands::project;
+use crate::storage::{current_iso_time, generate_id, Project};
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+const BATCH_EVENT: &str = "git-batch-event";
+
+/// 默认同时处理的项目数
+const DEFAULT_CONCURRENCY: usize = 4;
+const MAX_CONCURRENCY: usize = 16;
+
+/// 内存中保留的批量任务报告数
+const MAX_REPORTS: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchOperation {
+    Fetch,
+    Pull,
+    Push,
+}
+
+/// 单个项目的处理结果
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum BatchOutcome {
+    /// 有新的提交被拉取或推送
+    Updated,
+    UpToDate,
+    /// 无法快进、推送被拒绝或本地修改冲突，需要手动处理
+    Conflicted,
+    AuthFailed,
+    /// 未设置上游分支等，不适合执行该操作
+    Skipped,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchProjectResult {
+    pub project_id: String,
+    pub name: String,
+    pub path: String,
+    pub outcome: BatchOutcome,
+    pub message: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSummary {
+    pub updated: usize,
+    pub up_to_date: usize,
+    pub conflicted: usize,
+    pub auth_failed: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+}
+
+impl BatchSummary {
+    fn from_results(results: &[BatchProjectResult]) -> Self {
+        let mut summary = Self::default();
+        for result in results {
+            let counter = match result.outcome {
+                BatchOutcome::Updated => &mut summary.updated,
+                BatchOutcome::UpToDate => &mut summary.up_to_date,
+                BatchOutcome::Conflicted => &mut summary.conflicted,
+                BatchOutcome::AuthFailed => &mut summary.auth_failed,
+                BatchOutcome::Skipped => &mut summary.skipped,
+                BatchOutcome::Failed => &mut summary.failed,
+                BatchOutcome::Cancelled => &mut summary.cancelled,
+            };
+            *counter += 1;
+        }
+        summary
+    }
+}
+
+/// 批量任务报告
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReport {
+    pub job_id: String,
+    pub operation: BatchOperation,
+    pub results: Vec<BatchProjectResult>,
+    pub summary: BatchSummary,
+    pub cancelled: bool,
+    pub started_at: String,
+    pub finished_at: String,
+    pub elapsed_ms: u64,
+}
+
+/// 已开始的批量任务
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchJob {
+    pub id: String,
+    pub operation: BatchOperation,
+    pub project_ids: Vec<String>,
+    pub started_at: String,
+}
+
+/// 批量任务事件（用于前端实时更新）
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum BatchEvent {
+    Started { job: BatchJob },
+    ProjectStarted { job_id: String, project_id: String },
+    Progress { job_id: String, result: BatchProjectResult, completed: usize, total: usize },
+    Completed { report: BatchReport },
+}
+
+/// 要处理的项目：指定 ID，或按分类、标签筛选（条件同时满足）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBatchInput {
+    pub operation: BatchOperation,
+    pub project_ids: Option<Vec<String>>,
+    pub category: Option<String>,
+    pub label: Option<String>,
+    pub concurrency: Option<usize>,
+}
+
+/// 进行中的任务的取消信号
+static JOBS: Lazy<Mutex<HashMap<String, watch::Sender<bool>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 最近完成的任务报告
+static REPORTS: Lazy<Mutex<VecDeque<BatchReport>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+fn emit(app: &AppHandle, event: BatchEvent) {
+    if let Err(e) = app.emit(BATCH_EVENT, &event) {
+        log::warn!("发送批量任务事件失败: {}", e);
+    }
+}
+
+fn select_projects(input: &GitBatchInput) -> Result<Vec<Project>, String> {
+    let projects = project::get_projects()?;
+    let selected: Vec<Project> = projects
+        .into_iter()
+        .filter(|p| input.project_ids.as_ref().map(|ids| ids.contains(&p.id)).unwrap_or(true))
+        .filter(|p| input.category.as_ref().map(|c| p.tags.contains(c)).unwrap_or(true))
+        .filter(|p| input.label.as_ref().map(|l| p.labels.contains(l)).unwrap_or(true))
+        .collect();
+    if selected.is_empty() {
+        return Err("没有符合条件的项目".to_string());
+    }
+    Ok(selected)
+}
+
+pub(crate) struct GitOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// 执行 git 命令；取消时结束 git 进程并返回 None
+pub(crate) async fn git(path: &str, args: &[&str], cancel: &mut watch::Receiver<bool>) -> Result<Option<GitOutput>, String> {
+    let mut cmd = Command::new("git");
+    cmd.arg("-C")
+        .arg(path)
+        .args(args)
+        // 没有终端可供输入，需要凭据时直接失败而不是挂起
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .envs(GIT_ENGLISH_ENV)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    tokio::select! {
+        output = cmd.output() => {
+            let output = output.map_err(|e| format!("执行 git 失败: {}", e))?;
+            Ok(Some(GitOutput {
+                success: output.status.success(),
+                stdout: String::from_utf8_lossy(&output.stdout).trim().to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            }))
+        }
+        _ = cancel.wait_for(|cancelled| *cancelled) => Ok(None),
+    }
+}
+
+/// 根据 git 的错误输出判断失败原因
+fn classify_error(stderr: &str) -> BatchOutcome {
+    match GitError::classify(stderr, "") {
+        GitError::AuthRequired(_) => BatchOutcome::AuthFailed,
+        GitError::MergeConflict(_) | GitError::DirtyWorktree(_) => BatchOutcome::Conflicted,
+        _ => BatchOutcome::Failed,
+    }
+}
+
+/// 错误输出中最有用的一行：被拒绝的引用，否则是最后一条非提示信息
+pub(crate) fn last_line(text: &str) -> String {
+    let lines = || text.lines().map(str::trim).filter(|l| !l.is_empty());
+    lines()
+        .find(|l| l.contains("[rejected]"))
+        .or_else(|| lines().rfind(|l| !l.starts_with("hint:")))
+        .unwrap_or("")
+        .to_string()
+}
+
+/// 对单个项目执行操作，返回结果和说明
+async fn run_one(path: &str, operation: BatchOperation, cancel: &mut watch::Receiver<bool>) -> Result<(BatchOutcome, String), String> {
+    macro_rules! git_or_cancel {
+        ($args:expr) => {
+            match git(path, $args, cancel).await? {
+                Some(output) => output,
+                None => return Ok((BatchOutcome::Cancelled, "已取消".to_string())),
+            }
+        };
+    }
+
+    if !std::path::Path::new(path).join(".git").exists() {
+        return Ok((BatchOutcome::Skipped, "不是 Git 仓库".to_string()));
+    }
+
+    match operation {
+        BatchOperation::Fetch => {
+            let refs = &["for-each-ref", "--format=%(refname) %(objectname)", "refs/remotes"];
+            let before = git_or_cancel!(refs);
+            let fetch = git_or_cancel!(&["fetch", "--all", "--prune"]);
+            if !fetch.success {
+                return Ok((classify_error(&fetch.stderr), last_line(&fetch.stderr)));
+            }
+            let after = git_or_cancel!(refs);
+            if before.stdout == after.stdout {
+                Ok((BatchOutcome::UpToDate, "远程分支没有变化".to_string()))
+            } else {
+                Ok((BatchOutcome::Updated, "已获取远程更新".to_string()))
+            }
+        }
+        BatchOperation::Pull => {
+            let upstream = git_or_cancel!(&["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"]);
+            if !upstream.success {
+                return Ok((BatchOutcome::Skipped, "当前分支没有上游分支".to_string()));
+            }
+            let before = git_or_cancel!(&["rev-parse", "HEAD"]);
+            let pull = git_or_cancel!(&["pull", "--ff-only"]);
+            if !pull.success {
+                return Ok((classify_error(&pull.stderr), last_line(&pull.stderr)));
+            }
+            let after = git_or_cancel!(&["rev-parse", "HEAD"]);
+            if before.stdout == after.stdout {
+                Ok((BatchOutcome::UpToDate, format!("已是最新（{}）", upstream.stdout)))
+            } else {
+                let count = git_or_cancel!(&["rev-list", "--count", &format!("{}..{}", before.stdout, after.stdout)]);
+                Ok((BatchOutcome::Updated, format!("从 {} 拉取了 {} 个提交", upstream.stdout, count.stdout)))
+            }
+        }
+        BatchOperation::Push => {
+            let upstream = git_or_cancel!(&["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"]);
+            if !upstream.success {
+                return Ok((BatchOutcome::Skipped, "当前分支没有上游分支".to_string()));
+            }
+            let push = git_or_cancel!(&["push"]);
+            if !push.success {
+                return Ok((classify_error(&push.stderr), last_line(&push.stderr)));
+            }
+            if push.stderr.contains("Everything up-to-date") {
+                Ok((BatchOutcome::UpToDate, "没有需要推送的提交".to_string()))
+            } else {
+                Ok((BatchOutcome::Updated, format!("已推送到 {}", upstream.stdout)))
+            }
+        }
+    }
+}
+
+async fn run_job(app: AppHandle, job: BatchJob, projects: Vec<Project>, concurrency: usize, cancel: watch::Receiver<bool>) {
+    let started = Instant::now();
+    let total = projects.len();
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let completed = Arc::new(Mutex::new(Vec::with_capacity(total)));
+
+    let mut handles = Vec::new();
+    for project in projects {
+        let app = app.clone();
+        let job_id = job.id.clone();
+        let semaphore = semaphore.clone();
+        let completed = completed.clone();
+        let mut cancel = cancel.clone();
+        let operation = job.operation;
+
+        handles.push(tokio::spawn(async move {
+            // 排队期间被取消的项目不再执行
+            let permit = tokio::select! {
+                permit = semaphore.acquire_owned() => permit.ok(),
+                _ = cancel.wait_for(|cancelled| *cancelled) => None,
+            };
+
+            let project_started = Instant::now();
+            let (outcome, message) = if permit.is_none() || *cancel.borrow() {
+                (BatchOutcome::Cancelled, "已取消".to_string())
+            } else {
+                emit(&app, BatchEvent::ProjectStarted { job_id: job_id.clone(), project_id: project.id.clone() });
+                run_one(&project.path, operation, &mut cancel)
+                    .await
+                    .unwrap_or_else(|e| (BatchOutcome::Failed, e))
+            };
+            drop(permit);
+
+            let result = BatchProjectResult {
+                project_id: project.id,
+                name: project.name,
+                path: project.path,
+                outcome,
+                message,
+                duration_ms: project_started.elapsed().as_millis() as u64,
+            };
+            let done = {
+                let mut completed = completed.lock().unwrap_or_else(|e| e.into_inner());
+                completed.push(result.clone());
+                completed.len()
+            };
+            emit(&app, BatchEvent::Progress { job_id, result, completed: done, total });
+        }));
+    }
+    for handle in handles {
+        let _ = handle.await;
+    }
+
+    let results = std::mem::take(&mut *completed.lock().unwrap_or_else(|e| e.into_inner()));
+    let summary = BatchSummary::from_results(&results);
+    let report = BatchReport {
+        job_id: job.id.clone(),
+        operation: job.operation,
+        cancelled: *cancel.borrow(),
+        results,
+        summary,
+        started_at: job.started_at,
+        finished_at: current_iso_time(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    };
+    log::info!(
+        "批量 {:?} 完成: 更新 {}，已是最新 {}，冲突 {}，认证失败 {}，失败 {}",
+        report.operation,
+        report.summary.updated,
+        report.summary.up_to_date,
+        report.summary.conflicted,
+        report.summary.auth_failed,
+        report.summary.failed
+    );
+
+    if let Ok(mut jobs) = JOBS.lock() {
+        jobs.remove(&job.id);
+    }
+    if let Ok(mut reports) = REPORTS.lock() {
+        reports.push_front(report.clone());
+        reports.truncate(MAX_REPORTS);
+    }
+    emit(&app, BatchEvent::Completed { report });
+}
+
+/// 开始批量操作，立即返回任务信息，进度通过 git-batch-event 事件推送
+#[tauri::command]
+pub async fn start_git_batch(app: AppHandle, input: GitBatchInput) -> Result<BatchJob, String> {
+    let projects = select_projects(&input)?;
+    let concurrency = input.concurrency.unwrap_or(DEFAULT_CONCURRENCY).clamp(1, MAX_CONCURRENCY);
+
+    let job = BatchJob {
+        id: generate_id(),
+        operation: input.operation,
+        project_ids: projects.iter().map(|p| p.id.clone()).collect(),
+        started_at: current_iso_time(),
+    };
+    let (cancel_tx, cancel_rx) = watch::channel(false);
+    JOBS.lock().map_err(|e| e.to_string())?.insert(job.id.clone(), cancel_tx);
+
+    log::info!("开始批量 {:?}: {} 个项目，并发 {}", job.operation, projects.len(), concurrency);
+    emit(&app, BatchEvent::Started { job: job.clone() });
+    tokio::spawn(run_job(app, job.clone(), projects, concurrency, cancel_rx));
+    Ok(job)
+}
+
+/// 取消批量操作：进行中的 git 进程被结束，排队中的项目不再执行
+#[tauri::command]
+pub fn cancel_git_batch(job_id: String) -> Result<(), String> {
+    let jobs = JOBS.lock().map_err(|e| e.to_string())?;
+    let cancel = jobs.get(&job_id).ok_or("批量任务不存在或已结束")?;
+    let _ = cancel.send(true);
+    Ok(())
+}
+
+/// 最近完成的批量任务报告（新的在前）
+#[tauri::command]
+pub fn get_git_batch_reports() -> Result<Vec<BatchReport>, String> {
+    Ok(REPORTS.lock().map_err(|e| e.to_string())?.iter().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_common_git_errors() {
+        assert_eq!(
+            classify_error("fatal: Authentication failed for 'https://example.com/repo.git/'"),
+            BatchOutcome::AuthFailed
+        );
+        assert_eq!(
+            classify_error("fatal: could not read Username for 'https://github.com': terminal prompts disabled"),
+            BatchOutcome::AuthFailed
+        );
+        assert_eq!(
+            classify_error("hint: Diverging branches can't be fast-forwarded\nfatal: Not possible to fast-forward, aborting."),
+            BatchOutcome::Conflicted
+        );
+        assert_eq!(
+            classify_error(" ! [rejected]        main -> main (fetch first)"),
+            BatchOutcome::Conflicted
+        );
+        assert_eq!(classify_error("fatal: unable to access: Could not resolve host"), BatchOutcome::Failed);
+    }
+}
@@ -0,0 +1,282 @@
+// 分支管理 - 删除、重命名、设置上游分支，比较分支与默认分支的差异，清理已合并和远程已删除的分支
+
+use serde::Serialize;
+
+use crate::commands::git::{reject_option_like, CommitInfo, GitError};
+use crate::commands::git_backend::{self, run_git_command, LogQuery};
+
+/// 比较分支时最多列出的提交数
+const MAX_COMPARE_COMMITS: usize = 200;
+
+/// 本地分支的详细信息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchDetail {
+    pub name: String,
+    pub is_current: bool,
+    /// 上游分支（如 origin/main）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    /// 设置了上游分支，但远程分支已被删除
+    pub upstream_gone: bool,
+    /// 相对上游分支的领先、落后提交数（没有上游或上游已删除时为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ahead: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub behind: Option<u32>,
+    /// 相对默认分支的领先、落后提交数（没有默认分支时为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_ahead: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_behind: Option<u32>,
+    /// 已合并到默认分支：分支上没有默认分支不包含的提交，且分支不是指向默认分支主线上的提交
+    /// （刚创建、还没有提交的分支不算）
+    pub merged: bool,
+    pub last_commit_hash: String,
+    pub last_commit_message: String,
+    pub last_commit_date: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchOverview {
+    /// 默认分支：origin/HEAD 指向的分支，没有时为 main 或 master
+    pub default_branch: Option<String>,
+    pub branches: Vec<BranchDetail>,
+}
+
+impl BranchOverview {
+    /// 有默认分支不包含的提交的分支数（不含默认分支本身）
+    ///
+    /// 快进合并后或刚创建的分支指向默认分支主线上的提交，不算未合并
+    pub fn unmerged_count(&self) -> u32 {
+        self.branches
+            .iter()
+            .filter(|b| matches!(b.default_ahead, Some(ahead) if ahead > 0))
+            .filter(|b| Some(&b.name) != self.default_branch.as_ref())
+            .count() as u32
+    }
+}
+
+/// 两个引用之间的差异
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchComparison {
+    pub base: String,
+    pub head: String,
+    /// 共同祖先（没有共同历史时为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merge_base: Option<String>,
+    /// head 有而 base 没有的提交
+    pub ahead: Vec<CommitInfo>,
+    /// base 有而 head 没有的提交
+    pub behind: Vec<CommitInfo>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PruneReason {
+    /// 已合并到默认分支
+    Merged,
+    /// 上游分支已在远程删除
+    Gone,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneCandidate {
+    pub name: String,
+    pub reason: PruneReason,
+    pub last_commit_date: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneResult {
+    pub name: String,
+    pub deleted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 可以清理的分支：已合并或上游已删除，不包括当前分支和默认分支
+fn prune_candidates(overview: &BranchOverview) -> Vec<PruneCandidate> {
+    overview
+        .branches
+        .iter()
+        .filter(|b| !b.is_current && Some(&b.name) != overview.default_branch.as_ref())
+        .filter_map(|b| {
+            let reason = if b.merged {
+                PruneReason::Merged
+            } else if b.upstream_gone {
+                PruneReason::Gone
+            } else {
+                return None;
+            };
+            Some(PruneCandidate { name: b.name.clone(), reason, last_commit_date: b.last_commit_date.clone() })
+        })
+        .collect()
+}
+
+pub(crate) fn branch_overview(path: &str) -> Result<BranchOverview, GitError> {
+    git_backend::read(|backend| backend.branch_overview(path))
+}
+
+/// 本地分支及其与上游分支、默认分支的关系
+#[tauri::command]
+pub async fn get_branch_overview(path: String) -> Result<BranchOverview, GitError> {
+    branch_overview(&path)
+}
+
+/// 分支在远程仓库中的名称：上游分支在这个远程仓库时使用上游分支名，否则与本地分支同名
+fn remote_branch_name(path: &str, branch: &str, remote: &str) -> String {
+    let config = |key: &str| run_git_command(path, &["config", "--get", &format!("branch.{}.{}", branch, key)]).ok();
+    match (config("remote"), config("merge")) {
+        (Some(upstream_remote), Some(merge)) if upstream_remote == remote => {
+            merge.strip_prefix("refs/heads/").unwrap_or(&merge).to_string()
+        }
+        _ => branch.to_string(),
+    }
+}
+
+/// 删除本地分支，force 为 true 时删除未合并的分支；指定 remote 时同时删除远程分支
+#[tauri::command]
+pub async fn delete_branch(path: String, branch: String, force: bool, remote: Option<String>) -> Result<(), GitError> {
+    // 删除本地分支会同时删除它的上游配置，先读取远程分支名
+    let remote_branch = remote.as_deref().map(|remote| (remote, remote_branch_name(&path, &branch, remote)));
+    let flag = if force { "-D" } else { "-d" };
+    run_git_command(&path, &["branch", flag, "--", &branch])?;
+    if let Some((remote, remote_branch)) = remote_branch {
+        let remote_ref = format!("refs/heads/{}", remote_branch);
+        run_git_command(&path, &["push", remote, "--delete", &remote_ref])?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn rename_branch(path: String, branch: String, new_name: String) -> Result<(), GitError> {
+    if new_name.trim().is_empty() {
+        return Err(GitError::Failed("分支名不能为空".to_string()));
+    }
+    reject_option_like("分支名", &branch)?;
+    reject_option_like("分支名", &new_name)?;
+    run_git_command(&path, &["branch", "-m", &branch, &new_name]).map(|_| ())
+}
+
+/// 设置分支的上游分支（如 origin/main），upstream 为 None 时取消
+#[tauri::command]
+pub async fn set_branch_upstream(path: String, branch: String, upstream: Option<String>) -> Result<(), GitError> {
+    reject_option_like("分支名", &branch)?;
+    match upstream {
+        Some(upstream) => {
+            let flag = format!("--set-upstream-to={}", upstream);
+            run_git_command(&path, &["branch", &flag, &branch])
+        }
+        None => run_git_command(&path, &["branch", "--unset-upstream", &branch]),
+    }
+    .map(|_| ())
+}
+
+/// 比较两个引用：head 相对 base 领先和落后的提交
+#[tauri::command]
+pub async fn compare_branches(path: String, base: String, head: String) -> Result<BranchComparison, GitError> {
+    reject_option_like("引用", &base)?;
+    reject_option_like("引用", &head)?;
+    let merge_base = run_git_command(&path, &["merge-base", &base, &head]).ok();
+    let log = |range: String| {
+        let query = LogQuery { limit: MAX_COMPARE_COMMITS, rev: Some(&range), ..Default::default() };
+        git_backend::read(|backend| backend.log(&path, &query))
+    };
+    // 范围两端先解析成提交，标签和远程分支也能比较
+    let base_hash = git_backend::read(|backend| backend.commit(&path, &base))?.hash;
+    let head_hash = git_backend::read(|backend| backend.commit(&path, &head))?.hash;
+    let ahead = log(format!("{}..{}", base_hash, head_hash))?;
+    let behind = log(format!("{}..{}", head_hash, base_hash))?;
+    Ok(BranchComparison { base, head, merge_base, ahead, behind })
+}
+
+/// 预览可以清理的分支，fetch 为 true 时先拉取并清理远程已删除的分支引用
+#[tauri::command]
+pub async fn preview_prune_branches(path: String, fetch: Option<bool>) -> Result<Vec<PruneCandidate>, GitError> {
+    if fetch.unwrap_or(false) {
+        run_git_command(&path, &["fetch", "--all", "--prune"])?;
+    }
+    Ok(prune_candidates(&branch_overview(&path)?))
+}
+
+/// 删除选中的分支，只删除仍然符合清理条件的分支
+#[tauri::command]
+pub async fn prune_branches(path: String, branches: Vec<String>) -> Result<Vec<PruneResult>, GitError> {
+    let candidates = prune_candidates(&branch_overview(&path)?);
+    Ok(branches
+        .into_iter()
+        .map(|name| {
+            let result = match candidates.iter().find(|c| c.name == name) {
+                // 是否已合并是和默认分支比较的，git branch -d 却和当前分支比较，
+                // 不在默认分支上时会拒绝删除；远程已删除但未合并的分支也需要强制删除
+                Some(_) => run_git_command(&path, &["branch", "-D", "--", &name]).map(|_| ()),
+                None => Err(GitError::Failed("分支不是已合并或远程已删除的分支".to_string())),
+            };
+            match result {
+                Ok(()) => PruneResult { name, deleted: true, error: None },
+                Err(e) => PruneResult { name, deleted: false, error: Some(e.to_string()) },
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn branch(name: &str, is_current: bool, merged: bool, upstream_gone: bool) -> BranchDetail {
+        BranchDetail {
+            name: name.to_string(),
+            is_current,
+            upstream: None,
+            upstream_gone,
+            ahead: None,
+            behind: None,
+            default_ahead: Some(if merged { 0 } else { 1 }),
+            default_behind: None,
+            merged,
+            last_commit_hash: String::new(),
+            last_commit_message: String::new(),
+            last_commit_date: String::new(),
+        }
+    }
+
+    #[test]
+    fn selects_prune_candidates() {
+        let overview = BranchOverview {
+            default_branch: Some("main".to_string()),
+            branches: vec![
+                branch("main", false, true, false),
+                branch("done", false, true, false),
+                branch("gone", false, false, true),
+                branch("current", true, true, false),
+                branch("wip", false, false, false),
+                // 快进合并后指向主线上的提交：不算已合并，也不算未合并
+                BranchDetail { default_ahead: Some(0), ..branch("shipped", false, false, false) },
+            ],
+        };
+        let candidates = prune_candidates(&overview);
+        let names: Vec<(&str, PruneReason)> = candidates.iter().map(|c| (c.name.as_str(), c.reason)).collect();
+        assert_eq!(names, vec![("done", PruneReason::Merged), ("gone", PruneReason::Gone)]);
+        assert_eq!(overview.unmerged_count(), 2);
+    }
+
+    #[test]
+    fn deletes_the_upstream_branch_name_on_the_remote() {
+        let dir = TempDir::new("remote-branch");
+        let repo = dir.init_repo(&[("a.txt", "one\n")]);
+        let mut config = repo.config().unwrap();
+        config.set_str("branch.topic.remote", "origin").unwrap();
+        config.set_str("branch.topic.merge", "refs/heads/feature/topic").unwrap();
+        let path = dir.path().to_string_lossy().to_string();
+        assert_eq!(remote_branch_name(&path, "topic", "origin"), "feature/topic");
+        assert_eq!(remote_branch_name(&path, "topic", "fork"), "topic");
+        assert_eq!(remote_branch_name(&path, "other", "origin"), "other");
+    }
+}
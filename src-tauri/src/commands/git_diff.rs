@@ -0,0 +1,388 @@
+// 文件差异 - 工作区、暂存区和提交中单个文件的结构化差异，供并排差异视图使用
+
+use serde::Serialize;
+
+use crate::commands::git::GitError;
+use crate::commands::git_backend;
+
+/// 超过这个大小的文件不生成差异
+pub(crate) const MAX_DIFF_FILE_BYTES: u64 = 2 * 1024 * 1024;
+/// 最多返回的差异行数，超出部分截断
+pub(crate) const MAX_DIFF_LINES: usize = 10_000;
+const DEFAULT_CONTEXT_LINES: u32 = 3;
+
+/// 比较的对象
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum DiffTarget<'a> {
+    /// 工作区相对暂存区（包括未跟踪文件）
+    WorkingTree,
+    /// 暂存区相对 HEAD
+    Staged,
+    /// 提交相对第一个父提交
+    Commit(&'a str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffFileStatus {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+    Copied,
+    /// 只改了文件权限或类型
+    ModeChanged,
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffLineKind {
+    Context,
+    Addition,
+    Deletion,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    /// 旧文件中的行号（新增行为 None）
+    pub old_line: Option<u32>,
+    /// 新文件中的行号（删除行为 None）
+    pub new_line: Option<u32>,
+    pub content: String,
+    /// 该行是文件最后一行且没有换行符
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub no_newline: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    /// @@ 行本身，如 "@@ -1,3 +1,4 @@ fn main()"
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiff {
+    /// 旧文件路径（新增文件为 None）
+    pub old_path: Option<String>,
+    /// 新文件路径（删除文件为 None）
+    pub new_path: Option<String>,
+    pub status: DiffFileStatus,
+    /// 重命名、复制时的相似度（百分比）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub similarity: Option<u32>,
+    pub binary: bool,
+    /// 文件过大或差异行数超出上限，hunks 不完整
+    pub truncated: bool,
+    pub additions: u32,
+    pub deletions: u32,
+    pub hunks: Vec<DiffHunk>,
+}
+
+impl FileDiff {
+    pub(crate) fn unchanged(file: &str) -> Self {
+        FileDiff {
+            old_path: Some(file.to_string()),
+            new_path: Some(file.to_string()),
+            status: DiffFileStatus::Unchanged,
+            similarity: None,
+            binary: false,
+            truncated: false,
+            additions: 0,
+            deletions: 0,
+            hunks: Vec::new(),
+        }
+    }
+}
+
+/// 解析 "@@ -1,3 +1,4 @@" 中的 "1,3"（省略行数时为 1）
+fn parse_range(range: &str) -> (u32, u32) {
+    match range.split_once(',') {
+        Some((start, lines)) => (start.parse().unwrap_or(0), lines.parse().unwrap_or(0)),
+        None => (range.parse().unwrap_or(0), 1),
+    }
+}
+
+fn parse_hunk_header(line: &str) -> Option<DiffHunk> {
+    let rest = line.strip_prefix("@@ -")?;
+    let (ranges, _) = rest.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let (old_start, old_lines) = parse_range(old);
+    let (new_start, new_lines) = parse_range(new);
+    Some(DiffHunk {
+        header: line.to_string(),
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        lines: Vec::new(),
+    })
+}
+
+/// 解析单个文件的 git 格式差异（git diff 的输出或 libgit2 生成的补丁）
+///
+/// 路径由调用方填写，这里只从头部读取文件状态和相似度
+pub(crate) fn parse_unified_diff(text: &str, max_lines: usize) -> FileDiff {
+    let mut diff = FileDiff::unchanged("");
+    diff.old_path = None;
+    diff.new_path = None;
+    let mut has_header = false;
+    let mut line_count = 0;
+    let (mut old_line, mut new_line) = (0, 0);
+
+    for raw in text.split_inclusive('\n') {
+        // 只去掉换行符：CRLF 文件的 \r 属于行内容，构造部分暂存的补丁时要原样写回
+        let line = raw.strip_suffix('\n').unwrap_or(raw);
+        let header_line = line.trim_end_matches('\r');
+        if let Some(hunk) = parse_hunk_header(header_line) {
+            old_line = hunk.old_start;
+            new_line = hunk.new_start;
+            diff.hunks.push(hunk);
+            continue;
+        }
+        let Some(hunk) = diff.hunks.last_mut() else {
+            // 文件头
+            let line = header_line;
+            has_header |= line.starts_with("diff --git ") || line.starts_with("--- ");
+            if line.starts_with("new file mode") {
+                diff.status = DiffFileStatus::Added;
+            } else if line.starts_with("deleted file mode") {
+                diff.status = DiffFileStatus::Deleted;
+            } else if line.starts_with("rename from ") || line.starts_with("rename to ") {
+                diff.status = DiffFileStatus::Renamed;
+            } else if line.starts_with("copy from ") || line.starts_with("copy to ") {
+                diff.status = DiffFileStatus::Copied;
+            } else if line.starts_with("old mode ") && diff.status == DiffFileStatus::Unchanged {
+                diff.status = DiffFileStatus::ModeChanged;
+            } else if let Some(percent) = line.strip_prefix("similarity index ") {
+                diff.similarity = percent.trim_end_matches('%').parse().ok();
+            } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+                diff.binary = true;
+            }
+            continue;
+        };
+
+        let (kind, content) = match line.as_bytes().first() {
+            Some(b' ') => (DiffLineKind::Context, &line[1..]),
+            Some(b'+') => (DiffLineKind::Addition, &line[1..]),
+            Some(b'-') => (DiffLineKind::Deletion, &line[1..]),
+            Some(b'\\') => {
+                // "\ No newline at end of file" 属于上一行
+                if let Some(last) = hunk.lines.last_mut() {
+                    last.no_newline = true;
+                }
+                continue;
+            }
+            // 空的上下文行（末尾空白被去掉的情况）
+            None => (DiffLineKind::Context, ""),
+            Some(_) => continue,
+        };
+        let (old, new) = match kind {
+            DiffLineKind::Context => (Some(old_line), Some(new_line)),
+            DiffLineKind::Addition => (None, Some(new_line)),
+            DiffLineKind::Deletion => (Some(old_line), None),
+        };
+        match kind {
+            DiffLineKind::Context => {
+                old_line += 1;
+                new_line += 1;
+            }
+            DiffLineKind::Addition => {
+                new_line += 1;
+                diff.additions += 1;
+            }
+            DiffLineKind::Deletion => {
+                old_line += 1;
+                diff.deletions += 1;
+            }
+        }
+
+        if line_count >= max_lines {
+            diff.truncated = true;
+            continue;
+        }
+        line_count += 1;
+        hunk.lines.push(DiffLine {
+            kind,
+            old_line: old,
+            new_line: new,
+            content: content.to_string(),
+            no_newline: false,
+        });
+    }
+
+    let content_changed = diff.additions > 0 || diff.deletions > 0 || diff.binary;
+    match diff.status {
+        DiffFileStatus::Unchanged if has_header => diff.status = DiffFileStatus::Modified,
+        DiffFileStatus::ModeChanged if content_changed => diff.status = DiffFileStatus::Modified,
+        _ => {}
+    }
+    // 超出上限后不再保存的行所在的 hunk 是空的
+    diff.hunks.retain(|hunk| !hunk.lines.is_empty());
+    diff
+}
+
+/// 从文件头读取修改前后的路径（"--- a/x"、"rename from x"，没有这些行时取 "diff --git a/x b/y"）
+fn header_paths(text: &str) -> (Option<String>, Option<String>) {
+    let (mut old_path, mut new_path) = (None, None);
+    let mut header_old = None;
+    let mut header_new = None;
+    for line in text.lines().take_while(|line| !line.starts_with("@@")) {
+        if let Some(paths) = line.strip_prefix("diff --git a/") {
+            if let Some((old, new)) = paths.rsplit_once(" b/") {
+                header_old = Some(old.to_string());
+                header_new = Some(new.to_string());
+            }
+        } else if let Some(path) = line.strip_prefix("rename from ").or_else(|| line.strip_prefix("copy from ")) {
+            old_path = Some(path.to_string());
+        } else if let Some(path) = line.strip_prefix("rename to ").or_else(|| line.strip_prefix("copy to ")) {
+            new_path = Some(path.to_string());
+        } else if let Some(path) = line.strip_prefix("--- a/") {
+            old_path = Some(path.trim_end_matches('\t').to_string());
+        } else if let Some(path) = line.strip_prefix("+++ b/") {
+            new_path = Some(path.trim_end_matches('\t').to_string());
+        }
+    }
+    (old_path.or(header_old), new_path.or(header_new))
+}
+
+/// 把包含多个文件的差异（git diff、git show 的输出）拆分成每个文件的差异
+pub(crate) fn parse_multi_file_diff(text: &str, max_lines: usize) -> Vec<FileDiff> {
+    let mut chunks: Vec<String> = Vec::new();
+    // 按原样保留每一行（包括 CRLF 文件行尾的 \r）
+    for line in text.split_inclusive('\n') {
+        if line.starts_with("diff --git ") || chunks.is_empty() {
+            chunks.push(String::new());
+        }
+        if let Some(chunk) = chunks.last_mut() {
+            chunk.push_str(line);
+        }
+    }
+
+    chunks
+        .iter()
+        .filter(|chunk| chunk.starts_with("diff --git "))
+        .map(|chunk| {
+            let mut diff = parse_unified_diff(chunk, max_lines);
+            let (old_path, new_path) = header_paths(chunk);
+            diff.old_path = old_path.filter(|_| diff.status != DiffFileStatus::Added);
+            diff.new_path = new_path.filter(|_| diff.status != DiffFileStatus::Deleted);
+            diff
+        })
+        .collect()
+}
+
+fn context_lines(value: Option<u32>) -> u32 {
+    value.unwrap_or(DEFAULT_CONTEXT_LINES)
+}
+
+/// 工作区中文件的未暂存修改（未跟踪文件显示为新增）
+#[tauri::command]
+pub async fn get_working_diff(path: String, file: String, context: Option<u32>) -> Result<FileDiff, GitError> {
+    let context = context_lines(context);
+    git_backend::read(|backend| backend.file_diff(&path, DiffTarget::WorkingTree, &file, context))
+}
+
+/// 文件已暂存的修改
+#[tauri::command]
+pub async fn get_staged_diff(path: String, file: String, context: Option<u32>) -> Result<FileDiff, GitError> {
+    let context = context_lines(context);
+    git_backend::read(|backend| backend.file_diff(&path, DiffTarget::Staged, &file, context))
+}
+
+/// 提交中某个文件的修改（相对第一个父提交）
+#[tauri::command]
+pub async fn get_commit_file_diff(
+    path: String,
+    commit_hash: String,
+    file: String,
+    context: Option<u32>,
+) -> Result<FileDiff, GitError> {
+    let context = context_lines(context);
+    git_backend::read(|backend| backend.file_diff(&path, DiffTarget::Commit(&commit_hash), &file, context))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hunks_renames_and_missing_newline() {
+        let text = "diff --git a/old.txt b/new.txt\n\
+                    similarity index 80%\n\
+                    rename from old.txt\n\
+                    rename to new.txt\n\
+                    --- a/old.txt\n\
+                    +++ b/new.txt\n\
+                    @@ -1,3 +1,3 @@ intro\n \
+                    one\n\
+                    -two\n\
+                    +deux\n \
+                    three\n\
+                    \\ No newline at end of file\n\
+                    @@ -10 +10,2 @@\n\
+                    +ten\n";
+        let diff = parse_unified_diff(text, 100);
+        assert_eq!(diff.status, DiffFileStatus::Renamed);
+        assert_eq!(diff.similarity, Some(80));
+        assert_eq!((diff.additions, diff.deletions), (2, 1));
+        assert_eq!(diff.hunks.len(), 2);
+
+        let first = &diff.hunks[0];
+        assert_eq!((first.old_start, first.old_lines, first.new_start, first.new_lines), (1, 3, 1, 3));
+        assert_eq!((first.lines[1].old_line, first.lines[1].new_line), (Some(2), None));
+        assert_eq!((first.lines[2].old_line, first.lines[2].new_line), (None, Some(2)));
+        assert!(first.lines[3].no_newline);
+        assert_eq!((diff.hunks[1].new_start, diff.hunks[1].old_lines), (10, 1));
+
+        let truncated = parse_unified_diff(text, 2);
+        assert!(truncated.truncated);
+        assert_eq!(truncated.hunks.iter().map(|h| h.lines.len()).sum::<usize>(), 2);
+        assert_eq!((truncated.additions, truncated.deletions), (2, 1));
+    }
+
+    #[test]
+    fn detects_binary_and_new_files() {
+        let diff = parse_unified_diff("diff --git a/logo.png b/logo.png\nnew file mode 100644\nBinary files /dev/null and b/logo.png differ", 100);
+        assert_eq!(diff.status, DiffFileStatus::Added);
+        assert!(diff.binary);
+        assert!(diff.hunks.is_empty());
+        assert_eq!(parse_unified_diff("", 100).status, DiffFileStatus::Unchanged);
+    }
+
+    #[test]
+    fn splits_multi_file_diffs() {
+        let text = "diff --git a/src/a.rs b/src/a.rs\n\
+                    --- a/src/a.rs\n\
+                    +++ b/src/a.rs\n\
+                    @@ -1 +1 @@\n\
+                    -a\n\
+                    +b\n\
+                    diff --git a/new file.txt b/new file.txt\n\
+                    new file mode 100644\n\
+                    --- /dev/null\n\
+                    +++ b/new file.txt\t\n\
+                    @@ -0,0 +1 @@\n\
+                    +hello\n\
+                    diff --git a/logo.png b/logo.png\n\
+                    deleted file mode 100644\n\
+                    Binary files a/logo.png and /dev/null differ\n";
+        let diffs = parse_multi_file_diff(text, 100);
+        assert_eq!(diffs.len(), 3);
+        assert_eq!(diffs[0].old_path.as_deref(), Some("src/a.rs"));
+        assert_eq!((diffs[0].additions, diffs[0].deletions), (1, 1));
+        assert_eq!((diffs[1].old_path.as_deref(), diffs[1].new_path.as_deref()), (None, Some("new file.txt")));
+        assert_eq!(diffs[2].status, DiffFileStatus::Deleted);
+        assert_eq!((diffs[2].old_path.as_deref(), diffs[2].new_path.as_deref()), (Some("logo.png"), None));
+        assert!(diffs[2].binary);
+    }
+}
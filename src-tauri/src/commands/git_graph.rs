@@ -0,0 +1,296 @@
+// 提交图 - 为提交历史分配列（泳道）并给出连线，按游标分页，前端据此绘制分支图
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::git::{CommitInfo, GitError};
+use crate::commands::git_backend::{self, LogQuery};
+
+const DEFAULT_PAGE_SIZE: usize = 200;
+const MAX_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GraphEdgeKind {
+    /// 到第一个父提交
+    Parent,
+    /// 合并提交到其他父提交（分支汇入的地方）
+    Merge,
+    /// 经过这一行、属于其他提交的线
+    Pass,
+}
+
+/// 从这一行画到下一行的线
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphEdge {
+    /// 这一行中的起点列（Parent、Merge 为提交所在列）
+    pub from_column: usize,
+    /// 下一行中的终点列，下一行正是目标提交时为它所在的列
+    pub to_column: usize,
+    pub kind: GraphEdgeKind,
+    /// 线最终连到的提交
+    pub target: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphRow {
+    pub commit: CommitInfo,
+    /// 提交所在的列
+    pub column: usize,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// 分页游标：已返回的提交数、最后返回的提交和当时每一列等待的提交
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphCursor {
+    pub offset: usize,
+    /// 上一页最后一行的提交，用来确认提交历史在翻页之间没有变化
+    pub last_hash: Option<String>,
+    pub lanes: Vec<Option<String>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphPage {
+    pub rows: Vec<GraphRow>,
+    /// 本页用到的最大列数
+    pub columns: usize,
+    /// 没有更多提交时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<GraphCursor>,
+}
+
+/// 泳道分配：每一列记录下一个应该出现在这一列的提交
+#[derive(Debug, Default)]
+struct LaneLayout {
+    lanes: Vec<Option<String>>,
+}
+
+impl LaneLayout {
+    fn free_slot(&mut self) -> usize {
+        match self.lanes.iter().position(Option::is_none) {
+            Some(index) => index,
+            None => {
+                self.lanes.push(None);
+                self.lanes.len() - 1
+            }
+        }
+    }
+
+    /// 放置提交，返回所在列和连到下一行的线（终点列先按泳道填写，放置下一个提交时修正）
+    fn place(&mut self, hash: &str, parents: &[String]) -> (usize, Vec<GraphEdge>) {
+        // 等待这个提交的第一列就是它的位置，其他等待它的列在这里汇合
+        let column = match self.lanes.iter().position(|lane| lane.as_deref() == Some(hash)) {
+            Some(column) => column,
+            None => self.free_slot(),
+        };
+        for lane in self.lanes.iter_mut().filter(|lane| lane.as_deref() == Some(hash)) {
+            *lane = None;
+        }
+
+        let mut edges: Vec<GraphEdge> = self
+            .lanes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, lane)| {
+                let target = lane.clone()?;
+                Some(GraphEdge { from_column: index, to_column: index, kind: GraphEdgeKind::Pass, target })
+            })
+            .collect();
+
+        for (n, parent) in parents.iter().enumerate() {
+            let kind = if n == 0 { GraphEdgeKind::Parent } else { GraphEdgeKind::Merge };
+            // 父提交已经在其他列等待时连到那一列；第一个父提交只连到左边的列，
+            // 否则沿本列继续，两条线在父提交处汇合，主线保持在左边
+            let waiting = self.lanes.iter().position(|lane| lane.as_ref() == Some(parent));
+            let lane = match waiting {
+                Some(lane) if n > 0 || lane < column => lane,
+                _ => {
+                    let lane = if n == 0 { column } else { self.free_slot() };
+                    self.lanes[lane] = Some(parent.clone());
+                    lane
+                }
+            };
+            edges.push(GraphEdge { from_column: column, to_column: lane, kind, target: parent.clone() });
+        }
+
+        while self.lanes.last().is_some_and(Option::is_none) {
+            self.lanes.pop();
+        }
+        (column, edges)
+    }
+}
+
+/// 计算每个提交的列和连线，next 是下一页的第一个提交（用来确定最后一行连线的终点）
+///
+/// 返回各行、最后一行之后的泳道和用到的最大列数
+fn layout_rows(
+    lanes: Vec<Option<String>>,
+    commits: Vec<CommitInfo>,
+    next: Option<&CommitInfo>,
+) -> (Vec<GraphRow>, Vec<Option<String>>, usize) {
+    let mut layout = LaneLayout { lanes };
+    let mut columns = layout.lanes.len();
+    let mut rows: Vec<GraphRow> = Vec::with_capacity(commits.len());
+
+    // 上一行中连到这个提交的线终点改为它实际所在的列
+    let connect = |rows: &mut Vec<GraphRow>, hash: &str, column: usize| {
+        if let Some(previous) = rows.last_mut() {
+            for edge in previous.edges.iter_mut().filter(|edge| edge.target == hash) {
+                edge.to_column = column;
+            }
+        }
+    };
+
+    for commit in commits {
+        let parents = commit.parent_hashes.clone().unwrap_or_default();
+        let (column, edges) = layout.place(&commit.hash, &parents);
+        connect(&mut rows, &commit.hash, column);
+        columns = columns.max(column + 1).max(layout.lanes.len());
+        rows.push(GraphRow { commit, column, edges });
+    }
+
+    let lanes = layout.lanes.clone();
+    // 下一页的提交只用来修正连线，返回的泳道是放置它之前的
+    if let Some(next) = next {
+        let parents = next.parent_hashes.clone().unwrap_or_default();
+        let (column, _) = layout.place(&next.hash, &parents);
+        connect(&mut rows, &next.hash, column);
+    }
+    (rows, lanes, columns)
+}
+
+/// 提交图的一页
+///
+/// refs 为空时包括所有本地分支、远程分支、标签和 HEAD；cursor 为上一页返回的 nextCursor
+#[tauri::command]
+pub async fn get_commit_graph(
+    path: String,
+    refs: Option<Vec<String>>,
+    cursor: Option<GraphCursor>,
+    page_size: Option<u32>,
+) -> Result<GraphPage, GitError> {
+    let page_size = page_size.map_or(DEFAULT_PAGE_SIZE, |size| size as usize).clamp(1, MAX_PAGE_SIZE);
+    graph_page(&path, &refs.unwrap_or_default(), cursor.unwrap_or_default(), page_size)
+}
+
+fn graph_page(path: &str, refs: &[String], cursor: GraphCursor, page_size: usize) -> Result<GraphPage, GitError> {
+    if let Some(invalid) = refs.iter().find(|r| r.starts_with('-')) {
+        return Err(GitError::Failed(format!("无效的引用: {}", invalid)));
+    }
+    // 翻页时从上一页的最后一个提交开始取，确认它仍在原来的位置
+    let previous = match (cursor.offset, cursor.last_hash.as_deref()) {
+        (0, _) => None,
+        (_, Some(hash)) => Some(hash),
+        (_, None) => return Err(GitError::Failed("无效的分页游标".to_string())),
+    };
+    let query = LogQuery {
+        // 多取一个提交，用来确定本页最后一行连线的终点
+        limit: page_size + 1 + usize::from(previous.is_some()),
+        revs: refs,
+        all_refs: refs.is_empty(),
+        skip: cursor.offset - usize::from(previous.is_some()),
+        ..Default::default()
+    };
+    let mut commits = git_backend::read(|backend| backend.log(path, &query))?;
+    if let Some(previous) = previous {
+        if commits.first().map(|c| c.hash.as_str()) != Some(previous) {
+            return Err(GitError::Failed("提交历史已变化（有新的提交或分支被改写），请重新加载提交图".to_string()));
+        }
+        commits.remove(0);
+    }
+    let next = (commits.len() > page_size).then(|| commits.remove(page_size));
+
+    let (rows, lanes, columns) = layout_rows(cursor.lanes, commits, next.as_ref());
+    let next_cursor = next.map(|_| GraphCursor {
+        offset: cursor.offset + rows.len(),
+        last_hash: rows.last().map(|row| row.commit.hash.clone()),
+        lanes,
+    });
+    Ok(GraphPage { rows, columns, next_cursor })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn edges(row: &GraphRow) -> Vec<(usize, usize, GraphEdgeKind)> {
+        row.edges.iter().map(|e| (e.from_column, e.to_column, e.kind)).collect()
+    }
+
+    /// m 合并了 f，f 从 b 分出：
+    /// m      (0)
+    /// |\
+    /// | f    (1)
+    /// c |    (0)
+    /// |/
+    /// b      (0)
+    #[test]
+    fn assigns_lanes_for_merges_and_branch_offs() {
+        let commits = vec![
+            CommitInfo::fixture("m", &["c", "f"]),
+            CommitInfo::fixture("f", &["b"]),
+            CommitInfo::fixture("c", &["b"]),
+            CommitInfo::fixture("b", &[]),
+        ];
+        let (rows, lanes, columns) = layout_rows(Vec::new(), commits, None);
+        assert_eq!(rows.iter().map(|r| r.column).collect::<Vec<_>>(), vec![0, 1, 0, 0]);
+        assert_eq!(columns, 2);
+        assert!(lanes.is_empty());
+        assert_eq!(edges(&rows[0]), vec![(0, 0, GraphEdgeKind::Parent), (0, 1, GraphEdgeKind::Merge)]);
+        assert_eq!(edges(&rows[1]), vec![(0, 0, GraphEdgeKind::Pass), (1, 1, GraphEdgeKind::Parent)]);
+        // c 和 f 的线都汇合到 b 所在的第 0 列
+        assert_eq!(edges(&rows[2]), vec![(1, 0, GraphEdgeKind::Pass), (0, 0, GraphEdgeKind::Parent)]);
+        assert!(rows[3].edges.is_empty());
+    }
+
+    #[test]
+    fn continues_layout_across_pages() {
+        let all = vec![
+            CommitInfo::fixture("m", &["c", "f"]),
+            CommitInfo::fixture("f", &["b"]),
+            CommitInfo::fixture("c", &["b"]),
+            CommitInfo::fixture("b", &[]),
+        ];
+        let (whole, _, _) = layout_rows(Vec::new(), all.clone(), None);
+
+        let (first, lanes, _) = layout_rows(Vec::new(), all[..2].to_vec(), Some(&all[2]));
+        assert_eq!(lanes, vec![Some("c".to_string()), Some("b".to_string())]);
+        let (second, _, _) = layout_rows(lanes, all[2..].to_vec(), None);
+        let paged: Vec<_> = first.iter().chain(&second).map(|r| (r.column, edges(r))).collect();
+        let expected: Vec<_> = whole.iter().map(|r| (r.column, edges(r))).collect();
+        assert_eq!(paged, expected);
+    }
+
+    #[test]
+    fn rejects_stale_cursors_and_option_like_refs() {
+        let dir = TempDir::new("graph");
+        let repo = dir.init_repo(&[("a.txt", "one\n")]);
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        for message in ["second", "third", "fourth"] {
+            let parent = repo.head().unwrap().peel_to_commit().unwrap();
+            repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &[&parent]).unwrap();
+        }
+        let path = dir.path().to_string_lossy().to_string();
+        let head = vec!["HEAD".to_string()];
+
+        let first = graph_page(&path, &head, GraphCursor::default(), 2).unwrap();
+        let cursor = first.next_cursor.unwrap();
+        assert_eq!(cursor.last_hash.as_deref(), Some(first.rows[1].commit.hash.as_str()));
+        let second = graph_page(&path, &head, cursor.clone(), 2).unwrap();
+        let messages: Vec<&str> = second.rows.iter().map(|r| r.commit.message.as_str()).collect();
+        assert_eq!(messages, vec!["second", "init"]);
+        assert!(second.next_cursor.is_none());
+
+        // 翻页之间有了新提交，原来的位置对应了别的提交
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "fifth", &tree, &[&parent]).unwrap();
+        assert!(graph_page(&path, &head, cursor.clone(), 2).is_err());
+        assert!(graph_page(&path, &head, GraphCursor { last_hash: None, ..cursor }, 2).is_err());
+        assert!(graph_page(&path, &["--output=graph.txt".to_string()], GraphCursor::default(), 2).is_err());
+    }
+}
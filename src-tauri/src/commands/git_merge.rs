@@ -0,0 +1,404 @@
+// 合并与变基 - 检测进行中的合并、变基、拣选和还原，列出冲突文件，按选择的一方或编辑后的内容标记解决，继续或中止操作
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::commands::git::GitError;
+use crate::commands::git_backend::{run_git_bytes, run_git_command};
+
+/// 进行中的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RepoOperation {
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+    /// git am 应用邮件补丁
+    ApplyPatches,
+}
+
+impl RepoOperation {
+    /// 对应的 git 子命令
+    fn command(self) -> &'static str {
+        match self {
+            RepoOperation::Merge => "merge",
+            RepoOperation::Rebase => "rebase",
+            RepoOperation::CherryPick => "cherry-pick",
+            RepoOperation::Revert => "revert",
+            RepoOperation::ApplyPatches => "am",
+        }
+    }
+}
+
+/// 冲突的类型（按暂存区中存在的 base/ours/theirs 版本判断）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictKind {
+    BothModified,
+    BothAdded,
+    BothDeleted,
+    DeletedByUs,
+    DeletedByThem,
+    AddedByUs,
+    AddedByThem,
+}
+
+impl ConflictKind {
+    /// ours 或 theirs 一方是否有这个文件
+    fn has_side(self, ours: bool) -> bool {
+        match self {
+            ConflictKind::BothModified | ConflictKind::BothAdded => true,
+            ConflictKind::BothDeleted => false,
+            ConflictKind::DeletedByUs | ConflictKind::AddedByThem => !ours,
+            ConflictKind::DeletedByThem | ConflictKind::AddedByUs => ours,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictFile {
+    pub path: String,
+    pub kind: ConflictKind,
+}
+
+/// 仓库当前的操作状态
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationState {
+    /// 没有进行中的操作时为 None
+    pub operation: Option<RepoOperation>,
+    pub conflicts: Vec<ConflictFile>,
+    /// 变基的当前步骤和总步骤数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_steps: Option<u32>,
+    /// 正在变基的分支
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub head_name: Option<String>,
+    /// 变基的目标提交
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub onto: Option<String>,
+    /// 合并、拣选或还原准备使用的提交信息（第一行）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// 冲突文件的各个版本，二进制文件或不存在的版本为 None
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictContent {
+    pub path: String,
+    pub kind: ConflictKind,
+    pub binary: bool,
+    /// 共同祖先中的版本
+    pub base: Option<String>,
+    /// 当前分支的版本（变基时是目标分支，即已经应用的提交）
+    pub ours: Option<String>,
+    /// 合并进来的版本（变基时是正在应用的提交）
+    pub theirs: Option<String>,
+    /// 工作区中带冲突标记的文件
+    pub merged: Option<String>,
+}
+
+/// 解决冲突的方式
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ConflictResolution {
+    Ours,
+    Theirs,
+    /// 使用编辑后的内容
+    Content { content: String },
+}
+
+/// 合并、变基或继续操作的结果，有冲突时 state 中列出冲突文件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationResult {
+    pub output: String,
+    pub state: OperationState,
+}
+
+fn git_dir(path: &str) -> Result<PathBuf, GitError> {
+    let dir = PathBuf::from(run_git_command(path, &["rev-parse", "--git-dir"])?);
+    Ok(if dir.is_absolute() { dir } else { Path::new(path).join(dir) })
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn read_number(path: &Path) -> Option<u32> {
+    read_trimmed(path)?.parse().ok()
+}
+
+/// 解析 git ls-files -u -z 的输出："<mode> <object> <stage>\t<path>"
+fn parse_unmerged(output: &str) -> Vec<ConflictFile> {
+    let mut files: Vec<(String, [bool; 3])> = Vec::new();
+    for entry in output.split('\0').filter(|e| !e.is_empty()) {
+        let Some((info, file)) = entry.split_once('\t') else {
+            continue;
+        };
+        let Some(stage) = info.rsplit(' ').next().and_then(|s| s.parse::<usize>().ok()).filter(|s| (1..=3).contains(s)) else {
+            continue;
+        };
+        let index = match files.iter().position(|(path, _)| path == file) {
+            Some(index) => index,
+            None => {
+                files.push((file.to_string(), [false; 3]));
+                files.len() - 1
+            }
+        };
+        files[index].1[stage - 1] = true;
+    }
+
+    files
+        .into_iter()
+        .map(|(path, stages)| {
+            let kind = match stages {
+                [true, true, true] => ConflictKind::BothModified,
+                [false, true, true] => ConflictKind::BothAdded,
+                [true, true, false] => ConflictKind::DeletedByThem,
+                [true, false, true] => ConflictKind::DeletedByUs,
+                [false, true, false] => ConflictKind::AddedByUs,
+                [false, false, true] => ConflictKind::AddedByThem,
+                _ => ConflictKind::BothDeleted,
+            };
+            ConflictFile { path, kind }
+        })
+        .collect()
+}
+
+fn list_conflicts(path: &str) -> Result<Vec<ConflictFile>, GitError> {
+    let output = run_git_command(path, &["ls-files", "-u", "-z"])?;
+    Ok(parse_unmerged(&output))
+}
+
+pub(crate) fn operation_state(path: &str) -> Result<OperationState, GitError> {
+    let dir = git_dir(path)?;
+    let mut state = OperationState {
+        operation: None,
+        conflicts: list_conflicts(path)?,
+        step: None,
+        total_steps: None,
+        head_name: None,
+        onto: None,
+        message: None,
+    };
+
+    let rebase_merge = dir.join("rebase-merge");
+    let rebase_apply = dir.join("rebase-apply");
+    if rebase_apply.join("applying").is_file() {
+        // git am 也使用 rebase-apply 目录，用 applying 文件区分，中止时要用 git am --abort
+        state.operation = Some(RepoOperation::ApplyPatches);
+        state.step = read_number(&rebase_apply.join("next"));
+        state.total_steps = read_number(&rebase_apply.join("last"));
+    } else if rebase_merge.is_dir() || rebase_apply.is_dir() {
+        let (rebase_dir, step, total) = if rebase_merge.is_dir() {
+            (rebase_merge, "msgnum", "end")
+        } else {
+            (rebase_apply, "next", "last")
+        };
+        state.operation = Some(RepoOperation::Rebase);
+        state.step = read_number(&rebase_dir.join(step));
+        state.total_steps = read_number(&rebase_dir.join(total));
+        state.head_name = read_trimmed(&rebase_dir.join("head-name"))
+            .map(|name| name.trim_start_matches("refs/heads/").to_string());
+        state.onto = read_trimmed(&rebase_dir.join("onto"));
+    } else if dir.join("MERGE_HEAD").is_file() {
+        state.operation = Some(RepoOperation::Merge);
+    } else if dir.join("CHERRY_PICK_HEAD").is_file() {
+        state.operation = Some(RepoOperation::CherryPick);
+    } else if dir.join("REVERT_HEAD").is_file() {
+        state.operation = Some(RepoOperation::Revert);
+    }
+
+    if matches!(state.operation, Some(RepoOperation::Merge | RepoOperation::CherryPick | RepoOperation::Revert)) {
+        state.message = read_trimmed(&dir.join("MERGE_MSG"))
+            .and_then(|msg| msg.lines().next().map(str::to_string));
+    }
+    Ok(state)
+}
+
+/// 与 git 相同，开头 8000 字节内有 NUL 视为二进制文件
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8000).any(|b| *b == 0)
+}
+
+fn find_conflict(path: &str, file: &str) -> Result<ConflictFile, GitError> {
+    list_conflicts(path)?
+        .into_iter()
+        .find(|c| c.path == file)
+        .ok_or_else(|| GitError::Failed(format!("文件没有冲突: {}", file)))
+}
+
+/// 执行会产生冲突的操作，冲突时返回操作状态而不是错误
+fn run_operation(path: &str, args: &[&str]) -> Result<OperationResult, GitError> {
+    let output = match run_git_command(path, args) {
+        Ok(output) => output,
+        Err(GitError::MergeConflict(detail)) => {
+            let state = operation_state(path)?;
+            if state.operation.is_none() || state.conflicts.is_empty() {
+                return Err(GitError::MergeConflict(detail));
+            }
+            detail
+        }
+        Err(e) => return Err(e),
+    };
+    Ok(OperationResult { output, state: operation_state(path)? })
+}
+
+/// 当前进行中的合并、变基、拣选或还原，以及冲突文件
+#[tauri::command]
+pub async fn get_operation_state(path: String) -> Result<OperationState, GitError> {
+    operation_state(&path)
+}
+
+/// 冲突文件的 base、ours、theirs 版本和工作区中的内容
+#[tauri::command]
+pub async fn get_conflict_content(path: String, file: String) -> Result<ConflictContent, GitError> {
+    let conflict = find_conflict(&path, &file)?;
+    let mut binary = false;
+    let mut text = |bytes: Vec<u8>| {
+        binary |= is_binary(&bytes);
+        (!is_binary(&bytes)).then(|| String::from_utf8_lossy(&bytes).into_owned())
+    };
+
+    let mut stage = |number: u32| -> Result<Option<String>, GitError> {
+        let object = format!(":{}:{}", number, file);
+        // 删除的一方在暂存区中没有这个版本
+        if run_git_command(&path, &["cat-file", "-e", &object]).is_err() {
+            return Ok(None);
+        }
+        Ok(text(run_git_bytes(&path, &["cat-file", "blob", &object])?))
+    };
+    let base = stage(1)?;
+    let ours = stage(2)?;
+    let theirs = stage(3)?;
+    let merged = fs::read(Path::new(&path).join(&file)).ok().and_then(&mut text);
+
+    Ok(ConflictContent { path: file, kind: conflict.kind, binary, base, ours, theirs, merged })
+}
+
+/// 解决冲突并把文件标记为已解决
+#[tauri::command]
+pub async fn resolve_conflict(path: String, file: String, resolution: ConflictResolution) -> Result<OperationState, GitError> {
+    let conflict = find_conflict(&path, &file)?;
+    let side = match &resolution {
+        ConflictResolution::Ours => Some(("--ours", conflict.kind.has_side(true))),
+        ConflictResolution::Theirs => Some(("--theirs", conflict.kind.has_side(false))),
+        ConflictResolution::Content { content } => {
+            fs::write(Path::new(&path).join(&file), content)
+                .map_err(|e| GitError::Failed(format!("写入文件失败 {}: {}", file, e)))?;
+            None
+        }
+    };
+
+    match side {
+        // 选择的一方删除了文件
+        Some((_, false)) => run_git_command(&path, &["rm", "-q", "--", &file])?,
+        Some((flag, true)) => {
+            run_git_command(&path, &["checkout", flag, "--", &file])?;
+            run_git_command(&path, &["add", "--", &file])?
+        }
+        None => run_git_command(&path, &["add", "--", &file])?,
+    };
+    operation_state(&path)
+}
+
+/// 所有冲突解决后继续操作（使用默认的提交信息）
+#[tauri::command]
+pub async fn continue_operation(path: String) -> Result<OperationResult, GitError> {
+    let state = operation_state(&path)?;
+    let Some(operation) = state.operation else {
+        return Err(GitError::Failed("没有进行中的合并或变基".to_string()));
+    };
+    if !state.conflicts.is_empty() {
+        return Err(GitError::MergeConflict(format!("还有 {} 个冲突文件未解决", state.conflicts.len())));
+    }
+    // 不打开编辑器，直接使用 git 准备好的提交信息
+    run_operation(&path, &["-c", "core.editor=true", operation.command(), "--continue"])
+}
+
+/// 中止进行中的操作，恢复到操作开始前的状态
+#[tauri::command]
+pub async fn abort_operation(path: String) -> Result<OperationState, GitError> {
+    let Some(operation) = operation_state(&path)?.operation else {
+        return Err(GitError::Failed("没有进行中的合并或变基".to_string()));
+    };
+    run_git_command(&path, &[operation.command(), "--abort"])?;
+    operation_state(&path)
+}
+
+/// 把分支合并到当前分支
+#[tauri::command]
+pub async fn merge_branch(
+    path: String,
+    branch: String,
+    fast_forward_only: bool,
+    autostash: bool,
+) -> Result<OperationResult, GitError> {
+    let mut args = vec!["merge", "--no-edit"];
+    if fast_forward_only {
+        args.push("--ff-only");
+    }
+    if autostash {
+        args.push("--autostash");
+    }
+    args.extend(["--end-of-options", &branch]);
+    run_operation(&path, &args)
+}
+
+/// 把当前分支变基到 onto 上
+#[tauri::command]
+pub async fn rebase_onto(path: String, onto: String, autostash: bool) -> Result<OperationResult, GitError> {
+    let mut args = vec!["rebase"];
+    if autostash {
+        args.push("--autostash");
+    }
+    args.extend(["--end-of-options", &onto]);
+    run_operation(&path, &args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn parses_unmerged_entries() {
+        let output = "100644 aaa 1\tsrc/a.rs\0100644 bbb 2\tsrc/a.rs\0100644 ccc 3\tsrc/a.rs\0\
+                      100644 ddd 1\tgone.txt\0100644 eee 3\tgone.txt\0\
+                      100644 fff 2\tnew file.txt\0100644 ggg 3\tnew file.txt\0";
+        let files = parse_unmerged(output);
+        assert_eq!(files.len(), 3);
+        assert_eq!((files[0].path.as_str(), files[0].kind), ("src/a.rs", ConflictKind::BothModified));
+        assert_eq!(files[1].kind, ConflictKind::DeletedByUs);
+        assert_eq!((files[2].path.as_str(), files[2].kind), ("new file.txt", ConflictKind::BothAdded));
+    }
+
+    #[test]
+    fn tells_git_am_apart_from_rebase() {
+        let dir = TempDir::new("operation");
+        dir.init_repo(&[("a.txt", "one\n")]);
+        let path = dir.path().to_string_lossy().to_string();
+        assert_eq!(operation_state(&path).unwrap().operation, None);
+
+        dir.write(".git/rebase-apply/next", "2\n");
+        dir.write(".git/rebase-apply/last", "3\n");
+        dir.write(".git/rebase-apply/onto", "abc\n");
+        let state = operation_state(&path).unwrap();
+        assert_eq!((state.operation, state.step, state.total_steps), (Some(RepoOperation::Rebase), Some(2), Some(3)));
+
+        dir.write(".git/rebase-apply/applying", "");
+        let state = operation_state(&path).unwrap();
+        assert_eq!((state.operation, state.step, state.onto), (Some(RepoOperation::ApplyPatches), Some(2), None));
+        assert_eq!(RepoOperation::ApplyPatches.command(), "am");
+    }
+}
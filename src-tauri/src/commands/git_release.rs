@@ -0,0 +1,280 @@
+// 发布说明 - 收集两个引用之间的提交，按约定式提交（Conventional Commits）类型分组，生成 Markdown
+
+use serde::Serialize;
+use std::path::Path;
+
+use crate::commands::git::{parse_remote_url, CommitInfo, GitError};
+use crate::commands::git_backend::{self, run_git_command, LogQuery};
+use crate::storage::atomic::write_atomic;
+
+/// 发布说明最多收集的提交数
+const MAX_RELEASE_COMMITS: usize = 2000;
+
+const RELEASE_NOTES_FILE: &str = "RELEASE_NOTES.md";
+
+/// 分组的顺序和标题，未列出的类型归入"其他改动"
+const GROUPS: &[(&str, &str)] = &[
+    ("feat", "新功能"),
+    ("fix", "问题修复"),
+    ("perf", "性能优化"),
+    ("refactor", "代码重构"),
+    ("docs", "文档"),
+    ("test", "测试"),
+    ("build", "构建与发布"),
+    ("ci", "构建与发布"),
+];
+const BREAKING_TITLE: &str = "不兼容变更";
+const OTHER_TITLE: &str = "其他改动";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseNoteEntry {
+    pub hash: String,
+    pub short_hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    pub description: String,
+    pub breaking: bool,
+    /// 远程仓库中的提交页面
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseNoteGroup {
+    pub title: String,
+    pub entries: Vec<ReleaseNoteEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseNotes {
+    /// 起始引用（不包含），没有更早的标签时为 None，表示从第一个提交开始
+    pub from: Option<String>,
+    pub to: String,
+    pub commit_count: usize,
+    /// 范围内的提交超过 MAX_RELEASE_COMMITS，只收集了最近的部分
+    pub truncated: bool,
+    pub groups: Vec<ReleaseNoteGroup>,
+    pub markdown: String,
+}
+
+/// 约定式提交标题的各部分
+#[derive(Debug, PartialEq, Eq)]
+struct ConventionalSubject<'a> {
+    kind: Option<&'a str>,
+    scope: Option<&'a str>,
+    breaking: bool,
+    description: &'a str,
+}
+
+/// 解析 "feat(ui)!: 描述"，不符合格式时整行作为描述
+fn parse_subject(subject: &str) -> ConventionalSubject<'_> {
+    let plain = ConventionalSubject { kind: None, scope: None, breaking: false, description: subject.trim() };
+    let Some((head, description)) = subject.split_once(':') else {
+        return plain;
+    };
+    let (head, breaking) = match head.strip_suffix('!') {
+        Some(head) => (head, true),
+        None => (head, false),
+    };
+    let (kind, scope) = match head.split_once('(') {
+        Some((kind, scope)) => match scope.strip_suffix(')') {
+            Some(scope) => (kind, Some(scope.trim()).filter(|s| !s.is_empty())),
+            None => return plain,
+        },
+        None => (head, None),
+    };
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphabetic()) {
+        return plain;
+    }
+    ConventionalSubject { kind: Some(kind), scope, breaking, description: description.trim() }
+}
+
+/// 远程地址对应的提交页面地址前缀（与项目详情中的提交链接相同，支持 GitHub、Gitee、GitLab）
+fn commit_url_prefix(remote_url: &str) -> Option<String> {
+    let (host, repo) = parse_remote_url(remote_url);
+    let host = host.to_lowercase();
+    if repo.is_empty() {
+        None
+    } else if host == "github.com" || host == "gitee.com" {
+        Some(format!("https://{}/{}/commit/", host, repo))
+    } else if host.contains("gitlab") {
+        Some(format!("https://{}/{}/-/commit/", host, repo))
+    } else {
+        None
+    }
+}
+
+fn group_commits(commits: &[CommitInfo], url_prefix: Option<&str>) -> Vec<ReleaseNoteGroup> {
+    let mut groups: Vec<ReleaseNoteGroup> = Vec::new();
+    let mut push = |title: &str, entry: ReleaseNoteEntry| match groups.iter_mut().find(|g| g.title == title) {
+        Some(group) => group.entries.push(entry),
+        None => groups.push(ReleaseNoteGroup { title: title.to_string(), entries: vec![entry] }),
+    };
+
+    for commit in commits {
+        // 合并提交只是把分支合进来，内容已经体现在被合并的提交中
+        if commit.parent_hashes.as_ref().is_some_and(|parents| parents.len() > 1) {
+            continue;
+        }
+        let subject = parse_subject(&commit.message);
+        let breaking = subject.breaking
+            || commit.body.as_deref().is_some_and(|body| body.contains("BREAKING CHANGE"));
+        let entry = ReleaseNoteEntry {
+            hash: commit.hash.clone(),
+            short_hash: commit.short_hash.clone(),
+            scope: subject.scope.map(str::to_string),
+            description: subject.description.to_string(),
+            breaking,
+            url: url_prefix.map(|prefix| format!("{}{}", prefix, commit.hash)),
+        };
+        let title = if breaking {
+            BREAKING_TITLE
+        } else {
+            subject
+                .kind
+                .map(str::to_ascii_lowercase)
+                .and_then(|kind| GROUPS.iter().find(|(k, _)| *k == kind))
+                .map_or(OTHER_TITLE, |(_, title)| title)
+        };
+        push(title, entry);
+    }
+
+    // 不兼容变更放在最前面，其余按 GROUPS 的顺序，其他改动最后
+    let rank = |title: &str| {
+        if title == BREAKING_TITLE {
+            0
+        } else {
+            GROUPS.iter().position(|(_, t)| *t == title).map_or(GROUPS.len() + 1, |i| i + 1)
+        }
+    };
+    groups.sort_by_key(|group| rank(&group.title));
+    groups
+}
+
+fn render_markdown(title: &str, date: &str, groups: &[ReleaseNoteGroup]) -> String {
+    let mut markdown = format!("## {} ({})\n", title, date);
+    if groups.is_empty() {
+        markdown.push_str("\n没有新的提交。\n");
+    }
+    for group in groups {
+        markdown.push_str(&format!("\n### {}\n\n", group.title));
+        for entry in &group.entries {
+            let scope = entry.scope.as_ref().map(|s| format!("**{}:** ", s)).unwrap_or_default();
+            let link = match &entry.url {
+                Some(url) => format!("[{}]({})", entry.short_hash, url),
+                None => entry.short_hash.clone(),
+            };
+            markdown.push_str(&format!("- {}{} ({})\n", scope, entry.description, link));
+        }
+    }
+    markdown
+}
+
+/// to 之前最近的标签
+fn previous_tag(path: &str, to: &str) -> Option<String> {
+    let parent = format!("{}^", to);
+    run_git_command(path, &["describe", "--tags", "--abbrev=0", &parent]).ok()
+}
+
+/// 生成 from（不包含）到 to 之间的发布说明
+///
+/// from 默认为 to 之前最近的标签，to 默认为 HEAD；title 默认为 to
+#[tauri::command]
+pub async fn generate_release_notes(
+    path: String,
+    from: Option<String>,
+    to: Option<String>,
+    title: Option<String>,
+) -> Result<ReleaseNotes, GitError> {
+    let to = to.filter(|r| !r.trim().is_empty()).unwrap_or_else(|| "HEAD".to_string());
+    let from = from.filter(|r| !r.trim().is_empty()).or_else(|| previous_tag(&path, &to));
+
+    // 标签先解析成提交，范围两端都是提交哈希
+    let to_commit = git_backend::read(|backend| backend.commit(&path, &to))?.hash;
+    let range = match &from {
+        Some(from) => format!("{}..{}", git_backend::read(|backend| backend.commit(&path, from))?.hash, to_commit),
+        None => to_commit,
+    };
+    // 多取一个提交，判断范围内的提交是否超出上限
+    let query = LogQuery { limit: MAX_RELEASE_COMMITS + 1, rev: Some(&range), ..Default::default() };
+    let mut commits = git_backend::read(|backend| backend.log(&path, &query))?;
+    let truncated = commits.len() > MAX_RELEASE_COMMITS;
+    commits.truncate(MAX_RELEASE_COMMITS);
+
+    let remotes = git_backend::read(|backend| backend.remotes(&path)).unwrap_or_default();
+    let url_prefix = remotes
+        .iter()
+        .find(|r| r.name == "origin")
+        .or_else(|| remotes.first())
+        .and_then(|remote| commit_url_prefix(&remote.url));
+    let groups = group_commits(&commits, url_prefix.as_deref());
+
+    let title = title.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| to.clone());
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let mut markdown = render_markdown(&title, &date, &groups);
+    if truncated {
+        markdown.push_str(&format!("\n> 只列出了最近的 {} 个提交。\n", MAX_RELEASE_COMMITS));
+    }
+    let commit_count = groups.iter().map(|g| g.entries.len()).sum();
+
+    Ok(ReleaseNotes { from, to, commit_count, truncated, groups, markdown })
+}
+
+/// 把发布说明写入项目根目录的 RELEASE_NOTES.md（覆盖原有内容）
+#[tauri::command]
+pub async fn save_release_notes(path: String, content: String) -> Result<String, String> {
+    let file = Path::new(&path).join(RELEASE_NOTES_FILE);
+    write_atomic(&file, content)?;
+    Ok(file.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(hash: &str, message: &str, body: Option<&str>) -> CommitInfo {
+        CommitInfo {
+            short_hash: hash[..3].to_string(),
+            message: message.to_string(),
+            body: body.map(str::to_string),
+            ..CommitInfo::fixture(hash, &["p"])
+        }
+    }
+
+    #[test]
+    fn parses_conventional_subjects() {
+        let subject = parse_subject("feat(ui)!: 新的设置页");
+        assert_eq!((subject.kind, subject.scope, subject.breaking), (Some("feat"), Some("ui"), true));
+        assert_eq!(subject.description, "新的设置页");
+        assert_eq!(parse_subject("Merge branch 'main': sync").kind, None);
+        assert_eq!(parse_subject("更新 README").description, "更新 README");
+    }
+
+    #[test]
+    fn groups_and_renders_commits() {
+        let commits = vec![
+            commit("aaa111", "fix: 修复崩溃", None),
+            commit("bbb222", "feat(git): 储藏管理", None),
+            commit("ccc333", "chore: 升级依赖", None),
+            commit("ddd444", "refactor: 新的存储", Some("BREAKING CHANGE: 数据需要迁移")),
+        ];
+        let prefix = commit_url_prefix("git@github.com:en-o/codeshelf.git");
+        assert_eq!(prefix.as_deref(), Some("https://github.com/en-o/codeshelf/commit/"));
+        assert_eq!(
+            commit_url_prefix("https://gitlab.example.com/team/app.git").as_deref(),
+            Some("https://gitlab.example.com/team/app/-/commit/")
+        );
+        assert_eq!(commit_url_prefix("/srv/git/app.git"), None);
+
+        let groups = group_commits(&commits, prefix.as_deref());
+        let titles: Vec<&str> = groups.iter().map(|g| g.title.as_str()).collect();
+        assert_eq!(titles, vec!["不兼容变更", "新功能", "问题修复", "其他改动"]);
+
+        let markdown = render_markdown("v1.2.0", "2024-05-03", &groups);
+        assert!(markdown.starts_with("## v1.2.0 (2024-05-03)\n"));
+        assert!(markdown.contains("- **git:** 储藏管理 ([bbb](https://github.com/en-o/codeshelf/commit/bbb222))\n"));
+    }
+}
@@ -0,0 +1,497 @@
+// 按块暂存 - 暂存、取消暂存或丢弃文件中选中的 hunk 和行，由差异构造补丁后交给 git apply；
+// 丢弃前把工作区文件复制一份，可以从丢弃记录中恢复
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::commands::git::GitError;
+use crate::commands::git_backend::{self, run_git_command, run_git_with_input};
+use crate::commands::git_diff::{DiffFileStatus, DiffLineKind, DiffTarget, FileDiff};
+use crate::storage::{current_iso_time, generate_id, get_storage_config, keys, load_cache, save_cache};
+
+/// 保留的丢弃记录数，超出后删除最早的副本
+const MAX_DISCARDED: usize = 100;
+
+/// 选中的 hunk 及其中的行
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkSelection {
+    /// hunk 在差异中的序号（与获取差异时的上下文行数对应）
+    pub hunk_index: usize,
+    /// 选中的行在 hunk 中的序号，为空表示整个 hunk
+    #[serde(default)]
+    pub lines: Vec<usize>,
+}
+
+/// 一次丢弃操作保存的文件副本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscardedChange {
+    pub id: String,
+    pub project_path: String,
+    /// 相对仓库根目录的文件路径
+    pub file: String,
+    /// 丢弃前的文件副本
+    pub backup_path: String,
+    /// 只丢弃了部分修改
+    pub partial: bool,
+    pub created_at: String,
+}
+
+/// 丢弃记录（首次访问时从缓存加载）
+static DISCARDED: Lazy<Mutex<Vec<DiscardedChange>>> = Lazy::new(|| Mutex::new(load_discarded()));
+
+fn load_discarded() -> Vec<DiscardedChange> {
+    match load_cache(keys::DISCARDED_CHANGES) {
+        Ok(changes) => changes.unwrap_or_default(),
+        Err(e) => {
+            log::warn!("读取丢弃记录失败: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// 数据库被整体替换（如恢复备份）后重新加载丢弃记录
+pub fn reload_discarded() -> Result<(), String> {
+    let changes = load_discarded();
+    *DISCARDED.lock().map_err(|e| e.to_string())? = changes;
+    Ok(())
+}
+
+/// 普通文件在 git 中的模式
+const REGULAR_FILE_MODE: &str = "100644";
+
+/// 由选中的行构造的补丁
+#[derive(Debug)]
+struct PartialPatch {
+    text: String,
+    /// 选中了文件的全部修改，可以直接按整个文件处理
+    whole_file: bool,
+}
+
+/// 一侧不变时另一侧的起始行号
+///
+/// 行数为 0 时 git 把起始行号写成前一行，所以先换算成第一行再加上前面 hunk 造成的偏移
+fn shifted_start(start: u32, lines: u32, offset: i64, shifted_lines: u32) -> u32 {
+    let first = if lines == 0 { start as i64 + 1 } else { start as i64 };
+    let shifted = first + offset;
+    let shifted = if shifted_lines == 0 { shifted - 1 } else { shifted };
+    shifted.max(0) as u32
+}
+
+/// 由差异中选中的行构造补丁
+///
+/// 正向补丁（暂存）把旧文件改成只包含选中修改的版本：未选中的删除行保留为上下文，未选中的新增行去掉。
+/// 反向补丁（取消暂存、丢弃）用 git apply --reverse 应用，未选中的新增行保留为上下文，未选中的删除行去掉。
+///
+/// `new_file_mode` 是暂存新文件的部分内容时写入补丁头的文件模式
+fn build_patch(
+    diff: &FileDiff,
+    file: &str,
+    selections: &[HunkSelection],
+    reverse: bool,
+    new_file_mode: &str,
+) -> Result<PartialPatch, GitError> {
+    if diff.status == DiffFileStatus::Unchanged || diff.hunks.is_empty() {
+        return Err(GitError::Failed(format!("文件没有可操作的修改: {}", file)));
+    }
+    if let Some(selection) = selections.iter().find(|s| s.hunk_index >= diff.hunks.len()) {
+        return Err(GitError::Failed(format!("差异块不存在（序号 {}），请刷新差异后重试", selection.hunk_index)));
+    }
+
+    let mut body = String::new();
+    let mut offset: i64 = 0;
+    let mut selected_changes = 0;
+    for (index, hunk) in diff.hunks.iter().enumerate() {
+        let selection: Vec<&HunkSelection> = selections.iter().filter(|s| s.hunk_index == index).collect();
+        if selection.is_empty() {
+            continue;
+        }
+        let is_selected = |line: usize| selection.iter().any(|s| s.lines.is_empty() || s.lines.contains(&line));
+
+        let mut lines = String::new();
+        let (mut old_count, mut new_count, mut changes) = (0u32, 0u32, 0);
+        for (i, line) in hunk.lines.iter().enumerate() {
+            let prefix = match (line.kind, is_selected(i)) {
+                (DiffLineKind::Context, _) => ' ',
+                (DiffLineKind::Addition, true) => '+',
+                (DiffLineKind::Deletion, true) => '-',
+                (DiffLineKind::Addition, false) if reverse => ' ',
+                (DiffLineKind::Deletion, false) if !reverse => ' ',
+                _ => continue,
+            };
+            match prefix {
+                ' ' => {
+                    old_count += 1;
+                    new_count += 1;
+                }
+                '+' => {
+                    new_count += 1;
+                    changes += 1;
+                }
+                _ => {
+                    old_count += 1;
+                    changes += 1;
+                }
+            }
+            lines.push(prefix);
+            lines.push_str(&line.content);
+            lines.push('\n');
+            if line.no_newline {
+                lines.push_str("\\ No newline at end of file\n");
+            }
+        }
+        if changes == 0 {
+            continue;
+        }
+        selected_changes += changes;
+
+        // 应用补丁的一侧保持原样，另一侧的行号按前面 hunk 的行数变化平移
+        let (old_start, new_start) = if reverse {
+            (shifted_start(hunk.new_start, hunk.new_lines, -offset, old_count), hunk.new_start)
+        } else {
+            (hunk.old_start, shifted_start(hunk.old_start, hunk.old_lines, offset, new_count))
+        };
+        offset += new_count as i64 - old_count as i64;
+        body.push_str(&format!("@@ -{},{} +{},{} @@\n", old_start, old_count, new_start, new_count));
+        body.push_str(&lines);
+    }
+    if selected_changes == 0 {
+        return Err(GitError::Failed("没有选中任何修改".to_string()));
+    }
+
+    let whole_file = !diff.truncated && selected_changes == diff.additions + diff.deletions;
+    if !whole_file {
+        if diff.binary || diff.truncated {
+            return Err(GitError::Failed(format!("文件过大或为二进制文件，只能整个文件操作: {}", file)));
+        }
+        if matches!(diff.status, DiffFileStatus::Renamed | DiffFileStatus::Copied) {
+            return Err(GitError::Failed(format!("重命名的文件只能整个文件操作: {}", file)));
+        }
+        if reverse && diff.status == DiffFileStatus::Deleted {
+            return Err(GitError::Failed(format!("已删除的文件只能整个文件恢复: {}", file)));
+        }
+    }
+
+    // 新文件的部分内容按新增文件写入暂存区
+    let header = if !reverse && diff.status == DiffFileStatus::Added {
+        format!("diff --git a/{0} b/{0}\nnew file mode {1}\n--- /dev/null\n+++ b/{0}\n", file, new_file_mode)
+    } else {
+        format!("diff --git a/{0} b/{0}\n--- a/{0}\n+++ b/{0}\n", file)
+    };
+    Ok(PartialPatch { text: header + &body, whole_file })
+}
+
+/// 工作区文件在 git 中的模式：有执行权限时为 100755
+#[cfg(unix)]
+fn worktree_file_mode(path: &str, file: &str) -> &'static str {
+    use std::os::unix::fs::PermissionsExt;
+    match fs::metadata(Path::new(path).join(file)) {
+        Ok(metadata) if metadata.permissions().mode() & 0o111 != 0 => "100755",
+        _ => REGULAR_FILE_MODE,
+    }
+}
+
+#[cfg(not(unix))]
+fn worktree_file_mode(_path: &str, _file: &str) -> &'static str {
+    REGULAR_FILE_MODE
+}
+
+fn apply_patch(path: &str, patch: &str, cached: bool, reverse: bool, context: u32) -> Result<(), GitError> {
+    let mut args = vec!["apply", "--recount", "--whitespace=nowarn"];
+    if cached {
+        args.push("--cached");
+    }
+    if reverse {
+        args.push("--reverse");
+    }
+    if context == 0 {
+        args.push("--unidiff-zero");
+    }
+    args.push("-");
+    run_git_with_input(path, &args, patch).map(|_| ())
+}
+
+fn working_diff(path: &str, file: &str, context: u32) -> Result<FileDiff, GitError> {
+    git_backend::read(|backend| backend.file_diff(path, DiffTarget::WorkingTree, file, context))
+}
+
+fn lock_discarded() -> Result<std::sync::MutexGuard<'static, Vec<DiscardedChange>>, String> {
+    DISCARDED.lock().map_err(|e| format!("丢弃记录锁异常: {}", e))
+}
+
+fn save_discarded(changes: &[DiscardedChange]) {
+    if let Err(e) = save_cache(keys::DISCARDED_CHANGES, changes) {
+        log::warn!("保存丢弃记录失败: {}", e);
+    }
+}
+
+fn remove_backup(change: &DiscardedChange) {
+    if let Some(dir) = Path::new(&change.backup_path).parent() {
+        if let Err(e) = fs::remove_dir_all(dir) {
+            log::warn!("删除丢弃副本失败 {}: {}", dir.display(), e);
+        }
+    }
+}
+
+/// 把工作区中的文件复制到丢弃目录，文件不存在（已被删除）时不需要副本
+fn backup_file(path: &str, file: &str, partial: bool) -> Result<Option<DiscardedChange>, GitError> {
+    let source = Path::new(path).join(file);
+    if !source.is_file() {
+        return Ok(None);
+    }
+    let id = generate_id();
+    let dir = get_storage_config().map_err(GitError::Failed)?.discarded_dir().join(&id);
+    let name = source.file_name().map(PathBuf::from).unwrap_or_else(|| PathBuf::from("file"));
+    let target = dir.join(name);
+    fs::create_dir_all(&dir).map_err(|e| GitError::Failed(format!("创建丢弃副本目录失败: {}", e)))?;
+    fs::copy(&source, &target).map_err(|e| GitError::Failed(format!("保存丢弃副本失败 {}: {}", file, e)))?;
+
+    Ok(Some(DiscardedChange {
+        id,
+        project_path: path.to_string(),
+        file: file.to_string(),
+        backup_path: target.to_string_lossy().to_string(),
+        partial,
+        created_at: current_iso_time(),
+    }))
+}
+
+/// 记录丢弃副本，只保留最近的记录
+fn record_discarded(change: &DiscardedChange) {
+    let mut changes = match lock_discarded() {
+        Ok(changes) => changes,
+        Err(e) => {
+            log::error!("{}", e);
+            return;
+        }
+    };
+    changes.push(change.clone());
+    if changes.len() > MAX_DISCARDED {
+        let excess = changes.len() - MAX_DISCARDED;
+        let removed: Vec<DiscardedChange> = changes.drain(..excess).collect();
+        removed.iter().for_each(remove_backup);
+    }
+    save_discarded(&changes);
+}
+
+/// 先保存副本再修改工作区，修改失败时删除副本
+fn with_backup(
+    path: &str,
+    file: &str,
+    partial: bool,
+    discard: impl FnOnce() -> Result<(), GitError>,
+) -> Result<Option<DiscardedChange>, GitError> {
+    let backup = backup_file(path, file, partial)?;
+    if let Err(e) = discard() {
+        if let Some(change) = &backup {
+            remove_backup(change);
+        }
+        return Err(e);
+    }
+    if let Some(change) = &backup {
+        record_discarded(change);
+    }
+    Ok(backup)
+}
+
+/// 丢弃单个文件的未暂存修改：已跟踪的文件恢复为暂存区中的版本，未跟踪的文件直接删除
+fn discard_file(path: &str, file: &str) -> Result<Option<DiscardedChange>, GitError> {
+    if Path::new(path).join(file).is_dir() {
+        return Err(GitError::Failed(format!("不支持丢弃整个目录: {}", file)));
+    }
+    let tracked = run_git_command(path, &["ls-files", "--error-unmatch", "--", file]).is_ok();
+    with_backup(path, file, false, || {
+        if tracked {
+            run_git_command(path, &["checkout", "--", file]).map(|_| ())
+        } else {
+            fs::remove_file(Path::new(path).join(file))
+                .map_err(|e| GitError::Failed(format!("删除文件失败 {}: {}", file, e)))
+        }
+    })
+}
+
+/// 暂存文件中选中的 hunk 或行
+#[tauri::command]
+pub async fn stage_hunks(
+    path: String,
+    file: String,
+    hunks: Vec<HunkSelection>,
+    context: Option<u32>,
+) -> Result<(), GitError> {
+    let context = context.unwrap_or(3);
+    let diff = working_diff(&path, &file, context)?;
+    let patch = build_patch(&diff, &file, &hunks, false, worktree_file_mode(&path, &file))?;
+    if patch.whole_file {
+        return run_git_command(&path, &["add", "-A", "--", &file]).map(|_| ());
+    }
+    apply_patch(&path, &patch.text, true, false, context)
+}
+
+/// 从暂存区撤回文件中选中的 hunk 或行，工作区不变
+#[tauri::command]
+pub async fn unstage_hunks(
+    path: String,
+    file: String,
+    hunks: Vec<HunkSelection>,
+    context: Option<u32>,
+) -> Result<(), GitError> {
+    let context = context.unwrap_or(3);
+    let diff = git_backend::read(|backend| backend.file_diff(&path, DiffTarget::Staged, &file, context))?;
+    let patch = build_patch(&diff, &file, &hunks, true, REGULAR_FILE_MODE)?;
+    if patch.whole_file {
+        return run_git_command(&path, &["reset", "-q", "--", &file]).map(|_| ());
+    }
+    apply_patch(&path, &patch.text, true, true, context)
+}
+
+/// 丢弃文件中选中的未暂存 hunk 或行，丢弃前保存文件副本
+#[tauri::command]
+pub async fn discard_hunks(
+    path: String,
+    file: String,
+    hunks: Vec<HunkSelection>,
+    context: Option<u32>,
+) -> Result<Option<DiscardedChange>, GitError> {
+    let context = context.unwrap_or(3);
+    let diff = working_diff(&path, &file, context)?;
+    let patch = build_patch(&diff, &file, &hunks, true, REGULAR_FILE_MODE)?;
+    if patch.whole_file {
+        return discard_file(&path, &file);
+    }
+    with_backup(&path, &file, true, || apply_patch(&path, &patch.text, false, true, context))
+}
+
+/// 丢弃文件的全部未暂存修改，返回保存的副本
+#[tauri::command]
+pub async fn discard_files(path: String, files: Vec<String>) -> Result<Vec<DiscardedChange>, GitError> {
+    if files.is_empty() {
+        return Err(GitError::Failed("没有选择要丢弃的文件".to_string()));
+    }
+    let mut changes = Vec::new();
+    for file in &files {
+        changes.extend(discard_file(&path, file)?);
+    }
+    Ok(changes)
+}
+
+/// 丢弃记录（可按项目过滤），最新的在前
+#[tauri::command]
+pub async fn get_discarded_changes(path: Option<String>) -> Result<Vec<DiscardedChange>, String> {
+    let changes = lock_discarded()?;
+    Ok(changes
+        .iter()
+        .rev()
+        .filter(|c| path.is_none() || path.as_ref() == Some(&c.project_path))
+        .cloned()
+        .collect())
+}
+
+/// 用丢弃前的副本覆盖工作区中的文件，恢复后删除记录
+#[tauri::command]
+pub async fn restore_discarded_change(id: String) -> Result<(), String> {
+    let mut changes = lock_discarded()?;
+    let index = changes
+        .iter()
+        .position(|c| c.id == id)
+        .ok_or_else(|| "丢弃记录不存在".to_string())?;
+    let change = &changes[index];
+    let target = Path::new(&change.project_path).join(&change.file);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+    fs::copy(&change.backup_path, &target).map_err(|e| format!("恢复文件失败 {}: {}", change.file, e))?;
+
+    let change = changes.remove(index);
+    remove_backup(&change);
+    save_discarded(&changes);
+    Ok(())
+}
+
+/// 删除丢弃记录及其副本
+#[tauri::command]
+pub async fn delete_discarded_change(id: String) -> Result<(), String> {
+    let mut changes = lock_discarded()?;
+    if let Some(index) = changes.iter().position(|c| c.id == id) {
+        let change = changes.remove(index);
+        remove_backup(&change);
+        save_discarded(&changes);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::git_diff::parse_unified_diff;
+
+    fn select(hunk_index: usize, lines: &[usize]) -> HunkSelection {
+        HunkSelection { hunk_index, lines: lines.to_vec() }
+    }
+
+    #[test]
+    fn builds_partial_patches() {
+        let diff = parse_unified_diff(
+            "diff --git a/a.txt b/a.txt\n\
+             --- a/a.txt\n\
+             +++ b/a.txt\n\
+             @@ -1,3 +1,4 @@\n \
+             one\n\
+             -two\n\
+             +2\n\
+             +2b\n \
+             three\n\
+             @@ -10,2 +11,1 @@\n \
+             ten\n\
+             -eleven\n",
+            100,
+        );
+
+        // 只暂存第一个新增行：删除行保留为上下文，第二个 hunk 的新行号随之平移
+        let selections = [select(0, &[2]), select(1, &[])];
+        let patch = build_patch(&diff, "a.txt", &selections, false, REGULAR_FILE_MODE).unwrap();
+        assert!(!patch.whole_file);
+        assert_eq!(
+            patch.text,
+            "diff --git a/a.txt b/a.txt\n--- a/a.txt\n+++ b/a.txt\n\
+             @@ -1,3 +1,4 @@\n one\n two\n+2\n three\n\
+             @@ -10,2 +11,1 @@\n ten\n-eleven\n"
+        );
+
+        // 反向补丁中未选中的新增行保留为上下文，未选中的删除行去掉
+        let patch = build_patch(&diff, "a.txt", &[select(0, &[1])], true, REGULAR_FILE_MODE).unwrap();
+        assert_eq!(
+            patch.text,
+            "diff --git a/a.txt b/a.txt\n--- a/a.txt\n+++ b/a.txt\n\
+             @@ -1,5 +1,4 @@\n one\n-two\n 2\n 2b\n three\n"
+        );
+
+        let selections = [select(0, &[]), select(1, &[])];
+        let all = build_patch(&diff, "a.txt", &selections, true, REGULAR_FILE_MODE).unwrap();
+        assert!(all.whole_file);
+        assert!(build_patch(&diff, "a.txt", &[select(0, &[0])], false, REGULAR_FILE_MODE).is_err());
+        assert!(build_patch(&diff, "a.txt", &[select(5, &[])], false, REGULAR_FILE_MODE).is_err());
+
+        // CRLF 文件的行尾 \r 原样写回补丁，否则 git apply 找不到对应的行
+        let crlf = parse_unified_diff(
+            "diff --git a/b.txt b/b.txt\n--- a/b.txt\n+++ b/b.txt\n@@ -1,2 +1,3 @@\n one\r\n+1b\r\n+1c\r\n two\r\n",
+            100,
+        );
+        let patch = build_patch(&crlf, "b.txt", &[select(0, &[1])], false, REGULAR_FILE_MODE).unwrap();
+        assert_eq!(
+            patch.text,
+            "diff --git a/b.txt b/b.txt\n--- a/b.txt\n+++ b/b.txt\n@@ -1,2 +1,3 @@\n one\r\n+1b\r\n two\r\n"
+        );
+
+        // 暂存新文件的部分内容时使用工作区文件的模式
+        let added = parse_unified_diff(
+            "diff --git a/run.sh b/run.sh\nnew file mode 100755\n--- /dev/null\n+++ b/run.sh\n@@ -0,0 +1,2 @@\n+a\n+b\n",
+            100,
+        );
+        let patch = build_patch(&added, "run.sh", &[select(0, &[0])], false, "100755").unwrap();
+        assert!(patch.text.starts_with("diff --git a/run.sh b/run.sh\nnew file mode 100755\n--- /dev/null\n"));
+    }
+}
@@ -0,0 +1,235 @@
+// 储藏 - 列出、创建、应用和删除 stash，查看储藏的修改
+
+use serde::Serialize;
+
+use crate::commands::git::GitError;
+use crate::commands::git_backend::run_git_command;
+use crate::commands::git_diff::{self, FileDiff, MAX_DIFF_LINES};
+
+/// stash list 的格式：%x1f 分隔提交哈希、引用名、储藏说明和时间
+const STASH_FORMAT: &str = "--format=%H%x1f%gd%x1f%gs%x1f%aI";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StashEntry {
+    pub index: u32,
+    /// 如 "stash@{0}"
+    pub ref_name: String,
+    pub hash: String,
+    pub message: String,
+    /// 储藏时所在的分支（分离 HEAD 时为 None）
+    pub branch: Option<String>,
+    pub date: String,
+}
+
+/// 应用储藏的结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StashApplyResult {
+    /// 有冲突的文件，为空表示应用成功
+    pub conflicts: Vec<String>,
+    /// 储藏已从列表中删除（pop 成功时）
+    pub dropped: bool,
+}
+
+fn stash_ref(index: u32) -> String {
+    format!("stash@{{{}}}", index)
+}
+
+/// 解析 "WIP on main: 1a2b3c4 subject" 或 "On main: message"
+fn parse_stash_subject(subject: &str) -> (Option<String>, String) {
+    let rest = subject
+        .strip_prefix("WIP on ")
+        .or_else(|| subject.strip_prefix("On "));
+    match rest.and_then(|rest| rest.split_once(": ")) {
+        Some((branch, message)) if branch != "(no branch)" => (Some(branch.to_string()), message.to_string()),
+        Some((_, message)) => (None, message.to_string()),
+        None => (None, subject.to_string()),
+    }
+}
+
+fn parse_stash_list(output: &str) -> Vec<StashEntry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\x1f').collect();
+            let [hash, ref_name, subject, date] = fields.as_slice() else {
+                return None;
+            };
+            let index = ref_name.strip_prefix("stash@{")?.strip_suffix('}')?.parse().ok()?;
+            let (branch, message) = parse_stash_subject(subject);
+            Some(StashEntry {
+                index,
+                ref_name: ref_name.to_string(),
+                hash: hash.to_string(),
+                message,
+                branch,
+                date: date.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// 储藏列表最上面的储藏提交，没有储藏时为 None
+fn latest_stash(path: &str) -> Option<String> {
+    run_git_command(path, &["rev-parse", "-q", "--verify", "refs/stash"])
+        .ok()
+        .map(|hash| hash.trim().to_string())
+        .filter(|hash| !hash.is_empty())
+}
+
+/// 储藏当前修改，返回新建储藏的提交哈希，没有修改时为 None
+pub(crate) fn push_stash(
+    path: &str,
+    message: Option<&str>,
+    include_untracked: bool,
+    paths: &[String],
+) -> Result<Option<String>, GitError> {
+    let mut args = vec!["stash", "push"];
+    if include_untracked {
+        args.push("--include-untracked");
+    }
+    if let Some(message) = message.filter(|m| !m.trim().is_empty()) {
+        args.extend(["-m", message]);
+    }
+    if !paths.is_empty() {
+        args.push("--");
+        args.extend(paths.iter().map(String::as_str));
+    }
+    // 比较前后的 refs/stash 判断是否储藏了修改，git 的提示信息会随语言变化
+    let before = latest_stash(path);
+    run_git_command(path, &args)?;
+    Ok(latest_stash(path).filter(|hash| before.as_ref() != Some(hash)))
+}
+
+/// 按提交哈希查找储藏，之后新建的储藏会改变它的序号
+fn find_stash(path: &str, hash: &str) -> Result<Option<StashEntry>, GitError> {
+    let output = run_git_command(path, &["stash", "list", STASH_FORMAT])?;
+    Ok(parse_stash_list(&output).into_iter().find(|entry| entry.hash == hash))
+}
+
+/// 应用并删除 push_stash 新建的储藏
+pub(crate) fn pop_stash_commit(path: &str, hash: &str) -> Result<StashApplyResult, GitError> {
+    let entry = find_stash(path, hash)?.ok_or_else(|| GitError::Failed(format!("储藏 {} 已不在储藏列表中", hash)))?;
+    apply_stash_ref(path, entry.index, true)
+}
+
+/// 应用储藏，冲突时返回冲突文件（储藏保留在列表中）
+fn apply_stash_ref(path: &str, index: u32, pop: bool) -> Result<StashApplyResult, GitError> {
+    let name = stash_ref(index);
+    let action = if pop { "pop" } else { "apply" };
+    match run_git_command(path, &["stash", action, &name]) {
+        Ok(_) => Ok(StashApplyResult { conflicts: Vec::new(), dropped: pop }),
+        Err(GitError::MergeConflict(detail)) => {
+            let unmerged = run_git_command(path, &["-c", "core.quotePath=false", "diff", "--name-only", "--diff-filter=U"])?;
+            let conflicts: Vec<String> = unmerged.lines().map(str::to_string).collect();
+            if conflicts.is_empty() {
+                return Err(GitError::MergeConflict(detail));
+            }
+            Ok(StashApplyResult { conflicts, dropped: false })
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[tauri::command]
+pub async fn get_stashes(path: String) -> Result<Vec<StashEntry>, GitError> {
+    let output = run_git_command(&path, &["stash", "list", STASH_FORMAT])?;
+    Ok(parse_stash_list(&output))
+}
+
+/// 储藏修改，paths 不为空时只储藏这些文件
+#[tauri::command]
+pub async fn create_stash(
+    path: String,
+    message: Option<String>,
+    include_untracked: bool,
+    paths: Vec<String>,
+) -> Result<StashEntry, GitError> {
+    let hash = push_stash(&path, message.as_deref(), include_untracked, &paths)?
+        .ok_or_else(|| GitError::Failed("没有需要储藏的修改".to_string()))?;
+    find_stash(&path, &hash)?.ok_or_else(|| GitError::Failed("储藏后未找到储藏记录".to_string()))
+}
+
+/// 应用储藏，储藏保留在列表中
+#[tauri::command]
+pub async fn apply_stash(path: String, index: u32) -> Result<StashApplyResult, GitError> {
+    apply_stash_ref(&path, index, false)
+}
+
+/// 应用并删除储藏，有冲突时储藏不会被删除
+#[tauri::command]
+pub async fn pop_stash(path: String, index: u32) -> Result<StashApplyResult, GitError> {
+    apply_stash_ref(&path, index, true)
+}
+
+#[tauri::command]
+pub async fn drop_stash(path: String, index: u32) -> Result<(), GitError> {
+    run_git_command(&path, &["stash", "drop", &stash_ref(index)]).map(|_| ())
+}
+
+/// 储藏中每个文件的修改（包括一起储藏的未跟踪文件）
+#[tauri::command]
+pub async fn get_stash_diff(path: String, index: u32, context: Option<u32>) -> Result<Vec<FileDiff>, GitError> {
+    let name = stash_ref(index);
+    let context = format!("-U{}", context.unwrap_or(3));
+    let text = run_git_command(
+        &path,
+        &["-c", "core.quotePath=false", "stash", "show", "-p", "-M", "--no-color", "--no-ext-diff", &context, &name],
+    )?;
+    let mut diffs = git_diff::parse_multi_file_diff(&text, MAX_DIFF_LINES);
+
+    // --include-untracked 储藏的未跟踪文件在第三个父提交中
+    let untracked = format!("{}^3", name);
+    if run_git_command(&path, &["rev-parse", "-q", "--verify", &untracked]).is_ok() {
+        let text = run_git_command(
+            &path,
+            &["-c", "core.quotePath=false", "show", "--format=", "-p", "--no-color", "--no-ext-diff", &context, &untracked],
+        )?;
+        diffs.extend(git_diff::parse_multi_file_diff(&text, MAX_DIFF_LINES));
+    }
+    Ok(diffs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn parses_stash_list() {
+        let output = "aaa\x1fstash@{0}\x1fOn main: 临时保存\x1f2024-05-01T10:00:00+08:00\n\
+                      bbb\x1fstash@{1}\x1fWIP on feature/x: 1a2b3c4 fix: typo\x1f2024-04-30T09:00:00+08:00\n\
+                      ccc\x1fstash@{2}\x1fWIP on (no branch): 5d6e7f8 detached\x1f2024-04-29T09:00:00+08:00";
+        let stashes = parse_stash_list(output);
+        assert_eq!(stashes.len(), 3);
+        assert_eq!((stashes[0].index, stashes[0].branch.as_deref()), (0, Some("main")));
+        assert_eq!(stashes[0].message, "临时保存");
+        assert_eq!((stashes[1].branch.as_deref(), stashes[1].message.as_str()), (Some("feature/x"), "1a2b3c4 fix: typo"));
+        assert_eq!((stashes[2].index, stashes[2].branch.as_deref()), (2, None));
+    }
+
+    #[test]
+    fn pushes_and_pops_the_created_stash() {
+        let dir = TempDir::new("stash");
+        dir.init_repo(&[("a.txt", "one\n")]);
+        let path = dir.path().to_string_lossy().to_string();
+        assert_eq!(push_stash(&path, None, true, &[]).unwrap(), None);
+
+        dir.write("a.txt", "two\n");
+        dir.write("notes.md", "draft\n");
+        let first = push_stash(&path, Some("first"), true, &[]).unwrap().expect("应创建储藏");
+        assert!(!dir.path().join("notes.md").exists());
+        dir.write("a.txt", "three\n");
+        let second = push_stash(&path, Some("second"), false, &[]).unwrap().expect("应创建储藏");
+        assert_ne!(first, second);
+
+        // 之后的储藏把第一个储藏挤到了 stash@{1}
+        let result = pop_stash_commit(&path, &first).unwrap();
+        assert!(result.dropped && result.conflicts.is_empty());
+        assert_eq!(std::fs::read_to_string(dir.path().join("a.txt")).unwrap(), "two\n");
+        assert!(dir.path().join("notes.md").exists());
+        assert_eq!(find_stash(&path, &second).unwrap().map(|entry| entry.index), Some(0));
+        assert!(pop_stash_commit(&path, &first).is_err());
+    }
+}
@@ -0,0 +1,476 @@
+// 项目健康检查 - 检查项目目录是否仍然存在、是否仍是 Git 仓库，并为被移动的仓库找到新位置
+//
+// 目录正常的项目会记录一份仓库指纹（远程地址 + 根提交），目录丢失后用指纹在扫描根目录
+// （路径根目录和各项目所在的上级目录）下查找同一个仓库，作为迁移建议交给用户确认。
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::git::{normalize_remote_url, RepoKind};
+use crate::commands::git_backend;
+use crate::commands::repo_scan::{self, ScanConfig, ScanOptions};
+use crate::commands::project;
+use crate::storage::{
+    current_iso_time, keys, load_cache, load_setting, paths, save_cache, PathRoot, Project,
+    WatchedRoot,
+};
+
+const HEALTH_EVENT: &str = "project-health-event";
+
+/// 仓库指纹
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RepoFingerprint {
+    /// 规范化后的远程地址（如 github.com/en-o/codeshelf）
+    pub remote_urls: Vec<String>,
+    /// 根提交（没有父提交的提交），克隆和移动后不变
+    pub root_commits: Vec<String>,
+}
+
+impl RepoFingerprint {
+    fn is_empty(&self) -> bool {
+        self.remote_urls.is_empty() && self.root_commits.is_empty()
+    }
+
+    /// 与另一个指纹的匹配依据，没有共同点时为空
+    fn matches(&self, other: &RepoFingerprint) -> Vec<String> {
+        let mut matched = Vec::new();
+        if self.root_commits.iter().any(|c| other.root_commits.contains(c)) {
+            matched.push("rootCommit".to_string());
+        }
+        if self.remote_urls.iter().any(|u| other.remote_urls.contains(u)) {
+            matched.push("remote".to_string());
+        }
+        matched
+    }
+}
+
+/// 用户拒绝过的迁移建议，不再重复提示
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct RejectedRelocation {
+    project_id: String,
+    path: String,
+}
+
+/// 持久化的检查数据（缓存表 project_health）
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct HealthCache {
+    fingerprints: HashMap<String, RepoFingerprint>,
+    rejected: Vec<RejectedRelocation>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthStatus {
+    Ok,
+    /// 识别出技术栈的非 Git 项目，目录正常
+    Plain,
+    Missing,
+    NotGitRepo,
+}
+
+impl HealthStatus {
+    pub fn is_healthy(self) -> bool {
+        matches!(self, HealthStatus::Ok | HealthStatus::Plain)
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectHealth {
+    pub project_id: String,
+    pub name: String,
+    pub path: String,
+    pub status: HealthStatus,
+}
+
+/// 迁移建议
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RelocationSuggestion {
+    pub project_id: String,
+    pub name: String,
+    pub old_path: String,
+    pub new_path: String,
+    /// 匹配依据：rootCommit / remote
+    pub matched_by: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    pub checked_at: String,
+    pub projects: Vec<ProjectHealth>,
+    pub suggestions: Vec<RelocationSuggestion>,
+}
+
+/// 健康检查事件（用于前端实时更新）
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum HealthEvent {
+    Progress { checked: usize, total: usize },
+    Completed { report: HealthReport },
+    Failed { error: String },
+}
+
+/// 最近一次检查结果
+static LAST_REPORT: Lazy<Mutex<Option<HealthReport>>> = Lazy::new(|| Mutex::new(None));
+
+/// 是否有检查正在进行
+static CHECK_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// 读取仓库指纹（读取失败的部分为空）
+fn fingerprint(path: &str) -> RepoFingerprint {
+    let mut remote_urls: Vec<String> = git_backend::read(|backend| backend.remotes(path))
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|remote| [Some(remote.url), remote.push_url].into_iter().flatten())
+        .map(|url| normalize_remote_url(&url))
+        .collect();
+    remote_urls.sort();
+    remote_urls.dedup();
+
+    let mut root_commits = git_backend::read(|backend| backend.root_commits(path)).unwrap_or_default();
+    root_commits.sort();
+
+    RepoFingerprint { remote_urls, root_commits }
+}
+
+/// 检查项目目录，was_git 表示之前记录过仓库指纹（曾经是 Git 仓库）
+fn check_status(project: &Project, was_git: bool) -> HealthStatus {
+    let path = Path::new(&project.path);
+    if !path.is_dir() {
+        HealthStatus::Missing
+    } else if path.join(".git").exists() {
+        // .git 也可能是文件（工作树、子模块）
+        HealthStatus::Ok
+    } else if project.stack.is_some() && !was_git {
+        HealthStatus::Plain
+    } else {
+        HealthStatus::NotGitRepo
+    }
+}
+
+/// 查找被移动仓库的扫描根目录：路径根目录，以及各项目所在的上级目录
+fn search_roots(projects: &[Project]) -> Result<Vec<String>, String> {
+    let roots: Vec<PathRoot> = load_setting(keys::PATH_ROOTS)?.unwrap_or_default();
+    let watched: Vec<WatchedRoot> = load_setting(keys::WATCHED_ROOTS)?.unwrap_or_default();
+
+    let mut candidates: Vec<String> = roots.iter().map(|root| paths::expand_home(&root.path)).collect();
+    candidates.extend(watched.iter().map(|root| paths::expand_home(&root.path)));
+    candidates.extend(
+        projects
+            .iter()
+            .filter_map(|p| Path::new(&p.path).parent())
+            .map(|parent| parent.to_string_lossy().to_string()),
+    );
+
+    // 嵌套的目录仍分别扫描（扫描深度从各自开始计算），找到的仓库按路径去重
+    let mut seen = HashSet::new();
+    candidates.retain(|dir| Path::new(dir).is_dir() && seen.insert(paths::path_key(dir)));
+    Ok(candidates)
+}
+
+/// 在扫描根目录下为丢失的项目查找同一个仓库
+fn find_relocations(
+    projects: &[Project],
+    broken: &[&Project],
+    cache: &HealthCache,
+) -> Result<Vec<RelocationSuggestion>, String> {
+    let lost: Vec<(&Project, &RepoFingerprint)> = broken
+        .iter()
+        .filter_map(|p| Some((*p, cache.fingerprints.get(&p.id).filter(|f| !f.is_empty())?)))
+        .collect();
+    if lost.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let config = ScanConfig::load(ScanOptions::default())?;
+    let registered: HashSet<String> = projects.iter().map(|p| paths::path_key(&p.path)).collect();
+
+    let mut repos = Vec::new();
+    for root in search_roots(projects)? {
+        match repo_scan::scan_repos(&root, &config, |_| {}) {
+            Ok(report) => repos.extend(report.repos.into_iter().filter(|repo| repo.kind != RepoKind::Bare)),
+            Err(e) => log::warn!("扫描目录失败 {}: {}", root, e),
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let candidates: Vec<(String, RepoFingerprint)> = repos
+        .into_iter()
+        .filter(|repo| !registered.contains(&paths::path_key(&repo.path)) && seen.insert(paths::path_key(&repo.path)))
+        .map(|repo| {
+            let fingerprint = fingerprint(&repo.path);
+            (repo.path, fingerprint)
+        })
+        .collect();
+
+    let mut suggestions = Vec::new();
+    for (project, expected) in lost {
+        if let Some((path, matched_by)) = best_relocation(project, expected, &candidates, &cache.rejected) {
+            suggestions.push(RelocationSuggestion {
+                project_id: project.id.clone(),
+                name: project.name.clone(),
+                old_path: project.path.clone(),
+                new_path: path.to_string(),
+                matched_by,
+            });
+        }
+    }
+    Ok(suggestions)
+}
+
+/// 在候选仓库中为丢失的项目选出最可信的新位置，返回路径和匹配依据
+fn best_relocation<'a>(
+    project: &Project,
+    expected: &RepoFingerprint,
+    candidates: &'a [(String, RepoFingerprint)],
+    rejected: &[RejectedRelocation],
+) -> Option<(&'a str, Vec<String>)> {
+    let old_name = Path::new(&project.path).file_name();
+    candidates
+        .iter()
+        .filter(|(path, _)| !rejected.iter().any(|r| r.project_id == project.id && paths::same_path(&r.path, path)))
+        .filter_map(|(path, fingerprint)| {
+            let matched = expected.matches(fingerprint);
+            if matched.is_empty() {
+                return None;
+            }
+            // 同时匹配的依据越多越可信，目录名相同的优先
+            let same_name = Path::new(path).file_name() == old_name;
+            Some(((matched.len(), same_name), path.as_str(), matched))
+        })
+        .max_by_key(|(score, _, _)| *score)
+        .map(|(_, path, matched)| (path, matched))
+}
+
+fn emit(app: &AppHandle, event: HealthEvent) {
+    if let Err(e) = app.emit(HEALTH_EVENT, &event) {
+        log::warn!("发送健康检查事件失败: {}", e);
+    }
+}
+
+/// 执行一次完整检查（阻塞，耗时取决于项目数量和扫描深度）
+fn run_health_check(app: &AppHandle) -> Result<HealthReport, String> {
+    let projects = project::get_projects()?;
+    let mut cache: HealthCache = load_cache(keys::PROJECT_HEALTH)?.unwrap_or_default();
+
+    let total = projects.len();
+    let mut statuses = Vec::with_capacity(total);
+    let mut broken = Vec::new();
+    for (index, project) in projects.iter().enumerate() {
+        let status = check_status(project, cache.fingerprints.contains_key(&project.id));
+        if !status.is_healthy() {
+            broken.push(project);
+        } else if status == HealthStatus::Ok {
+            let fingerprint = fingerprint(&project.path);
+            if !fingerprint.is_empty() {
+                cache.fingerprints.insert(project.id.clone(), fingerprint);
+            }
+        }
+
+        statuses.push(ProjectHealth {
+            project_id: project.id.clone(),
+            name: project.name.clone(),
+            path: project.path.clone(),
+            status,
+        });
+        emit(app, HealthEvent::Progress { checked: index + 1, total });
+    }
+
+    // 清理已删除项目的数据
+    let ids: HashSet<&str> = projects.iter().map(|p| p.id.as_str()).collect();
+    cache.fingerprints.retain(|id, _| ids.contains(id.as_str()));
+    cache.rejected.retain(|r| ids.contains(r.project_id.as_str()));
+
+    let suggestions = find_relocations(&projects, &broken, &cache)?;
+    save_cache(keys::PROJECT_HEALTH, &cache)?;
+
+    log::info!(
+        "项目健康检查完成: {} 个项目，{} 个异常，{} 条迁移建议",
+        total,
+        broken.len(),
+        suggestions.len()
+    );
+
+    Ok(HealthReport {
+        checked_at: current_iso_time(),
+        projects: statuses,
+        suggestions,
+    })
+}
+
+/// 在后台线程执行检查，结果通过事件发送并保存为最近一次检查结果
+async fn check_in_background(app: AppHandle) -> Result<HealthReport, String> {
+    if CHECK_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("项目健康检查正在进行中".to_string());
+    }
+
+    let handle = app.clone();
+    let result = tokio::task::spawn_blocking(move || run_health_check(&handle))
+        .await
+        .map_err(|e| format!("项目健康检查失败: {}", e))
+        .and_then(|result| result);
+    CHECK_RUNNING.store(false, Ordering::SeqCst);
+
+    match &result {
+        Ok(report) => {
+            *LAST_REPORT.lock().map_err(|e| e.to_string())? = Some(report.clone());
+            emit(&app, HealthEvent::Completed { report: report.clone() });
+        }
+        Err(e) => {
+            log::error!("项目健康检查失败: {}", e);
+            emit(&app, HealthEvent::Failed { error: e.clone() });
+        }
+    }
+    result
+}
+
+/// 启动时在后台检查一次
+pub fn spawn_startup_check(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let _ = check_in_background(app).await;
+    });
+}
+
+/// 立即检查所有项目
+#[tauri::command]
+pub async fn check_project_health(app: AppHandle) -> Result<HealthReport, String> {
+    check_in_background(app).await
+}
+
+/// 获取最近一次检查结果（尚未检查过时为 None）
+#[tauri::command]
+pub fn get_project_health() -> Result<Option<HealthReport>, String> {
+    Ok(LAST_REPORT.lock().map_err(|e| e.to_string())?.clone())
+}
+
+/// 从最近一次检查结果中取出某个项目的迁移建议
+fn take_suggestion(project_id: &str) -> Result<RelocationSuggestion, String> {
+    let mut last = LAST_REPORT.lock().map_err(|e| e.to_string())?;
+    let report = last.as_mut().ok_or("没有待处理的迁移建议")?;
+    let index = report
+        .suggestions
+        .iter()
+        .position(|s| s.project_id == project_id)
+        .ok_or("没有待处理的迁移建议")?;
+    Ok(report.suggestions.remove(index))
+}
+
+/// 接受迁移建议，把项目路径改为建议的新位置
+#[tauri::command]
+pub fn accept_relocation(project_id: String) -> Result<Project, String> {
+    let suggestion = take_suggestion(&project_id)?;
+    let updated = project::set_project_path(&project_id, &suggestion.new_path)?;
+
+    if let Some(report) = LAST_REPORT.lock().map_err(|e| e.to_string())?.as_mut() {
+        if let Some(health) = report.projects.iter_mut().find(|h| h.project_id == project_id) {
+            health.path = updated.path.clone();
+            let cache: HealthCache = load_cache(keys::PROJECT_HEALTH)?.unwrap_or_default();
+            health.status = check_status(&updated, cache.fingerprints.contains_key(&updated.id));
+        }
+    }
+    log::info!("项目 {} 已迁移到 {}", updated.name, updated.path);
+    Ok(updated)
+}
+
+/// 拒绝迁移建议，之后的检查不再提示同一位置
+#[tauri::command]
+pub fn reject_relocation(project_id: String) -> Result<(), String> {
+    let suggestion = take_suggestion(&project_id)?;
+
+    let mut cache: HealthCache = load_cache(keys::PROJECT_HEALTH)?.unwrap_or_default();
+    cache.rejected.push(RejectedRelocation {
+        project_id: suggestion.project_id,
+        path: suggestion.new_path,
+    });
+    save_cache(keys::PROJECT_HEALTH, &cache)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::ProjectStack;
+    use crate::test_support::TempDir;
+
+    fn project(id: &str, path: &str) -> Project {
+        Project {
+            id: id.to_string(),
+            name: id.to_string(),
+            path: path.to_string(),
+            is_favorite: false,
+            tags: Vec::new(),
+            labels: Vec::new(),
+            created_at: String::new(),
+            updated_at: String::new(),
+            last_opened: None,
+            portable_path: None,
+            stack: None,
+            scripts: Vec::new(),
+        }
+    }
+
+    fn fingerprint(remotes: &[&str], roots: &[&str]) -> RepoFingerprint {
+        RepoFingerprint {
+            remote_urls: remotes.iter().map(|url| normalize_remote_url(url)).collect(),
+            root_commits: roots.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn non_git_projects_with_a_stack_are_healthy() {
+        let dir = TempDir::new("health");
+        let mut plain = project("p1", &dir.path().to_string_lossy());
+        assert_eq!(check_status(&plain, false), HealthStatus::NotGitRepo);
+
+        plain.stack = Some(ProjectStack {
+            language: "rust".to_string(),
+            framework: None,
+            declared_name: None,
+            version: None,
+            manifest: "Cargo.toml".to_string(),
+        });
+        assert_eq!(check_status(&plain, false), HealthStatus::Plain);
+        assert!(HealthStatus::Plain.is_healthy());
+        // 记录过仓库指纹说明原来是 Git 仓库，.git 丢失仍然是异常
+        assert_eq!(check_status(&plain, true), HealthStatus::NotGitRepo);
+
+        dir.mkdir(".git");
+        assert_eq!(check_status(&plain, true), HealthStatus::Ok);
+        plain.path = dir.path().join("gone").to_string_lossy().to_string();
+        assert_eq!(check_status(&plain, false), HealthStatus::Missing);
+    }
+
+    #[test]
+    fn picks_best_relocation_candidate() {
+        let lost = project("p1", "/old/app");
+        let expected = fingerprint(&["git@github.com:en-o/app.git"], &["r1"]);
+        let candidates = vec![
+            ("/new/other".to_string(), fingerprint(&[], &["r1"])),
+            ("/new/app".to_string(), fingerprint(&["https://github.com/en-o/app"], &[])),
+            ("/new/fork".to_string(), fingerprint(&["https://github.com/en-o/app"], &["r1"])),
+            ("/new/unrelated".to_string(), fingerprint(&["https://github.com/x/y"], &["r2"])),
+        ];
+
+        // 两个依据都匹配的优先
+        let (path, matched) = best_relocation(&lost, &expected, &candidates, &[]).unwrap();
+        assert_eq!(path, "/new/fork");
+        assert_eq!(matched, vec!["rootCommit", "remote"]);
+
+        // 拒绝过的位置不再建议，依据数相同时目录名相同的优先
+        let rejected = vec![RejectedRelocation { project_id: "p1".to_string(), path: "/new/fork".to_string() }];
+        let (path, _) = best_relocation(&lost, &expected, &candidates, &rejected).unwrap();
+        assert_eq!(path, "/new/app");
+
+        let nothing = fingerprint(&["https://github.com/a/b"], &["r9"]);
+        assert!(best_relocation(&lost, &nothing, &candidates, &[]).is_none());
+    }
+}
@@ -1,6 +1,25 @@
+pub mod data;
+pub mod env;
+pub mod fetch_scheduler;
 pub mod git;
+pub mod git_backend;
+pub mod git_batch;
+pub mod git_branch;
+pub mod git_diff;
+pub mod git_graph;
+pub mod git_merge;
+pub mod git_release;
+pub mod git_stage;
+pub mod git_stash;
+pub mod health;
 pub mod project;
+pub mod project_type;
+pub mod repo_scan;
 pub mod stats;
 pub mod system;
+pub mod tasks;
+pub mod templates;
 pub mod toolbox;
+pub mod services;
 pub mod settings;
+pub mod watcher;
@@ -2,11 +2,14 @@
 
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
 use once_cell::sync::Lazy;
 
-use crate::storage::{get_storage_config, generate_id, current_iso_time, Project};
+use crate::commands::{env, project_type};
+use crate::storage::repository::SettingsRepository;
+use crate::storage::{self, generate_id, current_iso_time, keys, paths, PathRoot, Project, ProjectRepository, ProjectScript};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateProjectInput {
@@ -24,73 +27,77 @@ pub struct UpdateProjectInput {
     pub labels: Option<Vec<String>>,
 }
 
-// 项目数据存储（内存缓存）
-static PROJECTS: Lazy<Mutex<Vec<Project>>> = Lazy::new(|| {
-    let projects = load_projects_from_file().unwrap_or_default();
-    Mutex::new(projects)
-});
-
-fn get_data_file_path() -> PathBuf {
-    match get_storage_config() {
-        Ok(config) => config.projects_file(),
-        Err(e) => {
-            log::error!("获取存储配置失败: {}", e);
-            PathBuf::from("data").join("projects.json")
-        }
-    }
-}
+// 项目数据存储（内存缓存，持久化在数据库中）
+static PROJECTS: Lazy<Mutex<Vec<Project>>> = Lazy::new(|| Mutex::new(Vec::new()));
 
-fn load_projects_from_file() -> Result<Vec<Project>, String> {
-    let path = get_data_file_path();
-    if !path.exists() {
-        return Ok(Vec::new());
-    }
+/// 是否已从数据库加载
+static PROJECTS_LOADED: AtomicBool = AtomicBool::new(false);
 
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read projects file: {}", e))?;
-
-    // 直接解析为项目数组
-    let projects: Vec<Project> = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse projects file: {}", e))?;
-
-    log::info!("从文件加载了 {} 个项目", projects.len());
+/// 锁定项目缓存，首次访问时从数据库加载
+///
+/// 加载失败（如数据版本高于当前程序）时返回错误并在下次访问重试，
+/// 不会把空列表当作用户数据展示或保存
+fn lock_projects() -> Result<MutexGuard<'static, Vec<Project>>, String> {
+    let mut projects = PROJECTS.lock().map_err(|e| e.to_string())?;
+    if !PROJECTS_LOADED.load(Ordering::SeqCst) {
+        *projects = load_projects()?;
+        PROJECTS_LOADED.store(true, Ordering::SeqCst);
+    }
     Ok(projects)
 }
 
-fn save_projects_to_file(projects: &[Project]) -> Result<(), String> {
-    let path = get_data_file_path();
+/// 加载项目，并按本机的路径根目录还原或补全可移植路径
+fn load_projects() -> Result<Vec<Project>, String> {
+    let projects = storage::with_transaction(|tx| {
+        let roots: Vec<PathRoot> = SettingsRepository::new(tx).get(keys::PATH_ROOTS)?.unwrap_or_default();
+        let repo = ProjectRepository::new(tx);
+        let mut projects = repo.list()?;
 
-    // 确保目录存在
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create data directory: {}", e))?;
-    }
+        let changed = paths::bind_project_paths(&mut projects, &roots);
+        for &index in &changed {
+            repo.upsert(&projects[index])?;
+        }
+        if !changed.is_empty() {
+            log::info!("按路径根目录更新了 {} 个项目的路径", changed.len());
+        }
+        Ok(projects)
+    })?;
+    log::info!("从数据库加载了 {} 个项目", projects.len());
+    Ok(projects)
+}
 
-    // 直接保存为项目数组
-    let content = serde_json::to_string(projects)
-        .map_err(|e| format!("Failed to serialize projects: {}", e))?;
+fn load_path_roots() -> Result<Vec<PathRoot>, String> {
+    Ok(storage::load_setting(keys::PATH_ROOTS)?.unwrap_or_default())
+}
 
-    fs::write(&path, content)
-        .map_err(|e| format!("Failed to write projects file: {}", e))?;
+/// 保存单个项目（新增或更新）
+fn save_project(project: &Project) -> Result<(), String> {
+    storage::with_connection(|conn| ProjectRepository::new(conn).upsert(project))
+}
 
-    Ok(())
+/// 在同一事务中保存多个项目
+fn save_projects(projects: &[Project]) -> Result<(), String> {
+    storage::with_transaction(|tx| ProjectRepository::new(tx).upsert_many(projects))
 }
 
 #[tauri::command]
 pub fn get_projects() -> Result<Vec<Project>, String> {
-    let projects = PROJECTS.lock().map_err(|e| e.to_string())?;
+    let projects = lock_projects()?;
     Ok(projects.clone())
 }
 
 #[tauri::command]
 pub fn create_project(input: CreateProjectInput) -> Result<Project, String> {
-    let mut projects = PROJECTS.lock().map_err(|e| e.to_string())?;
+    let mut projects = lock_projects()?;
 
-    // 检查路径是否已存在
-    if projects.iter().any(|p| p.path == input.path) {
+    // 检查路径是否已存在（忽略分隔符和末尾斜杠的差异）
+    if projects.iter().any(|p| paths::same_path(&p.path, &input.path)) {
         return Err("项目路径已存在".to_string());
     }
 
+    let portable_path = paths::to_portable(&input.path, &load_path_roots()?);
+    let stack = project_type::detect_stack(Path::new(&input.path));
+
     let now = current_iso_time();
     let project = Project {
         id: generate_id(),
@@ -102,54 +109,59 @@ pub fn create_project(input: CreateProjectInput) -> Result<Project, String> {
         created_at: now.clone(),
         updated_at: now,
         last_opened: None,
+        portable_path,
+        stack,
+        scripts: Vec::new(),
     };
 
+    save_project(&project)?;
     projects.push(project.clone());
-    save_projects_to_file(&projects)?;
 
     Ok(project)
 }
 
 #[tauri::command]
 pub fn update_project(input: UpdateProjectInput) -> Result<Project, String> {
-    let mut projects = PROJECTS.lock().map_err(|e| e.to_string())?;
+    let mut projects = lock_projects()?;
 
     let project = projects
         .iter_mut()
         .find(|p| p.id == input.id)
         .ok_or("项目不存在")?;
 
+    let mut updated = project.clone();
     if let Some(name) = input.name {
-        project.name = name;
+        updated.name = name;
     }
     if let Some(tags) = input.tags {
-        project.tags = tags;
+        updated.tags = tags;
     }
     if let Some(labels) = input.labels {
-        project.labels = labels;
+        updated.labels = labels;
     }
-    project.updated_at = current_iso_time();
+    updated.updated_at = current_iso_time();
 
-    let updated = project.clone();
-    save_projects_to_file(&projects)?;
+    save_project(&updated)?;
+    *project = updated.clone();
 
     Ok(updated)
 }
 
 #[tauri::command]
 pub fn delete_project(id: String) -> Result<(), String> {
-    let mut projects = PROJECTS.lock().map_err(|e| e.to_string())?;
+    let mut projects = lock_projects()?;
 
     let index = projects.iter().position(|p| p.id == id).ok_or("项目不存在")?;
-    projects.remove(index);
 
-    save_projects_to_file(&projects)?;
+    storage::with_connection(|conn| ProjectRepository::new(conn).delete(&id))?;
+    projects.remove(index);
+    env::forget_projects(&[id]);
     Ok(())
 }
 
 #[tauri::command]
 pub fn delete_project_directory(id: String) -> Result<(), String> {
-    let mut projects = PROJECTS.lock().map_err(|e| e.to_string())?;
+    let mut projects = lock_projects()?;
 
     let index = projects.iter().position(|p| p.id == id).ok_or("项目不存在")?;
     let project = &projects[index];
@@ -162,55 +174,59 @@ pub fn delete_project_directory(id: String) -> Result<(), String> {
     }
 
     // 从列表中移除
+    storage::with_connection(|conn| ProjectRepository::new(conn).delete(&id))?;
     projects.remove(index);
-    save_projects_to_file(&projects)?;
+    env::forget_projects(&[id]);
 
     Ok(())
 }
 
 #[tauri::command]
 pub fn toggle_favorite(id: String) -> Result<Project, String> {
-    let mut projects = PROJECTS.lock().map_err(|e| e.to_string())?;
+    let mut projects = lock_projects()?;
 
     let project = projects
         .iter_mut()
         .find(|p| p.id == id)
         .ok_or("项目不存在")?;
 
-    project.is_favorite = !project.is_favorite;
-    project.updated_at = current_iso_time();
+    let mut updated = project.clone();
+    updated.is_favorite = !updated.is_favorite;
+    updated.updated_at = current_iso_time();
 
-    let updated = project.clone();
-    save_projects_to_file(&projects)?;
+    save_project(&updated)?;
+    *project = updated.clone();
 
     Ok(updated)
 }
 
 #[tauri::command]
 pub fn update_last_opened(id: String) -> Result<Project, String> {
-    let mut projects = PROJECTS.lock().map_err(|e| e.to_string())?;
+    let mut projects = lock_projects()?;
 
     let project = projects
         .iter_mut()
         .find(|p| p.id == id)
         .ok_or("项目不存在")?;
 
-    project.last_opened = Some(current_iso_time());
-    project.updated_at = current_iso_time();
+    let mut updated = project.clone();
+    updated.last_opened = Some(current_iso_time());
+    updated.updated_at = current_iso_time();
 
-    let updated = project.clone();
-    save_projects_to_file(&projects)?;
+    save_project(&updated)?;
+    *project = updated.clone();
 
     Ok(updated)
 }
 
 #[tauri::command]
 pub fn batch_update_projects(updates: Vec<UpdateProjectInput>) -> Result<Vec<Project>, String> {
-    let mut projects = PROJECTS.lock().map_err(|e| e.to_string())?;
+    let mut projects = lock_projects()?;
     let mut updated_projects = Vec::new();
 
     for update in updates {
-        if let Some(project) = projects.iter_mut().find(|p| p.id == update.id) {
+        if let Some(project) = projects.iter().find(|p| p.id == update.id) {
+            let mut project = project.clone();
             if let Some(name) = update.name {
                 project.name = name;
             }
@@ -221,29 +237,40 @@ pub fn batch_update_projects(updates: Vec<UpdateProjectInput>) -> Result<Vec<Pro
                 project.labels = labels;
             }
             project.updated_at = current_iso_time();
-            updated_projects.push(project.clone());
+            updated_projects.push(project);
+        }
+    }
+
+    // 整批写入成功后再更新内存
+    save_projects(&updated_projects)?;
+    for updated in &updated_projects {
+        if let Some(project) = projects.iter_mut().find(|p| p.id == updated.id) {
+            *project = updated.clone();
         }
     }
 
-    save_projects_to_file(&projects)?;
     Ok(updated_projects)
 }
 
 #[tauri::command]
 pub fn batch_delete_projects(ids: Vec<String>) -> Result<(), String> {
-    let mut projects = PROJECTS.lock().map_err(|e| e.to_string())?;
+    let mut projects = lock_projects()?;
+    storage::with_transaction(|tx| ProjectRepository::new(tx).delete_many(&ids))?;
     projects.retain(|p| !ids.contains(&p.id));
-    save_projects_to_file(&projects)?;
+    env::forget_projects(&ids);
     Ok(())
 }
 
 #[tauri::command]
 pub fn import_projects(new_projects: Vec<CreateProjectInput>) -> Result<Vec<Project>, String> {
-    let mut projects = PROJECTS.lock().map_err(|e| e.to_string())?;
+    let mut projects = lock_projects()?;
+    let roots = load_path_roots()?;
     let mut imported = Vec::new();
 
     for input in new_projects {
-        if projects.iter().any(|p| p.path == input.path) {
+        if projects.iter().any(|p| paths::same_path(&p.path, &input.path))
+            || imported.iter().any(|p: &Project| paths::same_path(&p.path, &input.path))
+        {
             continue;
         }
 
@@ -251,6 +278,9 @@ pub fn import_projects(new_projects: Vec<CreateProjectInput>) -> Result<Vec<Proj
         let project = Project {
             id: generate_id(),
             name: input.name,
+            portable_path: paths::to_portable(&input.path, &roots),
+            stack: project_type::detect_stack(Path::new(&input.path)),
+            scripts: Vec::new(),
             path: input.path,
             is_favorite: false,
             tags: input.tags.unwrap_or_default(),
@@ -260,19 +290,220 @@ pub fn import_projects(new_projects: Vec<CreateProjectInput>) -> Result<Vec<Proj
             last_opened: None,
         };
 
-        projects.push(project.clone());
         imported.push(project);
     }
 
-    save_projects_to_file(&projects)?;
+    save_projects(&imported)?;
+    projects.extend(imported.iter().cloned());
     Ok(imported)
 }
 
-/// 重新加载项目（从文件同步）
+/// 重新加载项目（从数据库同步）
 #[tauri::command]
 pub fn reload_projects() -> Result<Vec<Project>, String> {
-    let new_projects = load_projects_from_file()?;
+    let new_projects = load_projects()?;
     let mut projects = PROJECTS.lock().map_err(|e| e.to_string())?;
     *projects = new_projects;
+    PROJECTS_LOADED.store(true, Ordering::SeqCst);
     Ok(projects.clone())
 }
+
+/// 重新识别项目的技术栈（清单文件变化后或旧版本数据升级后使用），不传 ids 时识别全部项目
+#[tauri::command]
+pub fn refresh_project_stacks(ids: Option<Vec<String>>) -> Result<Vec<Project>, String> {
+    let mut projects = lock_projects()?;
+
+    let mut changed = Vec::new();
+    for project in projects.iter() {
+        if ids.as_ref().is_some_and(|ids| !ids.contains(&project.id)) {
+            continue;
+        }
+        let stack = project_type::detect_stack(Path::new(&project.path));
+        if stack != project.stack {
+            changed.push(Project { stack, ..project.clone() });
+        }
+    }
+
+    save_projects(&changed)?;
+    for updated in &changed {
+        if let Some(project) = projects.iter_mut().find(|p| p.id == updated.id) {
+            *project = updated.clone();
+        }
+    }
+    Ok(projects.clone())
+}
+
+/// 按技术栈筛选项目（语言、框架不区分大小写，未指定的条件不参与筛选）
+#[tauri::command]
+pub fn get_projects_by_stack(language: Option<String>, framework: Option<String>) -> Result<Vec<Project>, String> {
+    let matches = |expected: &Option<String>, actual: Option<&String>| match expected {
+        Some(expected) => actual.is_some_and(|actual| actual.eq_ignore_ascii_case(expected)),
+        None => true,
+    };
+
+    let projects = lock_projects()?;
+    Ok(projects
+        .iter()
+        .filter(|p| {
+            let stack = p.stack.as_ref();
+            stack.is_some()
+                && matches(&language, stack.map(|s| &s.language))
+                && matches(&framework, stack.and_then(|s| s.framework.as_ref()))
+        })
+        .cloned()
+        .collect())
+}
+
+/// 保存项目的运行脚本（整体替换，名称不能为空或重复）
+#[tauri::command]
+pub fn set_project_scripts(id: String, scripts: Vec<ProjectScript>) -> Result<Project, String> {
+    for (index, script) in scripts.iter().enumerate() {
+        if script.name.trim().is_empty() || script.command.trim().is_empty() {
+            return Err("脚本名称和命令不能为空".to_string());
+        }
+        if scripts[..index].iter().any(|other| other.name == script.name) {
+            return Err(format!("脚本名称重复: {}", script.name));
+        }
+    }
+
+    let mut projects = lock_projects()?;
+    let project = projects.iter_mut().find(|p| p.id == id).ok_or("项目不存在")?;
+
+    let updated = Project {
+        scripts,
+        updated_at: current_iso_time(),
+        ..project.clone()
+    };
+    save_project(&updated)?;
+    *project = updated.clone();
+
+    Ok(updated)
+}
+
+/// 根据项目清单推荐运行脚本（不会保存，由用户确认后调用 set_project_scripts）
+#[tauri::command]
+pub fn suggest_project_scripts(id: String) -> Result<Vec<ProjectScript>, String> {
+    let projects = lock_projects()?;
+    let project = projects.iter().find(|p| p.id == id).ok_or("项目不存在")?;
+    Ok(project_type::suggest_scripts(Path::new(&project.path)))
+}
+
+/// 修改项目路径并按本机根目录更新可移植路径（接受目录迁移建议时使用）
+pub(crate) fn set_project_path(id: &str, path: &str) -> Result<Project, String> {
+    let mut projects = lock_projects()?;
+    if projects.iter().any(|p| p.id != id && paths::same_path(&p.path, path)) {
+        return Err("项目路径已存在".to_string());
+    }
+    let roots = load_path_roots()?;
+
+    let project = projects
+        .iter_mut()
+        .find(|p| p.id == id)
+        .ok_or("项目不存在")?;
+
+    let mut updated = project.clone();
+    updated.path = path.to_string();
+    updated.portable_path = paths::to_portable(path, &roots);
+    updated.updated_at = current_iso_time();
+
+    save_project(&updated)?;
+    *project = updated.clone();
+
+    Ok(updated)
+}
+
+/// 项目路径状态
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectPathStatus {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    pub portable_path: Option<String>,
+    pub exists: bool,
+}
+
+impl From<&Project> for ProjectPathStatus {
+    fn from(project: &Project) -> Self {
+        Self {
+            id: project.id.clone(),
+            name: project.name.clone(),
+            path: project.path.clone(),
+            portable_path: project.portable_path.clone(),
+            exists: Path::new(&project.path).is_dir(),
+        }
+    }
+}
+
+/// 批量改写路径的结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelocateProjectsReport {
+    /// 已改写路径的项目
+    pub relocated: Vec<ProjectPathStatus>,
+    /// 新路径与其他项目重复而未改写的项目
+    pub skipped: Vec<ProjectPathStatus>,
+    /// 改写后目录仍不存在的项目（包括不在前缀下的项目）
+    pub missing: Vec<ProjectPathStatus>,
+}
+
+/// 把 `from_prefix` 下所有项目的路径改写到 `to_prefix` 下（如代码目录从 D:\code 移到 ~/code）
+#[tauri::command]
+pub fn relocate_projects(from_prefix: String, to_prefix: String) -> Result<RelocateProjectsReport, String> {
+    // 空的原路径会匹配所有项目，这里和新路径一样要求绝对路径（原路径可以是其他平台的路径）
+    if !paths::is_absolute_path(&from_prefix) {
+        return Err("原路径必须是绝对路径".to_string());
+    }
+    if !Path::new(&paths::expand_home(&to_prefix)).is_absolute() {
+        return Err("新路径必须是绝对路径".to_string());
+    }
+
+    let mut projects = lock_projects()?;
+    let roots = load_path_roots()?;
+    let now = current_iso_time();
+
+    let mut relocated: Vec<Project> = Vec::new();
+    let mut skipped = Vec::new();
+    for project in projects.iter() {
+        let rest = match paths::strip_path_prefix(&project.path, &from_prefix) {
+            Some(rest) => rest,
+            None => continue,
+        };
+        let new_path = paths::join_components(&to_prefix, &rest);
+
+        let taken = projects
+            .iter()
+            .filter(|p| p.id != project.id && paths::strip_path_prefix(&p.path, &from_prefix).is_none())
+            .chain(relocated.iter())
+            .any(|p| paths::same_path(&p.path, &new_path));
+        if taken {
+            log::warn!("项目 {} 的新路径 {} 已被其他项目使用，跳过", project.name, new_path);
+            skipped.push(ProjectPathStatus::from(project));
+            continue;
+        }
+
+        let mut updated = project.clone();
+        updated.portable_path = paths::to_portable(&new_path, &roots);
+        updated.path = new_path;
+        updated.updated_at = now.clone();
+        relocated.push(updated);
+    }
+
+    save_projects(&relocated)?;
+    for updated in &relocated {
+        if let Some(project) = projects.iter_mut().find(|p| p.id == updated.id) {
+            *project = updated.clone();
+        }
+    }
+    log::info!("已改写 {} 个项目的路径: {} -> {}", relocated.len(), from_prefix, to_prefix);
+
+    Ok(RelocateProjectsReport {
+        relocated: relocated.iter().map(ProjectPathStatus::from).collect(),
+        skipped,
+        missing: projects
+            .iter()
+            .map(ProjectPathStatus::from)
+            .filter(|status| !status.exists)
+            .collect(),
+    })
+}
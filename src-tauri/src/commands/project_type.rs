@@ -0,0 +1,372 @@
+// 项目类型识别 - 读取 Cargo.toml、package.json、pom.xml、build.gradle、go.mod、pyproject.toml
+// 等清单文件，得到项目的语言、框架、声明的名称和版本，并据此推荐运行脚本
+
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+use crate::storage::{ProjectScript, ProjectStack};
+
+/// 依赖名到框架名的对应关系，按优先级排列（如同时依赖 next 和 react 时识别为 next）
+const NPM_FRAMEWORKS: &[(&str, &str)] = &[
+    ("@tauri-apps/api", "tauri"),
+    ("electron", "electron"),
+    ("next", "next"),
+    ("nuxt", "nuxt"),
+    ("@angular/core", "angular"),
+    ("@sveltejs/kit", "sveltekit"),
+    ("svelte", "svelte"),
+    ("vue", "vue"),
+    ("react", "react"),
+    ("@nestjs/core", "nestjs"),
+    ("express", "express"),
+    ("koa", "koa"),
+];
+
+const CARGO_FRAMEWORKS: &[(&str, &str)] = &[
+    ("tauri", "tauri"),
+    ("actix-web", "actix-web"),
+    ("axum", "axum"),
+    ("rocket", "rocket"),
+    ("bevy", "bevy"),
+    ("leptos", "leptos"),
+];
+
+const JVM_FRAMEWORKS: &[(&str, &str)] = &[
+    ("spring-boot", "spring-boot"),
+    ("quarkus", "quarkus"),
+    ("micronaut", "micronaut"),
+    ("com.android", "android"),
+];
+
+const GO_FRAMEWORKS: &[(&str, &str)] = &[
+    ("github.com/gin-gonic/gin", "gin"),
+    ("github.com/labstack/echo", "echo"),
+    ("github.com/gofiber/fiber", "fiber"),
+    ("github.com/beego/beego", "beego"),
+];
+
+const PYTHON_FRAMEWORKS: &[&str] = &["django", "fastapi", "flask", "streamlit"];
+
+/// 能识别的清单文件（扫描时用来判断目录是否为项目）
+pub const MANIFEST_FILES: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "go.mod",
+    "pyproject.toml",
+];
+
+/// 识别目录的技术栈，按 MANIFEST_FILES 的顺序取第一个能解析的清单
+pub fn detect_stack(dir: &Path) -> Option<ProjectStack> {
+    MANIFEST_FILES.iter().find_map(|manifest| {
+        let path = dir.join(manifest);
+        if !path.is_file() {
+            return None;
+        }
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                log::warn!("读取清单文件失败 {:?}: {}", path, e);
+                return None;
+            }
+        };
+        let stack = match *manifest {
+            "Cargo.toml" => parse_cargo(&content),
+            "package.json" => parse_package_json(dir, &content),
+            "pom.xml" => Some(parse_pom(&content)),
+            "build.gradle" | "build.gradle.kts" => Some(parse_gradle(dir, manifest, &content)),
+            "go.mod" => Some(parse_go_mod(&content)),
+            "pyproject.toml" => parse_pyproject(&content),
+            _ => None,
+        };
+        stack.map(|stack| ProjectStack { manifest: manifest.to_string(), ..stack })
+    })
+}
+
+fn stack(language: &str) -> ProjectStack {
+    ProjectStack {
+        language: language.to_string(),
+        framework: None,
+        declared_name: None,
+        version: None,
+        manifest: String::new(),
+    }
+}
+
+fn find_framework(table: &[(&str, &str)], has: impl Fn(&str) -> bool) -> Option<String> {
+    table.iter().find(|(key, _)| has(key)).map(|(_, name)| name.to_string())
+}
+
+fn parse_cargo(content: &str) -> Option<ProjectStack> {
+    let manifest: toml::Table = toml::from_str(content).ok()?;
+    let package = manifest.get("package").and_then(|p| p.as_table());
+    let text = |key: &str| package.and_then(|p| p.get(key)).and_then(|v| v.as_str()).map(str::to_string);
+
+    // 工作区根目录的依赖写在 [workspace.dependencies]
+    let dependency_tables: Vec<&toml::Table> = [
+        manifest.get("dependencies"),
+        manifest.get("workspace").and_then(|w| w.get("dependencies")),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|t| t.as_table())
+    .collect();
+
+    Some(ProjectStack {
+        framework: find_framework(CARGO_FRAMEWORKS, |name| dependency_tables.iter().any(|t| t.contains_key(name))),
+        declared_name: text("name"),
+        version: text("version"),
+        ..stack("rust")
+    })
+}
+
+fn parse_package_json(dir: &Path, content: &str) -> Option<ProjectStack> {
+    let manifest: Value = serde_json::from_str(content).ok()?;
+    let has_dependency = |name: &str| {
+        ["dependencies", "devDependencies", "peerDependencies"]
+            .iter()
+            .any(|key| manifest.get(key).and_then(|d| d.get(name)).is_some())
+    };
+    let text = |key: &str| manifest.get(key).and_then(Value::as_str).map(str::to_string);
+
+    let language = if has_dependency("typescript") || dir.join("tsconfig.json").is_file() {
+        "typescript"
+    } else {
+        "javascript"
+    };
+
+    Some(ProjectStack {
+        framework: find_framework(NPM_FRAMEWORKS, has_dependency),
+        declared_name: text("name"),
+        version: text("version"),
+        ..stack(language)
+    })
+}
+
+/// 取出第一个 <tag>...</tag> 的文本
+fn xml_text<'a>(content: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let start = content.find(&open)? + open.len();
+    let end = content[start..].find(&format!("</{}>", tag))?;
+    Some(content[start..start + end].trim())
+}
+
+/// 去掉 <tag>...</tag> 区块，避免把父 POM 或依赖的坐标当成项目本身的
+fn strip_xml_block(content: &str, tag: &str) -> String {
+    let (open, close) = (format!("<{}>", tag), format!("</{}>", tag));
+    let mut result = content.to_string();
+    while let Some(start) = result.find(&open) {
+        match result[start..].find(&close) {
+            Some(end) => result.replace_range(start..start + end + close.len(), ""),
+            None => break,
+        }
+    }
+    result
+}
+
+/// pom.xml 只做简单的文本提取，不引入完整的 XML 解析
+fn parse_pom(content: &str) -> ProjectStack {
+    let own = ["parent", "dependencies", "dependencyManagement", "build", "profiles"]
+        .iter()
+        .fold(content.to_string(), |text, tag| strip_xml_block(&text, tag));
+
+    let language = if content.contains("kotlin-maven-plugin") || content.contains("kotlin-stdlib") {
+        "kotlin"
+    } else {
+        "java"
+    };
+
+    ProjectStack {
+        framework: find_framework(JVM_FRAMEWORKS, |name| content.contains(name)),
+        declared_name: xml_text(&own, "artifactId").map(str::to_string),
+        version: xml_text(&own, "version").map(str::to_string),
+        ..stack(language)
+    }
+}
+
+/// 取出 `key = 'value'`、`key = "value"` 或 `key "value"` 形式的值
+fn assigned_value(content: &str, key: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix(key)?;
+        let rest = rest.trim_start().trim_start_matches('=').trim();
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &rest[1..];
+        Some(value[..value.find(quote)?].to_string())
+    })
+}
+
+fn parse_gradle(dir: &Path, manifest: &str, content: &str) -> ProjectStack {
+    let language = if manifest.ends_with(".kts") || content.contains("kotlin(") || content.contains("org.jetbrains.kotlin") {
+        "kotlin"
+    } else {
+        "java"
+    };
+    // 项目名写在 settings.gradle 的 rootProject.name 中，没有时 Gradle 使用目录名
+    let declared_name = ["settings.gradle", "settings.gradle.kts"]
+        .iter()
+        .filter_map(|name| fs::read_to_string(dir.join(name)).ok())
+        .find_map(|settings| assigned_value(&settings, "rootProject.name"));
+
+    ProjectStack {
+        framework: find_framework(JVM_FRAMEWORKS, |name| content.contains(name)),
+        declared_name,
+        version: assigned_value(content, "version"),
+        ..stack(language)
+    }
+}
+
+fn parse_go_mod(content: &str) -> ProjectStack {
+    let module = content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("module "))
+        .map(|module| module.trim().trim_matches('"').to_string());
+
+    // go.mod 没有项目版本号，版本由 git 标签决定
+    ProjectStack {
+        framework: find_framework(GO_FRAMEWORKS, |name| content.contains(name)),
+        declared_name: module,
+        ..stack("go")
+    }
+}
+
+fn parse_pyproject(content: &str) -> Option<ProjectStack> {
+    let manifest: toml::Table = toml::from_str(content).ok()?;
+    // PEP 621 的 [project]，或 Poetry 的 [tool.poetry]
+    let project = manifest
+        .get("project")
+        .or_else(|| manifest.get("tool").and_then(|t| t.get("poetry")))
+        .and_then(|p| p.as_table());
+    let text = |key: &str| project.and_then(|p| p.get(key)).and_then(|v| v.as_str()).map(str::to_string);
+
+    let dependencies = [
+        project.and_then(|p| p.get("dependencies")).map(|d| d.to_string()),
+        manifest.get("tool").and_then(|t| t.get("poetry")).and_then(|p| p.get("dependencies")).map(|d| d.to_string()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<String>()
+    .to_lowercase();
+
+    Some(ProjectStack {
+        framework: PYTHON_FRAMEWORKS
+            .iter()
+            .find(|name| dependencies.contains(*name))
+            .map(|name| name.to_string()),
+        declared_name: text("name"),
+        version: text("version"),
+        ..stack("python")
+    })
+}
+
+// ============== 运行脚本推荐 ==============
+
+/// 根据 package.json 的 scripts、Cargo.toml 和 Makefile 推荐运行脚本（同名脚本只保留先出现的）
+pub fn suggest_scripts(dir: &Path) -> Vec<ProjectScript> {
+    let mut scripts: Vec<ProjectScript> = Vec::new();
+    let mut add = |name: &str, command: String| {
+        if !scripts.iter().any(|s| s.name == name) {
+            scripts.push(ProjectScript { name: name.to_string(), command });
+        }
+    };
+
+    if let Some(manifest) = fs::read_to_string(dir.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+    {
+        let runner = node_package_manager(dir);
+        if let Some(entries) = manifest.get("scripts").and_then(Value::as_object) {
+            for name in entries.keys() {
+                add(name, format!("{} run {}", runner, name));
+            }
+        }
+    }
+
+    if dir.join("Cargo.toml").is_file() {
+        if dir.join("src").join("main.rs").is_file() {
+            add("dev", "cargo run".to_string());
+        }
+        add("build", "cargo build".to_string());
+        add("test", "cargo test".to_string());
+    }
+
+    if let Ok(makefile) = fs::read_to_string(dir.join("Makefile")) {
+        for target in make_targets(&makefile) {
+            add(target, format!("make {}", target));
+        }
+    }
+
+    scripts
+}
+
+/// 按锁文件判断使用的包管理器
+fn node_package_manager(dir: &Path) -> &'static str {
+    [
+        ("pnpm-lock.yaml", "pnpm"),
+        ("yarn.lock", "yarn"),
+        ("bun.lockb", "bun"),
+        ("bun.lock", "bun"),
+    ]
+    .iter()
+    .find(|(lock, _)| dir.join(lock).is_file())
+    .map(|(_, runner)| *runner)
+    .unwrap_or("npm")
+}
+
+/// Makefile 中显式声明的目标（跳过 .PHONY 等特殊目标、模式规则和变量赋值）
+fn make_targets(content: &str) -> Vec<&str> {
+    let mut targets = Vec::new();
+    for line in content.lines() {
+        if line.starts_with(['\t', ' ', '#', '.']) {
+            continue;
+        }
+        let Some((head, rest)) = line.split_once(':') else { continue };
+        if rest.starts_with('=') || head.contains(['=', '%', '$']) {
+            continue;
+        }
+        for target in head.split_whitespace() {
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+    }
+    targets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pom_ignores_parent_and_dependency_coordinates() {
+        let pom = "<project><parent><artifactId>spring-boot-starter-parent</artifactId><version>3.2.0</version></parent>\
+                   <artifactId>order-service</artifactId><version>1.4.0</version>\
+                   <dependencies><dependency><artifactId>lombok</artifactId></dependency></dependencies></project>";
+        let stack = parse_pom(pom);
+        assert_eq!(stack.declared_name.as_deref(), Some("order-service"));
+        assert_eq!(stack.version.as_deref(), Some("1.4.0"));
+        assert_eq!(stack.framework.as_deref(), Some("spring-boot"));
+    }
+
+    #[test]
+    fn package_json_prefers_meta_framework() {
+        let json = r#"{ "name": "site", "dependencies": { "react": "19", "next": "15" } }"#;
+        let stack = parse_package_json(Path::new("/nonexistent"), json).unwrap();
+        assert_eq!(stack.language, "javascript");
+        assert_eq!(stack.framework.as_deref(), Some("next"));
+    }
+
+    #[test]
+    fn make_targets_skip_special_and_pattern_rules() {
+        let makefile = ".PHONY: build test\nCC := gcc\nbuild: deps\n\tgo build\ntest:\n%.o: %.c\n";
+        assert_eq!(make_targets(makefile), vec!["build", "test"]);
+    }
+
+    #[test]
+    fn gradle_version_skips_similar_keys() {
+        let gradle = "versionCode 3\nversion = '2.1.0'\n";
+        assert_eq!(assigned_value(gradle, "version").as_deref(), Some("2.1.0"));
+    }
+}
@@ -0,0 +1,351 @@
+// 仓库扫描 - 多线程遍历目录查找 Git 仓库
+//
+// 遍历遵循 .gitignore 和设置中的排除目录，识别普通仓库、裸仓库、工作树和子模块，
+// 也可以发现只有项目清单文件（Cargo.toml、package.json 等）的非 Git 项目。
+// 无法读取的目录记录下来继续扫描，不会让整次扫描失败。
+
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::{DirEntry, WalkBuilder, WalkState};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::git::{GitRepo, RepoKind};
+use crate::commands::project_type;
+use crate::storage::{keys, load_setting, AppSettings};
+
+const SCAN_EVENT: &str = "repo-scan-event";
+
+/// 扫描线程数上限（磁盘 IO 为主，线程再多也不会更快）
+const MAX_SCAN_THREADS: usize = 8;
+
+/// 扫描参数，未指定的项使用应用设置
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanOptions {
+    pub depth: Option<u32>,
+    pub excludes: Option<Vec<String>>,
+    pub threads: Option<usize>,
+    /// 是否同时发现非 Git 项目目录，默认只找 Git 仓库
+    pub include_plain: Option<bool>,
+}
+
+/// 无法读取的路径
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanError {
+    pub path: Option<String>,
+    pub message: String,
+}
+
+/// 扫描结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanReport {
+    pub root: String,
+    pub repos: Vec<GitRepo>,
+    pub errors: Vec<ScanError>,
+    pub elapsed_ms: u64,
+}
+
+/// 扫描事件（找到的仓库会在扫描过程中逐个推送）
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ScanEvent {
+    Found { root: String, repo: GitRepo },
+    Completed { report: ScanReport },
+}
+
+/// 解析后的扫描配置
+pub(crate) struct ScanConfig {
+    /// 与原扫描一致：深度 N 时最多发现根目录下 N-1 层的仓库
+    pub depth: u32,
+    pub excludes: Vec<String>,
+    pub threads: usize,
+    pub include_plain: bool,
+}
+
+impl ScanConfig {
+    pub fn load(options: ScanOptions) -> Result<Self, String> {
+        let settings = load_setting::<AppSettings>(keys::APP_SETTINGS)?.unwrap_or_default();
+        let default_threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4);
+
+        Ok(Self {
+            depth: options.depth.unwrap_or(settings.scan_depth),
+            excludes: options.excludes.unwrap_or(settings.scan_excludes),
+            threads: options.threads.unwrap_or(default_threads).clamp(1, MAX_SCAN_THREADS),
+            include_plain: options.include_plain.unwrap_or(false),
+        })
+    }
+}
+
+/// 校验排除规则能否解析为 glob
+pub fn validate_excludes(excludes: &[String]) -> Result<(), String> {
+    build_overrides(Path::new("/"), excludes).map(|_| ())
+}
+
+/// 排除规则转为忽略型 override（只有 "!" 开头的规则时，不匹配的路径照常遍历）
+pub(crate) fn build_overrides(root: &Path, excludes: &[String]) -> Result<Override, String> {
+    let mut builder = OverrideBuilder::new(root);
+    for pattern in excludes.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let pattern = pattern.trim_start_matches('!');
+        builder
+            .add(&format!("!{}", pattern))
+            .map_err(|e| format!("排除规则无效 {}: {}", pattern, e))?;
+    }
+    builder.build().map_err(|e| format!("排除规则无效: {}", e))
+}
+
+/// 裸仓库：目录本身包含 HEAD、objects 和 refs
+fn is_bare_repo(dir: &Path) -> bool {
+    dir.join("HEAD").is_file() && dir.join("objects").is_dir() && dir.join("refs").is_dir()
+}
+
+/// 判断目录是否为仓库及其类型
+fn repo_kind(dir: &Path) -> Option<RepoKind> {
+    let dot_git = dir.join(".git");
+    match std::fs::metadata(&dot_git) {
+        Ok(meta) if meta.is_dir() => Some(RepoKind::Normal),
+        // .git 文件指向真正的 git 目录：子模块在父仓库的 modules/ 下，工作树在 worktrees/ 下
+        Ok(_) => {
+            let content = std::fs::read_to_string(&dot_git).ok()?;
+            let gitdir = content.trim().strip_prefix("gitdir:")?.trim().replace('\\', "/");
+            if gitdir.contains("/modules/") {
+                Some(RepoKind::Submodule)
+            } else if gitdir.contains("/worktrees/") {
+                Some(RepoKind::Worktree)
+            } else {
+                Some(RepoKind::Normal)
+            }
+        }
+        Err(_) if is_bare_repo(dir) => Some(RepoKind::Bare),
+        Err(_) => None,
+    }
+}
+
+/// 有项目清单文件且不在已发现的 Git 仓库内
+fn is_plain_project(entry: &DirEntry, git_dirs: &Mutex<HashSet<PathBuf>>) -> bool {
+    let has_manifest = project_type::MANIFEST_FILES
+        .iter()
+        .any(|manifest| entry.path().join(manifest).is_file());
+    if !has_manifest {
+        return false;
+    }
+    // 父目录总是先于子目录被访问，检查时祖先仓库已经登记
+    let git_dirs = git_dirs.lock().unwrap_or_else(|e| e.into_inner());
+    !entry.path().ancestors().skip(1).any(|dir| git_dirs.contains(dir))
+}
+
+fn scan_error(err: &ignore::Error) -> ScanError {
+    fn error_path(err: &ignore::Error) -> Option<&Path> {
+        match err {
+            ignore::Error::WithPath { path, .. } => Some(path),
+            ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => error_path(err),
+            ignore::Error::Partial(errs) => errs.iter().find_map(error_path),
+            ignore::Error::Loop { child, .. } => Some(child),
+            _ => None,
+        }
+    }
+
+    ScanError {
+        path: error_path(err).map(|p| p.to_string_lossy().to_string()),
+        message: err.to_string(),
+    }
+}
+
+fn to_repo(entry: &DirEntry, kind: RepoKind) -> GitRepo {
+    let path = entry.path();
+    GitRepo {
+        path: path.to_string_lossy().to_string(),
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Unknown".to_string()),
+        kind,
+        stack: project_type::detect_stack(path),
+    }
+}
+
+/// 扫描目录下的仓库，每找到一个就调用 `on_found`（在扫描线程中调用）
+pub(crate) fn scan_repos<F>(root: &str, config: &ScanConfig, on_found: F) -> Result<ScanReport, String>
+where
+    F: Fn(&GitRepo) + Sync,
+{
+    let started = Instant::now();
+    let root_path = Path::new(root);
+    if !root_path.is_dir() {
+        return Err(format!("目录不存在: {}", root));
+    }
+
+    let repos = Mutex::new(Vec::new());
+    let errors = Mutex::new(Vec::new());
+    // 已发现的 Git 仓库，仓库内的子目录（如前端、后端子项目）不再单独作为非 Git 项目
+    let git_dirs = Mutex::new(HashSet::<PathBuf>::new());
+
+    if config.depth > 0 {
+        let overrides = build_overrides(root_path, &config.excludes)?;
+        let walker = WalkBuilder::new(root_path)
+            .threads(config.threads)
+            .max_depth(Some(config.depth as usize - 1))
+            .follow_links(false)
+            .overrides(overrides)
+            .filter_entry(|entry| entry.file_type().is_some_and(|t| t.is_dir()))
+            .build_parallel();
+
+        walker.run(|| {
+            let repos = &repos;
+            let errors = &errors;
+            let on_found = &on_found;
+            let git_dirs = &git_dirs;
+            Box::new(move |result| {
+                let entry = match result {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        errors.lock().unwrap_or_else(|e| e.into_inner()).push(scan_error(&e));
+                        return WalkState::Continue;
+                    }
+                };
+                // 目录内 .gitignore 解析失败不影响扫描，只记录下来
+                if let Some(e) = entry.error() {
+                    errors.lock().unwrap_or_else(|e| e.into_inner()).push(scan_error(e));
+                }
+
+                let kind = match repo_kind(entry.path()) {
+                    Some(kind) => kind,
+                    None if config.include_plain && is_plain_project(&entry, git_dirs) => RepoKind::Plain,
+                    None => return WalkState::Continue,
+                };
+                if kind != RepoKind::Plain {
+                    git_dirs.lock().unwrap_or_else(|e| e.into_inner()).insert(entry.path().to_path_buf());
+                }
+
+                let repo = to_repo(&entry, kind);
+                on_found(&repo);
+                repos.lock().unwrap_or_else(|e| e.into_inner()).push(repo);
+                // 裸仓库内部只有 git 数据，非 Git 项目的子目录属于项目本身；
+                // Git 仓库继续向下查找子模块和嵌套仓库
+                match kind {
+                    RepoKind::Bare | RepoKind::Plain => WalkState::Skip,
+                    _ => WalkState::Continue,
+                }
+            })
+        });
+    }
+
+    let mut repos = repos.into_inner().unwrap_or_else(|e| e.into_inner());
+    repos.sort_by(|a, b| a.path.cmp(&b.path));
+    let errors = errors.into_inner().unwrap_or_else(|e| e.into_inner());
+
+    Ok(ScanReport {
+        root: root.to_string(),
+        repos,
+        errors,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// 扫描目录，找到的仓库通过事件实时推送，完成后返回完整结果
+#[tauri::command]
+pub async fn scan_repositories(app: AppHandle, path: String, options: Option<ScanOptions>) -> Result<ScanReport, String> {
+    let config = ScanConfig::load(options.unwrap_or_default())?;
+
+    let emitter = app.clone();
+
+    let report = tokio::task::spawn_blocking(move || {
+        scan_repos(&path, &config, |repo| {
+            let event = ScanEvent::Found { root: path.clone(), repo: repo.clone() };
+            if let Err(e) = emitter.emit(SCAN_EVENT, &event) {
+                log::warn!("发送扫描事件失败: {}", e);
+            }
+        })
+    })
+    .await
+    .map_err(|e| format!("扫描任务异常: {}", e))??;
+
+    if !report.errors.is_empty() {
+        log::warn!("扫描 {} 时有 {} 个路径无法读取", report.root, report.errors.len());
+    }
+    let _ = app.emit(SCAN_EVENT, &ScanEvent::Completed { report: report.clone() });
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn detects_repo_kinds() {
+        let dir = TempDir::new("repo-kind");
+        dir.mkdir("normal/.git");
+        dir.write("sub/.git", "gitdir: ../.git/modules/sub\n");
+        dir.write("tree/.git", "gitdir: C:\\repos\\app\\.git\\worktrees\\tree\n");
+        dir.write("linked/.git", "gitdir: /srv/app.git\n");
+        dir.write("broken/.git", "not a gitdir file");
+        for sub in ["bare.git/objects", "bare.git/refs"] {
+            dir.mkdir(sub);
+        }
+        dir.write("bare.git/HEAD", "ref: refs/heads/main\n");
+        dir.mkdir("plain");
+
+        let kind = |name: &str| repo_kind(&dir.path().join(name));
+        assert_eq!(kind("normal"), Some(RepoKind::Normal));
+        assert_eq!(kind("sub"), Some(RepoKind::Submodule));
+        assert_eq!(kind("tree"), Some(RepoKind::Worktree));
+        assert_eq!(kind("linked"), Some(RepoKind::Normal));
+        assert_eq!(kind("broken"), None);
+        assert_eq!(kind("bare.git"), Some(RepoKind::Bare));
+        assert_eq!(kind("plain"), None);
+    }
+
+    #[test]
+    fn builds_exclusion_overrides() {
+        let root = Path::new("/work");
+        let excludes = vec!["node_modules".to_string(), " !dist ".to_string(), "".to_string()];
+        let overrides = build_overrides(root, &excludes).unwrap();
+        assert!(overrides.matched("/work/app/node_modules", true).is_ignore());
+        // "!" 开头的规则也按排除处理，其他目录照常遍历
+        assert!(overrides.matched("/work/dist", true).is_ignore());
+        assert!(!overrides.matched("/work/app/src", true).is_ignore());
+
+        assert!(build_overrides(root, &["a[".to_string()]).is_err());
+        assert!(validate_excludes(&["target".to_string()]).is_ok());
+    }
+
+    #[test]
+    fn scans_repositories_within_depth() {
+        let dir = TempDir::new("scan");
+        dir.mkdir("a/.git");
+        dir.mkdir("a/nested/.git");
+        dir.mkdir("group/b/.git");
+        dir.mkdir("node_modules/c/.git");
+        dir.write("tool/Cargo.toml", "[package]\nname = \"tool\"\n");
+
+        let config = ScanConfig {
+            depth: 3,
+            excludes: vec!["node_modules".to_string()],
+            threads: 2,
+            include_plain: true,
+        };
+        let report = scan_repos(&dir.path().to_string_lossy(), &config, |_| {}).unwrap();
+        let found: Vec<(String, RepoKind)> = report
+            .repos
+            .iter()
+            .map(|r| (Path::new(&r.path).strip_prefix(dir.path()).unwrap().to_string_lossy().replace('\\', "/"), r.kind))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("a".to_string(), RepoKind::Normal),
+                ("a/nested".to_string(), RepoKind::Normal),
+                ("group/b".to_string(), RepoKind::Normal),
+                ("tool".to_string(), RepoKind::Plain),
+            ]
+        );
+    }
+}
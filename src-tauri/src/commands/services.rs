@@ -0,0 +1,450 @@
+// 开发服务守护 - 以项目脚本启动常驻服务（dev server 等），异常退出时按退避时间自动重启，
+// 并根据进程树找出服务监听的端口
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+
+use crate::commands::tasks::{self, TaskStatus};
+use crate::commands::toolbox::process;
+use crate::storage::{current_iso_time, generate_id};
+
+const SERVICE_EVENT: &str = "service-event";
+
+/// 第一次重启前的等待时间，之后每次翻倍
+const RESTART_BACKOFF_BASE_MS: u64 = 1000;
+
+/// 重启等待时间上限
+const RESTART_BACKOFF_MAX_MS: u64 = 60_000;
+
+/// 连续崩溃超过该次数后不再重启
+const MAX_CONSECUTIVE_CRASHES: u32 = 5;
+
+/// 运行超过该时长后退出视为偶发崩溃，重新计算退避时间
+const STABLE_RUN_SECS: u64 = 60;
+
+/// 服务状态
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ServiceStatus {
+    Running,
+    /// 异常退出，等待重启
+    Restarting,
+    /// 被用户停止
+    Stopped,
+    /// 正常退出（退出码为 0），不会重启
+    Exited,
+    /// 连续崩溃次数过多或无法启动，已放弃重启
+    Crashed,
+}
+
+/// 服务信息
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceInfo {
+    pub id: String,
+    pub project_id: String,
+    pub script_name: String,
+    pub command: String,
+    pub status: ServiceStatus,
+    /// 当前运行对应的任务运行 ID，输出通过任务事件推送
+    pub run_id: Option<String>,
+    pub pid: Option<u32>,
+    /// 自动重启的总次数
+    pub restarts: u32,
+    pub last_exit_code: Option<i32>,
+    pub started_at: String,
+    pub next_restart_at: Option<String>,
+    /// 服务进程树监听的端口（查询时计算）
+    pub ports: Vec<u16>,
+}
+
+/// 服务事件
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ServiceEvent {
+    Updated { service: ServiceInfo },
+}
+
+/// 守护中的服务
+struct Service {
+    info: Mutex<ServiceInfo>,
+    /// 用户停止时通知守护循环退出（包括正在等待重启的情况）
+    stop: Notify,
+    stop_requested: AtomicBool,
+}
+
+impl Service {
+    fn snapshot(&self) -> ServiceInfo {
+        self.info.lock().map(|info| info.clone()).unwrap_or_else(|e| e.into_inner().clone())
+    }
+
+    fn update(&self, app: &AppHandle, apply: impl FnOnce(&mut ServiceInfo)) {
+        let service = {
+            let mut info = self.info.lock().unwrap_or_else(|e| e.into_inner());
+            apply(&mut info);
+            info.clone()
+        };
+        if let Err(e) = app.emit(SERVICE_EVENT, &ServiceEvent::Updated { service }) {
+            log::warn!("发送服务事件失败: {}", e);
+        }
+    }
+
+    fn is_stop_requested(&self) -> bool {
+        self.stop_requested.load(Ordering::SeqCst)
+    }
+}
+
+/// 所有服务（按服务 ID），停止后保留到用户移除，便于查看退出原因
+static SERVICES: Lazy<Mutex<HashMap<String, Arc<Service>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn get_service(service_id: &str) -> Result<Arc<Service>, String> {
+    SERVICES
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(service_id)
+        .cloned()
+        .ok_or_else(|| "服务不存在".to_string())
+}
+
+fn is_active(status: ServiceStatus) -> bool {
+    matches!(status, ServiceStatus::Running | ServiceStatus::Restarting)
+}
+
+/// 第 n 次连续崩溃后的等待时间
+fn backoff(consecutive_crashes: u32) -> Duration {
+    let factor = 1u64 << consecutive_crashes.saturating_sub(1).min(16);
+    Duration::from_millis((RESTART_BACKOFF_BASE_MS * factor).min(RESTART_BACKOFF_MAX_MS))
+}
+
+/// 服务进程退出后的处理
+#[derive(Debug, PartialEq, Eq)]
+enum ExitDecision {
+    /// 用户停止
+    Stopped,
+    /// 正常退出，不重启
+    Exited,
+    /// 连续崩溃次数过多，放弃重启
+    GiveUp,
+    /// 等待后重启
+    Restart(Duration),
+}
+
+/// 根据退出情况决定是否重启，并更新连续崩溃次数
+fn decide_exit(stop_requested: bool, status: TaskStatus, ran_for: Duration, consecutive_crashes: &mut u32) -> ExitDecision {
+    if stop_requested || status == TaskStatus::Stopped {
+        return ExitDecision::Stopped;
+    }
+    if status == TaskStatus::Succeeded {
+        return ExitDecision::Exited;
+    }
+    if ran_for >= Duration::from_secs(STABLE_RUN_SECS) {
+        *consecutive_crashes = 0;
+    }
+    *consecutive_crashes += 1;
+    if *consecutive_crashes > MAX_CONSECUTIVE_CRASHES {
+        ExitDecision::GiveUp
+    } else {
+        ExitDecision::Restart(backoff(*consecutive_crashes))
+    }
+}
+
+/// 守护循环：启动脚本，异常退出时等待退避时间后重启
+async fn supervise(app: AppHandle, service: Arc<Service>) {
+    let (project_id, script_name) = {
+        let info = service.snapshot();
+        (info.project_id, info.script_name)
+    };
+    let mut consecutive_crashes = 0;
+
+    loop {
+        // 每次都重新读取项目脚本，修改命令后重启即可生效
+        let started = tasks::find_project(&project_id)
+            .and_then(|project| {
+                let script = tasks::find_script(&project, &script_name)?;
+                tasks::start_script(&app, &project, &script).map(|(run, done)| (script, run, done))
+            });
+        let (script, run, done) = match started {
+            Ok(started) => started,
+            Err(e) => {
+                log::error!("启动服务 {} 失败: {}", script_name, e);
+                service.update(&app, |info| {
+                    info.status = ServiceStatus::Crashed;
+                    info.pid = None;
+                    info.next_restart_at = None;
+                });
+                return;
+            }
+        };
+
+        service.update(&app, |info| {
+            info.status = ServiceStatus::Running;
+            info.command = script.command.clone();
+            info.run_id = Some(run.id.clone());
+            info.pid = run.pid;
+            info.next_restart_at = None;
+        });
+        // 停止请求可能在启动期间到达，那时读到的还是上一次运行的 ID
+        if service.is_stop_requested() && tasks::is_running(&run.id) {
+            if let Err(e) = tasks::stop_run(&run.id).await {
+                log::warn!("停止服务 {} 失败: {}", script_name, e);
+            }
+        }
+
+        let launched = Instant::now();
+        let finished = match done.await {
+            Ok(finished) => finished,
+            Err(e) => {
+                log::error!("等待服务 {} 结束失败: {}", script_name, e);
+                service.update(&app, |info| info.status = ServiceStatus::Crashed);
+                return;
+            }
+        };
+
+        let finish = |status: ServiceStatus| {
+            service.update(&app, |info| {
+                info.status = status;
+                info.pid = None;
+                info.last_exit_code = finished.exit_code;
+            })
+        };
+        let ran_for = launched.elapsed();
+        let delay = match decide_exit(service.is_stop_requested(), finished.status, ran_for, &mut consecutive_crashes) {
+            ExitDecision::Restart(delay) => delay,
+            ExitDecision::Stopped => return finish(ServiceStatus::Stopped),
+            ExitDecision::Exited => return finish(ServiceStatus::Exited),
+            ExitDecision::GiveUp => {
+                log::warn!("服务 {} 连续崩溃 {} 次，不再重启", script_name, MAX_CONSECUTIVE_CRASHES);
+                return finish(ServiceStatus::Crashed);
+            }
+        };
+
+        log::warn!("服务 {} 异常退出（{:?}），{}ms 后重启", script_name, finished.exit_code, delay.as_millis());
+        let next_restart_at = chrono::Utc::now() + chrono::Duration::milliseconds(delay.as_millis() as i64);
+        service.update(&app, |info| {
+            info.status = ServiceStatus::Restarting;
+            info.pid = None;
+            info.last_exit_code = finished.exit_code;
+            info.next_restart_at = Some(next_restart_at.to_rfc3339());
+        });
+
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = service.stop.notified() => {}
+        }
+        if service.is_stop_requested() {
+            service.update(&app, |info| {
+                info.status = ServiceStatus::Stopped;
+                info.next_restart_at = None;
+            });
+            return;
+        }
+        service.update(&app, |info| info.restarts += 1);
+    }
+}
+
+/// 停止服务：不再重启，并结束当前的进程树
+async fn stop_service_inner(service: &Service) -> Result<(), String> {
+    service.stop_requested.store(true, Ordering::SeqCst);
+    service.stop.notify_one();
+
+    match service.snapshot().run_id {
+        Some(run_id) if tasks::is_running(&run_id) => tasks::stop_run(&run_id).await,
+        _ => Ok(()),
+    }
+}
+
+/// 填充服务进程树监听的端口
+async fn with_ports(mut services: Vec<ServiceInfo>) -> Vec<ServiceInfo> {
+    if !services.iter().any(|s| s.pid.is_some()) {
+        return services;
+    }
+    let port_map = match process::get_port_pid_map().await {
+        Ok(map) => map,
+        Err(e) => {
+            log::warn!("获取端口占用失败: {}", e);
+            return services;
+        }
+    };
+
+    for service in services.iter_mut() {
+        let Some(pid) = service.pid else { continue };
+        let tree = process::process_tree(pid);
+        let mut ports: Vec<u16> = port_map
+            .iter()
+            .filter(|(_, pids)| pids.iter().any(|pid| tree.contains(pid)))
+            .map(|(port, _)| *port)
+            .collect();
+        ports.sort_unstable();
+        service.ports = ports;
+    }
+    services
+}
+
+/// 以项目脚本启动服务（同一脚本同时只能有一个服务在运行）
+#[tauri::command]
+pub async fn start_service(app: AppHandle, project_id: String, script_name: String) -> Result<ServiceInfo, String> {
+    let project = tasks::find_project(&project_id)?;
+    let script = tasks::find_script(&project, &script_name)?;
+
+    let service = {
+        let mut services = SERVICES.lock().map_err(|e| e.to_string())?;
+        let running = services.values().any(|s| {
+            let info = s.snapshot();
+            info.project_id == project_id
+                && info.script_name == script_name
+                && is_active(info.status)
+                && !s.is_stop_requested()
+        });
+        if running {
+            return Err(format!("服务 {} 已在运行", script_name));
+        }
+        // 同一脚本只保留最新的一条服务记录
+        services.retain(|_, s| {
+            let info = s.snapshot();
+            !(info.project_id == project_id && info.script_name == script_name)
+        });
+
+        let service = Arc::new(Service {
+            info: Mutex::new(ServiceInfo {
+                id: generate_id(),
+                project_id,
+                script_name,
+                command: script.command,
+                status: ServiceStatus::Running,
+                run_id: None,
+                pid: None,
+                restarts: 0,
+                last_exit_code: None,
+                started_at: current_iso_time(),
+                next_restart_at: None,
+                ports: Vec::new(),
+            }),
+            stop: Notify::new(),
+            stop_requested: AtomicBool::new(false),
+        });
+        services.insert(service.snapshot().id, service.clone());
+        service
+    };
+
+    let info = service.snapshot();
+    tokio::spawn(supervise(app, service));
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn stop_service(service_id: String) -> Result<(), String> {
+    let service = get_service(&service_id)?;
+    stop_service_inner(&service).await
+}
+
+/// 重启服务：停止当前进程后重新启动（重启计数清零）
+#[tauri::command]
+pub async fn restart_service(app: AppHandle, service_id: String) -> Result<ServiceInfo, String> {
+    let service = get_service(&service_id)?;
+    stop_service_inner(&service).await?;
+
+    let info = service.snapshot();
+    start_service(app, info.project_id, info.script_name).await
+}
+
+/// 移除已停止的服务记录
+#[tauri::command]
+pub fn remove_service(service_id: String) -> Result<(), String> {
+    let mut services = SERVICES.lock().map_err(|e| e.to_string())?;
+    if let Some(service) = services.get(&service_id) {
+        if is_active(service.snapshot().status) {
+            return Err("服务仍在运行，请先停止".to_string());
+        }
+    }
+    services.remove(&service_id);
+    Ok(())
+}
+
+/// 服务列表及各自监听的端口，可按项目筛选
+#[tauri::command]
+pub async fn get_services(project_id: Option<String>) -> Result<Vec<ServiceInfo>, String> {
+    let mut services: Vec<ServiceInfo> = SERVICES
+        .lock()
+        .map_err(|e| e.to_string())?
+        .values()
+        .map(|s| s.snapshot())
+        .filter(|info| project_id.is_none() || project_id.as_deref() == Some(info.project_id.as_str()))
+        .collect();
+    services.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+    Ok(with_ports(services).await)
+}
+
+/// 停止项目的所有服务和运行中的任务（包括它们启动的子进程）
+#[tauri::command]
+pub async fn stop_project(project_id: String) -> Result<(), String> {
+    let services: Vec<Arc<Service>> = SERVICES
+        .lock()
+        .map_err(|e| e.to_string())?
+        .values()
+        .filter(|s| s.snapshot().project_id == project_id)
+        .cloned()
+        .collect();
+
+    let mut errors = Vec::new();
+    for service in services {
+        if let Err(e) = stop_service_inner(&service).await {
+            errors.push(e);
+        }
+    }
+    for run_id in tasks::running_runs(&project_id) {
+        if let Err(e) = tasks::stop_run(&run_id).await {
+            errors.push(e);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("部分进程停止失败: {}", errors.join("; ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_limit() {
+        let delays: Vec<u64> = (1..=8).map(|n| backoff(n).as_millis() as u64).collect();
+        assert_eq!(delays, vec![1000, 2000, 4000, 8000, 16000, 32000, 60000, 60000]);
+        assert_eq!(backoff(0), Duration::from_millis(RESTART_BACKOFF_BASE_MS));
+        assert_eq!(backoff(u32::MAX), Duration::from_millis(RESTART_BACKOFF_MAX_MS));
+    }
+
+    #[test]
+    fn restarts_crashes_until_the_limit() {
+        let quick = Duration::from_secs(1);
+        let mut crashes = 0;
+        for n in 1..=MAX_CONSECUTIVE_CRASHES {
+            assert_eq!(decide_exit(false, TaskStatus::Failed, quick, &mut crashes), ExitDecision::Restart(backoff(n)));
+        }
+        assert_eq!(decide_exit(false, TaskStatus::Failed, quick, &mut crashes), ExitDecision::GiveUp);
+
+        // 稳定运行一段时间后的崩溃重新计算退避时间
+        let mut crashes = MAX_CONSECUTIVE_CRASHES;
+        let stable = Duration::from_secs(STABLE_RUN_SECS);
+        assert_eq!(decide_exit(false, TaskStatus::Failed, stable, &mut crashes), ExitDecision::Restart(backoff(1)));
+        assert_eq!(crashes, 1);
+    }
+
+    #[test]
+    fn stop_and_clean_exit_do_not_restart() {
+        let mut crashes = 2;
+        let quick = Duration::from_secs(1);
+        assert_eq!(decide_exit(true, TaskStatus::Failed, quick, &mut crashes), ExitDecision::Stopped);
+        assert_eq!(decide_exit(false, TaskStatus::Stopped, quick, &mut crashes), ExitDecision::Stopped);
+        assert_eq!(decide_exit(false, TaskStatus::Succeeded, quick, &mut crashes), ExitDecision::Exited);
+        assert_eq!(crashes, 2);
+    }
+}
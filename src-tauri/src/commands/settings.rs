@@ -1,46 +1,31 @@
 // 设置管理模块 - 标签、分类、编辑器、终端、应用设置、UI状态、通知
 
 use serde::{Deserialize, Serialize};
-use std::fs;
 
+use crate::commands::{project, repo_scan};
 use crate::storage::{
-    get_storage_config, generate_id, current_iso_time,
-    EditorConfig, TerminalConfig, AppSettings, UiState, Notification,
+    keys, load_setting, save_setting, generate_id, current_iso_time, paths,
+    EditorConfig, TerminalConfig, AppSettings, UiState, Notification, PathRoot, Project,
 };
 
 // ============== 标签管理 ==============
 
 #[tauri::command]
 pub async fn get_labels() -> Result<Vec<String>, String> {
-    let config = get_storage_config()?;
-    let path = config.labels_file();
-
-    if !path.exists() {
-        return Ok(vec![
-            "Java".to_string(), "Python".to_string(), "JavaScript".to_string(),
-            "TypeScript".to_string(), "Rust".to_string(), "Go".to_string(),
-            "Vue".to_string(), "React".to_string(),
-        ]);
-    }
-
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("读取标签文件失败: {}", e))?;
-
-    let labels: Vec<String> = serde_json::from_str(&content).unwrap_or_default();
-    Ok(labels)
+    let labels = load_setting::<Vec<String>>(keys::LABELS)
+        .map_err(|e| format!("读取标签失败: {}", e))?;
+
+    Ok(labels.unwrap_or_else(|| vec![
+        "Java".to_string(), "Python".to_string(), "JavaScript".to_string(),
+        "TypeScript".to_string(), "Rust".to_string(), "Go".to_string(),
+        "Vue".to_string(), "React".to_string(),
+    ]))
 }
 
 #[tauri::command]
 pub async fn save_labels(labels: Vec<String>) -> Result<(), String> {
-    let config = get_storage_config()?;
-    config.ensure_dirs()?;
-
-    let content = serde_json::to_string(&labels)
-        .map_err(|e| format!("序列化标签失败: {}", e))?;
-
-    fs::write(config.labels_file(), content)
-        .map_err(|e| format!("保存标签失败: {}", e))?;
-    Ok(())
+    save_setting(keys::LABELS, &labels)
+        .map_err(|e| format!("保存标签失败: {}", e))
 }
 
 #[tauri::command]
@@ -65,31 +50,18 @@ pub async fn remove_label(label: String) -> Result<Vec<String>, String> {
 
 #[tauri::command]
 pub async fn get_categories() -> Result<Vec<String>, String> {
-    let config = get_storage_config()?;
-    let path = config.categories_file();
+    let categories = load_setting::<Vec<String>>(keys::CATEGORIES)
+        .map_err(|e| format!("读取分类失败: {}", e))?;
 
-    if !path.exists() {
-        return Ok(vec!["工作".to_string(), "个人".to_string(), "学习".to_string(), "测试".to_string()]);
-    }
-
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("读取分类文件失败: {}", e))?;
-
-    let categories: Vec<String> = serde_json::from_str(&content).unwrap_or_default();
-    Ok(categories)
+    Ok(categories.unwrap_or_else(|| {
+        vec!["工作".to_string(), "个人".to_string(), "学习".to_string(), "测试".to_string()]
+    }))
 }
 
 #[tauri::command]
 pub async fn save_categories(categories: Vec<String>) -> Result<(), String> {
-    let config = get_storage_config()?;
-    config.ensure_dirs()?;
-
-    let content = serde_json::to_string(&categories)
-        .map_err(|e| format!("序列化分类失败: {}", e))?;
-
-    fs::write(config.categories_file(), content)
-        .map_err(|e| format!("保存分类失败: {}", e))?;
-    Ok(())
+    save_setting(keys::CATEGORIES, &categories)
+        .map_err(|e| format!("保存分类失败: {}", e))
 }
 
 #[tauri::command]
@@ -122,30 +94,14 @@ pub struct EditorInput {
 
 #[tauri::command]
 pub async fn get_editors() -> Result<Vec<EditorConfig>, String> {
-    let config = get_storage_config()?;
-    let path = config.editors_file();
-
-    if !path.exists() {
-        return Ok(vec![]);
-    }
-
-    let content = fs::read_to_string(&path)
+    let editors = load_setting::<Vec<EditorConfig>>(keys::EDITORS)
         .map_err(|e| format!("读取编辑器配置失败: {}", e))?;
-
-    let editors: Vec<EditorConfig> = serde_json::from_str(&content).unwrap_or_default();
-    Ok(editors)
+    Ok(editors.unwrap_or_default())
 }
 
 async fn save_editors(editors: &[EditorConfig]) -> Result<(), String> {
-    let config = get_storage_config()?;
-    config.ensure_dirs()?;
-
-    let content = serde_json::to_string(editors)
-        .map_err(|e| format!("序列化编辑器配置失败: {}", e))?;
-
-    fs::write(config.editors_file(), content)
-        .map_err(|e| format!("保存编辑器配置失败: {}", e))?;
-    Ok(())
+    save_setting(keys::EDITORS, editors)
+        .map_err(|e| format!("保存编辑器配置失败: {}", e))
 }
 
 #[tauri::command]
@@ -233,37 +189,39 @@ pub struct TerminalInput {
 
 #[tauri::command]
 pub async fn get_terminal_config() -> Result<TerminalConfig, String> {
-    let config = get_storage_config()?;
-    let path = config.terminal_file();
-
-    if !path.exists() {
-        return Ok(TerminalConfig::default());
-    }
-
-    let content = fs::read_to_string(&path)
+    let terminal = load_setting::<TerminalConfig>(keys::TERMINAL)
         .map_err(|e| format!("读取终端配置失败: {}", e))?;
-
-    let terminal: TerminalConfig = serde_json::from_str(&content).unwrap_or_default();
-    Ok(terminal)
+    Ok(terminal.unwrap_or_default())
 }
 
 #[tauri::command]
 pub async fn save_terminal_config(input: TerminalInput) -> Result<(), String> {
-    let config = get_storage_config()?;
-    config.ensure_dirs()?;
-
     let terminal = TerminalConfig {
         terminal_type: input.terminal_type,
         custom_path: input.custom_path,
         terminal_path: input.terminal_path,
     };
 
-    let content = serde_json::to_string(&terminal)
-        .map_err(|e| format!("序列化终端配置失败: {}", e))?;
+    save_setting(keys::TERMINAL, &terminal)
+        .map_err(|e| format!("保存终端配置失败: {}", e))
+}
+
+// ============== 路径根目录管理 ==============
 
-    fs::write(config.terminal_file(), content)
-        .map_err(|e| format!("保存终端配置失败: {}", e))?;
-    Ok(())
+#[tauri::command]
+pub async fn get_path_roots() -> Result<Vec<PathRoot>, String> {
+    let roots = load_setting::<Vec<PathRoot>>(keys::PATH_ROOTS)
+        .map_err(|e| format!("读取路径根目录失败: {}", e))?;
+    Ok(roots.unwrap_or_default())
+}
+
+/// 保存路径根目录，并按新的根目录重新还原项目路径，返回更新后的项目列表
+#[tauri::command]
+pub async fn save_path_roots(roots: Vec<PathRoot>) -> Result<Vec<Project>, String> {
+    paths::validate_roots(&roots)?;
+    save_setting(keys::PATH_ROOTS, &roots)
+        .map_err(|e| format!("保存路径根目录失败: {}", e))?;
+    project::reload_projects()
 }
 
 // ============== 应用设置管理 ==============
@@ -274,22 +232,15 @@ pub struct AppSettingsInput {
     pub view_mode: Option<String>,
     pub sidebar_collapsed: Option<bool>,
     pub scan_depth: Option<u32>,
+    pub backup_keep_count: Option<u32>,
+    pub scan_excludes: Option<Vec<String>>,
 }
 
 #[tauri::command]
 pub async fn get_app_settings() -> Result<AppSettings, String> {
-    let config = get_storage_config()?;
-    let path = config.app_settings_file();
-
-    if !path.exists() {
-        return Ok(AppSettings::default());
-    }
-
-    let content = fs::read_to_string(&path)
+    let settings = load_setting::<AppSettings>(keys::APP_SETTINGS)
         .map_err(|e| format!("读取应用设置失败: {}", e))?;
-
-    let settings: AppSettings = serde_json::from_str(&content).unwrap_or_default();
-    Ok(settings)
+    Ok(settings.unwrap_or_default())
 }
 
 #[tauri::command]
@@ -300,14 +251,13 @@ pub async fn save_app_settings(input: AppSettingsInput) -> Result<AppSettings, S
     if let Some(view_mode) = input.view_mode { settings.view_mode = view_mode; }
     if let Some(sidebar_collapsed) = input.sidebar_collapsed { settings.sidebar_collapsed = sidebar_collapsed; }
     if let Some(scan_depth) = input.scan_depth { settings.scan_depth = scan_depth; }
+    if let Some(backup_keep_count) = input.backup_keep_count { settings.backup_keep_count = backup_keep_count.max(1); }
+    if let Some(scan_excludes) = input.scan_excludes {
+        repo_scan::validate_excludes(&scan_excludes)?;
+        settings.scan_excludes = scan_excludes;
+    }
 
-    let config = get_storage_config()?;
-    config.ensure_dirs()?;
-
-    let content = serde_json::to_string(&settings)
-        .map_err(|e| format!("序列化应用设置失败: {}", e))?;
-
-    fs::write(config.app_settings_file(), content)
+    save_setting(keys::APP_SETTINGS, &settings)
         .map_err(|e| format!("保存应用设置失败: {}", e))?;
 
     Ok(settings)
@@ -322,18 +272,9 @@ pub struct UiStateInput {
 
 #[tauri::command]
 pub async fn get_ui_state() -> Result<UiState, String> {
-    let config = get_storage_config()?;
-    let path = config.ui_state_file();
-
-    if !path.exists() {
-        return Ok(UiState::default());
-    }
-
-    let content = fs::read_to_string(&path)
+    let ui_state = load_setting::<UiState>(keys::UI_STATE)
         .map_err(|e| format!("读取UI状态失败: {}", e))?;
-
-    let ui_state: UiState = serde_json::from_str(&content).unwrap_or_default();
-    Ok(ui_state)
+    Ok(ui_state.unwrap_or_default())
 }
 
 #[tauri::command]
@@ -344,13 +285,7 @@ pub async fn save_ui_state(input: UiStateInput) -> Result<UiState, String> {
         ui_state.recent_detail_project_ids = ids;
     }
 
-    let config = get_storage_config()?;
-    config.ensure_dirs()?;
-
-    let content = serde_json::to_string(&ui_state)
-        .map_err(|e| format!("序列化UI状态失败: {}", e))?;
-
-    fs::write(config.ui_state_file(), content)
+    save_setting(keys::UI_STATE, &ui_state)
         .map_err(|e| format!("保存UI状态失败: {}", e))?;
 
     Ok(ui_state)
@@ -367,30 +302,14 @@ pub struct NotificationInput {
 
 #[tauri::command]
 pub async fn get_notifications() -> Result<Vec<Notification>, String> {
-    let config = get_storage_config()?;
-    let path = config.notifications_file();
-
-    if !path.exists() {
-        return Ok(vec![]);
-    }
-
-    let content = fs::read_to_string(&path)
+    let notifications = load_setting::<Vec<Notification>>(keys::NOTIFICATIONS)
         .map_err(|e| format!("读取通知失败: {}", e))?;
-
-    let notifications: Vec<Notification> = serde_json::from_str(&content).unwrap_or_default();
-    Ok(notifications)
+    Ok(notifications.unwrap_or_default())
 }
 
 async fn save_notifications_internal(notifications: &[Notification]) -> Result<(), String> {
-    let config = get_storage_config()?;
-    config.ensure_dirs()?;
-
-    let content = serde_json::to_string(notifications)
-        .map_err(|e| format!("序列化通知失败: {}", e))?;
-
-    fs::write(config.notifications_file(), content)
-        .map_err(|e| format!("保存通知失败: {}", e))?;
-    Ok(())
+    save_setting(keys::NOTIFICATIONS, notifications)
+        .map_err(|e| format!("保存通知失败: {}", e))
 }
 
 #[tauri::command]
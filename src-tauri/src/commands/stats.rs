@@ -1,21 +1,14 @@
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
 use std::sync::Mutex;
 use once_cell::sync::Lazy;
 use tokio::task;
 
+use crate::commands::git::GitError;
+use crate::commands::git_backend::{self, LogQuery};
+use crate::commands::git_branch;
 use crate::storage;
 
-#[cfg(target_os = "windows")]
-use std::os::windows::process::CommandExt;
-
-// Windows: CREATE_NO_WINDOW flag to hide console window
-#[cfg(target_os = "windows")]
-const CREATE_NO_WINDOW: u32 = 0x08000000;
-
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct DashboardStats {
     pub total_projects: u32,
@@ -65,6 +58,9 @@ pub struct PersistedStatsCache {
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct ProjectStatsCache {
     pub unpushed: u32,
+    /// 未合并到默认分支的本地分支数
+    #[serde(default)]
+    pub unmerged_branches: u32,
     pub commits_by_date: HashMap<String, u32>,
     pub recent_commits: Vec<RecentCommit>,
     pub last_updated: i64,
@@ -72,71 +68,30 @@ pub struct ProjectStatsCache {
 
 // Global stats cache (内存缓存)
 static STATS_CACHE: Lazy<Mutex<PersistedStatsCache>> = Lazy::new(|| {
-    // 启动时从文件加载
-    let cache = load_stats_from_file().unwrap_or_default();
+    // 启动时从数据库加载，缓存损坏时重新统计即可
+    let cache = load_stats_cache().unwrap_or_else(|e| {
+        log::warn!("加载统计缓存失败，将重新统计: {}", e);
+        PersistedStatsCache::default()
+    });
     Mutex::new(cache)
 });
 
-/// 获取统计缓存文件路径
-fn get_stats_cache_path() -> PathBuf {
-    // 使用安装目录的 data 文件夹
-    match storage::get_storage_config() {
-        Ok(config) => config.stats_cache_file(),
-        Err(e) => {
-            log::error!("获取存储配置失败: {}", e);
-            // 如果无法获取配置，使用当前目录的 data 文件夹
-            PathBuf::from("data").join("stats_cache.json")
-        }
-    }
-}
-
-/// 从文件加载统计缓存
-fn load_stats_from_file() -> Result<PersistedStatsCache, String> {
-    let path = get_stats_cache_path();
-    if !path.exists() {
-        return Ok(PersistedStatsCache::default());
-    }
-
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read stats cache: {}", e))?;
-
-    serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse stats cache: {}", e))
+/// 数据库被整体替换（如恢复备份）后重新加载统计缓存
+pub fn reload_stats_cache() -> Result<(), String> {
+    let cache = load_stats_cache()?;
+    let mut current = STATS_CACHE.lock().map_err(|e| e.to_string())?;
+    *current = cache;
+    Ok(())
 }
 
-/// 保存统计缓存到文件
-fn save_stats_to_file(cache: &PersistedStatsCache) -> Result<(), String> {
-    let path = get_stats_cache_path();
-    let content = serde_json::to_string(cache)
-        .map_err(|e| format!("Failed to serialize stats: {}", e))?;
-
-    fs::write(&path, content)
-        .map_err(|e| format!("Failed to write stats cache: {}", e))?;
-
-    Ok(())
+/// 从数据库加载统计缓存
+fn load_stats_cache() -> Result<PersistedStatsCache, String> {
+    Ok(storage::load_cache(storage::keys::STATS_CACHE)?.unwrap_or_default())
 }
 
-fn run_git_command(path: &str, args: &[&str]) -> Result<String, String> {
-    #[cfg(target_os = "windows")]
-    let output = Command::new("git")
-        .args(["-C", path])
-        .args(args)
-        .creation_flags(CREATE_NO_WINDOW)
-        .output()
-        .map_err(|e| e.to_string())?;
-
-    #[cfg(not(target_os = "windows"))]
-    let output = Command::new("git")
-        .args(["-C", path])
-        .args(args)
-        .output()
-        .map_err(|e| e.to_string())?;
-
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
-    } else {
-        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
-    }
+/// 保存统计缓存到数据库
+fn save_stats_cache(cache: &PersistedStatsCache) -> Result<(), String> {
+    storage::save_cache(storage::keys::STATS_CACHE, cache)
 }
 
 fn get_current_time() -> String {
@@ -167,48 +122,41 @@ fn get_dates_in_last_week() -> Vec<String> {
 
 // Get commit history for a project (last year for heatmap)
 fn get_project_commits(path: &str, limit: u32) -> Vec<(String, String, String, String, String, String)> {
-    let format = "%H|%h|%s|%an|%ae|%ai";
-    let output = run_git_command(path, &["log", &format!("-{}", limit), &format!("--format={}", format)]);
-
-    match output {
-        Ok(result) => {
-            result
-                .lines()
-                .filter_map(|line| {
-                    let parts: Vec<&str> = line.split('|').collect();
-                    if parts.len() >= 6 {
-                        Some((
-                            parts[0].to_string(),
-                            parts[1].to_string(),
-                            parts[2].to_string(),
-                            parts[3].to_string(),
-                            parts[4].to_string(),
-                            parts[5].to_string(),
-                        ))
-                    } else {
-                        None
-                    }
-                })
-                .collect()
+    let query = LogQuery { limit: limit as usize, ..Default::default() };
+    let commits = match git_backend::read(|backend| backend.log(path, &query)) {
+        Ok(commits) => commits,
+        Err(GitError::NotARepo) => Vec::new(),
+        Err(e) => {
+            log::warn!("读取 {} 的提交历史失败: {}", path, e);
+            Vec::new()
         }
-        Err(_) => Vec::new(),
-    }
+    };
+
+    commits
+        .into_iter()
+        .map(|commit| {
+            // 与 git log 的 %ai 格式一致（2024-01-01 12:00:00 +0800），日期部分用于热力图
+            let date = chrono::DateTime::parse_from_rfc3339(&commit.date)
+                .map(|d| d.format("%Y-%m-%d %H:%M:%S %z").to_string())
+                .unwrap_or(commit.date);
+            (commit.hash, commit.short_hash, commit.message, commit.author, commit.email, date)
+        })
+        .collect()
 }
 
 // Get unpushed commit count
 fn get_unpushed_count(path: &str) -> u32 {
-    let output = run_git_command(path, &["rev-list", "--left-right", "--count", "HEAD...@{upstream}"]);
-
-    match output {
-        Ok(result) => {
-            let parts: Vec<&str> = result.split_whitespace().collect();
-            if !parts.is_empty() {
-                parts[0].parse().unwrap_or(0)
-            } else {
-                0
-            }
+    git_backend::counts_or_zero(path, git_backend::read(|backend| backend.ahead_behind(path))).0
+}
+
+fn get_unmerged_branch_count(path: &str) -> u32 {
+    match git_branch::branch_overview(path) {
+        Ok(overview) => overview.unmerged_count(),
+        Err(GitError::NotARepo) => 0,
+        Err(e) => {
+            log::warn!("无法读取 {} 的分支: {}", path, e);
+            0
         }
-        Err(_) => 0,
     }
 }
 
@@ -222,6 +170,7 @@ pub struct ProjectInfo {
 /// 分析单个项目
 fn analyze_project(name: String, path: String) -> ProjectStatsCache {
     let unpushed = get_unpushed_count(&path);
+    let unmerged_branches = get_unmerged_branch_count(&path);
     let commits = get_project_commits(&path, 365);
 
     let mut commits_by_date: HashMap<String, u32> = HashMap::new();
@@ -247,6 +196,7 @@ fn analyze_project(name: String, path: String) -> ProjectStatsCache {
 
     ProjectStatsCache {
         unpushed,
+        unmerged_branches,
         commits_by_date,
         recent_commits,
         last_updated: get_current_timestamp(),
@@ -264,9 +214,11 @@ fn aggregate_dashboard_data(
     let mut commits_by_date: HashMap<String, u32> = HashMap::new();
     let mut all_recent_commits: Vec<RecentCommit> = Vec::new();
     let mut unpushed_commits = 0u32;
+    let mut unmerged_branches = 0u32;
 
     for stats in project_stats.values() {
         unpushed_commits += stats.unpushed;
+        unmerged_branches += stats.unmerged_branches;
 
         for (date, count) in &stats.commits_by_date {
             *commits_by_date.entry(date.clone()).or_insert(0) += count;
@@ -294,7 +246,7 @@ fn aggregate_dashboard_data(
             today_commits,
             week_commits,
             unpushed_commits,
-            unmerged_branches: 0,
+            unmerged_branches,
             last_updated: get_current_time(),
         },
         heatmap_data,
@@ -393,8 +345,8 @@ pub async fn refresh_dirty_stats(projects: Vec<ProjectInfo>) -> Result<CachedDas
         cache.data = aggregate_dashboard_data(&cache.project_stats, projects.len() as u32);
         cache.last_updated = get_current_timestamp();
 
-        // 保存到文件
-        let _ = save_stats_to_file(&cache);
+        // 保存到数据库
+        let _ = save_stats_cache(&cache);
 
         cache.data.clone()
     };
@@ -413,7 +365,7 @@ pub async fn refresh_dashboard_stats(projects: Vec<ProjectInfo>) -> Result<Cache
         cache.data = cached_data.clone();
         cache.project_stats.clear();
         cache.dirty_projects.clear();
-        let _ = save_stats_to_file(&cache);
+        let _ = save_stats_cache(&cache);
         return Ok(cached_data);
     }
 
@@ -445,8 +397,8 @@ pub async fn refresh_dashboard_stats(projects: Vec<ProjectInfo>) -> Result<Cache
         cache.dirty_projects.clear();
         cache.last_updated = get_current_timestamp();
 
-        // 保存到文件
-        let _ = save_stats_to_file(&cache);
+        // 保存到数据库
+        let _ = save_stats_cache(&cache);
     }
 
     Ok(cached_data)
@@ -506,6 +458,6 @@ pub async fn cleanup_stats_cache(current_project_paths: Vec<String>) -> Result<(
     cache.project_stats.retain(|path, _| paths_set.contains(path));
     cache.dirty_projects.retain(|path| paths_set.contains(path));
 
-    let _ = save_stats_to_file(&cache);
+    let _ = save_stats_cache(&cache);
     Ok(())
 }
@@ -1,5 +1,6 @@
 use std::process::Command;
 
+use crate::commands::env;
 use crate::storage;
 
 #[cfg(target_os = "windows")]
@@ -62,11 +63,14 @@ pub async fn open_in_editor(path: String, editor_path: Option<String>) -> Result
         #[cfg(target_os = "linux")]
         return "code".to_string();
     });
+    // 注入项目选中的环境变量（编辑器已在运行时新窗口可能沿用原进程的环境）
+    let env = env::env_for_path(&path);
 
     #[cfg(target_os = "windows")]
     {
         Command::new(&editor)
             .arg(&path)
+            .envs(env.iter().cloned())
             .spawn()
             .map_err(|e| format!("Failed to open editor '{}': {}", editor, e))?;
     }
@@ -75,6 +79,7 @@ pub async fn open_in_editor(path: String, editor_path: Option<String>) -> Result
     {
         Command::new(&editor)
             .arg(&path)
+            .envs(env.iter().cloned())
             .spawn()
             .map_err(|e| format!("Failed to open editor '{}': {}", editor, e))?;
     }
@@ -85,6 +90,8 @@ pub async fn open_in_editor(path: String, editor_path: Option<String>) -> Result
 #[tauri::command]
 pub async fn open_in_terminal(path: String, terminal_type: Option<String>, custom_path: Option<String>, terminal_path: Option<String>) -> Result<(), String> {
     let term_type = terminal_type.unwrap_or_else(|| "default".to_string());
+    // 注入项目选中的环境变量（macOS 通过 open -a 启动的终端不会继承）
+    let env = env::env_for_path(&path);
 
     #[cfg(target_os = "windows")]
     {
@@ -96,6 +103,7 @@ pub async fn open_in_terminal(path: String, terminal_type: Option<String>, custo
                 Command::new(ps_path)
                     .args(["-NoExit", "-Command", &format!("Set-Location -LiteralPath '{}'", escaped_path)])
                     .creation_flags(CREATE_NEW_CONSOLE)
+                    .envs(env.iter().cloned())
                     .spawn()
                     .map_err(|e| e.to_string())?;
             }
@@ -105,6 +113,7 @@ pub async fn open_in_terminal(path: String, terminal_type: Option<String>, custo
                 Command::new(cmd_path)
                     .args(["/k", &format!("cd /d \"{}\"", path)])
                     .creation_flags(CREATE_NEW_CONSOLE)
+                    .envs(env.iter().cloned())
                     .spawn()
                     .map_err(|e| e.to_string())?;
             }
@@ -113,6 +122,7 @@ pub async fn open_in_terminal(path: String, terminal_type: Option<String>, custo
                     Command::new(&custom)
                         .arg(&path)
                         .creation_flags(CREATE_NEW_CONSOLE)
+                        .envs(env.iter().cloned())
                         .spawn()
                         .map_err(|e| format!("Failed to open custom terminal '{}': {}", custom, e))?;
                 } else {
@@ -124,6 +134,7 @@ pub async fn open_in_terminal(path: String, terminal_type: Option<String>, custo
                 let wt_path = terminal_path.as_deref().unwrap_or("wt");
                 let wt_result = Command::new(wt_path)
                     .args(["-d", &path])
+                    .envs(env.iter().cloned())
                     .spawn();
 
                 if wt_result.is_err() {
@@ -131,6 +142,7 @@ pub async fn open_in_terminal(path: String, terminal_type: Option<String>, custo
                     Command::new("powershell")
                         .args(["-NoExit", "-Command", &format!("Set-Location -LiteralPath '{}'", escaped_path)])
                         .creation_flags(CREATE_NEW_CONSOLE)
+                        .envs(env.iter().cloned())
                         .spawn()
                         .map_err(|e| e.to_string())?;
                 }
@@ -144,6 +156,7 @@ pub async fn open_in_terminal(path: String, terminal_type: Option<String>, custo
             "iterm" => {
                 Command::new("open")
                     .args(["-a", "iTerm", &path])
+                    .envs(env.iter().cloned())
                     .spawn()
                     .map_err(|e| e.to_string())?;
             }
@@ -151,6 +164,7 @@ pub async fn open_in_terminal(path: String, terminal_type: Option<String>, custo
                 if let Some(custom) = custom_path {
                     Command::new(&custom)
                         .arg(&path)
+                        .envs(env.iter().cloned())
                         .spawn()
                         .map_err(|e| format!("Failed to open custom terminal '{}': {}", custom, e))?;
                 } else {
@@ -161,6 +175,7 @@ pub async fn open_in_terminal(path: String, terminal_type: Option<String>, custo
                 // Default: Terminal.app
                 Command::new("open")
                     .args(["-a", "Terminal", &path])
+                    .envs(env.iter().cloned())
                     .spawn()
                     .map_err(|e| e.to_string())?;
             }
@@ -174,6 +189,7 @@ pub async fn open_in_terminal(path: String, terminal_type: Option<String>, custo
                 if let Some(custom) = custom_path {
                     Command::new(&custom)
                         .current_dir(&path)
+                        .envs(env.iter().cloned())
                         .spawn()
                         .map_err(|e| format!("Failed to open custom terminal '{}': {}", custom, e))?;
                 } else {
@@ -185,11 +201,13 @@ pub async fn open_in_terminal(path: String, terminal_type: Option<String>, custo
                 let ps_path = terminal_path.as_deref().unwrap_or("powershell.exe");
                 let result = Command::new(ps_path)
                     .args(["-NoExit", "-Command", &format!("cd '{}'", path)])
+                    .envs(env.iter().cloned())
                     .spawn();
                 if result.is_err() {
                     // Fallback: native powershell with original path
                     Command::new("powershell")
                         .args(["-NoExit", "-Command", &format!("cd '{}'", path)])
+                        .envs(env.iter().cloned())
                         .spawn()
                         .map_err(|e| e.to_string())?;
                 }
@@ -199,10 +217,12 @@ pub async fn open_in_terminal(path: String, terminal_type: Option<String>, custo
                 let cmd_path = terminal_path.as_deref().unwrap_or("cmd.exe");
                 let result = Command::new(cmd_path)
                     .args(["/k", &format!("cd /d {}", path)])
+                    .envs(env.iter().cloned())
                     .spawn();
                 if result.is_err() {
                     Command::new("cmd")
                         .args(["/k", &format!("cd /d {}", path)])
+                        .envs(env.iter().cloned())
                         .spawn()
                         .map_err(|e| e.to_string())?;
                 }
@@ -212,6 +232,7 @@ pub async fn open_in_terminal(path: String, terminal_type: Option<String>, custo
                 let wt_path = terminal_path.as_deref().unwrap_or("wt.exe");
                 let wt_result = Command::new(wt_path)
                     .args(["-d", &path])
+                    .envs(env.iter().cloned())
                     .spawn();
 
                 if wt_result.is_err() {
@@ -222,9 +243,11 @@ pub async fn open_in_terminal(path: String, terminal_type: Option<String>, custo
                         let result = match term {
                             "gnome-terminal" => Command::new(term)
                                 .args(["--working-directory", &path])
+                                .envs(env.iter().cloned())
                                 .spawn(),
                             _ => Command::new(term)
                                 .current_dir(&path)
+                                .envs(env.iter().cloned())
                                 .spawn(),
                         };
 
@@ -580,6 +603,7 @@ pub async fn read_readme(path: String) -> Result<String, String> {
     Err("README file not found".to_string())
 }
 
+/// git 命令的版本。读取仓库已内置 libgit2，未安装 git 只影响推送、拉取等远程操作
 #[tauri::command]
 pub async fn check_git_version() -> Result<String, String> {
     #[cfg(target_os = "windows")]
@@ -635,6 +659,8 @@ pub struct AppPaths {
     pub cache_dir: String,
     pub log_dir: String,
     pub install_dir: String,
+    /// 数据目录来源：env / portable / custom / installDir / user
+    pub storage_mode: Option<crate::storage::config::StorageMode>,
 }
 
 #[tauri::command]
@@ -644,10 +670,11 @@ pub async fn get_app_paths(app_handle: tauri::AppHandle) -> Result<AppPaths, Str
     let path_resolver = app_handle.path();
 
     // 优先使用新的存储路径
-    let (data_dir, log_dir) = if let Ok(config) = storage::get_storage_config() {
+    let (data_dir, log_dir, storage_mode) = if let Ok(config) = storage::get_storage_config() {
         (
             config.data_dir.to_string_lossy().to_string(),
             config.logs_dir.to_string_lossy().to_string(),
+            Some(config.mode),
         )
     } else {
         (
@@ -657,6 +684,7 @@ pub async fn get_app_paths(app_handle: tauri::AppHandle) -> Result<AppPaths, Str
             path_resolver.app_log_dir()
                 .map(|p| p.to_string_lossy().to_string())
                 .unwrap_or_else(|_| "未知".to_string()),
+            None,
         )
     };
 
@@ -679,6 +707,7 @@ pub async fn get_app_paths(app_handle: tauri::AppHandle) -> Result<AppPaths, Str
         cache_dir,
         log_dir,
         install_dir,
+        storage_mode,
     })
 }
 
@@ -0,0 +1,547 @@
+// 任务运行 - 在项目目录下执行运行脚本，实时推送输出，保留最近的运行记录
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::task::JoinHandle;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+use crate::commands::{env, project};
+use crate::storage::{current_iso_time, generate_id, keys, load_cache, save_cache, Project, ProjectScript};
+
+// Windows: CREATE_NO_WINDOW flag to hide console window
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+const TASK_EVENT: &str = "task-event";
+
+/// 每个项目保留的运行记录数
+const MAX_RUNS_PER_PROJECT: usize = 20;
+
+/// 每次运行在内存中保留的输出行数
+const MAX_OUTPUT_LINES: usize = 2000;
+
+/// 停止任务时等待进程自行退出的时间，超时后强制结束
+const STOP_GRACE_PERIOD_MS: u64 = 3000;
+
+/// 运行状态
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskStatus {
+    Running,
+    /// 退出码为 0
+    Succeeded,
+    /// 退出码非 0 或启动失败
+    Failed,
+    /// 被用户停止
+    Stopped,
+    /// 程序退出时仍在运行，结果未知
+    Interrupted,
+}
+
+/// 一次运行记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskRun {
+    pub id: String,
+    pub project_id: String,
+    pub script_name: String,
+    pub command: String,
+    pub status: TaskStatus,
+    pub pid: Option<u32>,
+    pub exit_code: Option<i32>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub duration_ms: Option<u64>,
+}
+
+/// 一行输出
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskOutputLine {
+    pub stream: OutputStream,
+    pub line: String,
+}
+
+#[derive(Debug, Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// 任务事件
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TaskEvent {
+    Started { run: TaskRun },
+    #[serde(rename_all = "camelCase")]
+    Output { run_id: String, stream: OutputStream, line: String },
+    Finished { run: TaskRun },
+}
+
+/// 运行中的任务
+struct RunningTask {
+    pid: Option<u32>,
+    stop_requested: AtomicBool,
+}
+
+/// 运行记录（首次访问时从缓存加载）
+static RUNS: Lazy<Mutex<Vec<TaskRun>>> = Lazy::new(|| Mutex::new(load_runs()));
+
+/// 运行中的任务（按运行 ID）
+static RUNNING: Lazy<Mutex<HashMap<String, Arc<RunningTask>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 运行输出（按运行 ID，只保存在内存中）
+static OUTPUT: Lazy<Mutex<HashMap<String, VecDeque<TaskOutputLine>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn load_runs() -> Vec<TaskRun> {
+    let mut runs: Vec<TaskRun> = match load_cache(keys::TASK_RUNS) {
+        Ok(runs) => runs.unwrap_or_default(),
+        Err(e) => {
+            log::warn!("读取任务运行记录失败: {}", e);
+            Vec::new()
+        }
+    };
+    mark_interrupted(&mut runs);
+    runs
+}
+
+/// 上次退出时仍在运行的任务已随程序结束
+fn mark_interrupted(runs: &mut [TaskRun]) {
+    for run in runs.iter_mut().filter(|run| run.status == TaskStatus::Running) {
+        run.status = TaskStatus::Interrupted;
+        run.pid = None;
+    }
+}
+
+/// 数据库被整体替换（如恢复备份）后重新加载运行记录
+///
+/// 仍在运行的任务保留内存中的记录，结束时照常更新
+pub fn reload_runs() -> Result<(), String> {
+    let mut restored = load_runs();
+    let mut runs = RUNS.lock().map_err(|e| e.to_string())?;
+    for run in runs.iter().filter(|run| is_running(&run.id)) {
+        upsert_run(&mut restored, run);
+    }
+    *runs = restored;
+    if let Ok(mut output) = OUTPUT.lock() {
+        output.retain(|id, _| runs.iter().any(|run| &run.id == id));
+    }
+    Ok(())
+}
+
+fn save_runs(runs: &[TaskRun]) {
+    if let Err(e) = save_cache(keys::TASK_RUNS, runs) {
+        log::warn!("保存任务运行记录失败: {}", e);
+    }
+}
+
+/// 新增或更新运行记录，每个项目只保留最近的记录
+fn record_run(run: &TaskRun) {
+    let mut runs = match RUNS.lock() {
+        Ok(runs) => runs,
+        Err(e) => {
+            log::error!("任务运行记录锁异常: {}", e);
+            return;
+        }
+    };
+    let removed = upsert_run(&mut runs, run);
+    if !removed.is_empty() {
+        if let Ok(mut output) = OUTPUT.lock() {
+            for id in &removed {
+                output.remove(id);
+            }
+        }
+    }
+    save_runs(&runs);
+}
+
+/// 写入运行记录，删除该项目超出保留数量的已结束记录，返回被删除的运行 ID
+fn upsert_run(runs: &mut Vec<TaskRun>, run: &TaskRun) -> Vec<String> {
+    match runs.iter_mut().find(|r| r.id == run.id) {
+        Some(existing) => *existing = run.clone(),
+        None => runs.push(run.clone()),
+    }
+
+    let mut kept = 0;
+    let mut removed = Vec::new();
+    for r in runs.iter().rev() {
+        if r.project_id == run.project_id && r.status != TaskStatus::Running {
+            kept += 1;
+            if kept > MAX_RUNS_PER_PROJECT {
+                removed.push(r.id.clone());
+            }
+        }
+    }
+    runs.retain(|r| !removed.contains(&r.id));
+    removed
+}
+
+/// 追加一行输出，超过 MAX_OUTPUT_LINES 时丢弃最早的行
+fn push_output_line(lines: &mut VecDeque<TaskOutputLine>, line: TaskOutputLine) {
+    if lines.len() >= MAX_OUTPUT_LINES {
+        lines.pop_front();
+    }
+    lines.push_back(line);
+}
+
+fn emit(app: &AppHandle, event: TaskEvent) {
+    if let Err(e) = app.emit(TASK_EVENT, &event) {
+        log::warn!("发送任务事件失败: {}", e);
+    }
+}
+
+/// 用系统 shell 执行命令行，输出通过管道读取
+pub(crate) fn shell_command(command_line: &str, cwd: &str) -> Command {
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command_line]).creation_flags(CREATE_NO_WINDOW);
+        cmd
+    };
+
+    #[cfg(not(target_os = "windows"))]
+    let mut cmd = {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command_line]);
+        // 放入独立的进程组，停止时可以结束 shell 启动的所有子进程
+        cmd.process_group(0);
+        cmd
+    };
+
+    cmd.current_dir(cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    cmd
+}
+
+/// 结束进程及其所有子进程
+pub(crate) fn kill_process_tree(pid: u32, force: bool) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    let output = {
+        let mut cmd = std::process::Command::new("taskkill");
+        cmd.creation_flags(CREATE_NO_WINDOW).args(["/T", "/PID", &pid.to_string()]);
+        if force {
+            cmd.arg("/F");
+        }
+        cmd.output().map_err(|e| format!("执行 taskkill 失败: {}", e))?
+    };
+
+    // 进程以自身 PID 作为进程组 ID 启动，向负 PID 发信号即发给整个进程组
+    #[cfg(not(target_os = "windows"))]
+    let output = std::process::Command::new("kill")
+        .args([if force { "-KILL" } else { "-TERM" }, "--", &format!("-{}", pid)])
+        .output()
+        .map_err(|e| format!("执行 kill 失败: {}", e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// 逐行读取输出并推送，非 UTF-8 内容按有损方式转换
+async fn pump_output<R: AsyncRead + Unpin>(app: AppHandle, run_id: String, stream: OutputStream, reader: R) {
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf).await {
+            Ok(0) => break,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&buf).trim_end_matches(['\r', '\n']).to_string();
+                if let Ok(mut output) = OUTPUT.lock() {
+                    let lines = output.entry(run_id.clone()).or_default();
+                    push_output_line(lines, TaskOutputLine { stream, line: line.clone() });
+                }
+                emit(&app, TaskEvent::Output { run_id: run_id.clone(), stream, line });
+            }
+            Err(e) => {
+                log::warn!("读取任务输出失败 {}: {}", run_id, e);
+                break;
+            }
+        }
+    }
+}
+
+/// 启动脚本，返回运行记录和等待结束的句柄；进程结束后更新记录并发送 Finished 事件
+pub(crate) fn start_script(
+    app: &AppHandle,
+    project: &Project,
+    script: &ProjectScript,
+) -> Result<(TaskRun, JoinHandle<TaskRun>), String> {
+    let mut run = TaskRun {
+        id: generate_id(),
+        project_id: project.id.clone(),
+        script_name: script.name.clone(),
+        command: script.command.clone(),
+        status: TaskStatus::Running,
+        pid: None,
+        exit_code: None,
+        started_at: current_iso_time(),
+        finished_at: None,
+        duration_ms: None,
+    };
+
+    let started = Instant::now();
+    let mut command = shell_command(&script.command, &project.path);
+    command.envs(env::env_for_project(&project.id));
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            run.status = TaskStatus::Failed;
+            run.finished_at = Some(current_iso_time());
+            run.duration_ms = Some(0);
+            record_run(&run);
+            return Err(format!("启动脚本 {} 失败: {}", script.name, e));
+        }
+    };
+    run.pid = child.id();
+    log::info!("启动脚本 {} ({}): {}", script.name, project.name, script.command);
+
+    let task = Arc::new(RunningTask {
+        pid: run.pid,
+        stop_requested: AtomicBool::new(false),
+    });
+    RUNNING.lock().map_err(|e| e.to_string())?.insert(run.id.clone(), task.clone());
+    record_run(&run);
+    emit(app, TaskEvent::Started { run: run.clone() });
+
+    let stdout = child.stdout.take().map(|out| {
+        tokio::spawn(pump_output(app.clone(), run.id.clone(), OutputStream::Stdout, out))
+    });
+    let stderr = child.stderr.take().map(|err| {
+        tokio::spawn(pump_output(app.clone(), run.id.clone(), OutputStream::Stderr, err))
+    });
+
+    let app = app.clone();
+    let mut finished = run.clone();
+    let done = tokio::spawn(async move {
+        let status = child.wait().await;
+        // 等输出读完再发送结束事件，保证前端先收到全部输出
+        for reader in [stdout, stderr].into_iter().flatten() {
+            let _ = reader.await;
+        }
+
+        finished.pid = None;
+        finished.finished_at = Some(current_iso_time());
+        finished.duration_ms = Some(started.elapsed().as_millis() as u64);
+        finished.exit_code = status.as_ref().ok().and_then(|s| s.code());
+        finished.status = if task.stop_requested.load(Ordering::SeqCst) {
+            TaskStatus::Stopped
+        } else if status.map(|s| s.success()).unwrap_or(false) {
+            TaskStatus::Succeeded
+        } else {
+            TaskStatus::Failed
+        };
+
+        if let Ok(mut running) = RUNNING.lock() {
+            running.remove(&finished.id);
+        }
+        record_run(&finished);
+        log::info!("脚本 {} 结束: {:?}", finished.script_name, finished.status);
+        emit(&app, TaskEvent::Finished { run: finished.clone() });
+        finished
+    });
+
+    Ok((run, done))
+}
+
+/// 停止运行中的任务：先正常结束进程树，超时后强制结束
+pub(crate) async fn stop_run(run_id: &str) -> Result<(), String> {
+    let task = RUNNING
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(run_id)
+        .cloned()
+        .ok_or("任务未在运行")?;
+    let pid = task.pid.ok_or("无法获取任务进程 ID")?;
+
+    task.stop_requested.store(true, Ordering::SeqCst);
+    if let Err(e) = kill_process_tree(pid, false) {
+        log::warn!("结束任务进程失败 {}: {}", pid, e);
+    }
+
+    let deadline = Instant::now() + std::time::Duration::from_millis(STOP_GRACE_PERIOD_MS);
+    while Instant::now() < deadline {
+        if !is_running(run_id) {
+            return Ok(());
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    log::warn!("任务 {} 未在 {}ms 内退出，强制结束", run_id, STOP_GRACE_PERIOD_MS);
+    kill_process_tree(pid, true)
+}
+
+pub(crate) fn is_running(run_id: &str) -> bool {
+    RUNNING.lock().map(|running| running.contains_key(run_id)).unwrap_or(false)
+}
+
+/// 项目下正在运行的任务 ID
+pub(crate) fn running_runs(project_id: &str) -> Vec<String> {
+    let runs = match RUNS.lock() {
+        Ok(runs) => runs,
+        Err(_) => return Vec::new(),
+    };
+    runs.iter()
+        .filter(|r| r.project_id == project_id && is_running(&r.id))
+        .map(|r| r.id.clone())
+        .collect()
+}
+
+pub(crate) fn find_script(project: &Project, script_name: &str) -> Result<ProjectScript, String> {
+    project
+        .scripts
+        .iter()
+        .find(|s| s.name == script_name)
+        .cloned()
+        .ok_or_else(|| format!("脚本不存在: {}", script_name))
+}
+
+pub(crate) fn find_project(project_id: &str) -> Result<Project, String> {
+    project::get_projects()?
+        .into_iter()
+        .find(|p| p.id == project_id)
+        .ok_or_else(|| "项目不存在".to_string())
+}
+
+#[tauri::command]
+pub async fn run_project_script(app: AppHandle, project_id: String, script_name: String) -> Result<TaskRun, String> {
+    let project = find_project(&project_id)?;
+    let script = find_script(&project, &script_name)?;
+    start_script(&app, &project, &script).map(|(run, _)| run)
+}
+
+#[tauri::command]
+pub async fn stop_task(run_id: String) -> Result<(), String> {
+    stop_run(&run_id).await
+}
+
+/// 重新运行：任务仍在运行时先停止，再用项目当前的脚本命令启动
+#[tauri::command]
+pub async fn restart_task(app: AppHandle, run_id: String) -> Result<TaskRun, String> {
+    let previous = RUNS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .find(|r| r.id == run_id)
+        .cloned()
+        .ok_or("运行记录不存在")?;
+
+    if is_running(&run_id) {
+        stop_run(&run_id).await?;
+    }
+
+    let project = find_project(&previous.project_id)?;
+    let script = find_script(&project, &previous.script_name)?;
+    start_script(&app, &project, &script).map(|(run, _)| run)
+}
+
+/// 运行记录（按开始时间倒序），可按项目筛选
+#[tauri::command]
+pub fn get_task_runs(project_id: Option<String>) -> Result<Vec<TaskRun>, String> {
+    let runs = RUNS.lock().map_err(|e| e.to_string())?;
+    Ok(runs
+        .iter()
+        .rev()
+        .filter(|r| project_id.is_none() || project_id.as_deref() == Some(r.project_id.as_str()))
+        .cloned()
+        .collect())
+}
+
+/// 运行的输出（程序重启后之前的输出不再保留）
+#[tauri::command]
+pub fn get_task_output(run_id: String) -> Result<Vec<TaskOutputLine>, String> {
+    let output = OUTPUT.lock().map_err(|e| e.to_string())?;
+    Ok(output.get(&run_id).map(|lines| lines.iter().cloned().collect()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(id: &str, project_id: &str, status: TaskStatus) -> TaskRun {
+        TaskRun {
+            id: id.to_string(),
+            project_id: project_id.to_string(),
+            script_name: "dev".to_string(),
+            command: "npm run dev".to_string(),
+            status,
+            pid: Some(1),
+            exit_code: None,
+            started_at: String::new(),
+            finished_at: None,
+            duration_ms: None,
+        }
+    }
+
+    #[test]
+    fn keeps_recent_finished_runs_per_project() {
+        let mut runs = vec![run("running", "p1", TaskStatus::Running), run("other", "p2", TaskStatus::Failed)];
+        for i in 0..MAX_RUNS_PER_PROJECT {
+            assert!(upsert_run(&mut runs, &run(&format!("r{}", i), "p1", TaskStatus::Succeeded)).is_empty());
+        }
+        let removed = upsert_run(&mut runs, &run("newest", "p1", TaskStatus::Failed));
+        assert_eq!(removed, vec!["r0"]);
+        // 运行中的记录和其他项目的记录不受影响
+        assert!(runs.iter().any(|r| r.id == "running") && runs.iter().any(|r| r.id == "other"));
+        assert_eq!(runs.len(), MAX_RUNS_PER_PROJECT + 2);
+
+        // 更新已有记录不会新增；运行中的记录结束后按开始顺序参与保留计数
+        assert!(upsert_run(&mut runs, &run("newest", "p1", TaskStatus::Stopped)).is_empty());
+        assert_eq!(runs.last().unwrap().status, TaskStatus::Stopped);
+        assert_eq!(upsert_run(&mut runs, &run("running", "p1", TaskStatus::Stopped)), vec!["running"]);
+
+        let mut loaded = vec![run("a", "p1", TaskStatus::Running), run("b", "p1", TaskStatus::Succeeded)];
+        mark_interrupted(&mut loaded);
+        assert_eq!((loaded[0].status, loaded[0].pid), (TaskStatus::Interrupted, None));
+        assert_eq!(loaded[1].status, TaskStatus::Succeeded);
+    }
+
+    #[test]
+    fn output_buffer_drops_oldest_lines() {
+        let mut lines = VecDeque::new();
+        for i in 0..MAX_OUTPUT_LINES + 5 {
+            push_output_line(&mut lines, TaskOutputLine { stream: OutputStream::Stdout, line: i.to_string() });
+        }
+        assert_eq!(lines.len(), MAX_OUTPUT_LINES);
+        assert_eq!(lines.front().unwrap().line, "5");
+        assert_eq!(lines.back().unwrap().line, (MAX_OUTPUT_LINES + 4).to_string());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn shell_command_runs_in_project_dir_and_can_be_killed() {
+        let dir = crate::test_support::TempDir::new("tasks");
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let output = shell_command("echo hello && pwd", &dir.path().to_string_lossy()).output().await.unwrap();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let mut lines = stdout.lines();
+            assert_eq!(lines.next(), Some("hello"));
+            let cwd = std::path::PathBuf::from(lines.next().unwrap());
+            assert_eq!(cwd.canonicalize().unwrap(), dir.path().canonicalize().unwrap());
+
+            // shell 启动的子进程也随进程组一起结束
+            let mut child = shell_command("sleep 30 & sleep 30; wait", "/").spawn().unwrap();
+            let pid = child.id().unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            kill_process_tree(pid, false).unwrap();
+            let status = tokio::time::timeout(std::time::Duration::from_secs(5), child.wait()).await;
+            assert!(!status.unwrap().unwrap().success());
+        });
+    }
+}
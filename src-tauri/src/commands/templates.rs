@@ -0,0 +1,329 @@
+// 项目模板 - 从本机骨架目录或 Git 仓库创建新项目，替换文件名和内容中的 {{name}} 等占位符
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::commands::git::run_git_command;
+use crate::commands::project::{self, CreateProjectInput};
+use crate::storage::{
+    current_iso_time, generate_id, keys, load_setting, save_setting, Project, ProjectTemplate, TemplateSource,
+};
+
+/// 超过这个大小的文件不做占位符替换
+const MAX_RENDER_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateInput {
+    pub name: String,
+    pub description: Option<String>,
+    pub source: TemplateSource,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateFromTemplateInput {
+    pub template_id: String,
+    /// 新项目所在的父目录
+    pub target_dir: String,
+    /// 项目名，同时作为目录名和 {{name}} 的值
+    pub name: String,
+    /// 额外的占位符取值
+    pub variables: Option<HashMap<String, String>>,
+    /// 创建后执行 git init 并提交一次
+    pub init_git: bool,
+    pub category: Option<String>,
+    pub labels: Option<Vec<String>>,
+}
+
+fn load_templates() -> Result<Vec<ProjectTemplate>, String> {
+    let templates = load_setting::<Vec<ProjectTemplate>>(keys::PROJECT_TEMPLATES)
+        .map_err(|e| format!("读取项目模板失败: {}", e))?;
+    Ok(templates.unwrap_or_default())
+}
+
+fn save_templates(templates: &[ProjectTemplate]) -> Result<(), String> {
+    save_setting(keys::PROJECT_TEMPLATES, templates).map_err(|e| format!("保存项目模板失败: {}", e))
+}
+
+fn validate_template(input: &TemplateInput) -> Result<(), String> {
+    if input.name.trim().is_empty() {
+        return Err("模板名称不能为空".to_string());
+    }
+    match &input.source {
+        TemplateSource::Local { path } => {
+            if !Path::new(path).is_dir() {
+                return Err(format!("模板目录不存在: {}", path));
+            }
+        }
+        TemplateSource::Git { url, reference } => {
+            if url.trim().is_empty() {
+                return Err("模板仓库地址不能为空".to_string());
+            }
+            if let Some(reference) = reference {
+                validate_reference(reference)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 模板的分支、标签或提交不能以 - 开头，否则会被 git 当作选项
+fn validate_reference(reference: &str) -> Result<(), String> {
+    if reference.starts_with('-') {
+        return Err(format!("无效的模板版本: {}", reference));
+    }
+    Ok(())
+}
+
+/// 占位符的值会出现在文件名中，不能包含路径分隔符或 ..，避免写到项目目录之外
+fn validate_variables(vars: &HashMap<String, String>) -> Result<(), String> {
+    for (key, value) in vars {
+        if value.contains(['/', '\\']) || value.contains("..") {
+            return Err(format!("占位符 {} 的值不能包含路径分隔符或 ..: {}", key, value));
+        }
+    }
+    Ok(())
+}
+
+/// 替换文本中的 {{key}} 占位符（括号内可有空白），未提供取值的占位符原样保留
+fn render_placeholders(text: &str, vars: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => match vars.get(after[..end].trim()) {
+                Some(value) => {
+                    result.push_str(value);
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    result.push_str("{{");
+                    rest = after;
+                }
+            },
+            None => {
+                result.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// 复制模板目录（不含 .git）
+fn copy_dir(src: &Path, dest: &Path) -> Result<(), String> {
+    fs::create_dir_all(dest).map_err(|e| format!("创建目录失败 {}: {}", dest.display(), e))?;
+    let entries = fs::read_dir(src).map_err(|e| format!("读取模板目录失败 {}: {}", src.display(), e))?;
+    for entry in entries.flatten() {
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let from = entry.path();
+        let to = dest.join(entry.file_name());
+        if from.is_dir() {
+            copy_dir(&from, &to)?;
+        } else {
+            fs::copy(&from, &to).map_err(|e| format!("复制文件失败 {}: {}", from.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// 克隆模板仓库并去掉其提交历史
+fn clone_template(url: &str, reference: Option<&str>, target: &Path) -> Result<(), String> {
+    let parent = target.parent().ok_or("无效的目标目录")?.to_string_lossy().to_string();
+    let target_str = target.to_string_lossy().to_string();
+
+    match reference {
+        None => {
+            run_git_command(&parent, &["clone", "--depth", "1", "--", url, &target_str])?;
+        }
+        Some(reference) => {
+            validate_reference(reference)?;
+            // 分支和标签可以浅克隆，提交哈希需要完整克隆后再检出
+            if run_git_command(&parent, &["clone", "--depth", "1", "--branch", reference, "--", url, &target_str]).is_err() {
+                let _ = fs::remove_dir_all(target);
+                run_git_command(&parent, &["clone", "--", url, &target_str])?;
+                run_git_command(&target_str, &["checkout", "--detach", reference])?;
+            }
+        }
+    }
+
+    fs::remove_dir_all(target.join(".git")).map_err(|e| format!("清理模板仓库历史失败: {}", e))
+}
+
+/// 替换目录中文件名和文本文件内容里的占位符（先处理子项再重命名当前项）
+fn apply_placeholders(dir: &Path, vars: &HashMap<String, String>) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("读取目录失败 {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            apply_placeholders(&path, vars)?;
+        } else if entry.metadata().map(|m| m.len() <= MAX_RENDER_SIZE).unwrap_or(false) {
+            // 只处理 UTF-8 文本文件，二进制文件原样保留
+            if let Ok(content) = fs::read_to_string(&path) {
+                if content.contains("{{") && !content.contains('\0') {
+                    let rendered = render_placeholders(&content, vars);
+                    if rendered != content {
+                        fs::write(&path, rendered).map_err(|e| format!("写入文件失败 {}: {}", path.display(), e))?;
+                    }
+                }
+            }
+        }
+
+        let name = entry.file_name().to_string_lossy().to_string();
+        let new_name = render_placeholders(&name, vars);
+        if new_name != name {
+            let new_path = dir.join(&new_name);
+            if new_path.exists() {
+                return Err(format!("替换占位符后文件名冲突: {}", new_path.display()));
+            }
+            fs::rename(&path, &new_path).map_err(|e| format!("重命名失败 {}: {}", path.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// 生成项目目录：复制或克隆模板、替换占位符
+fn scaffold(template: &ProjectTemplate, target: &Path, vars: &HashMap<String, String>) -> Result<(), String> {
+    match &template.source {
+        TemplateSource::Local { path } => copy_dir(Path::new(path), target)?,
+        TemplateSource::Git { url, reference } => clone_template(url, reference.as_deref(), target)?,
+    }
+    apply_placeholders(target, vars)
+}
+
+/// 初始化仓库并提交一次
+fn init_repository(target: &Path, template_name: &str) -> Result<(), String> {
+    let path = target.to_string_lossy().to_string();
+    run_git_command(&path, &["init"]).map_err(|e| format!("初始化仓库失败: {}", e))?;
+    run_git_command(&path, &["add", "-A"]).map_err(|e| format!("暂存文件失败: {}", e))?;
+    let message = format!("Initial commit from template {}", template_name);
+    run_git_command(&path, &["commit", "-m", &message]).map_err(|e| format!("首次提交失败: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_templates() -> Result<Vec<ProjectTemplate>, String> {
+    load_templates()
+}
+
+#[tauri::command]
+pub fn add_template(input: TemplateInput) -> Result<Vec<ProjectTemplate>, String> {
+    validate_template(&input)?;
+    let mut templates = load_templates()?;
+    templates.push(ProjectTemplate {
+        id: generate_id(),
+        name: input.name.trim().to_string(),
+        description: input.description,
+        source: input.source,
+        created_at: current_iso_time(),
+    });
+    save_templates(&templates)?;
+    Ok(templates)
+}
+
+#[tauri::command]
+pub fn update_template(id: String, input: TemplateInput) -> Result<Vec<ProjectTemplate>, String> {
+    validate_template(&input)?;
+    let mut templates = load_templates()?;
+    let template = templates.iter_mut().find(|t| t.id == id).ok_or("模板不存在")?;
+    template.name = input.name.trim().to_string();
+    template.description = input.description;
+    template.source = input.source;
+    save_templates(&templates)?;
+    Ok(templates)
+}
+
+#[tauri::command]
+pub fn remove_template(id: String) -> Result<Vec<ProjectTemplate>, String> {
+    let mut templates = load_templates()?;
+    templates.retain(|t| t.id != id);
+    save_templates(&templates)?;
+    Ok(templates)
+}
+
+/// 从模板创建项目并加入项目列表，生成文件失败时删除已生成的目录；
+/// 初始化仓库失败时保留已生成的文件，由用户处理后手动添加
+#[tauri::command]
+pub async fn create_from_template(input: CreateFromTemplateInput) -> Result<Project, String> {
+    let name = input.name.trim().to_string();
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(format!("无效的项目名称: {}", input.name));
+    }
+    let template = load_templates()?
+        .into_iter()
+        .find(|t| t.id == input.template_id)
+        .ok_or("模板不存在")?;
+
+    if !Path::new(&input.target_dir).is_dir() {
+        return Err(format!("目标目录不存在: {}", input.target_dir));
+    }
+    let target: PathBuf = Path::new(&input.target_dir).join(&name);
+    if target.exists() {
+        return Err(format!("目录已存在: {}", target.display()));
+    }
+
+    let mut vars = input.variables.unwrap_or_default();
+    vars.insert("name".to_string(), name.clone());
+    validate_variables(&vars)?;
+
+    let init_git = input.init_git;
+    let scaffold_target = target.clone();
+    tokio::task::spawn_blocking(move || {
+        let result = scaffold(&template, &scaffold_target, &vars);
+        if result.is_err() && scaffold_target.exists() {
+            let _ = fs::remove_dir_all(&scaffold_target);
+        }
+        result?;
+        if init_git {
+            init_repository(&scaffold_target, &template.name)
+                .map_err(|e| format!("{}（已生成的文件保留在 {}）", e, scaffold_target.display()))?;
+        }
+        Ok::<(), String>(())
+    })
+    .await
+    .map_err(|e| format!("创建项目失败: {}", e))??;
+
+    let path = target.to_string_lossy().to_string();
+    log::info!("已从模板创建项目: {}", path);
+    project::import_projects(vec![CreateProjectInput {
+        name,
+        path,
+        tags: input.category.map(|category| vec![category]),
+        labels: input.labels,
+    }])?
+    .into_iter()
+    .next()
+    .ok_or_else(|| "项目已在列表中".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_known_placeholders_only() {
+        let vars = HashMap::from([("name".to_string(), "demo".to_string())]);
+        assert_eq!(render_placeholders("{{name}}-{{ name }}.rs", &vars), "demo-demo.rs");
+        assert_eq!(render_placeholders("{{other}} {{name}}", &vars), "{{other}} demo");
+        assert_eq!(render_placeholders("{{name", &vars), "{{name");
+    }
+
+    #[test]
+    fn rejects_variables_that_escape_the_project() {
+        let vars = |value: &str| HashMap::from([("module".to_string(), value.to_string())]);
+        assert!(validate_variables(&vars("core-utils")).is_ok());
+        for value in ["..", "a/b", "..\\evil", "../x"] {
+            assert!(validate_variables(&vars(value)).is_err(), "{}", value);
+        }
+        assert!(validate_reference("-uexploit").is_err());
+        assert!(validate_reference("v1.0").is_ok());
+    }
+}
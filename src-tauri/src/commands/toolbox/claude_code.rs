@@ -1,14 +1,13 @@
 // Claude Code 配置管理模块
 
 use serde::{Deserialize, Serialize};
-use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 
-use crate::storage;
+use crate::storage::{atomic, keys, load_cache, load_setting, remove_cache, save_cache, save_setting};
 use crate::storage::schema::{
     ClaudeQuickConfig, ClaudeInstallation, ConfigFileInfo as SchemaConfigFileInfo,
 };
@@ -848,21 +847,13 @@ pub async fn read_claude_config_file(env_type: EnvType, env_name: String, path:
 pub async fn write_claude_config_file(env_type: EnvType, env_name: String, path: String, content: String) -> Result<(), String> {
     // 如果是 UNC 路径，直接用 Windows API 写入
     if is_wsl_unc_path(&path) {
-        if let Some(parent) = std::path::Path::new(&path).parent() {
-            std::fs::create_dir_all(parent)
-                .map_err(|e| format!("创建目录失败: {}", e))?;
-        }
-        return std::fs::write(&path, content)
+        return atomic::write_atomic(std::path::Path::new(&path), content)
             .map_err(|e| format!("写入配置文件失败: {}", e));
     }
 
     match env_type {
         EnvType::Host => {
-            if let Some(parent) = std::path::Path::new(&path).parent() {
-                std::fs::create_dir_all(parent)
-                    .map_err(|e| format!("创建目录失败: {}", e))?;
-            }
-            std::fs::write(&path, content)
+            atomic::write_atomic(std::path::Path::new(&path), content)
                 .map_err(|e| format!("写入配置文件失败: {}", e))
         }
         #[cfg(target_os = "windows")]
@@ -1134,17 +1125,11 @@ pub async fn apply_quick_config(
 /// 获取保存的配置档案列表
 #[tauri::command]
 pub async fn get_config_profiles(env_type: EnvType, env_name: String) -> Result<Vec<ConfigProfile>, String> {
-    let profiles_path = get_profiles_storage_path(&env_type, &env_name);
-
-    if !profiles_path.exists() {
-        return Ok(vec![]);
-    }
-
-    let content = std::fs::read_to_string(&profiles_path)
-        .map_err(|e| format!("读取配置档案失败: {}", e))?;
+    let key = get_profiles_storage_key(&env_type, &env_name);
 
-    serde_json::from_str(&content)
-        .map_err(|e| format!("解析配置档案失败: {}", e))
+    load_setting::<Vec<ConfigProfile>>(&key)
+        .map(Option::unwrap_or_default)
+        .map_err(|e| format!("读取配置档案失败: {}", e))
 }
 
 /// 保存配置档案（如果名称已存在则更新，否则新建）
@@ -1218,8 +1203,8 @@ pub async fn apply_config_profile(
     write_claude_config_file(env_type, env_name, config_path, content).await
 }
 
-/// 获取配置档案存储路径（按环境隔离）
-fn get_profiles_storage_path(env_type: &EnvType, env_name: &str) -> PathBuf {
+/// 获取配置档案存储键（按环境隔离）
+fn get_profiles_storage_key(env_type: &EnvType, env_name: &str) -> String {
     // 根据环境类型和名称生成唯一的文件名
     let env_suffix = match env_type {
         EnvType::Host => "host".to_string(),
@@ -1230,30 +1215,14 @@ fn get_profiles_storage_path(env_type: &EnvType, env_name: &str) -> PathBuf {
         }
     };
 
-    // 使用安装目录的 data 文件夹
-    match storage::get_storage_config() {
-        Ok(config) => config.data_dir.join(format!("claude_profiles_{}.json", env_suffix)),
-        Err(e) => {
-            log::error!("获取存储配置失败: {}", e);
-            // 如果无法获取配置，使用当前目录的 data 文件夹
-            PathBuf::from("data").join(format!("claude_profiles_{}.json", env_suffix))
-        }
-    }
+    keys::claude_profiles(&env_suffix)
 }
 
-/// 保存配置档案到文件
+/// 保存配置档案到数据库
 fn save_profiles(env_type: &EnvType, env_name: &str, profiles: &[ConfigProfile]) -> Result<(), String> {
-    let path = get_profiles_storage_path(env_type, env_name);
-
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)
-            .map_err(|e| format!("创建目录失败: {}", e))?;
-    }
-
-    let content = serde_json::to_string(profiles)
-        .map_err(|e| format!("序列化配置档案失败: {}", e))?;
+    let key = get_profiles_storage_key(env_type, env_name);
 
-    std::fs::write(&path, content)
+    save_setting(&key, profiles)
         .map_err(|e| format!("保存配置档案失败: {}", e))
 }
 
@@ -1350,33 +1319,16 @@ pub async fn scan_claude_config_dir(env_type: EnvType, env_name: String, config_
 /// 获取保存的 Claude 快捷配置
 #[tauri::command]
 pub async fn get_saved_quick_configs() -> Result<Vec<ClaudeQuickConfig>, String> {
-    if let Ok(config) = storage::get_storage_config() {
-        let path = config.claude_quick_configs_file();
-        if path.exists() {
-            let content = fs::read_to_string(&path)
-                .map_err(|e| format!("读取快捷配置失败: {}", e))?;
-
-            // 直接解析为配置数组
-            let configs: Vec<ClaudeQuickConfig> = serde_json::from_str(&content)
-                .unwrap_or_default();
-            return Ok(configs);
-        }
-    }
-    Ok(vec![])
+    load_setting::<Vec<ClaudeQuickConfig>>(keys::CLAUDE_QUICK_CONFIGS)
+        .map(Option::unwrap_or_default)
+        .map_err(|e| format!("读取快捷配置失败: {}", e))
 }
 
 /// 保存 Claude 快捷配置
 #[tauri::command]
 pub async fn save_quick_configs(configs: Vec<ClaudeQuickConfig>) -> Result<(), String> {
-    let config = storage::get_storage_config()?;
-    config.ensure_dirs()?;
-
-    // 直接保存为配置数组
-    let content = serde_json::to_string(&configs)
-        .map_err(|e| format!("序列化快捷配置失败: {}", e))?;
-    fs::write(config.claude_quick_configs_file(), content)
-        .map_err(|e| format!("保存快捷配置失败: {}", e))?;
-    Ok(())
+    save_setting(keys::CLAUDE_QUICK_CONFIGS, &configs)
+        .map_err(|e| format!("保存快捷配置失败: {}", e))
 }
 
 // ============== Claude 安装信息缓存 ==============
@@ -1384,47 +1336,41 @@ pub async fn save_quick_configs(configs: Vec<ClaudeQuickConfig>) -> Result<(), S
 /// 获取缓存的 Claude 安装信息
 #[tauri::command]
 pub async fn get_claude_installations_cache() -> Result<Option<Vec<ClaudeCodeInfo>>, String> {
-    if let Ok(config) = storage::get_storage_config() {
-        let path = config.claude_installations_cache_file();
-        if path.exists() {
-            let content = fs::read_to_string(&path)
-                .map_err(|e| format!("读取安装缓存失败: {}", e))?;
-
-            // 直接解析为安装信息数组
-            let installations: Vec<ClaudeInstallation> = serde_json::from_str(&content)
-                .unwrap_or_default();
-
-            // 转换为 ClaudeCodeInfo
-            let result: Vec<ClaudeCodeInfo> = installations.into_iter().map(|i| {
-                ClaudeCodeInfo {
-                    env_type: if i.env_type == "wsl" { EnvType::Wsl } else { EnvType::Host },
-                    env_name: i.env_name,
-                    installed: true,
-                    version: i.version,
-                    path: i.path,
-                    config_dir: Some(i.config_dir),
-                    config_files: i.config_files.into_iter().map(|f| ConfigFileInfo {
-                        name: f.name,
-                        path: f.path,
-                        exists: f.exists,
-                        size: 0,
-                        modified: None,
-                        description: String::new(),
-                    }).collect(),
-                }
-            }).collect();
-            return Ok(Some(result));
+    let installations: Vec<ClaudeInstallation> = match load_cache(keys::CLAUDE_INSTALLATIONS) {
+        Ok(Some(installations)) => installations,
+        Ok(None) => return Ok(None),
+        Err(e) => {
+            // 缓存损坏时视为无缓存，重新检测即可
+            log::warn!("读取安装缓存失败: {}", e);
+            return Ok(None);
         }
-    }
-    Ok(None)
+    };
+
+    // 转换为 ClaudeCodeInfo
+    let result: Vec<ClaudeCodeInfo> = installations.into_iter().map(|i| {
+        ClaudeCodeInfo {
+            env_type: if i.env_type == "wsl" { EnvType::Wsl } else { EnvType::Host },
+            env_name: i.env_name,
+            installed: true,
+            version: i.version,
+            path: i.path,
+            config_dir: Some(i.config_dir),
+            config_files: i.config_files.into_iter().map(|f| ConfigFileInfo {
+                name: f.name,
+                path: f.path,
+                exists: f.exists,
+                size: 0,
+                modified: None,
+                description: String::new(),
+            }).collect(),
+        }
+    }).collect();
+    Ok(Some(result))
 }
 
 /// 保存 Claude 安装信息缓存
 #[tauri::command]
 pub async fn save_claude_installations_cache(installs: Vec<ClaudeCodeInfo>) -> Result<(), String> {
-    let config = storage::get_storage_config()?;
-    config.ensure_dirs()?;
-
     // 转换为简化的安装信息格式
     let installations: Vec<ClaudeInstallation> = installs.iter().map(|i| {
         ClaudeInstallation {
@@ -1446,23 +1392,13 @@ pub async fn save_claude_installations_cache(installs: Vec<ClaudeCodeInfo>) -> R
         }
     }).collect();
 
-    // 直接保存为安装信息数组
-    let content = serde_json::to_string(&installations)
-        .map_err(|e| format!("序列化安装缓存失败: {}", e))?;
-    fs::write(config.claude_installations_cache_file(), content)
-        .map_err(|e| format!("保存安装缓存失败: {}", e))?;
-    Ok(())
+    save_cache(keys::CLAUDE_INSTALLATIONS, &installations)
+        .map_err(|e| format!("保存安装缓存失败: {}", e))
 }
 
 /// 清除 Claude 安装信息缓存
 #[tauri::command]
 pub async fn clear_claude_installations_cache() -> Result<(), String> {
-    if let Ok(config) = storage::get_storage_config() {
-        let path = config.claude_installations_cache_file();
-        if path.exists() {
-            fs::remove_file(&path)
-                .map_err(|e| format!("删除缓存文件失败: {}", e))?;
-        }
-    }
-    Ok(())
+    remove_cache(keys::CLAUDE_INSTALLATIONS)
+        .map_err(|e| format!("删除安装缓存失败: {}", e))
 }
@@ -16,48 +16,54 @@ use tokio::time::{sleep, Duration};
 static DOWNLOAD_TASKS: Lazy<Arc<Mutex<HashMap<String, DownloadTask>>>> =
     Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
 
-/// 是否已从文件加载
+/// 是否已从数据库加载
 static TASKS_LOADED: Lazy<Arc<Mutex<bool>>> = Lazy::new(|| Arc::new(Mutex::new(false)));
 
 /// 下载取消标志
 static DOWNLOAD_CANCELLED: Lazy<Arc<Mutex<HashMap<String, AtomicBool>>>> =
     Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
 
-/// 确保下载任务已从文件加载
-async fn ensure_tasks_loaded() {
+impl storage::Record for DownloadTask {
+    fn record_id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// 确保下载任务已从数据库加载
+async fn ensure_tasks_loaded() -> Result<(), String> {
     let mut loaded = TASKS_LOADED.lock().await;
     if !*loaded {
-        match load_tasks_from_file() {
-            Ok(tasks) => {
-                let mut tasks_map = DOWNLOAD_TASKS.lock().await;
-                *tasks_map = tasks;
-                *loaded = true;
-            }
-            Err(e) => {
-                log::warn!("加载下载任务失败，将在下次重试: {}", e);
-            }
-        }
+        let items = load_tasks_from_storage()?;
+        let mut tasks_map = DOWNLOAD_TASKS.lock().await;
+        *tasks_map = items;
+        *loaded = true;
     }
+    Ok(())
 }
 
-/// 从文件加载下载任务
-fn load_tasks_from_file() -> Result<HashMap<String, DownloadTask>, String> {
-    let config = storage::get_storage_config()?;
-    let path = config.download_tasks_file();
-
-    log::info!("加载下载任务: {:?}", path);
+/// 数据库被整体替换（如恢复备份）后重新加载下载任务，正在下载的任务以内存中的状态为准
+pub async fn reload_tasks() -> Result<(), String> {
+    let mut loaded = TASKS_LOADED.lock().await;
+    let mut tasks = load_tasks_from_storage()?;
 
-    if !path.exists() {
-        log::info!("下载任务文件不存在，返回空列表");
-        return Ok(HashMap::new());
+    let mut tasks_map = DOWNLOAD_TASKS.lock().await;
+    for (id, task) in tasks_map.iter() {
+        if task.status == "downloading" {
+            tasks.insert(id.clone(), task.clone());
+        }
     }
 
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("读取下载任务失败: {}", e))?;
+    *tasks_map = tasks;
+    *loaded = true;
+    Ok(())
+}
 
-    // 直接解析为任务数组
-    let tasks: Vec<DownloadTask> = serde_json::from_str(&content)
-        .unwrap_or_default();
+/// 从数据库加载下载任务
+fn load_tasks_from_storage() -> Result<HashMap<String, DownloadTask>, String> {
+    let tasks: Vec<DownloadTask> = storage::with_connection(|conn| {
+        storage::RuleRepository::new(conn).list(storage::RuleKind::DownloadTask)
+    })
+    .map_err(|e| format!("读取下载任务失败: {}", e))?;
 
     let result: HashMap<String, DownloadTask> = tasks.into_iter()
         .map(|mut t| {
@@ -73,23 +79,18 @@ fn load_tasks_from_file() -> Result<HashMap<String, DownloadTask>, String> {
     Ok(result)
 }
 
-/// 保存下载任务到文件
-async fn save_tasks_to_file() -> Result<(), String> {
-    let config = storage::get_storage_config()?;
-    config.ensure_dirs()?;
-
+/// 保存下载任务到数据库
+async fn save_tasks_to_storage() -> Result<(), String> {
     let tasks = DOWNLOAD_TASKS.lock().await;
-    let tasks_vec: Vec<&DownloadTask> = tasks.values().collect();
-
-    // 直接保存为任务数组
-    let content = serde_json::to_string(&tasks_vec)
-        .map_err(|e| format!("序列化下载任务失败: {}", e))?;
 
-    let path = config.download_tasks_file();
-    log::info!("保存下载任务到: {:?}", path);
+    // 按创建时间排序，保持列表顺序稳定
+    let mut tasks_vec: Vec<&DownloadTask> = tasks.values().collect();
+    tasks_vec.sort_by(|a, b| a.created_at.cmp(&b.created_at));
 
-    fs::write(&path, content)
-        .map_err(|e| format!("写入下载任务失败: {}", e))?;
+    storage::with_transaction(|tx| {
+        storage::RuleRepository::new(tx).replace_all(storage::RuleKind::DownloadTask, tasks_vec)
+    })
+    .map_err(|e| format!("写入下载任务失败: {}", e))?;
 
     log::info!("下载任务保存成功，共 {} 个任务", tasks.len());
     Ok(())
@@ -126,7 +127,7 @@ fn extract_filename(url: &str) -> String {
 /// 开始下载
 #[tauri::command]
 pub async fn start_download(config: DownloadConfig) -> Result<String, String> {
-    ensure_tasks_loaded().await;
+    ensure_tasks_loaded().await?;
 
     let task_id = generate_id();
 
@@ -159,7 +160,7 @@ pub async fn start_download(config: DownloadConfig) -> Result<String, String> {
     }
 
     // 持久化保存
-    if let Err(e) = save_tasks_to_file().await {
+    if let Err(e) = save_tasks_to_storage().await {
         log::error!("保存下载任务失败: {}", e);
     }
 
@@ -390,7 +391,7 @@ async fn update_task_status(task_id: &str, status: &str, error: Option<String>)
 
     // 在终态时持久化保存
     if status == "completed" || status == "failed" || status == "cancelled" || status == "paused" {
-        if let Err(e) = save_tasks_to_file().await {
+        if let Err(e) = save_tasks_to_storage().await {
             log::error!("保存下载任务失败: {}", e);
         }
     }
@@ -399,7 +400,7 @@ async fn update_task_status(task_id: &str, status: &str, error: Option<String>)
 /// 暂停下载
 #[tauri::command]
 pub async fn pause_download(task_id: String) -> Result<(), String> {
-    ensure_tasks_loaded().await;
+    ensure_tasks_loaded().await?;
 
     // 设置取消标志
     {
@@ -418,7 +419,7 @@ pub async fn pause_download(task_id: String) -> Result<(), String> {
 /// 恢复下载
 #[tauri::command]
 pub async fn resume_download(task_id: String) -> Result<(), String> {
-    ensure_tasks_loaded().await;
+    ensure_tasks_loaded().await?;
 
     // 获取任务信息
     let task = {
@@ -453,7 +454,7 @@ pub async fn resume_download(task_id: String) -> Result<(), String> {
 /// 取消下载
 #[tauri::command]
 pub async fn cancel_download(task_id: String) -> Result<(), String> {
-    ensure_tasks_loaded().await;
+    ensure_tasks_loaded().await?;
 
     // 设置取消标志
     {
@@ -485,7 +486,7 @@ pub async fn cancel_download(task_id: String) -> Result<(), String> {
     }
 
     // 持久化保存
-    if let Err(e) = save_tasks_to_file().await {
+    if let Err(e) = save_tasks_to_storage().await {
         log::error!("保存下载任务失败: {}", e);
     }
 
@@ -495,7 +496,7 @@ pub async fn cancel_download(task_id: String) -> Result<(), String> {
 /// 获取所有下载任务
 #[tauri::command]
 pub async fn get_download_tasks() -> Result<Vec<DownloadTask>, String> {
-    ensure_tasks_loaded().await;
+    ensure_tasks_loaded().await?;
 
     let tasks = DOWNLOAD_TASKS.lock().await;
     Ok(tasks.values().cloned().collect())
@@ -504,7 +505,7 @@ pub async fn get_download_tasks() -> Result<Vec<DownloadTask>, String> {
 /// 获取单个下载任务
 #[tauri::command]
 pub async fn get_download_task(task_id: String) -> Result<Option<DownloadTask>, String> {
-    ensure_tasks_loaded().await;
+    ensure_tasks_loaded().await?;
 
     let tasks = DOWNLOAD_TASKS.lock().await;
     Ok(tasks.get(&task_id).cloned())
@@ -513,7 +514,7 @@ pub async fn get_download_task(task_id: String) -> Result<Option<DownloadTask>,
 /// 清除已完成的下载任务
 #[tauri::command]
 pub async fn clear_completed_downloads() -> Result<u32, String> {
-    ensure_tasks_loaded().await;
+    ensure_tasks_loaded().await?;
 
     let mut tasks = DOWNLOAD_TASKS.lock().await;
     let initial_count = tasks.len();
@@ -525,7 +526,7 @@ pub async fn clear_completed_downloads() -> Result<u32, String> {
 
     // 持久化保存
     if removed_count > 0 {
-        if let Err(e) = save_tasks_to_file().await {
+        if let Err(e) = save_tasks_to_storage().await {
             log::error!("保存下载任务失败: {}", e);
         }
     }
@@ -536,7 +537,7 @@ pub async fn clear_completed_downloads() -> Result<u32, String> {
 /// 打开下载文件夹
 #[tauri::command]
 pub async fn open_download_folder(task_id: String) -> Result<(), String> {
-    ensure_tasks_loaded().await;
+    ensure_tasks_loaded().await?;
 
     let save_path = {
         let tasks = DOWNLOAD_TASKS.lock().await;
@@ -582,7 +583,7 @@ pub async fn open_download_folder(task_id: String) -> Result<(), String> {
 /// 删除下载任务（可选删除文件）
 #[tauri::command]
 pub async fn remove_download_task(task_id: String, delete_file: Option<bool>) -> Result<(), String> {
-    ensure_tasks_loaded().await;
+    ensure_tasks_loaded().await?;
 
     let delete_file = delete_file.unwrap_or(false);
 
@@ -619,7 +620,7 @@ pub async fn remove_download_task(task_id: String, delete_file: Option<bool>) ->
     }
 
     // 持久化保存
-    if let Err(e) = save_tasks_to_file().await {
+    if let Err(e) = save_tasks_to_storage().await {
         log::error!("保存下载任务失败: {}", e);
     }
 
@@ -5,7 +5,6 @@ use crate::storage;
 use once_cell::sync::Lazy;
 use socket2::{Domain, Socket, Type};
 use std::collections::HashMap;
-use std::fs;
 use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::io::AsyncWriteExt;
@@ -17,54 +16,57 @@ use tokio::time::{timeout, Duration};
 static FORWARD_RULES: Lazy<Arc<Mutex<HashMap<String, ForwardRule>>>> =
     Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
 
-/// 是否已从文件加载
+/// 是否已从数据库加载
 static RULES_LOADED: Lazy<Arc<Mutex<bool>>> = Lazy::new(|| Arc::new(Mutex::new(false)));
 
 /// 转发控制器（用于停止转发）
 static FORWARD_CONTROLLERS: Lazy<Arc<Mutex<HashMap<String, Arc<ForwardController>>>>> =
     Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
 
-/// 确保转发规则已从文件加载
-async fn ensure_rules_loaded() {
+impl storage::Record for ForwardRule {
+    fn record_id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// 确保转发规则已从数据库加载
+async fn ensure_rules_loaded() -> Result<(), String> {
     let mut loaded = RULES_LOADED.lock().await;
     if !*loaded {
-        match load_rules_from_file() {
-            Ok(rules) => {
-                let mut rules_map = FORWARD_RULES.lock().await;
-                *rules_map = rules;
-                *loaded = true; // 只有成功加载才设置为 true
-            }
-            Err(e) => {
-                log::warn!("加载转发规则失败，将在下次重试: {}", e);
-                // 不设置 loaded = true，允许下次重试
-            }
-        }
+        // 加载失败时返回错误而不是当作空列表，避免随后的保存覆盖数据库中的数据
+        let items = load_rules_from_storage()?;
+        let mut rules_map = FORWARD_RULES.lock().await;
+        *rules_map = items;
+        *loaded = true;
     }
+    Ok(())
 }
 
-/// 从文件加载转发规则
-fn load_rules_from_file() -> Result<HashMap<String, ForwardRule>, String> {
-    let config = storage::get_storage_config()?;
-    let path = config.forward_rules_file();
-
-    log::info!("加载转发规则: {:?}", path);
+/// 数据库被整体替换（如恢复备份）后重新加载转发规则，正在运行的规则保持运行状态
+pub async fn reload_rules() -> Result<(), String> {
+    let mut loaded = RULES_LOADED.lock().await;
+    let mut rules = load_rules_from_storage()?;
 
-    if !path.exists() {
-        log::info!("转发规则文件不存在，返回空列表");
-        return Ok(HashMap::new());
+    {
+        let controllers = FORWARD_CONTROLLERS.lock().await;
+        for (id, rule) in rules.iter_mut() {
+            if controllers.contains_key(id) {
+                rule.status = "running".to_string();
+            }
+        }
     }
 
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("读取转发规则失败: {}", e))?;
+    *FORWARD_RULES.lock().await = rules;
+    *loaded = true;
+    Ok(())
+}
 
-    // 直接解析为规则数组
-    let rules_arr: Vec<ForwardRule> = match serde_json::from_str(&content) {
-        Ok(arr) => arr,
-        Err(e) => {
-            log::error!("解析转发规则 JSON 失败: {}，内容: {}", e, &content[..content.len().min(200)]);
-            Vec::new()
-        }
-    };
+/// 从数据库加载转发规则
+fn load_rules_from_storage() -> Result<HashMap<String, ForwardRule>, String> {
+    let rules_arr: Vec<ForwardRule> = storage::with_connection(|conn| {
+        storage::RuleRepository::new(conn).list(storage::RuleKind::ForwardRule)
+    })
+    .map_err(|e| format!("读取转发规则失败: {}", e))?;
 
     let mut rules = HashMap::new();
     for mut rule in rules_arr {
@@ -81,26 +83,18 @@ fn load_rules_from_file() -> Result<HashMap<String, ForwardRule>, String> {
     Ok(rules)
 }
 
-/// 保存转发规则到文件
-async fn save_rules_to_file() -> Result<(), String> {
-    let config = storage::get_storage_config()?;
-
-    // 确保数据目录存在
-    config.ensure_dirs()?;
-
+/// 保存转发规则到数据库
+async fn save_rules_to_storage() -> Result<(), String> {
     let rules = FORWARD_RULES.lock().await;
 
-    // 直接序列化（serde 会自动用 camelCase）
-    let rules_data: Vec<&ForwardRule> = rules.values().collect();
-
-    let content = serde_json::to_string(&rules_data)
-        .map_err(|e| format!("序列化转发规则失败: {}", e))?;
+    // 按创建时间排序，保持列表顺序稳定
+    let mut rules_data: Vec<&ForwardRule> = rules.values().collect();
+    rules_data.sort_by(|a, b| a.created_at.cmp(&b.created_at));
 
-    let path = config.forward_rules_file();
-    log::info!("保存转发规则到: {:?}", path);
-
-    fs::write(&path, content)
-        .map_err(|e| format!("写入转发规则失败: {}", e))?;
+    storage::with_transaction(|tx| {
+        storage::RuleRepository::new(tx).replace_all(storage::RuleKind::ForwardRule, rules_data)
+    })
+    .map_err(|e| format!("写入转发规则失败: {}", e))?;
 
     log::info!("转发规则保存成功，共 {} 个规则", rules.len());
     Ok(())
@@ -164,7 +158,7 @@ impl ForwardController {
 /// 添加转发规则
 #[tauri::command]
 pub async fn add_forward_rule(input: ForwardRuleInput) -> Result<ForwardRule, String> {
-    ensure_rules_loaded().await;
+    ensure_rules_loaded().await?;
 
     // 验证端口
     if input.local_port == 0 {
@@ -208,8 +202,8 @@ pub async fn add_forward_rule(input: ForwardRuleInput) -> Result<ForwardRule, St
         rules.insert(rule_id.clone(), rule.clone());
     }
 
-    // 持久化到文件
-    if let Err(e) = save_rules_to_file().await {
+    // 持久化到数据库
+    if let Err(e) = save_rules_to_storage().await {
         log::error!("保存转发规则失败: {}", e);
         // 移除刚添加的规则，因为无法持久化
         let mut rules = FORWARD_RULES.lock().await;
@@ -223,7 +217,7 @@ pub async fn add_forward_rule(input: ForwardRuleInput) -> Result<ForwardRule, St
 /// 移除转发规则
 #[tauri::command]
 pub async fn remove_forward_rule(rule_id: String) -> Result<(), String> {
-    ensure_rules_loaded().await;
+    ensure_rules_loaded().await?;
 
     // 先停止转发
     let _ = stop_forwarding(rule_id.clone()).await;
@@ -240,8 +234,8 @@ pub async fn remove_forward_rule(rule_id: String) -> Result<(), String> {
         rules.remove(&rule_id);
     }
 
-    // 持久化到文件
-    if let Err(e) = save_rules_to_file().await {
+    // 持久化到数据库
+    if let Err(e) = save_rules_to_storage().await {
         log::error!("保存转发规则失败: {}", e);
         // 回滚：恢复删除的规则
         if let Some(rule) = old_rule {
@@ -257,7 +251,7 @@ pub async fn remove_forward_rule(rule_id: String) -> Result<(), String> {
 /// 启动转发
 #[tauri::command]
 pub async fn start_forwarding(rule_id: String) -> Result<(), String> {
-    ensure_rules_loaded().await;
+    ensure_rules_loaded().await?;
 
     // 获取规则
     let rule = {
@@ -544,7 +538,7 @@ pub async fn stop_forwarding(rule_id: String) -> Result<(), String> {
 /// 获取所有转发规则
 #[tauri::command]
 pub async fn get_forward_rules() -> Result<Vec<ForwardRule>, String> {
-    ensure_rules_loaded().await;
+    ensure_rules_loaded().await?;
 
     // 先更新所有运行中规则的统计信息
     let rule_ids: Vec<String> = {
@@ -567,7 +561,7 @@ pub async fn get_forward_rules() -> Result<Vec<ForwardRule>, String> {
 /// 获取单个转发规则
 #[tauri::command]
 pub async fn get_forward_rule(rule_id: String) -> Result<Option<ForwardRule>, String> {
-    ensure_rules_loaded().await;
+    ensure_rules_loaded().await?;
 
     update_rule_stats(&rule_id).await;
 
@@ -595,7 +589,7 @@ pub async fn get_forward_stats(rule_id: String) -> Result<ForwardStats, String>
 /// 更新转发规则
 #[tauri::command]
 pub async fn update_forward_rule(rule_id: String, input: ForwardRuleInput) -> Result<ForwardRule, String> {
-    ensure_rules_loaded().await;
+    ensure_rules_loaded().await?;
 
     // 获取当前规则（用于回滚）
     let current_rule = {
@@ -623,8 +617,8 @@ pub async fn update_forward_rule(rule_id: String, input: ForwardRuleInput) -> Re
         }
     }
 
-    // 持久化到文件
-    if let Err(e) = save_rules_to_file().await {
+    // 持久化到数据库
+    if let Err(e) = save_rules_to_storage().await {
         log::error!("保存转发规则失败: {}", e);
         // 回滚：恢复旧规则
         let mut rules = FORWARD_RULES.lock().await;
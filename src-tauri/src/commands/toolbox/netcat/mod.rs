@@ -8,7 +8,7 @@ mod udp;
 pub use types::*;
 
 use super::generate_id;
-use crate::storage::get_storage_config;
+use crate::storage::{self, RuleKind, RuleRepository};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
@@ -26,55 +26,65 @@ impl NetcatState {
         }
     }
 
-    /// 从文件加载会话配置
+    /// 从数据库加载会话配置
     pub async fn load_sessions(&self) -> Result<(), String> {
-        let config = get_storage_config()?;
-        let file_path = config.netcat_sessions_file();
-
-        if !file_path.exists() {
-            return Ok(());
+        let configs = load_session_configs()?;
+        let mut sessions = self.sessions.write().await;
+        for cfg in configs {
+            let id = cfg.id.clone();
+            sessions.insert(id, Arc::new(RwLock::new(SessionState::new(session_from_config(cfg)))));
         }
 
-        let content = std::fs::read_to_string(&file_path)
-            .map_err(|e| format!("读取 Netcat 会话文件失败: {}", e))?;
+        Ok(())
+    }
 
-        let configs: Vec<NetcatSessionConfig> = serde_json::from_str(&content)
-            .map_err(|e| format!("解析 Netcat 会话文件失败: {}", e))?;
+    /// 数据库被整体替换（如恢复备份）后重新加载会话：已有的会话保留连接状态并更新配置，
+    /// 新数据中没有的会话停止后移除
+    pub async fn reload_sessions(&self) -> Result<(), String> {
+        let configs = load_session_configs()?;
+        let removed: Vec<String> = {
+            let sessions = self.sessions.read().await;
+            sessions
+                .keys()
+                .filter(|id| !configs.iter().any(|cfg| &cfg.id == *id))
+                .cloned()
+                .collect()
+        };
+        for id in &removed {
+            let _ = stop_session_internal(self, id).await;
+        }
 
         let mut sessions = self.sessions.write().await;
+        for id in &removed {
+            sessions.remove(id);
+        }
         for cfg in configs {
-            let session = NetcatSession {
-                id: cfg.id.clone(),
-                name: cfg.name,
-                protocol: cfg.protocol,
-                mode: cfg.mode,
-                host: cfg.host,
-                port: cfg.port,
-                status: SessionStatus::Disconnected,
-                auto_reconnect: cfg.auto_reconnect,
-                timeout_ms: cfg.timeout_ms,
-                created_at: cfg.created_at,
-                connected_at: None,
-                last_activity: None,
-                bytes_sent: 0,
-                bytes_received: 0,
-                message_count: 0,
-                error_message: None,
-                client_count: 0,
-                auto_send: cfg.auto_send,
-            };
-            let session_state = Arc::new(RwLock::new(SessionState::new(session)));
-            sessions.insert(cfg.id, session_state);
+            match sessions.get(&cfg.id) {
+                Some(session_state) => {
+                    let mut s = session_state.write().await;
+                    let session = &mut s.session;
+                    session.name = cfg.name;
+                    session.protocol = cfg.protocol;
+                    session.mode = cfg.mode;
+                    session.host = cfg.host;
+                    session.port = cfg.port;
+                    session.auto_reconnect = cfg.auto_reconnect;
+                    session.timeout_ms = cfg.timeout_ms;
+                    session.created_at = cfg.created_at;
+                    session.auto_send = cfg.auto_send;
+                }
+                None => {
+                    let id = cfg.id.clone();
+                    sessions.insert(id, Arc::new(RwLock::new(SessionState::new(session_from_config(cfg)))));
+                }
+            }
         }
 
         Ok(())
     }
 
-    /// 保存会话配置到文件
+    /// 保存会话配置到数据库
     pub async fn save_sessions(&self) -> Result<(), String> {
-        let config = get_storage_config()?;
-        let file_path = config.netcat_sessions_file();
-
         let sessions = self.sessions.read().await;
         let mut configs: Vec<NetcatSessionConfig> = Vec::new();
 
@@ -94,16 +104,53 @@ impl NetcatState {
             });
         }
 
-        let content = serde_json::to_string_pretty(&configs)
-            .map_err(|e| format!("序列化 Netcat 会话失败: {}", e))?;
+        // 按创建时间排序，保持列表顺序稳定
+        configs.sort_by_key(|c| c.created_at);
 
-        std::fs::write(&file_path, content)
-            .map_err(|e| format!("保存 Netcat 会话文件失败: {}", e))?;
+        storage::with_transaction(|tx| {
+            RuleRepository::new(tx).replace_all(RuleKind::NetcatSession, &configs)
+        })
+        .map_err(|e| format!("保存 Netcat 会话失败: {}", e))?;
 
         Ok(())
     }
 }
 
+fn load_session_configs() -> Result<Vec<NetcatSessionConfig>, String> {
+    storage::with_connection(|conn| RuleRepository::new(conn).list(RuleKind::NetcatSession))
+        .map_err(|e| format!("读取 Netcat 会话失败: {}", e))
+}
+
+/// 保存的会话配置对应的未连接会话
+fn session_from_config(cfg: NetcatSessionConfig) -> NetcatSession {
+    NetcatSession {
+        id: cfg.id,
+        name: cfg.name,
+        protocol: cfg.protocol,
+        mode: cfg.mode,
+        host: cfg.host,
+        port: cfg.port,
+        status: SessionStatus::Disconnected,
+        auto_reconnect: cfg.auto_reconnect,
+        timeout_ms: cfg.timeout_ms,
+        created_at: cfg.created_at,
+        connected_at: None,
+        last_activity: None,
+        bytes_sent: 0,
+        bytes_received: 0,
+        message_count: 0,
+        error_message: None,
+        client_count: 0,
+        auto_send: cfg.auto_send,
+    }
+}
+
+impl storage::Record for NetcatSessionConfig {
+    fn record_id(&self) -> &str {
+        &self.id
+    }
+}
+
 impl Default for NetcatState {
     fn default() -> Self {
         Self::new()
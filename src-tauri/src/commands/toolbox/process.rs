@@ -77,6 +77,25 @@ pub async fn get_processes(filter: Option<ProcessFilter>) -> Result<Vec<ProcessI
     Ok(processes)
 }
 
+/// 进程及其所有子孙进程的 PID（第一个是 root 本身）
+pub(crate) fn process_tree(root: u32) -> Vec<u32> {
+    let mut sys = System::new();
+    sys.refresh_processes();
+
+    let mut tree = vec![root];
+    let mut index = 0;
+    while index < tree.len() {
+        let parent = Pid::from_u32(tree[index]);
+        for (pid, proc) in sys.processes() {
+            if proc.parent() == Some(parent) && !tree.contains(&pid.as_u32()) {
+                tree.push(pid.as_u32());
+            }
+        }
+        index += 1;
+    }
+    tree
+}
+
 /// 构建进程信息
 fn build_process_info(
     pid: u32,
@@ -120,7 +139,7 @@ fn format_process_status(status: ProcessStatus) -> String {
 
 /// 获取端口-进程映射
 #[cfg(target_os = "windows")]
-async fn get_port_pid_map() -> Result<HashMap<u16, Vec<u32>>, String> {
+pub(crate) async fn get_port_pid_map() -> Result<HashMap<u16, Vec<u32>>, String> {
     use std::process::Command;
 
     let output = Command::new("netstat")
@@ -163,7 +182,7 @@ async fn get_port_pid_map() -> Result<HashMap<u16, Vec<u32>>, String> {
 
 /// 获取端口-进程映射（Linux）
 #[cfg(target_os = "linux")]
-async fn get_port_pid_map() -> Result<HashMap<u16, Vec<u32>>, String> {
+pub(crate) async fn get_port_pid_map() -> Result<HashMap<u16, Vec<u32>>, String> {
     use std::process::Command;
 
     // 尝试使用 ss 命令（需要 root 权限才能看到 PID）
@@ -215,7 +234,7 @@ async fn get_port_pid_map() -> Result<HashMap<u16, Vec<u32>>, String> {
 
 /// 获取端口-进程映射（macOS）
 #[cfg(target_os = "macos")]
-async fn get_port_pid_map() -> Result<HashMap<u16, Vec<u32>>, String> {
+pub(crate) async fn get_port_pid_map() -> Result<HashMap<u16, Vec<u32>>, String> {
     use std::process::Command;
 
     let output = Command::new("lsof")
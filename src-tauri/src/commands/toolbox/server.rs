@@ -13,7 +13,6 @@ use axum::{
 use once_cell::sync::Lazy;
 use socket2::{Domain, Socket, Type};
 use std::collections::HashMap;
-use std::fs;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -25,58 +24,60 @@ use tower_http::{
     services::ServeDir,
 };
 
-/// 服务配置存储 - 延迟初始化，首次访问时从文件加载
+/// 服务配置存储 - 延迟初始化，首次访问时从数据库加载
 static SERVERS: Lazy<Arc<Mutex<HashMap<String, ServerConfig>>>> =
     Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
 
-/// 是否已从文件加载
+/// 是否已从数据库加载
 static SERVERS_LOADED: Lazy<Arc<Mutex<bool>>> = Lazy::new(|| Arc::new(Mutex::new(false)));
 
 /// 服务控制器
 static SERVER_CONTROLLERS: Lazy<Arc<Mutex<HashMap<String, Arc<ServerController>>>>> =
     Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
 
-/// 确保服务配置已从文件加载
-async fn ensure_servers_loaded() {
+impl storage::Record for ServerConfig {
+    fn record_id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// 确保服务配置已从数据库加载
+async fn ensure_servers_loaded() -> Result<(), String> {
     let mut loaded = SERVERS_LOADED.lock().await;
     if !*loaded {
-        match load_servers_from_file() {
-            Ok(servers) => {
-                let mut servers_map = SERVERS.lock().await;
-                *servers_map = servers;
-                *loaded = true; // 只有成功加载才设置为 true
-            }
-            Err(e) => {
-                log::warn!("加载服务配置失败，将在下次重试: {}", e);
-                // 不设置 loaded = true，允许下次重试
-            }
-        }
+        let items = load_servers_from_storage()?;
+        let mut servers_map = SERVERS.lock().await;
+        *servers_map = items;
+        *loaded = true;
     }
+    Ok(())
 }
 
-/// 从文件加载服务配置
-fn load_servers_from_file() -> Result<HashMap<String, ServerConfig>, String> {
-    let config = storage::get_storage_config()?;
-    let path = config.server_configs_file();
-
-    log::info!("加载服务配置: {:?}", path);
+/// 数据库被整体替换（如恢复备份）后重新加载服务配置，正在运行的服务保持运行状态
+pub async fn reload_servers() -> Result<(), String> {
+    let mut loaded = SERVERS_LOADED.lock().await;
+    let mut servers = load_servers_from_storage()?;
 
-    if !path.exists() {
-        log::info!("服务配置文件不存在，返回空列表");
-        return Ok(HashMap::new());
+    {
+        let controllers = SERVER_CONTROLLERS.lock().await;
+        for (id, server) in servers.iter_mut() {
+            if controllers.contains_key(id) {
+                server.status = "running".to_string();
+            }
+        }
     }
 
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("读取服务配置失败: {}", e))?;
+    *SERVERS.lock().await = servers;
+    *loaded = true;
+    Ok(())
+}
 
-    // 直接解析为服务配置数组
-    let servers_arr: Vec<ServerConfig> = match serde_json::from_str(&content) {
-        Ok(arr) => arr,
-        Err(e) => {
-            log::error!("解析服务配置 JSON 失败: {}，内容: {}", e, &content[..content.len().min(200)]);
-            Vec::new()
-        }
-    };
+/// 从数据库加载服务配置
+fn load_servers_from_storage() -> Result<HashMap<String, ServerConfig>, String> {
+    let servers_arr: Vec<ServerConfig> = storage::with_connection(|conn| {
+        storage::RuleRepository::new(conn).list(storage::RuleKind::ServerConfig)
+    })
+    .map_err(|e| format!("读取服务配置失败: {}", e))?;
 
     let mut servers = HashMap::new();
     for mut server in servers_arr {
@@ -90,26 +91,18 @@ fn load_servers_from_file() -> Result<HashMap<String, ServerConfig>, String> {
     Ok(servers)
 }
 
-/// 保存服务配置到文件
-async fn save_servers_to_file() -> Result<(), String> {
-    let config = storage::get_storage_config()?;
-
-    // 确保数据目录存在
-    config.ensure_dirs()?;
-
+/// 保存服务配置到数据库
+async fn save_servers_to_storage() -> Result<(), String> {
     let servers = SERVERS.lock().await;
 
-    // 直接序列化（serde 会自动用 camelCase）
-    let servers_data: Vec<&ServerConfig> = servers.values().collect();
-
-    let content = serde_json::to_string(&servers_data)
-        .map_err(|e| format!("序列化服务配置失败: {}", e))?;
-
-    let path = config.server_configs_file();
-    log::info!("保存服务配置到: {:?}", path);
+    // 按创建时间排序，保持列表顺序稳定
+    let mut servers_data: Vec<&ServerConfig> = servers.values().collect();
+    servers_data.sort_by(|a, b| a.created_at.cmp(&b.created_at));
 
-    fs::write(&path, content)
-        .map_err(|e| format!("写入服务配置失败: {}", e))?;
+    storage::with_transaction(|tx| {
+        storage::RuleRepository::new(tx).replace_all(storage::RuleKind::ServerConfig, servers_data)
+    })
+    .map_err(|e| format!("写入服务配置失败: {}", e))?;
 
     log::info!("服务配置保存成功，共 {} 个服务", servers.len());
     Ok(())
@@ -145,7 +138,7 @@ struct ProxyState {
 /// 创建服务
 #[tauri::command]
 pub async fn create_server(input: ServerConfigInput) -> Result<ServerConfig, String> {
-    ensure_servers_loaded().await;
+    ensure_servers_loaded().await?;
 
     // 验证
     if input.port == 0 {
@@ -213,8 +206,8 @@ pub async fn create_server(input: ServerConfigInput) -> Result<ServerConfig, Str
         servers.insert(server_id.clone(), config.clone());
     }
 
-    // 持久化到文件
-    if let Err(e) = save_servers_to_file().await {
+    // 持久化到数据库
+    if let Err(e) = save_servers_to_storage().await {
         log::error!("保存服务配置失败: {}", e);
         // 移除刚添加的配置，因为无法持久化
         let mut servers = SERVERS.lock().await;
@@ -228,7 +221,7 @@ pub async fn create_server(input: ServerConfigInput) -> Result<ServerConfig, Str
 /// 启动服务
 #[tauri::command]
 pub async fn start_server(server_id: String) -> Result<String, String> {
-    ensure_servers_loaded().await;
+    ensure_servers_loaded().await?;
 
     // 获取配置
     let config = {
@@ -757,7 +750,7 @@ pub async fn stop_server(server_id: String) -> Result<(), String> {
 /// 移除服务
 #[tauri::command]
 pub async fn remove_server(server_id: String) -> Result<(), String> {
-    ensure_servers_loaded().await;
+    ensure_servers_loaded().await?;
 
     // 先停止服务
     let _ = stop_server(server_id.clone()).await;
@@ -774,8 +767,8 @@ pub async fn remove_server(server_id: String) -> Result<(), String> {
         servers.remove(&server_id);
     }
 
-    // 持久化到文件
-    if let Err(e) = save_servers_to_file().await {
+    // 持久化到数据库
+    if let Err(e) = save_servers_to_storage().await {
         log::error!("保存服务配置失败: {}", e);
         // 回滚：恢复删除的配置
         if let Some(config) = old_config {
@@ -791,7 +784,7 @@ pub async fn remove_server(server_id: String) -> Result<(), String> {
 /// 获取所有服务
 #[tauri::command]
 pub async fn get_servers() -> Result<Vec<ServerConfig>, String> {
-    ensure_servers_loaded().await;
+    ensure_servers_loaded().await?;
 
     let servers = SERVERS.lock().await;
     Ok(servers.values().cloned().collect())
@@ -800,7 +793,7 @@ pub async fn get_servers() -> Result<Vec<ServerConfig>, String> {
 /// 获取单个服务
 #[tauri::command]
 pub async fn get_server(server_id: String) -> Result<Option<ServerConfig>, String> {
-    ensure_servers_loaded().await;
+    ensure_servers_loaded().await?;
 
     let servers = SERVERS.lock().await;
     Ok(servers.get(&server_id).cloned())
@@ -809,7 +802,7 @@ pub async fn get_server(server_id: String) -> Result<Option<ServerConfig>, Strin
 /// 更新服务配置
 #[tauri::command]
 pub async fn update_server(server_id: String, input: ServerConfigInput) -> Result<ServerConfig, String> {
-    ensure_servers_loaded().await;
+    ensure_servers_loaded().await?;
 
     // 获取当前配置（用于回滚）
     let current = {
@@ -862,8 +855,8 @@ pub async fn update_server(server_id: String, input: ServerConfigInput) -> Resul
         }
     }
 
-    // 持久化到文件
-    if let Err(e) = save_servers_to_file().await {
+    // 持久化到数据库
+    if let Err(e) = save_servers_to_storage().await {
         log::error!("保存服务配置失败: {}", e);
         // 回滚：恢复旧配置
         let mut servers = SERVERS.lock().await;
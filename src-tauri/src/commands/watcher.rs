@@ -0,0 +1,486 @@
+// 仓库自动发现 - 监听目录下新建或克隆的 Git 仓库，作为待导入项提示用户或直接导入
+//
+// 只逐个（非递归）监听监听深度以内的目录，跳过隐藏目录、.gitignore 忽略的目录和扫描排除目录，
+// 避免监听 node_modules、target 等大目录耗尽系统的监听数量。新建的目录在监听线程中补充监听。
+
+use ignore::overrides::Override;
+use ignore::WalkBuilder;
+use notify::event::{EventKind, ModifyKind};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::project::{self, CreateProjectInput};
+use crate::commands::repo_scan;
+use crate::storage::{
+    current_iso_time, generate_id, keys, load_setting, paths, save_setting, AppSettings, Project, WatchedRoot,
+};
+
+const DISCOVERY_EVENT: &str = "repo-discovery-event";
+
+/// 待导入的仓库
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingImport {
+    pub path: String,
+    pub name: String,
+    pub root_id: String,
+    pub discovered_at: String,
+}
+
+/// 仓库发现事件（用于前端实时更新）
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum DiscoveryEvent {
+    /// 发现新仓库，等待用户确认导入
+    Pending { repo: PendingImport },
+    /// 发现新仓库并已自动导入
+    Imported { project: Box<Project> },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchedRootInput {
+    pub path: String,
+    pub depth: Option<u32>,
+    pub auto_import: Option<bool>,
+    /// 更新时为空字符串表示清除分类，未提供时保持不变
+    pub category: Option<String>,
+    pub labels: Option<Vec<String>>,
+}
+
+/// 发给监听线程的消息
+enum WatchMessage {
+    Event(notify::Event),
+    Stop,
+}
+
+/// 当前监听线程的消息通道（发送 Stop 或置空即停止监听）
+static WATCHER: Lazy<Mutex<Option<Sender<WatchMessage>>>> = Lazy::new(|| Mutex::new(None));
+
+/// 待导入的仓库（只保存在内存中，重启后由用户重新扫描）
+static PENDING: Lazy<Mutex<Vec<PendingImport>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// 本次运行中被忽略的仓库（路径键）
+static DISMISSED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+fn load_watched_roots() -> Result<Vec<WatchedRoot>, String> {
+    let roots = load_setting::<Vec<WatchedRoot>>(keys::WATCHED_ROOTS)
+        .map_err(|e| format!("读取监听目录失败: {}", e))?;
+    Ok(roots.unwrap_or_default())
+}
+
+fn save_watched_roots(roots: &[WatchedRoot]) -> Result<(), String> {
+    save_setting(keys::WATCHED_ROOTS, roots).map_err(|e| format!("保存监听目录失败: {}", e))
+}
+
+/// 事件路径对应的仓库目录：新建的 .git 目录（克隆、git init），或移入的带 .git 的目录
+fn repo_dir(path: &Path) -> Option<PathBuf> {
+    if path.file_name()? == ".git" {
+        return path.parent().map(Path::to_path_buf);
+    }
+    if path.is_dir() && path.join(".git").exists() {
+        return Some(path.to_path_buf());
+    }
+    None
+}
+
+/// 仓库所属的监听目录（超出该目录的深度时不算）
+fn owning_root<'a>(roots: &'a [WatchedRoot], repo: &str) -> Option<&'a WatchedRoot> {
+    roots.iter().find(|root| {
+        paths::strip_path_prefix(repo, &root.path)
+            .map(|rest| rest.len() as u32 <= root.depth)
+            .unwrap_or(false)
+    })
+}
+
+fn emit(app: &AppHandle, event: DiscoveryEvent) {
+    if let Err(e) = app.emit(DISCOVERY_EVENT, &event) {
+        log::warn!("发送仓库发现事件失败: {}", e);
+    }
+}
+
+/// 处理一个新发现的仓库
+fn discover(app: &AppHandle, roots: &[WatchedRoot], repo: &Path) -> Result<(), String> {
+    let repo_path = repo.to_string_lossy().to_string();
+    let root = match owning_root(roots, &repo_path) {
+        Some(root) => root,
+        None => return Ok(()),
+    };
+
+    let key = paths::path_key(&repo_path);
+    if DISMISSED.lock().map_err(|e| e.to_string())?.contains(&key)
+        || project::get_projects()?.iter().any(|p| paths::same_path(&p.path, &repo_path))
+    {
+        return Ok(());
+    }
+
+    let name = repo
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| repo_path.clone());
+
+    if root.auto_import {
+        let imported = project::import_projects(vec![CreateProjectInput {
+            name,
+            path: repo_path,
+            tags: root.category.clone().map(|category| vec![category]),
+            labels: Some(root.labels.clone()),
+        }])?;
+        for project in imported {
+            log::info!("已自动导入新仓库: {}", project.path);
+            emit(app, DiscoveryEvent::Imported { project: Box::new(project) });
+        }
+        return Ok(());
+    }
+
+    let repo = {
+        let mut pending = PENDING.lock().map_err(|e| e.to_string())?;
+        if pending.iter().any(|p| paths::same_path(&p.path, &repo_path)) {
+            return Ok(());
+        }
+        let repo = PendingImport {
+            path: repo_path,
+            name,
+            root_id: root.id.clone(),
+            discovered_at: current_iso_time(),
+        };
+        pending.push(repo.clone());
+        repo
+    };
+    log::info!("发现新仓库: {}", repo.path);
+    emit(app, DiscoveryEvent::Pending { repo });
+    Ok(())
+}
+
+/// 一个监听目录及其排除规则
+struct WatchScope {
+    root: PathBuf,
+    depth: usize,
+    overrides: Override,
+}
+
+impl WatchScope {
+    fn new(root: &WatchedRoot, excludes: &[String]) -> Result<Self, String> {
+        let path = PathBuf::from(paths::expand_home(&root.path));
+        let overrides = repo_scan::build_overrides(&path, excludes)?;
+        Ok(Self { root: path, depth: root.depth as usize, overrides })
+    }
+
+    /// 新建的目录需要监听时，返回它下面还能继续监听的层数
+    fn remaining_depth(&self, dir: &Path) -> Option<usize> {
+        let level = dir.strip_prefix(&self.root).ok()?.components().count();
+        if level == 0 || level > self.depth {
+            return None;
+        }
+        let hidden = dir.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden || self.overrides.matched(dir, true).is_ignore() {
+            return None;
+        }
+        Some(self.depth - level)
+    }
+}
+
+/// 需要监听的目录：dir 本身和 depth 层以内的子目录，跳过隐藏、忽略和排除的目录
+fn watch_dirs(dir: &Path, depth: usize, overrides: &Override) -> Vec<PathBuf> {
+    WalkBuilder::new(dir)
+        .max_depth(Some(depth))
+        .follow_links(false)
+        .overrides(overrides.clone())
+        .filter_entry(|entry| entry.file_type().is_some_and(|t| t.is_dir()))
+        .build()
+        .flatten()
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+fn watch_tree(watcher: &mut RecommendedWatcher, dir: &Path, depth: usize, overrides: &Override) -> usize {
+    let mut count = 0;
+    for dir in watch_dirs(dir, depth, overrides) {
+        match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            Ok(()) => count += 1,
+            Err(e) => log::debug!("无法监听目录 {:?}: {}", dir, e),
+        }
+    }
+    count
+}
+
+/// 监听线程：持有监听器，补充监听新建的目录并处理新仓库。
+/// 不能在 notify 的回调中调用 watch（回调所在线程就是 watch 要等待的事件线程）
+fn run_watcher(
+    mut watcher: RecommendedWatcher,
+    messages: Receiver<WatchMessage>,
+    app: AppHandle,
+    roots: Vec<WatchedRoot>,
+    excludes: Vec<String>,
+) {
+    let mut scopes = Vec::new();
+    for root in &roots {
+        match WatchScope::new(root, &excludes) {
+            Ok(scope) => {
+                let count = watch_tree(&mut watcher, &scope.root, scope.depth, &scope.overrides);
+                log::info!("开始监听目录: {:?}（{} 个子目录）", scope.root, count);
+                scopes.push(scope);
+            }
+            Err(e) => log::warn!("无法监听目录 {}: {}", root.path, e),
+        }
+    }
+
+    while let Ok(WatchMessage::Event(event)) = messages.recv() {
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))) {
+            continue;
+        }
+        for path in &event.paths {
+            if path.is_dir() {
+                if let Some((scope, depth)) = scopes.iter().find_map(|s| Some((s, s.remaining_depth(path)?))) {
+                    watch_tree(&mut watcher, path, depth, &scope.overrides);
+                }
+            }
+            if let Some(repo) = repo_dir(path) {
+                if let Err(e) = discover(&app, &roots, &repo) {
+                    log::warn!("处理新仓库失败 {:?}: {}", repo, e);
+                }
+            }
+        }
+    }
+}
+
+/// 按当前的监听目录设置重新创建监听器
+pub fn restart_watcher(app: &AppHandle) -> Result<(), String> {
+    let roots = load_watched_roots()?;
+    let mut watcher_slot = WATCHER.lock().map_err(|e| e.to_string())?;
+    if let Some(previous) = watcher_slot.take() {
+        let _ = previous.send(WatchMessage::Stop);
+    }
+    if roots.is_empty() {
+        return Ok(());
+    }
+
+    let excludes = load_setting::<AppSettings>(keys::APP_SETTINGS)?.unwrap_or_default().scan_excludes;
+    let (sender, receiver) = mpsc::channel();
+    let events = sender.clone();
+    let watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| match result {
+        Ok(event) => {
+            let _ = events.send(WatchMessage::Event(event));
+        }
+        Err(e) => log::warn!("监听目录出错: {}", e),
+    })
+    .map_err(|e| format!("创建目录监听器失败: {}", e))?;
+
+    let handler_app = app.clone();
+    std::thread::Builder::new()
+        .name("repo-watcher".to_string())
+        .spawn(move || run_watcher(watcher, receiver, handler_app, roots, excludes))
+        .map_err(|e| format!("启动目录监听线程失败: {}", e))?;
+
+    *watcher_slot = Some(sender);
+    Ok(())
+}
+
+/// 校验监听目录设置
+fn validate_root(root: &WatchedRoot, roots: &[WatchedRoot]) -> Result<(), String> {
+    let path = paths::expand_home(&root.path);
+    if !Path::new(&path).is_dir() {
+        return Err(format!("目录不存在: {}", root.path));
+    }
+    if root.depth == 0 {
+        return Err("监听深度至少为 1".to_string());
+    }
+    if roots.iter().any(|other| other.id != root.id && paths::same_path(&other.path, &root.path)) {
+        return Err("该目录已在监听列表中".to_string());
+    }
+    Ok(())
+}
+
+// ============== 监听目录管理 ==============
+
+#[tauri::command]
+pub fn get_watched_roots() -> Result<Vec<WatchedRoot>, String> {
+    load_watched_roots()
+}
+
+#[tauri::command]
+pub fn add_watched_root(app: AppHandle, input: WatchedRootInput) -> Result<Vec<WatchedRoot>, String> {
+    let mut roots = load_watched_roots()?;
+    let default_depth = load_setting::<AppSettings>(keys::APP_SETTINGS)?.unwrap_or_default().scan_depth;
+
+    let root = WatchedRoot {
+        id: generate_id(),
+        path: input.path,
+        depth: input.depth.unwrap_or(default_depth),
+        auto_import: input.auto_import.unwrap_or(false),
+        category: input.category,
+        labels: input.labels.unwrap_or_default(),
+    };
+    validate_root(&root, &roots)?;
+
+    roots.push(root);
+    save_watched_roots(&roots)?;
+    restart_watcher(&app)?;
+    Ok(roots)
+}
+
+/// 只更新提供了的字段
+fn apply_update(root: &mut WatchedRoot, input: WatchedRootInput) {
+    root.path = input.path;
+    if let Some(depth) = input.depth {
+        root.depth = depth;
+    }
+    if let Some(auto_import) = input.auto_import {
+        root.auto_import = auto_import;
+    }
+    if let Some(category) = input.category {
+        root.category = Some(category).filter(|c| !c.is_empty());
+    }
+    if let Some(labels) = input.labels {
+        root.labels = labels;
+    }
+}
+
+#[tauri::command]
+pub fn update_watched_root(app: AppHandle, id: String, input: WatchedRootInput) -> Result<Vec<WatchedRoot>, String> {
+    let mut roots = load_watched_roots()?;
+    let index = roots.iter().position(|r| r.id == id).ok_or("监听目录不存在")?;
+
+    let mut updated = roots[index].clone();
+    apply_update(&mut updated, input);
+    validate_root(&updated, &roots)?;
+
+    roots[index] = updated;
+    save_watched_roots(&roots)?;
+    restart_watcher(&app)?;
+    Ok(roots)
+}
+
+#[tauri::command]
+pub fn remove_watched_root(app: AppHandle, id: String) -> Result<Vec<WatchedRoot>, String> {
+    let mut roots = load_watched_roots()?;
+    roots.retain(|r| r.id != id);
+    save_watched_roots(&roots)?;
+    restart_watcher(&app)?;
+
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.retain(|p| p.root_id != id);
+    }
+    Ok(roots)
+}
+
+// ============== 待导入仓库 ==============
+
+#[tauri::command]
+pub fn get_pending_imports() -> Result<Vec<PendingImport>, String> {
+    Ok(PENDING.lock().map_err(|e| e.to_string())?.clone())
+}
+
+/// 导入选中的待导入仓库，使用各自监听目录的默认分类和标签
+#[tauri::command]
+pub fn accept_pending_imports(paths: Vec<String>) -> Result<Vec<Project>, String> {
+    let roots = load_watched_roots()?;
+    let accepted: Vec<PendingImport> = {
+        let mut pending = PENDING.lock().map_err(|e| e.to_string())?;
+        let (accepted, rest) = pending
+            .drain(..)
+            .partition(|p| paths.iter().any(|path| crate::storage::paths::same_path(path, &p.path)));
+        *pending = rest;
+        accepted
+    };
+
+    let inputs = accepted
+        .into_iter()
+        .map(|repo| {
+            let root = roots.iter().find(|r| r.id == repo.root_id);
+            CreateProjectInput {
+                name: repo.name,
+                path: repo.path,
+                tags: root.and_then(|r| r.category.clone()).map(|category| vec![category]),
+                labels: root.map(|r| r.labels.clone()),
+            }
+        })
+        .collect();
+    project::import_projects(inputs)
+}
+
+/// 忽略选中的待导入仓库，本次运行中不再提示
+#[tauri::command]
+pub fn dismiss_pending_imports(paths: Vec<String>) -> Result<(), String> {
+    let mut pending = PENDING.lock().map_err(|e| e.to_string())?;
+    let mut dismissed = DISMISSED.lock().map_err(|e| e.to_string())?;
+    for path in &paths {
+        dismissed.insert(crate::storage::paths::path_key(path));
+    }
+    pending.retain(|p| !paths.iter().any(|path| crate::storage::paths::same_path(path, &p.path)));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DEFAULT_SCAN_EXCLUDES;
+    use crate::test_support::TempDir;
+
+    fn excludes() -> Vec<String> {
+        DEFAULT_SCAN_EXCLUDES.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn watched_root(path: &Path, depth: u32) -> WatchedRoot {
+        WatchedRoot {
+            id: "r1".to_string(),
+            path: path.to_string_lossy().to_string(),
+            depth,
+            auto_import: false,
+            category: Some("work".to_string()),
+            labels: vec!["rust".to_string()],
+        }
+    }
+
+    #[test]
+    fn watches_only_directories_within_depth_and_not_excluded() {
+        let dir = TempDir::new("watch");
+        for sub in ["a/b/c", "a/node_modules/pkg", "a/target/debug", ".cache/x", "d"] {
+            dir.mkdir(sub);
+        }
+        dir.write("a/file.txt", "");
+
+        let scope = WatchScope::new(&watched_root(dir.path(), 2), &excludes()).unwrap();
+        let mut watched: Vec<String> = watch_dirs(&scope.root, scope.depth, &scope.overrides)
+            .iter()
+            .map(|p| p.strip_prefix(dir.path()).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        watched.sort();
+        assert_eq!(watched, vec!["", "a", "a/b", "d"]);
+
+        assert_eq!(scope.remaining_depth(&dir.path().join("new")), Some(1));
+        assert_eq!(scope.remaining_depth(&dir.path().join("a/new")), Some(0));
+        assert_eq!(scope.remaining_depth(&dir.path().join("a/b/new")), None);
+        assert_eq!(scope.remaining_depth(&dir.path().join("a/node_modules")), None);
+        assert_eq!(scope.remaining_depth(&dir.path().join(".git")), None);
+        assert_eq!(scope.remaining_depth(Path::new("/elsewhere/repo")), None);
+    }
+
+    #[test]
+    fn update_keeps_fields_that_are_not_provided() {
+        let mut root = watched_root(Path::new("/work"), 2);
+        let input = |category: Option<&str>| WatchedRootInput {
+            path: "/work".to_string(),
+            depth: None,
+            auto_import: Some(true),
+            category: category.map(str::to_string),
+            labels: None,
+        };
+
+        apply_update(&mut root, input(None));
+        assert_eq!((root.depth, root.auto_import), (2, true));
+        assert_eq!(root.category.as_deref(), Some("work"));
+        assert_eq!(root.labels, vec!["rust"]);
+
+        apply_update(&mut root, input(Some("oss")));
+        assert_eq!(root.category.as_deref(), Some("oss"));
+        apply_update(&mut root, input(Some("")));
+        assert_eq!(root.category, None);
+    }
+}
@@ -1,13 +1,16 @@
 mod commands;
 mod storage;
+#[cfg(test)]
+mod test_support;
 
-use commands::{git, project, stats, system, toolbox, settings};
+use commands::{data, env, fetch_scheduler, git, git_backend, git_batch, git_branch, git_diff, git_graph, git_merge, git_release, git_stage, git_stash, health, project, repo_scan, services, stats, system, tasks, templates, toolbox, settings, watcher};
 use tauri::{
     Manager,
     tray::TrayIconBuilder,
     menu::{Menu, MenuItem},
     image::Image,
 };
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -31,8 +34,15 @@ pub fn run() {
         .setup(|app| {
             // 初始化存储系统
             if let Err(e) = storage::init_storage() {
-                eprintln!("存储系统初始化警告: {}", e);
-                // 不阻止应用启动，只是警告
+                // 数据目录不可用时不能继续，否则读写会落到别处新建的空数据库；提示后退出
+                eprintln!("存储系统初始化失败: {}", e);
+                let handle = app.handle().clone();
+                app.dialog()
+                    .message(format!("无法打开数据目录：{}", e))
+                    .title("CodeShelf")
+                    .kind(MessageDialogKind::Error)
+                    .show(move |_| handle.exit(1));
+                return Ok(());
             }
 
             // 获取日志目录路径
@@ -63,6 +73,19 @@ pub fn run() {
                     .build(),
             )?;
 
+            // 打开数据库（首次运行时导入旧版 JSON 数据）
+            if let Err(e) = storage::init_database() {
+                log::error!("数据库初始化失败: {}", e);
+            }
+
+            // 后台检查项目目录是否丢失或被移动
+            health::spawn_startup_check(app.handle().clone());
+
+            // 监听设置的目录，发现新建或克隆的仓库
+            if let Err(e) = watcher::restart_watcher(app.handle()) {
+                log::error!("启动目录监听失败: {}", e);
+            }
+
             // 创建托盘右键菜单
             let show = MenuItem::with_id(app, "show", "显示主窗口", true, None::<&str>)?;
             let quit = MenuItem::with_id(app, "quit", "退出程序", true, None::<&str>)?;
@@ -73,7 +96,7 @@ pub fn run() {
                 .expect("Failed to load tray icon");
 
             // 创建系统托盘
-            let _tray = TrayIconBuilder::new()
+            let _tray = TrayIconBuilder::with_id(fetch_scheduler::TRAY_ID)
                 .icon(icon)
                 .tooltip("CodeShelf - 代码书架")
                 .menu(&menu)
@@ -121,6 +144,9 @@ pub fn run() {
                 })
                 .build(app)?;
 
+            // 按设置定时 fetch 项目，落后远程时通知并更新托盘提示
+            fetch_scheduler::spawn_fetch_scheduler(app.handle().clone());
+
             // 初始化 Netcat 状态
             app.manage(toolbox::netcat::NetcatState::new());
 
@@ -138,6 +164,7 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             // Git commands
             git::scan_directory,
+            repo_scan::scan_repositories,
             git::get_git_status,
             git::get_commit_history,
             git::get_commit_detail,
@@ -161,6 +188,60 @@ pub fn run() {
             git::git_add_and_commit,
             git::is_git_repo,
             git::git_init,
+            git::get_tags,
+            git::create_tag,
+            git::delete_tag,
+            git::push_tags,
+            git_backend::get_git_backend_info,
+            // Branch management commands
+            git_branch::get_branch_overview,
+            git_branch::delete_branch,
+            git_branch::rename_branch,
+            git_branch::set_branch_upstream,
+            git_branch::compare_branches,
+            git_branch::preview_prune_branches,
+            git_branch::prune_branches,
+            // File diff commands
+            git_diff::get_working_diff,
+            git_diff::get_staged_diff,
+            git_diff::get_commit_file_diff,
+            // Hunk staging commands
+            git_stage::stage_hunks,
+            git_stage::unstage_hunks,
+            git_stage::discard_hunks,
+            git_stage::discard_files,
+            git_stage::get_discarded_changes,
+            git_stage::restore_discarded_change,
+            git_stage::delete_discarded_change,
+            // Stash commands
+            git_stash::get_stashes,
+            git_stash::create_stash,
+            git_stash::apply_stash,
+            git_stash::pop_stash,
+            git_stash::drop_stash,
+            git_stash::get_stash_diff,
+            // Merge and rebase commands
+            git_merge::get_operation_state,
+            git_merge::get_conflict_content,
+            git_merge::resolve_conflict,
+            git_merge::continue_operation,
+            git_merge::abort_operation,
+            git_merge::merge_branch,
+            git_merge::rebase_onto,
+            // Commit graph commands
+            git_graph::get_commit_graph,
+            // Release notes commands
+            git_release::generate_release_notes,
+            git_release::save_release_notes,
+            // Batch git commands
+            git_batch::start_git_batch,
+            git_batch::cancel_git_batch,
+            git_batch::get_git_batch_reports,
+            // Remote update commands
+            fetch_scheduler::get_fetch_schedule,
+            fetch_scheduler::set_fetch_schedule,
+            fetch_scheduler::fetch_remotes_now,
+            fetch_scheduler::get_remote_statuses,
             // Project commands
             project::get_projects,
             project::create_project,
@@ -173,6 +254,51 @@ pub fn run() {
             project::batch_delete_projects,
             project::import_projects,
             project::reload_projects,
+            project::relocate_projects,
+            project::refresh_project_stacks,
+            project::get_projects_by_stack,
+            project::set_project_scripts,
+            project::suggest_project_scripts,
+            // Project health commands
+            health::check_project_health,
+            health::get_project_health,
+            health::accept_relocation,
+            health::reject_relocation,
+            // Task runner commands
+            tasks::run_project_script,
+            tasks::stop_task,
+            tasks::restart_task,
+            tasks::get_task_runs,
+            tasks::get_task_output,
+            // Dev service commands
+            services::start_service,
+            services::stop_service,
+            services::restart_service,
+            services::remove_service,
+            services::get_services,
+            services::stop_project,
+            // Project environment commands
+            env::get_project_env,
+            env::save_env_profile,
+            env::delete_env_profile,
+            env::set_active_env,
+            env::list_env_files,
+            env::import_env_file,
+            env::write_env_file,
+            // Project template commands
+            templates::get_templates,
+            templates::add_template,
+            templates::update_template,
+            templates::remove_template,
+            templates::create_from_template,
+            // Repository discovery commands
+            watcher::get_watched_roots,
+            watcher::add_watched_root,
+            watcher::update_watched_root,
+            watcher::remove_watched_root,
+            watcher::get_pending_imports,
+            watcher::accept_pending_imports,
+            watcher::dismiss_pending_imports,
             // Stats commands
             stats::get_dashboard_stats,
             stats::refresh_dashboard_stats,
@@ -286,6 +412,8 @@ pub fn run() {
             settings::save_terminal_config,
             settings::get_app_settings,
             settings::save_app_settings,
+            settings::get_path_roots,
+            settings::save_path_roots,
             // Settings - UI State commands
             settings::get_ui_state,
             settings::save_ui_state,
@@ -295,6 +423,13 @@ pub fn run() {
             settings::add_notification,
             settings::remove_notification,
             settings::clear_notifications,
+            // Data commands
+            data::list_backups,
+            data::create_backup,
+            data::restore_backup,
+            data::move_data_directory,
+            data::export_workspace,
+            data::import_workspace,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,64 @@
+// 原子写入 - 先写临时文件并落盘，再重命名覆盖目标文件
+//
+// 写入过程中断电或崩溃时，目标文件要么是旧内容，要么是完整的新内容，不会出现半截文件。
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// 同目录下的临时文件路径（重命名只有在同一文件系统内才是原子的）
+fn temp_path(path: &Path) -> Result<PathBuf, String> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("无效的文件路径: {:?}", path))?
+        .to_string_lossy();
+    Ok(path.with_file_name(format!(".{}.{}.tmp", name, std::process::id())))
+}
+
+/// 把父目录的元数据落盘，确保重命名本身在断电后仍然有效
+///
+/// Windows 不支持打开目录句柄，重命名由 NTFS 日志保证，这里直接跳过
+pub fn sync_parent_dir(path: &Path) -> Result<(), String> {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        File::open(parent)
+            .and_then(|dir| dir.sync_all())
+            .map_err(|e| format!("同步目录失败 {:?}: {}", parent, e))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// 用 `write` 生成内容并原子地替换 `path`
+pub fn write_atomic_with(path: &Path, write: impl FnOnce(&mut File) -> io::Result<()>) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+
+    let temp = temp_path(path)?;
+    let result = File::create(&temp)
+        .and_then(|mut file| {
+            write(&mut file)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp, path));
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("写入文件失败 {:?}: {}", path, e));
+    }
+
+    sync_parent_dir(path)
+}
+
+/// 原子写入文件内容
+pub fn write_atomic(path: &Path, content: impl AsRef<[u8]>) -> Result<(), String> {
+    write_atomic_with(path, |file| file.write_all(content.as_ref()))
+}
+
+/// 原子地把 `src` 复制为 `dest`
+pub fn copy_atomic(src: &Path, dest: &Path) -> Result<(), String> {
+    let mut source = File::open(src).map_err(|e| format!("打开文件失败 {:?}: {}", src, e))?;
+    write_atomic_with(dest, |file| io::copy(&mut source, file).map(|_| ()))
+}
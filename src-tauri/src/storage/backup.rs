@@ -0,0 +1,170 @@
+// 数据备份 - 数据库的带时间戳快照，按设置保留最近 N 份
+//
+// 备份文件名形如 codeshelf-20240101-120000-000-startup.db，
+// 时间戳之后是创建原因（启动、手动、恢复前、导入前）。
+
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::atomic;
+use super::config::get_storage_config;
+use super::db;
+use super::repository::{keys, SettingsRepository};
+use super::schema::AppSettings;
+
+pub const REASON_STARTUP: &str = "startup";
+pub const REASON_MANUAL: &str = "manual";
+pub const REASON_PRE_RESTORE: &str = "pre-restore";
+pub const REASON_PRE_IMPORT: &str = "pre-import";
+
+const FILE_PREFIX: &str = "codeshelf-";
+const FILE_SUFFIX: &str = ".db";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S-%3f";
+/// 时间戳部分的长度，如 20240101-120000-000
+const TIMESTAMP_LEN: usize = 19;
+
+/// 备份信息
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub path: String,
+    pub size: u64,
+    pub created_at: String,
+    pub reason: String,
+}
+
+/// 从文件名解析备份信息，不是备份文件时返回 None
+fn parse_backup(path: &Path) -> Option<BackupInfo> {
+    let file_name = path.file_name()?.to_string_lossy().to_string();
+    let stem = file_name.strip_prefix(FILE_PREFIX)?.strip_suffix(FILE_SUFFIX)?;
+    if stem.len() <= TIMESTAMP_LEN + 1 || !stem.is_char_boundary(TIMESTAMP_LEN) {
+        return None;
+    }
+
+    let (timestamp, reason) = stem.split_at(TIMESTAMP_LEN);
+    let created_at = chrono::NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?;
+    let reason = reason.trim_start_matches('-').to_string();
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+
+    Some(BackupInfo {
+        path: path.to_string_lossy().to_string(),
+        file_name,
+        size,
+        created_at: created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        reason,
+    })
+}
+
+/// 列出所有备份（最新的在前）
+pub fn list_backups() -> Result<Vec<BackupInfo>, String> {
+    let dir = get_storage_config()?.backups_dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&dir).map_err(|e| format!("读取备份目录失败: {}", e))?;
+    let mut backups: Vec<BackupInfo> = entries
+        .flatten()
+        .filter_map(|entry| parse_backup(&entry.path()))
+        .collect();
+
+    // 文件名以时间戳开头，按文件名倒序即按时间倒序
+    backups.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+    Ok(backups)
+}
+
+/// 创建一份数据库快照，并清理超出保留份数的旧备份
+pub fn create_backup(reason: &str) -> Result<BackupInfo, String> {
+    let (backup, keep) = snapshot(reason)?;
+    prune_backups(keep)?;
+    Ok(backup)
+}
+
+/// 生成快照，返回备份信息和设置中的保留份数
+fn snapshot(reason: &str) -> Result<(BackupInfo, usize), String> {
+    let dir = get_storage_config()?.backups_dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("创建备份目录失败: {}", e))?;
+
+    let timestamp = chrono::Local::now().format(TIMESTAMP_FORMAT);
+    let path = dir.join(format!("{}{}-{}{}", FILE_PREFIX, timestamp, reason, FILE_SUFFIX));
+    let temp = dir.join(format!(".{}{}.tmp", FILE_PREFIX, timestamp));
+
+    // VACUUM INTO 生成一份一致的快照，不受 WAL 中未写回内容的影响
+    let keep = db::with_connection(|conn| {
+        conn.execute("VACUUM INTO ?1", [temp.to_string_lossy()])
+            .map_err(|e| format!("生成数据库快照失败: {}", e))?;
+
+        let settings: Option<AppSettings> = SettingsRepository::new(conn).get(keys::APP_SETTINGS)?;
+        Ok(settings.unwrap_or_default().backup_keep_count)
+    });
+
+    let keep = match keep {
+        Ok(keep) => keep,
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            return Err(e);
+        }
+    };
+
+    fs::rename(&temp, &path).map_err(|e| format!("保存备份失败: {}", e))?;
+    atomic::sync_parent_dir(&path)?;
+    log::info!("已创建数据备份: {:?}", path);
+
+    let backup = parse_backup(&path).ok_or_else(|| format!("无效的备份文件: {:?}", path))?;
+    Ok((backup, keep as usize))
+}
+
+/// 只保留最近的 `keep` 份备份（至少保留一份）
+fn prune_backups(keep: usize) -> Result<(), String> {
+    for backup in list_backups()?.into_iter().skip(keep.max(1)) {
+        if let Err(e) = fs::remove_file(&backup.path) {
+            log::warn!("删除旧备份失败 {}: {}", backup.path, e);
+        }
+    }
+    Ok(())
+}
+
+/// 检查备份文件可以被当前程序打开
+fn verify_backup(path: &Path) -> Result<(), String> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("打开备份失败: {}", e))?;
+
+    let result: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| format!("校验备份失败: {}", e))?;
+    if result != "ok" {
+        return Err(format!("备份文件已损坏: {}", result));
+    }
+
+    db::schema_version(&conn, path)?;
+    Ok(())
+}
+
+/// 从备份恢复数据库
+///
+/// 恢复前会先为当前数据创建一份备份，返回这份备份的信息，以便撤销恢复
+pub fn restore_backup(file_name: &str) -> Result<BackupInfo, String> {
+    let dir = get_storage_config()?.backups_dir();
+    let path: PathBuf = dir.join(file_name);
+
+    // 只允许恢复备份目录中的备份文件
+    if Path::new(file_name).file_name().map(|n| n != file_name).unwrap_or(true)
+        || parse_backup(&path).is_none()
+        || !path.is_file()
+    {
+        return Err(format!("备份不存在: {}", file_name));
+    }
+
+    verify_backup(&path)?;
+
+    // 先恢复再清理旧备份，避免要恢复的备份被清理掉
+    let (safety, keep) = snapshot(REASON_PRE_RESTORE)?;
+    db::replace_database_file(&path)?;
+    log::info!("已从备份恢复数据: {}", file_name);
+
+    prune_backups(keep)?;
+    Ok(safety)
+}
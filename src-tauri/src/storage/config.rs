@@ -1,34 +1,77 @@
-// 存储配置 - 使用安装目录下的 data 和 logs 文件夹
+// 存储配置 - 数据目录与日志目录的位置
+//
+// 数据根目录按以下顺序确定（其下包含 data 和 logs 两个文件夹）：
+// 1. 环境变量 CODESHELF_HOME
+// 2. 便携模式：可执行文件旁有 .portable（或 portable）标记文件时，使用安装目录
+// 3. 迁移后的位置：数据目录迁移命令写入的位置文件（见 `location_file`）
+// 4. 兼容旧版本：安装目录下已有数据时继续使用安装目录
+// 5. 系统的用户数据目录（如 %APPDATA%/codeshelf、~/.local/share/codeshelf）
 
+use serde::Serialize;
+use std::ffi::OsString;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
+use super::documents::{claude_profiles_target, json_documents};
+
 /// 存储配置（全局单例）
 static STORAGE_CONFIG: OnceLock<StorageConfig> = OnceLock::new();
 
+/// 覆盖数据根目录的环境变量
+pub const HOME_ENV: &str = "CODESHELF_HOME";
+
+/// 便携模式标记文件名（放在可执行文件旁）
+const PORTABLE_MARKERS: &[&str] = &[".portable", "portable"];
+
+/// 应用在系统目录中的文件夹名
+const APP_DIR_NAME: &str = "codeshelf";
+
+/// 数据根目录的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StorageMode {
+    /// 环境变量指定
+    Env,
+    /// 便携模式（安装目录）
+    Portable,
+    /// 迁移命令指定的位置
+    Custom,
+    /// 旧版本的安装目录
+    InstallDir,
+    /// 系统用户数据目录
+    User,
+}
+
 /// 存储配置
 #[derive(Debug, Clone)]
 pub struct StorageConfig {
-    /// 数据目录: <安装目录>/data
+    /// 数据目录: <根目录>/data
     pub data_dir: PathBuf,
-    /// 日志目录: <安装目录>/logs
+    /// 日志目录: <根目录>/logs
     pub logs_dir: PathBuf,
+    /// 根目录的来源
+    pub mode: StorageMode,
 }
 
 impl StorageConfig {
     /// 创建存储配置
     pub fn new() -> Result<Self, String> {
-        let install_dir = std::env::current_exe()
-            .map_err(|e| format!("获取可执行文件路径失败: {}", e))?
-            .parent()
-            .map(|p| p.to_path_buf())
-            .ok_or_else(|| "无法获取安装目录".to_string())?;
-
-        Ok(Self {
-            data_dir: install_dir.join("data"),
-            logs_dir: install_dir.join("logs"),
-        })
+        let (root_dir, mode) = resolve_root_dir()?;
+        Ok(Self::with_root(root_dir, mode))
+    }
+
+    pub fn with_root(root_dir: PathBuf, mode: StorageMode) -> Self {
+        Self {
+            data_dir: root_dir.join("data"),
+            logs_dir: root_dir.join("logs"),
+            mode,
+        }
+    }
+
+    /// 数据目录是否可以通过迁移命令移动（环境变量和便携模式由外部决定位置）
+    pub fn is_relocatable(&self) -> bool {
+        !matches!(self.mode, StorageMode::Env | StorageMode::Portable)
     }
 
     /// 确保目录存在
@@ -40,7 +83,28 @@ impl StorageConfig {
         Ok(())
     }
 
-    // ============== 数据文件路径 ==============
+    // ============== 数据库文件路径 ==============
+
+    pub fn database_file(&self) -> PathBuf {
+        self.data_dir.join("codeshelf.db")
+    }
+
+    /// 数据库自动备份目录
+    pub fn backups_dir(&self) -> PathBuf {
+        self.data_dir.join("backups")
+    }
+
+    /// 丢弃修改前保存的文件副本目录
+    pub fn discarded_dir(&self) -> PathBuf {
+        self.data_dir.join("discarded")
+    }
+
+    /// 已导入数据库的旧版 JSON 文件归档目录
+    pub fn legacy_archive_dir(&self) -> PathBuf {
+        self.data_dir.join("legacy_json")
+    }
+
+    // ============== 旧版 JSON 数据文件路径（首次启动时导入数据库） ==============
 
     pub fn projects_file(&self) -> PathBuf {
         self.data_dir.join("projects.json")
@@ -103,6 +167,99 @@ impl StorageConfig {
     }
 }
 
+/// 可执行文件所在目录
+fn install_dir() -> Result<PathBuf, String> {
+    std::env::current_exe()
+        .map_err(|e| format!("获取可执行文件路径失败: {}", e))?
+        .parent()
+        .map(|p| p.to_path_buf())
+        .ok_or_else(|| "无法获取安装目录".to_string())
+}
+
+/// 记录迁移后数据根目录的位置文件（在系统的用户配置目录中）
+pub fn location_file() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR_NAME).join("data_location"))
+}
+
+/// 读取迁移命令写入的数据根目录
+fn read_location_file() -> Option<PathBuf> {
+    let content = fs::read_to_string(location_file()?).ok()?;
+    let path = PathBuf::from(content.trim());
+    if path.as_os_str().is_empty() {
+        return None;
+    }
+    Some(path)
+}
+
+/// 安装目录下是否有旧版本留下的数据（数据库或任一旧版 JSON 文档）
+fn has_install_dir_data(install_dir: &Path) -> bool {
+    let config = StorageConfig::with_root(install_dir.to_path_buf(), StorageMode::InstallDir);
+    if config.database_file().exists() || json_documents(&config).iter().any(|(path, _)| path.exists()) {
+        return true;
+    }
+    // Claude 配置档案的文件名按环境生成
+    fs::read_dir(&config.data_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|entry| claude_profiles_target(&entry.file_name().to_string_lossy()).is_some())
+        })
+        .unwrap_or(false)
+}
+
+/// 确定数据根目录需要的外部信息
+struct RootSources {
+    /// 环境变量 CODESHELF_HOME 的值
+    env_home: Option<OsString>,
+    install_dir: PathBuf,
+    /// 位置文件中记录的目录
+    location: Option<PathBuf>,
+    /// 系统的用户数据目录
+    user_data_dir: Option<PathBuf>,
+}
+
+/// 按优先级确定数据根目录
+fn choose_root_dir(sources: RootSources) -> Result<(PathBuf, StorageMode), String> {
+    if let Some(home) = sources.env_home.filter(|v| !v.is_empty()) {
+        return Ok((PathBuf::from(home), StorageMode::Env));
+    }
+
+    let install_dir = sources.install_dir;
+    if PORTABLE_MARKERS.iter().any(|name| install_dir.join(name).is_file()) {
+        return Ok((install_dir, StorageMode::Portable));
+    }
+
+    if let Some(path) = sources.location {
+        // 位置不可用（如移动硬盘未连接）时不能悄悄在那里新建一个空数据库
+        if !path.is_dir() {
+            let reset_hint = location_file()
+                .map(|file| format!("；如需改回默认位置，删除 {} 后重新启动", file.display()))
+                .unwrap_or_default();
+            return Err(format!("数据目录 {} 不存在，请确认所在的磁盘已连接后重新启动{}", path.display(), reset_hint));
+        }
+        return Ok((path, StorageMode::Custom));
+    }
+
+    if has_install_dir_data(&install_dir) {
+        return Ok((install_dir, StorageMode::InstallDir));
+    }
+
+    let user_dir = sources
+        .user_data_dir
+        .ok_or_else(|| "无法获取用户数据目录".to_string())?
+        .join(APP_DIR_NAME);
+    Ok((user_dir, StorageMode::User))
+}
+
+fn resolve_root_dir() -> Result<(PathBuf, StorageMode), String> {
+    choose_root_dir(RootSources {
+        env_home: std::env::var_os(HOME_ENV),
+        install_dir: install_dir()?,
+        location: read_location_file(),
+        user_data_dir: dirs::data_dir(),
+    })
+}
+
 /// 初始化存储配置
 pub fn init_storage() -> Result<&'static StorageConfig, String> {
     let config = StorageConfig::new()?;
@@ -110,9 +267,10 @@ pub fn init_storage() -> Result<&'static StorageConfig, String> {
 
     let _ = STORAGE_CONFIG.set(config);
 
-    log::info!("存储初始化完成，数据目录: {:?}", STORAGE_CONFIG.get().unwrap().data_dir);
+    let config = STORAGE_CONFIG.get().unwrap();
+    log::info!("存储初始化完成，数据目录: {:?}（{:?}）", config.data_dir, config.mode);
 
-    Ok(STORAGE_CONFIG.get().unwrap())
+    Ok(config)
 }
 
 /// 获取存储配置
@@ -125,3 +283,73 @@ pub fn get_storage_config() -> Result<&'static StorageConfig, String> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn sources(install_dir: &Path) -> RootSources {
+        RootSources {
+            env_home: None,
+            install_dir: install_dir.to_path_buf(),
+            location: None,
+            user_data_dir: Some(PathBuf::from("/home/user/.local/share")),
+        }
+    }
+
+    #[test]
+    fn resolves_root_dir_in_priority_order() {
+        let install = TempDir::new("install");
+        let user_dir = PathBuf::from("/home/user/.local/share/codeshelf");
+        assert_eq!(choose_root_dir(sources(install.path())).unwrap(), (user_dir, StorageMode::User));
+
+        // 旧版本的安装目录数据优先于用户目录
+        install.write("data/editors.json", "[]");
+        let (root, mode) = choose_root_dir(sources(install.path())).unwrap();
+        assert_eq!((root.as_path(), mode), (install.path(), StorageMode::InstallDir));
+
+        // 迁移后的位置优先于安装目录数据
+        let moved_dir = TempDir::new("moved");
+        let moved = moved_dir.path().to_path_buf();
+        let with_location = RootSources { location: Some(moved.clone()), ..sources(install.path()) };
+        assert_eq!(choose_root_dir(with_location).unwrap(), (moved.clone(), StorageMode::Custom));
+
+        // 便携标记优先于位置文件
+        install.write(".portable", "");
+        let with_location = RootSources { location: Some(moved), ..sources(install.path()) };
+        let (root, mode) = choose_root_dir(with_location).unwrap();
+        assert_eq!((root.as_path(), mode), (install.path(), StorageMode::Portable));
+
+        // 环境变量优先于一切，空值忽略
+        let with_env = RootSources { env_home: Some(OsString::from("/opt/shelf")), ..sources(install.path()) };
+        assert_eq!(choose_root_dir(with_env).unwrap(), (PathBuf::from("/opt/shelf"), StorageMode::Env));
+        let empty_env = RootSources { env_home: Some(OsString::new()), ..sources(install.path()) };
+        assert_eq!(choose_root_dir(empty_env).unwrap().1, StorageMode::Portable);
+    }
+
+    #[test]
+    fn refuses_a_missing_relocated_dir() {
+        let install = TempDir::new("install-missing");
+        install.write("data/editors.json", "[]");
+        let missing = install.path().join("unplugged/codeshelf");
+        let with_location = RootSources { location: Some(missing.clone()), ..sources(install.path()) };
+        let error = choose_root_dir(with_location).unwrap_err();
+        assert!(error.contains(&missing.display().to_string()), "{}", error);
+        assert!(!missing.exists());
+    }
+
+    #[test]
+    fn detects_any_legacy_document_in_install_dir() {
+        let install = TempDir::new("legacy");
+        assert!(!has_install_dir_data(install.path()));
+        install.write("data/unrelated.txt", "");
+        assert!(!has_install_dir_data(install.path()));
+        install.write("data/claude_profiles_wsl.json", "[]");
+        assert!(has_install_dir_data(install.path()));
+
+        let install = TempDir::new("legacy-rules");
+        install.write("data/forward_rules.json", "[]");
+        assert!(has_install_dir_data(install.path()));
+    }
+}
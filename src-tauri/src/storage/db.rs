@@ -0,0 +1,226 @@
+// SQLite 数据库 - 连接管理、表结构与事务
+
+use once_cell::sync::Lazy;
+use rusqlite::{Connection, Transaction, TransactionBehavior};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::atomic;
+use super::backup;
+use super::config::get_storage_config;
+use super::legacy;
+
+/// 全局数据库连接（首次使用时打开）
+///
+/// 注意：连接锁不可重入，`with_connection` / `with_transaction` 的闭包内
+/// 只能使用传入的连接，不能再次调用这两个函数。
+static CONNECTION: Lazy<Mutex<Option<Connection>>> = Lazy::new(|| Mutex::new(None));
+
+/// 数据目录已迁移到的新位置（迁移后到重启前禁止继续写入旧位置）
+static RELOCATED_TO: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+/// 初始表结构（结构版本 v1）
+///
+/// - projects: 项目，按 position 保持用户的排列顺序
+/// - settings: 用户设置（标签、分类、编辑器、终端、应用设置等），按 key 存储 JSON
+/// - toolbox_rules: 工具箱规则（转发规则、静态服务、下载任务、Netcat 会话），按 kind + id 存储
+/// - caches: 可重建的缓存（统计缓存、Claude 安装信息），按 key 存储 JSON
+/// - meta: 存储层自身的元数据（如旧版 JSON 是否已导入）
+const SCHEMA_V1: &str = "
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS projects (
+    id TEXT PRIMARY KEY,
+    path TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_projects_position ON projects(position);
+CREATE TABLE IF NOT EXISTS settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS toolbox_rules (
+    kind TEXT NOT NULL,
+    id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (kind, id)
+);
+CREATE TABLE IF NOT EXISTS caches (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+";
+
+/// 结构版本 v2：每条文档记录自身的数据版本（见 `migration`），已有数据视为 v0
+const SCHEMA_V2: &str = "
+ALTER TABLE projects ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE settings ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE toolbox_rules ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE caches ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+";
+
+/// 表结构迁移，第 i 条把结构版本（PRAGMA user_version）从 v{i} 升级到 v{i+1}
+const SCHEMA_MIGRATIONS: &[&str] = &[SCHEMA_V1, SCHEMA_V2];
+
+/// 读取表结构版本，数据库来自更新版本的程序时报错
+pub(super) fn schema_version(conn: &Connection, path: &Path) -> Result<usize, String> {
+    let version: usize = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| format!("读取数据库版本失败: {}", e))?;
+
+    if version > SCHEMA_MIGRATIONS.len() {
+        return Err(format!(
+            "数据库 {:?} 的结构版本为 v{}，高于当前程序支持的 v{}，可能由更新版本的 CodeShelf 创建，请升级程序后再打开",
+            path,
+            version,
+            SCHEMA_MIGRATIONS.len()
+        ));
+    }
+    Ok(version)
+}
+
+/// 把表结构升级到最新版本
+fn migrate_schema(conn: &mut Connection, path: &Path) -> Result<(), String> {
+    let version = schema_version(conn, path)?;
+
+    for (from, sql) in SCHEMA_MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| format!("开启事务失败: {}", e))?;
+        tx.execute_batch(sql)
+            .and_then(|_| tx.pragma_update(None, "user_version", from + 1))
+            .map_err(|e| format!("升级数据库结构 v{} -> v{} 失败: {}", from, from + 1, e))?;
+        tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+        log::info!("数据库结构已升级到 v{}", from + 1);
+    }
+
+    Ok(())
+}
+
+/// 打开数据库并确保表结构存在
+fn open_connection() -> Result<Connection, String> {
+    if let Some(target) = RELOCATED_TO.lock().map_err(|e| e.to_string())?.as_ref() {
+        return Err(format!("数据目录已迁移到 {:?}，请重启应用后继续使用", target));
+    }
+
+    let config = get_storage_config()?;
+    config.ensure_dirs()?;
+
+    let path = config.database_file();
+    let mut conn = Connection::open(&path)
+        .map_err(|e| format!("打开数据库失败 {:?}: {}", path, e))?;
+
+    // WAL + FULL 同步：写入要么完整提交，要么完全不生效
+    conn.execute_batch(
+        "PRAGMA journal_mode = WAL;
+         PRAGMA synchronous = FULL;
+         PRAGMA busy_timeout = 5000;",
+    )
+    .map_err(|e| format!("设置数据库参数失败: {}", e))?;
+
+    migrate_schema(&mut conn, &path)?;
+
+    log::info!("数据库已打开: {:?}", path);
+    Ok(conn)
+}
+
+/// 使用数据库连接执行操作
+pub fn with_connection<T>(f: impl FnOnce(&Connection) -> Result<T, String>) -> Result<T, String> {
+    let mut guard = CONNECTION.lock().map_err(|e| e.to_string())?;
+    if guard.is_none() {
+        *guard = Some(open_connection()?);
+    }
+
+    match guard.as_ref() {
+        Some(conn) => f(conn),
+        None => Err("数据库未打开".to_string()),
+    }
+}
+
+/// 在事务中执行操作：闭包返回 Err 时整体回滚，返回 Ok 时提交
+pub fn with_transaction<T>(f: impl FnOnce(&Transaction) -> Result<T, String>) -> Result<T, String> {
+    let mut guard = CONNECTION.lock().map_err(|e| e.to_string())?;
+    if guard.is_none() {
+        *guard = Some(open_connection()?);
+    }
+
+    let conn = guard.as_mut().ok_or_else(|| "数据库未打开".to_string())?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("开启事务失败: {}", e))?;
+
+    // 闭包出错时 tx 被 drop，自动回滚
+    let result = f(&tx)?;
+
+    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(result)
+}
+
+/// 用另一个数据库文件替换当前数据库（恢复备份时使用）
+///
+/// 先关闭全局连接（WAL 内容会在关闭时写回主文件），再原子地覆盖数据库文件，
+/// 下次访问时自动重新打开。
+pub(super) fn replace_database_file(src: &Path) -> Result<(), String> {
+    let config = get_storage_config()?;
+    let path = config.database_file();
+
+    let mut guard = CONNECTION.lock().map_err(|e| e.to_string())?;
+    if let Some(conn) = guard.take() {
+        conn.close().map_err(|(_, e)| format!("关闭数据库失败: {}", e))?;
+    }
+
+    // 关闭后残留的 WAL 文件属于旧数据库，必须在替换前删除
+    for suffix in ["-wal", "-shm"] {
+        let mut side = path.clone().into_os_string();
+        side.push(suffix);
+        let side = PathBuf::from(side);
+        if side.exists() {
+            fs::remove_file(&side).map_err(|e| format!("删除 {:?} 失败: {}", side, e))?;
+        }
+    }
+
+    atomic::copy_atomic(src, &path)?;
+    log::info!("数据库已替换为 {:?}", src);
+    Ok(())
+}
+
+/// 关闭数据库并禁止重新打开，用于迁移数据目录
+///
+/// `relocated_to` 为 None 时撤销禁止（迁移失败时调用）
+pub(super) fn set_relocated(relocated_to: Option<PathBuf>) -> Result<(), String> {
+    let mut guard = CONNECTION.lock().map_err(|e| e.to_string())?;
+    if relocated_to.is_some() {
+        if let Some(conn) = guard.take() {
+            conn.close().map_err(|(_, e)| format!("关闭数据库失败: {}", e))?;
+        }
+    }
+    *RELOCATED_TO.lock().map_err(|e| e.to_string())? = relocated_to;
+    Ok(())
+}
+
+/// 初始化数据库（应用启动时调用）
+/// 打开数据库，并在首次运行时导入旧版 JSON 数据文件
+pub fn init_database() -> Result<(), String> {
+    let config = get_storage_config()?;
+    let report = with_transaction(|tx| legacy::import_legacy_json(tx, config))?;
+
+    if report.imported_files > 0 {
+        log::info!("已从旧版 JSON 导入 {} 个数据文件", report.imported_files);
+    }
+    if !report.archivable.is_empty() {
+        legacy::archive_imported_files(config, &report);
+    }
+
+    // 每次启动保留一份快照，备份失败不影响启动
+    if let Err(e) = backup::create_backup(backup::REASON_STARTUP) {
+        log::warn!("创建启动备份失败: {}", e);
+    }
+    Ok(())
+}
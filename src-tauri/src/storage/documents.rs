@@ -0,0 +1,82 @@
+// 文档清单 - 所有持久化文档在数据库中的位置，以及对应的 JSON 文件名
+//
+// 旧版数据文件和工作区归档使用同样的 JSON 文件名，导入时都按这里的清单分发到数据库。
+
+use serde_json::Value;
+use std::path::PathBuf;
+
+use super::config::StorageConfig;
+use super::migration::{self, PROJECT_DOCUMENT};
+use super::repository::{keys, RuleKind};
+
+/// Claude 配置档案的文件名前缀（按环境区分：claude_profiles_<env>.json）
+pub const CLAUDE_PROFILES_PREFIX: &str = "claude_profiles_";
+
+/// 文档在数据库中的位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DocumentTarget {
+    Projects,
+    Setting(String),
+    Cache(&'static str),
+    Rules(RuleKind),
+}
+
+impl DocumentTarget {
+    /// 迁移注册表中的文档名
+    pub fn document_name(&self) -> &str {
+        match self {
+            DocumentTarget::Projects => PROJECT_DOCUMENT,
+            DocumentTarget::Setting(key) => migration::document_name(key),
+            DocumentTarget::Cache(key) => key,
+            DocumentTarget::Rules(kind) => kind.as_str(),
+        }
+    }
+
+    /// 是否是可重建的缓存（不随工作区导出）
+    pub fn is_cache(&self) -> bool {
+        matches!(self, DocumentTarget::Cache(_))
+    }
+}
+
+/// 固定文件名的文档（Claude 配置档案按环境动态生成，不在此列）
+pub fn json_documents(config: &StorageConfig) -> Vec<(PathBuf, DocumentTarget)> {
+    vec![
+        (config.projects_file(), DocumentTarget::Projects),
+        (config.labels_file(), DocumentTarget::Setting(keys::LABELS.to_string())),
+        (config.categories_file(), DocumentTarget::Setting(keys::CATEGORIES.to_string())),
+        (config.editors_file(), DocumentTarget::Setting(keys::EDITORS.to_string())),
+        (config.terminal_file(), DocumentTarget::Setting(keys::TERMINAL.to_string())),
+        (config.app_settings_file(), DocumentTarget::Setting(keys::APP_SETTINGS.to_string())),
+        (config.ui_state_file(), DocumentTarget::Setting(keys::UI_STATE.to_string())),
+        (config.notifications_file(), DocumentTarget::Setting(keys::NOTIFICATIONS.to_string())),
+        (config.claude_quick_configs_file(), DocumentTarget::Setting(keys::CLAUDE_QUICK_CONFIGS.to_string())),
+        (config.stats_cache_file(), DocumentTarget::Cache(keys::STATS_CACHE)),
+        (config.claude_installations_cache_file(), DocumentTarget::Cache(keys::CLAUDE_INSTALLATIONS)),
+        (config.download_tasks_file(), DocumentTarget::Rules(RuleKind::DownloadTask)),
+        (config.forward_rules_file(), DocumentTarget::Rules(RuleKind::ForwardRule)),
+        (config.server_configs_file(), DocumentTarget::Rules(RuleKind::ServerConfig)),
+        (config.netcat_sessions_file(), DocumentTarget::Rules(RuleKind::NetcatSession)),
+    ]
+}
+
+/// 从文件名识别 Claude 配置档案，返回对应的设置项
+pub fn claude_profiles_target(file_name: &str) -> Option<DocumentTarget> {
+    let suffix = file_name
+        .strip_prefix(CLAUDE_PROFILES_PREFIX)?
+        .strip_suffix(".json")?;
+    Some(DocumentTarget::Setting(keys::claude_profiles(suffix)))
+}
+
+/// Claude 配置档案设置项对应的文件名
+pub fn claude_profiles_file_name(key: &str) -> Option<String> {
+    key.strip_prefix(CLAUDE_PROFILES_PREFIX)
+        .map(|suffix| format!("{}{}.json", CLAUDE_PROFILES_PREFIX, suffix))
+}
+
+/// 项目和规则文档的内容是数组，每个元素单独升级
+pub fn into_array(value: Value) -> Result<Vec<Value>, String> {
+    match value {
+        Value::Array(items) => Ok(items),
+        _ => Err("内容不是数组".to_string()),
+    }
+}
@@ -0,0 +1,158 @@
+// 旧版 JSON 数据导入 - 首次启动时把 data 目录下的 JSON 文件导入 SQLite
+//
+// 导入成功的文件会被移动到 data/legacy_json，解析失败的文件保留原位并在下次启动时重试。
+// 目标位置已有数据时跳过对应文件，避免覆盖用户在数据库中的新修改。
+// 旧版文件没有版本号，按 v0 处理，经迁移升级到当前版本后再写入数据库。
+
+use rusqlite::Connection;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::config::StorageConfig;
+use super::documents::{claude_profiles_target, into_array, json_documents, DocumentTarget};
+use super::migration::{self, LEGACY_VERSION};
+use super::repository::{
+    CacheRepository, JsonRecord, MetaRepository, ProjectRepository, RuleRepository, SettingsRepository,
+};
+use super::schema::Project;
+
+/// 元数据键：旧版 JSON 是否已全部导入
+const META_LEGACY_IMPORTED: &str = "legacy_json_imported";
+
+/// 导入结果
+#[derive(Debug, Default)]
+pub struct LegacyImportReport {
+    pub imported_files: usize,
+    /// 已导入（或因目标已有数据而跳过）的文件，可以归档
+    pub archivable: Vec<PathBuf>,
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// 列出所有旧版数据文件及其导入目标
+fn legacy_files(config: &StorageConfig) -> Vec<(PathBuf, DocumentTarget)> {
+    let mut files = json_documents(config);
+
+    if let Ok(entries) = fs::read_dir(&config.data_dir) {
+        for entry in entries.flatten() {
+            if let Some(target) = claude_profiles_target(&entry.file_name().to_string_lossy()) {
+                files.push((entry.path(), target));
+            }
+        }
+    }
+
+    files
+}
+
+/// 导入单个文件，返回是否实际写入了数据
+fn import_file(conn: &Connection, path: &Path, target: &DocumentTarget) -> Result<bool, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("读取失败: {}", e))?;
+    let value: Value = serde_json::from_str(&content).map_err(|e| format!("解析失败: {}", e))?;
+    let location = format!("旧版数据文件 {:?}", path);
+    let document = target.document_name();
+
+    match target {
+        DocumentTarget::Projects => {
+            let repo = ProjectRepository::new(conn);
+            if repo.count()? > 0 {
+                return Ok(false);
+            }
+            let projects = into_array(value)?
+                .into_iter()
+                .map(|item| {
+                    let item = migration::upgrade(document, &location, LEGACY_VERSION, item)?;
+                    serde_json::from_value::<Project>(item).map_err(|e| format!("解析项目失败: {}", e))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            repo.replace_all(&projects)?;
+        }
+        DocumentTarget::Setting(key) => {
+            let repo = SettingsRepository::new(conn);
+            if repo.contains(key)? {
+                return Ok(false);
+            }
+            let value = migration::upgrade(document, &location, LEGACY_VERSION, value)?;
+            repo.set(key, &value)?;
+        }
+        DocumentTarget::Cache(key) => {
+            let repo = CacheRepository::new(conn);
+            if repo.contains(key)? {
+                return Ok(false);
+            }
+            let value = migration::upgrade(document, &location, LEGACY_VERSION, value)?;
+            repo.set(key, &value)?;
+        }
+        DocumentTarget::Rules(kind) => {
+            let repo = RuleRepository::new(conn);
+            if repo.count(*kind)? > 0 {
+                return Ok(false);
+            }
+            let items = into_array(value)?
+                .into_iter()
+                .map(|item| migration::upgrade(document, &location, LEGACY_VERSION, item))
+                .collect::<Result<Vec<_>, String>>()?;
+            let (records, skipped) = JsonRecord::from_values(items);
+            if skipped > 0 {
+                log::warn!("{} 中有 {} 条记录缺少 id，未导入", location, skipped);
+            }
+            repo.replace_all(*kind, &records)?;
+        }
+    }
+
+    Ok(true)
+}
+
+/// 导入旧版 JSON 数据文件（已全部导入过则直接返回）
+pub fn import_legacy_json(conn: &Connection, config: &StorageConfig) -> Result<LegacyImportReport, String> {
+    let meta = MetaRepository::new(conn);
+    let mut report = LegacyImportReport::default();
+
+    if meta.get(META_LEGACY_IMPORTED)?.is_some() {
+        return Ok(report);
+    }
+
+    for (path, target) in legacy_files(config) {
+        if !path.exists() {
+            continue;
+        }
+
+        match import_file(conn, &path, &target) {
+            Ok(imported) => {
+                if imported {
+                    report.imported_files += 1;
+                    log::info!("已导入旧版数据文件: {:?}", path);
+                } else {
+                    log::info!("数据库中已有数据，跳过旧版文件: {:?}", path);
+                }
+                report.archivable.push(path);
+            }
+            Err(e) => {
+                log::error!("导入旧版数据文件失败 {:?}: {}", path, e);
+                report.failed.push((path, e));
+            }
+        }
+    }
+
+    if report.failed.is_empty() {
+        meta.set(META_LEGACY_IMPORTED, &super::schema::current_iso_time())?;
+    }
+
+    Ok(report)
+}
+
+/// 把已导入的旧版文件移动到归档目录，避免被误认为仍在使用
+pub fn archive_imported_files(config: &StorageConfig, report: &LegacyImportReport) {
+    let archive_dir = config.legacy_archive_dir();
+    if let Err(e) = fs::create_dir_all(&archive_dir) {
+        log::warn!("创建旧版数据归档目录失败: {}", e);
+        return;
+    }
+
+    for path in &report.archivable {
+        if let Some(name) = path.file_name() {
+            if let Err(e) = fs::rename(path, archive_dir.join(name)) {
+                log::warn!("归档旧版数据文件失败 {:?}: {}", path, e);
+            }
+        }
+    }
+}
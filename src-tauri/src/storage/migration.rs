@@ -0,0 +1,187 @@
+// 数据迁移 - 持久化文档的版本号与逐步升级
+//
+// 数据库中每条文档（项目、设置项、缓存项、工具箱规则）都带有 version 列，
+// 读取时按注册表从旧版本逐步升级到当前版本，写入时总是写当前版本。
+// 版本高于当前程序时直接报错，避免旧程序把新数据当成默认值覆盖掉。
+//
+// 修改持久化结构（新增/重命名字段等）时，在对应文档的迁移列表末尾追加一步即可，
+// 当前版本号等于迁移步数，会自动加一。
+
+use serde_json::{Map, Value};
+
+use super::schema::{DEFAULT_BACKUP_KEEP_COUNT, DEFAULT_SCAN_EXCLUDES};
+
+/// 一步迁移：把第 N 版数据升级为第 N+1 版
+type MigrationStep = fn(Value) -> Result<Value, String>;
+
+/// 旧版无版本号 JSON 文件对应的版本
+pub const LEGACY_VERSION: u32 = 0;
+
+/// 项目文档名（设置、缓存用各自的键，工具箱规则用规则类型作为文档名）
+pub const PROJECT_DOCUMENT: &str = "project";
+
+/// 各文档的迁移列表，第 i 步把 v{i} 升级到 v{i+1}
+fn steps(document: &str) -> &'static [MigrationStep] {
+    match document {
+        // 早期版本用 snake_case 字段名保存，之前靠 #[serde(alias)] 兼容
+        "forward_rule" | "server_config" | "download_task" => &[v1_snake_case_to_camel_case],
+        "app_settings" => &[v1_initial, v2_app_settings_backup_keep_count, v3_app_settings_scan_excludes],
+        PROJECT_DOCUMENT => &[v1_initial, v2_project_portable_path, v3_project_stack, v4_project_scripts],
+        _ => &[v1_initial],
+    }
+}
+
+/// 设置/缓存键对应的文档名（按环境区分的键共用同一套迁移）
+pub fn document_name(key: &str) -> &str {
+    if key.starts_with("claude_profiles_") {
+        "claude_profiles"
+    } else {
+        key
+    }
+}
+
+/// 文档的当前版本
+pub fn current_version(document: &str) -> u32 {
+    steps(document).len() as u32
+}
+
+/// 把文档从 `version` 逐步升级到当前版本
+///
+/// `location` 用于错误信息，说明数据来自哪里（如 "数据库 xxx.db 中的 settings/app_settings"）
+pub fn upgrade(document: &str, location: &str, version: u32, mut value: Value) -> Result<Value, String> {
+    let steps = steps(document);
+    let current = steps.len() as u32;
+
+    if version > current {
+        return Err(format!(
+            "{} 的数据版本为 v{}，高于当前程序支持的 v{}，可能由更新版本的 CodeShelf 写入，请升级程序后再打开",
+            location, version, current
+        ));
+    }
+
+    for (from, step) in steps.iter().enumerate().skip(version as usize) {
+        value = step(value)
+            .map_err(|e| format!("{} 从 v{} 升级到 v{} 失败: {}", location, from, from + 1, e))?;
+    }
+
+    Ok(value)
+}
+
+// ============== 迁移步骤 ==============
+
+fn into_object(value: Value) -> Result<Map<String, Value>, String> {
+    match value {
+        Value::Object(object) => Ok(object),
+        _ => Err("数据不是对象".to_string()),
+    }
+}
+
+/// v0 -> v1：结构未变，只是开始记录版本号
+fn v1_initial(value: Value) -> Result<Value, String> {
+    Ok(value)
+}
+
+/// v0 -> v1：顶层 snake_case 字段改为 camelCase（已有 camelCase 字段时保留后者）
+fn v1_snake_case_to_camel_case(value: Value) -> Result<Value, String> {
+    let object = into_object(value)?;
+
+    let mut migrated = Map::new();
+    for (key, field) in object {
+        let camel = snake_to_camel(&key);
+        if camel != key && migrated.contains_key(&camel) {
+            continue;
+        }
+        migrated.insert(camel, field);
+    }
+    Ok(Value::Object(migrated))
+}
+
+/// app_settings v1 -> v2：新增备份保留份数
+fn v2_app_settings_backup_keep_count(value: Value) -> Result<Value, String> {
+    let mut object = into_object(value)?;
+    object
+        .entry("backup_keep_count")
+        .or_insert_with(|| Value::from(DEFAULT_BACKUP_KEEP_COUNT));
+    Ok(Value::Object(object))
+}
+
+/// app_settings v2 -> v3：新增扫描排除目录，使用默认列表
+fn v3_app_settings_scan_excludes(value: Value) -> Result<Value, String> {
+    let mut object = into_object(value)?;
+    object
+        .entry("scan_excludes")
+        .or_insert_with(|| Value::from(DEFAULT_SCAN_EXCLUDES.to_vec()));
+    Ok(Value::Object(object))
+}
+
+/// project v1 -> v2：新增可移植路径，加载时按本机的路径根目录补全
+fn v2_project_portable_path(value: Value) -> Result<Value, String> {
+    let mut object = into_object(value)?;
+    object.entry("portablePath").or_insert(Value::Null);
+    Ok(Value::Object(object))
+}
+
+/// project v2 -> v3：新增技术栈，加载后由识别命令补全
+fn v3_project_stack(value: Value) -> Result<Value, String> {
+    let mut object = into_object(value)?;
+    object.entry("stack").or_insert(Value::Null);
+    Ok(Value::Object(object))
+}
+
+/// project v3 -> v4：新增运行脚本
+fn v4_project_scripts(value: Value) -> Result<Value, String> {
+    let mut object = into_object(value)?;
+    object.entry("scripts").or_insert_with(|| Value::Array(Vec::new()));
+    Ok(Value::Object(object))
+}
+
+fn snake_to_camel(key: &str) -> String {
+    let mut result = String::with_capacity(key.len());
+    let mut upper = false;
+    for c in key.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            result.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn upgrades_legacy_snake_case_rule() {
+        let legacy = json!({ "id": "1", "local_port": 8080, "remote_host": "a", "createdAt": "t" });
+        let value = upgrade("forward_rule", "test", LEGACY_VERSION, legacy).unwrap();
+        assert_eq!(value, json!({ "id": "1", "localPort": 8080, "remoteHost": "a", "createdAt": "t" }));
+    }
+
+    #[test]
+    fn camel_case_field_wins_over_snake_case() {
+        let legacy = json!({ "localPort": 1, "local_port": 2 });
+        let value = upgrade("forward_rule", "test", LEGACY_VERSION, legacy).unwrap();
+        assert_eq!(value, json!({ "localPort": 1 }));
+    }
+
+    #[test]
+    fn current_version_is_unchanged() {
+        let value = json!({ "theme": "dark" });
+        let current = current_version("app_settings");
+        assert_eq!(upgrade("app_settings", "test", current, value.clone()).unwrap(), value);
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        let current = current_version(PROJECT_DOCUMENT);
+        let err = upgrade(PROJECT_DOCUMENT, "projects/abc", current + 1, json!({})).unwrap_err();
+        assert!(err.contains("projects/abc"));
+        assert!(err.contains(&format!("v{}", current + 1)));
+    }
+}
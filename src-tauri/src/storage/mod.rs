@@ -1,7 +1,22 @@
-// 存储模块 - 简洁版本，无迁移逻辑
+// 存储模块 - SQLite 数据库 + 类型化数据仓库
 
+pub mod atomic;
+pub mod backup;
 pub mod config;
+pub mod db;
+pub mod documents;
+mod legacy;
+pub mod migration;
+pub mod paths;
+pub mod relocation;
+pub mod repository;
 pub mod schema;
+pub mod workspace;
 
 pub use config::{get_storage_config, init_storage};
+pub use db::{init_database, with_connection, with_transaction};
+pub use repository::{
+    keys, load_cache, load_setting, remove_cache, save_cache, save_setting, ProjectRepository,
+    Record, RuleKind, RuleRepository,
+};
 pub use schema::*;
@@ -0,0 +1,234 @@
+// 项目路径 - 路径根目录（${WORK} 等）与可移植路径、路径比较
+//
+// 项目在数据库中同时保存本机绝对路径 path 和相对根目录的 portable_path（如 ${WORK}/codeshelf）。
+// 根目录映射是本机设置：同一份书架在另一台电脑上只要把 ${WORK} 指向对应目录，
+// 加载时就能从 portable_path 还原出正确的本机路径。
+
+use std::path::{Path, PathBuf};
+
+use super::schema::{PathRoot, Project};
+
+/// Windows 和 macOS 的文件系统默认不区分大小写，比较路径时忽略大小写
+const CASE_INSENSITIVE: bool = cfg!(any(windows, target_os = "macos"));
+
+/// 展开开头的 ~ 为用户主目录
+pub fn expand_home(path: &str) -> String {
+    let rest = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => rest,
+        _ => return path.to_string(),
+    };
+    match dirs::home_dir() {
+        Some(home) => format!("{}{}", home.to_string_lossy(), rest),
+        None => path.to_string(),
+    }
+}
+
+/// 是否为绝对路径，同时识别 Windows 的盘符路径（`D:\code`、`D:/code`）和 UNC 路径，
+/// 这样在 macOS/Linux 上也能填写从 Windows 迁移过来的原路径
+pub fn is_absolute_path(path: &str) -> bool {
+    let path = expand_home(path.trim());
+    let bytes = path.as_bytes();
+    let windows_drive = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'/' | b'\\');
+    windows_drive || path.starts_with(['/', '\\']) || Path::new(&path).is_absolute()
+}
+
+/// 拆分路径为各级名称（统一分隔符，去掉空段和 "."），绝对路径的首段为空字符串
+fn components(path: &str) -> Vec<&str> {
+    let path = path.trim();
+    let mut parts: Vec<&str> = path
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect();
+    if path.starts_with(['/', '\\']) {
+        parts.insert(0, "");
+    }
+    parts
+}
+
+fn same_component(a: &str, b: &str) -> bool {
+    if CASE_INSENSITIVE {
+        a.to_lowercase() == b.to_lowercase()
+    } else {
+        a == b
+    }
+}
+
+/// 用于比较和去重的路径键：统一分隔符，去掉末尾分隔符，不区分大小写的平台上转为小写
+///
+/// `D:\code\app\`、`d:/code/app` 在 Windows 上得到同一个键
+pub fn path_key(path: &str) -> String {
+    let key = components(&expand_home(path)).join("/");
+    let key = if key.is_empty() && path.trim().starts_with(['/', '\\']) { "/".to_string() } else { key };
+    if CASE_INSENSITIVE {
+        key.to_lowercase()
+    } else {
+        key
+    }
+}
+
+/// 两个路径是否指向同一位置（只比较字符串，不访问文件系统）
+pub fn same_path(a: &str, b: &str) -> bool {
+    path_key(a) == path_key(b)
+}
+
+/// `path` 在 `prefix` 之下时返回剩余的各级名称（按整段比较，/code 不是 /codeshelf 的前缀）
+pub fn strip_path_prefix<'a>(path: &'a str, prefix: &str) -> Option<Vec<&'a str>> {
+    let path_parts = components(path);
+    let prefix = expand_home(prefix);
+    let prefix_parts = components(&prefix);
+
+    if prefix_parts.len() > path_parts.len()
+        || !prefix_parts.iter().zip(&path_parts).all(|(a, b)| same_component(a, b))
+    {
+        return None;
+    }
+    Some(path_parts[prefix_parts.len()..].to_vec())
+}
+
+/// 把 `base` 和剩余的各级名称拼成本机路径
+pub fn join_components(base: &str, rest: &[&str]) -> String {
+    rest.iter()
+        .fold(PathBuf::from(expand_home(base)), |path, part| path.join(part))
+        .to_string_lossy()
+        .to_string()
+}
+
+/// 拆分可移植路径为根目录名和剩余部分，如 ${WORK}/a/b -> ("WORK", "a/b")
+pub fn parse_portable(portable: &str) -> Option<(&str, &str)> {
+    let (name, rest) = portable.strip_prefix("${")?.split_once('}')?;
+    Some((name, rest.trim_start_matches(['/', '\\'])))
+}
+
+/// 按最长匹配的根目录生成可移植路径，不在任何根目录下时返回 None
+pub fn to_portable(path: &str, roots: &[PathRoot]) -> Option<String> {
+    roots
+        .iter()
+        .filter_map(|root| {
+            let rest = strip_path_prefix(path, &root.path)?;
+            Some((components(&expand_home(&root.path)).len(), root, rest))
+        })
+        .max_by_key(|(depth, _, _)| *depth)
+        .map(|(_, root, rest)| {
+            if rest.is_empty() {
+                format!("${{{}}}", root.name)
+            } else {
+                format!("${{{}}}/{}", root.name, rest.join("/"))
+            }
+        })
+}
+
+/// 用本机的根目录还原可移植路径，根目录未定义时返回 None
+pub fn resolve_portable(portable: &str, roots: &[PathRoot]) -> Option<String> {
+    let (name, rest) = parse_portable(portable)?;
+    let root = roots.iter().find(|root| root.name == name)?;
+    Some(join_components(&root.path, &components(rest)))
+}
+
+/// 校验根目录：名称只能包含字母、数字和下划线且不能重复，目录必须是绝对路径
+pub fn validate_roots(roots: &[PathRoot]) -> Result<(), String> {
+    for (index, root) in roots.iter().enumerate() {
+        let valid_name = !root.name.is_empty()
+            && !root.name.starts_with(|c: char| c.is_ascii_digit())
+            && root.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_name {
+            return Err(format!("根目录名称无效: {}（只能包含字母、数字和下划线，且不能以数字开头）", root.name));
+        }
+        if roots[..index].iter().any(|other| other.name == root.name) {
+            return Err(format!("根目录名称重复: {}", root.name));
+        }
+        if !Path::new(&expand_home(&root.path)).is_absolute() {
+            return Err(format!("根目录 {} 必须是绝对路径: {}", root.name, root.path));
+        }
+    }
+    Ok(())
+}
+
+/// 让项目的 path 与 portable_path 和本机根目录保持一致，返回有变化的项目下标
+///
+/// 有可移植路径且根目录已定义时以它为准更新 path；否则按 path 补全可移植路径
+pub fn bind_project_paths(projects: &mut [Project], roots: &[PathRoot]) -> Vec<usize> {
+    let mut changed = Vec::new();
+    for (index, project) in projects.iter_mut().enumerate() {
+        let resolved = project
+            .portable_path
+            .as_deref()
+            .and_then(|portable| resolve_portable(portable, roots));
+
+        match resolved {
+            Some(path) => {
+                if !same_path(&path, &project.path) {
+                    project.path = path;
+                    changed.push(index);
+                }
+            }
+            None => {
+                // 根目录未在本机定义时保留原有的可移植路径，定义后即可还原
+                if project.portable_path.as_deref().and_then(parse_portable).is_some() {
+                    continue;
+                }
+                let portable = to_portable(&project.path, roots);
+                if portable != project.portable_path {
+                    project.portable_path = portable;
+                    changed.push(index);
+                }
+            }
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roots() -> Vec<PathRoot> {
+        vec![
+            PathRoot { name: "WORK".to_string(), path: "/home/dev/code".to_string() },
+            PathRoot { name: "OSS".to_string(), path: "/home/dev/code/oss".to_string() },
+        ]
+    }
+
+    #[test]
+    fn path_key_ignores_separators_and_trailing_slash() {
+        assert_eq!(path_key("/home/dev/code/"), path_key("/home/dev//code"));
+        assert_eq!(path_key("C:\\code\\app\\"), path_key("C:/code/app"));
+        assert_eq!(path_key("/"), "/");
+    }
+
+    #[test]
+    fn recognizes_absolute_paths_of_any_platform() {
+        assert!(is_absolute_path("/home/dev/code"));
+        assert!(is_absolute_path("D:\\code"));
+        assert!(is_absolute_path("d:/code"));
+        assert!(is_absolute_path("\\\\server\\share"));
+        assert!(!is_absolute_path(""));
+        assert!(!is_absolute_path("   "));
+        assert!(!is_absolute_path("code/app"));
+        assert!(!is_absolute_path("D:code"));
+    }
+
+    #[test]
+    fn portable_path_uses_longest_root() {
+        assert_eq!(to_portable("/home/dev/code/app", &roots()).as_deref(), Some("${WORK}/app"));
+        assert_eq!(to_portable("/home/dev/code/oss/tauri", &roots()).as_deref(), Some("${OSS}/tauri"));
+        assert_eq!(to_portable("/home/dev/codeshelf", &roots()), None);
+    }
+
+    #[test]
+    fn portable_path_round_trips_to_another_root() {
+        let other = vec![PathRoot { name: "WORK".to_string(), path: "/Users/dev/src".to_string() }];
+        let resolved = resolve_portable("${WORK}/app/web", &other).unwrap();
+        assert!(same_path(&resolved, "/Users/dev/src/app/web"));
+        assert_eq!(resolve_portable("${MISSING}/app", &other), None);
+    }
+
+    #[test]
+    fn rejects_invalid_root_names() {
+        let bad = vec![PathRoot { name: "MY-ROOT".to_string(), path: "/tmp".to_string() }];
+        assert!(validate_roots(&bad).is_err());
+        assert!(validate_roots(&roots()).is_ok());
+    }
+}
@@ -0,0 +1,135 @@
+// 数据目录迁移 - 把数据目录复制到新位置，校验后记录新位置，重启后生效
+//
+// 原目录保留不删除，确认新位置正常后可以手动清理。
+
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use super::atomic;
+use super::config::{get_storage_config, location_file, StorageConfig, StorageMode};
+use super::db;
+
+/// 迁移结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RelocationReport {
+    pub from: String,
+    pub to: String,
+    pub files: usize,
+    pub bytes: u64,
+    /// 需要重启应用才能使用新位置
+    pub restart_required: bool,
+}
+
+/// 递归复制目录，返回复制的文件列表（相对路径）和总字节数
+fn copy_dir(src: &Path, dest: &Path, relative: &Path, files: &mut Vec<PathBuf>) -> Result<u64, String> {
+    fs::create_dir_all(dest).map_err(|e| format!("创建目录失败 {:?}: {}", dest, e))?;
+
+    let mut bytes = 0;
+    let entries = fs::read_dir(src).map_err(|e| format!("读取目录失败 {:?}: {}", src, e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("读取目录失败 {:?}: {}", src, e))?;
+        let file_type = entry.file_type().map_err(|e| format!("读取文件类型失败: {}", e))?;
+        let name = entry.file_name();
+        let relative = relative.join(&name);
+
+        if file_type.is_dir() {
+            bytes += copy_dir(&entry.path(), &dest.join(&name), &relative, files)?;
+        } else if file_type.is_file() {
+            bytes += fs::copy(entry.path(), dest.join(&name))
+                .map_err(|e| format!("复制文件失败 {:?}: {}", relative, e))?;
+            File::open(dest.join(&name))
+                .and_then(|f| f.sync_all())
+                .map_err(|e| format!("同步文件失败 {:?}: {}", relative, e))?;
+            files.push(relative);
+        }
+    }
+    Ok(bytes)
+}
+
+/// 逐字节比较两个文件
+fn same_content(a: &Path, b: &Path) -> Result<bool, String> {
+    let open = |p: &Path| File::open(p).map(BufReader::new).map_err(|e| format!("打开文件失败 {:?}: {}", p, e));
+    let (mut a, mut b) = (open(a)?, open(b)?);
+    let (mut buf_a, mut buf_b) = ([0u8; 8192], [0u8; 8192]);
+
+    loop {
+        let n = a.read(&mut buf_a).map_err(|e| format!("读取文件失败: {}", e))?;
+        if n == 0 {
+            // a 已读完，b 也必须读完
+            return Ok(b.read(&mut buf_b).map_err(|e| format!("读取文件失败: {}", e))? == 0);
+        }
+        if b.read_exact(&mut buf_b[..n]).is_err() || buf_a[..n] != buf_b[..n] {
+            return Ok(false);
+        }
+    }
+}
+
+/// 校验复制结果与原目录一致
+fn verify_copy(src: &Path, dest: &Path, files: &[PathBuf]) -> Result<(), String> {
+    for relative in files {
+        if !same_content(&src.join(relative), &dest.join(relative))? {
+            return Err(format!("校验失败，文件内容不一致: {:?}", relative));
+        }
+    }
+    Ok(())
+}
+
+/// 把数据目录迁移到 `target_root/data`，成功后需要重启应用
+pub fn move_data_directory(target_root: &Path) -> Result<RelocationReport, String> {
+    let config = get_storage_config()?;
+    if !config.is_relocatable() {
+        return Err(match config.mode {
+            StorageMode::Env => format!("数据目录由环境变量 {} 指定，请修改环境变量", super::config::HOME_ENV),
+            _ => "便携模式下数据目录固定在程序目录，无法迁移".to_string(),
+        });
+    }
+
+    if !target_root.is_absolute() {
+        return Err("请选择绝对路径".to_string());
+    }
+
+    let target = StorageConfig::with_root(target_root.to_path_buf(), StorageMode::Custom);
+    if target.data_dir.starts_with(&config.data_dir) || config.data_dir.starts_with(&target.data_dir) {
+        return Err("新位置不能与当前数据目录重叠".to_string());
+    }
+    if target.data_dir.exists()
+        && fs::read_dir(&target.data_dir).map(|mut d| d.next().is_some()).unwrap_or(true)
+    {
+        return Err(format!("目标位置已存在数据目录: {:?}", target.data_dir));
+    }
+
+    // 关闭数据库（WAL 内容写回主文件），迁移期间及之后都不再写入旧位置
+    db::set_relocated(Some(target_root.to_path_buf()))?;
+
+    let result = (|| {
+        let mut files = Vec::new();
+        let bytes = copy_dir(&config.data_dir, &target.data_dir, Path::new(""), &mut files)?;
+        verify_copy(&config.data_dir, &target.data_dir, &files)?;
+
+        let location = location_file().ok_or_else(|| "无法获取用户配置目录".to_string())?;
+        atomic::write_atomic(&location, target_root.to_string_lossy().as_bytes())?;
+        Ok((files.len(), bytes))
+    })();
+
+    match result {
+        Ok((files, bytes)) => {
+            log::info!("数据目录已迁移: {:?} -> {:?}（{} 个文件）", config.data_dir, target.data_dir, files);
+            Ok(RelocationReport {
+                from: config.data_dir.to_string_lossy().to_string(),
+                to: target.data_dir.to_string_lossy().to_string(),
+                files,
+                bytes,
+                restart_required: true,
+            })
+        }
+        Err(e) => {
+            log::error!("迁移数据目录失败: {}", e);
+            let _ = fs::remove_dir_all(&target.data_dir);
+            db::set_relocated(None)?;
+            Err(e)
+        }
+    }
+}
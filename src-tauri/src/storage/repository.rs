@@ -0,0 +1,508 @@
+// 数据仓库 - 项目、设置、工具箱规则、缓存的类型化读写
+//
+// 所有仓库都借用一个连接（或事务），需要原子性的多步写入时
+// 在 `db::with_transaction` 中构造仓库即可。
+//
+// 每条文档都带有 version 列：写入时记录当前版本，读取时经 `migration::upgrade`
+// 升级到当前版本后再反序列化。
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+use super::migration::{self, PROJECT_DOCUMENT};
+use super::schema::{current_iso_time, Project};
+
+/// 可按 ID 存储的记录
+pub trait Record: Serialize {
+    fn record_id(&self) -> &str;
+}
+
+impl Record for Project {
+    fn record_id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// 原样保存的 JSON 记录（只提取 id 字段），用于导入等不关心具体类型的场景
+pub struct JsonRecord {
+    pub id: String,
+    pub value: Value,
+}
+
+impl JsonRecord {
+    /// 从带 id 字段的对象构造，没有 id 时返回 None
+    pub fn from_value(value: Value) -> Option<Self> {
+        let id = value.get("id").and_then(Value::as_str)?.to_string();
+        Some(Self { id, value })
+    }
+
+    /// 从对象数组构造，返回记录和因缺少 id 被跳过的数量
+    pub fn from_values(values: Vec<Value>) -> (Vec<Self>, usize) {
+        let total = values.len();
+        let records: Vec<Self> = values.into_iter().filter_map(Self::from_value).collect();
+        let skipped = total - records.len();
+        (records, skipped)
+    }
+}
+
+impl Serialize for JsonRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+impl Record for JsonRecord {
+    fn record_id(&self) -> &str {
+        &self.id
+    }
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T, what: &str) -> Result<String, String> {
+    serde_json::to_string(value).map_err(|e| format!("序列化{}失败: {}", what, e))
+}
+
+/// 解析一条文档并升级到当前版本
+///
+/// `location` 形如 "settings/app_settings"，出错时连同数据库路径一起写入错误信息
+fn decode<T: DeserializeOwned>(
+    conn: &Connection,
+    document: &str,
+    location: &str,
+    content: &str,
+    version: u32,
+) -> Result<T, String> {
+    let location = format!("数据库 {} 中的 {}", conn.path().unwrap_or(":memory:"), location);
+    let value: Value =
+        serde_json::from_str(content).map_err(|e| format!("解析{}失败: {}", location, e))?;
+    let value = migration::upgrade(document, &location, version, value)?;
+    serde_json::from_value(value).map_err(|e| format!("解析{}失败: {}", location, e))
+}
+
+// ============== 项目 ==============
+
+pub struct ProjectRepository<'c> {
+    conn: &'c Connection,
+}
+
+impl<'c> ProjectRepository<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    /// 按用户排列顺序列出所有项目
+    pub fn list(&self) -> Result<Vec<Project>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, data, version FROM projects ORDER BY position, rowid")
+            .map_err(|e| format!("查询项目失败: {}", e))?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, u32>(2)?))
+            })
+            .map_err(|e| format!("查询项目失败: {}", e))?;
+
+        let mut projects = Vec::new();
+        for row in rows {
+            let (id, content, version) = row.map_err(|e| format!("读取项目失败: {}", e))?;
+            let location = format!("projects/{}", id);
+            projects.push(decode(self.conn, PROJECT_DOCUMENT, &location, &content, version)?);
+        }
+        Ok(projects)
+    }
+
+    /// 新增或更新项目，已存在的项目保持原有位置，新项目追加到末尾
+    pub fn upsert(&self, project: &Project) -> Result<(), String> {
+        let data = to_json(project, "项目")?;
+        self.conn
+            .execute(
+                "INSERT INTO projects (id, path, position, data, version)
+                 VALUES (?1, ?2, (SELECT COALESCE(MAX(position), -1) + 1 FROM projects), ?3, ?4)
+                 ON CONFLICT(id) DO UPDATE SET
+                     path = excluded.path, data = excluded.data, version = excluded.version",
+                params![project.id, project.path, data, migration::current_version(PROJECT_DOCUMENT)],
+            )
+            .map_err(|e| format!("保存项目失败: {}", e))?;
+        Ok(())
+    }
+
+    pub fn count(&self) -> Result<usize, String> {
+        self.conn
+            .query_row("SELECT COUNT(*) FROM projects", [], |row| row.get::<_, i64>(0))
+            .map(|n| n as usize)
+            .map_err(|e| format!("统计项目失败: {}", e))
+    }
+
+    pub fn upsert_many(&self, projects: &[Project]) -> Result<(), String> {
+        for project in projects {
+            self.upsert(project)?;
+        }
+        Ok(())
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        self.conn
+            .execute("DELETE FROM projects WHERE id = ?1", params![id])
+            .map_err(|e| format!("删除项目失败: {}", e))?;
+        Ok(())
+    }
+
+    pub fn delete_many(&self, ids: &[String]) -> Result<(), String> {
+        for id in ids {
+            self.delete(id)?;
+        }
+        Ok(())
+    }
+
+    /// 用给定列表整体替换所有项目（顺序即列表顺序）
+    pub fn replace_all(&self, projects: &[Project]) -> Result<(), String> {
+        self.conn
+            .execute("DELETE FROM projects", [])
+            .map_err(|e| format!("清空项目失败: {}", e))?;
+
+        for (position, project) in projects.iter().enumerate() {
+            let data = to_json(project, "项目")?;
+            self.conn
+                .execute(
+                    "INSERT INTO projects (id, path, position, data, version) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        project.id,
+                        project.path,
+                        position as i64,
+                        data,
+                        migration::current_version(PROJECT_DOCUMENT)
+                    ],
+                )
+                .map_err(|e| format!("保存项目失败: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+// ============== 设置与缓存（键值表） ==============
+
+/// 设置项的键
+pub mod keys {
+    pub const LABELS: &str = "labels";
+    pub const CATEGORIES: &str = "categories";
+    pub const EDITORS: &str = "editors";
+    pub const TERMINAL: &str = "terminal";
+    pub const APP_SETTINGS: &str = "app_settings";
+    pub const UI_STATE: &str = "ui_state";
+    pub const NOTIFICATIONS: &str = "notifications";
+    pub const CLAUDE_QUICK_CONFIGS: &str = "claude_quick_configs";
+    /// 路径根目录（本机设置，不随工作区导出）
+    pub const PATH_ROOTS: &str = "path_roots";
+    /// 监听目录（本机设置，不随工作区导出）
+    pub const WATCHED_ROOTS: &str = "watched_roots";
+    /// 项目环境变量（本机设置，含敏感值，不随工作区导出）
+    pub const PROJECT_ENV: &str = "project_env";
+    /// 项目模板（本机设置，本地模板指向本机目录，不随工作区导出）
+    pub const PROJECT_TEMPLATES: &str = "project_templates";
+    /// 定时 fetch 设置（本机设置，不随工作区导出）
+    pub const FETCH_SCHEDULE: &str = "fetch_schedule";
+
+    /// Claude 配置档案（按环境区分）
+    pub fn claude_profiles(env_suffix: &str) -> String {
+        format!("claude_profiles_{}", env_suffix)
+    }
+
+    // 缓存
+    pub const STATS_CACHE: &str = "stats_cache";
+    pub const CLAUDE_INSTALLATIONS: &str = "claude_installations";
+    pub const PROJECT_HEALTH: &str = "project_health";
+    pub const TASK_RUNS: &str = "task_runs";
+    pub const REMOTE_STATUS: &str = "remote_status";
+    pub const DISCARDED_CHANGES: &str = "discarded_changes";
+}
+
+/// 键值表的通用读写
+struct KeyValueTable<'c> {
+    conn: &'c Connection,
+    table: &'static str,
+}
+
+impl KeyValueTable<'_> {
+    fn get_raw(&self, key: &str) -> Result<Option<(String, u32)>, String> {
+        self.conn
+            .query_row(
+                &format!("SELECT value, version FROM {} WHERE key = ?1", self.table),
+                params![key],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, u32>(1)?)),
+            )
+            .optional()
+            .map_err(|e| format!("读取 {} 失败: {}", key, e))
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String> {
+        match self.get_raw(key)? {
+            Some((content, version)) => {
+                let location = format!("{}/{}", self.table, key);
+                decode(self.conn, migration::document_name(key), &location, &content, version).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn set_raw(&self, key: &str, value: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                &format!(
+                    "INSERT INTO {} (key, value, version, updated_at) VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT(key) DO UPDATE SET
+                         value = excluded.value, version = excluded.version, updated_at = excluded.updated_at",
+                    self.table
+                ),
+                params![
+                    key,
+                    value,
+                    migration::current_version(migration::document_name(key)),
+                    current_iso_time()
+                ],
+            )
+            .map_err(|e| format!("保存 {} 失败: {}", key, e))?;
+        Ok(())
+    }
+
+    fn set<T: Serialize + ?Sized>(&self, key: &str, value: &T) -> Result<(), String> {
+        self.set_raw(key, &to_json(value, key)?)
+    }
+
+    fn contains(&self, key: &str) -> Result<bool, String> {
+        Ok(self.get_raw(key)?.is_some())
+    }
+
+    fn keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT key FROM {} WHERE substr(key, 1, ?1) = ?2 ORDER BY key", self.table))
+            .map_err(|e| format!("查询 {} 失败: {}", self.table, e))?;
+        let rows = stmt
+            .query_map(params![prefix.len() as i64, prefix], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("查询 {} 失败: {}", self.table, e))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("读取 {} 失败: {}", self.table, e))
+    }
+
+    fn remove(&self, key: &str) -> Result<(), String> {
+        self.conn
+            .execute(&format!("DELETE FROM {} WHERE key = ?1", self.table), params![key])
+            .map_err(|e| format!("删除 {} 失败: {}", key, e))?;
+        Ok(())
+    }
+}
+
+/// 用户设置
+pub struct SettingsRepository<'c> {
+    table: KeyValueTable<'c>,
+}
+
+impl<'c> SettingsRepository<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { table: KeyValueTable { conn, table: "settings" } }
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String> {
+        self.table.get(key)
+    }
+
+    pub fn set<T: Serialize + ?Sized>(&self, key: &str, value: &T) -> Result<(), String> {
+        self.table.set(key, value)
+    }
+
+    pub fn contains(&self, key: &str) -> Result<bool, String> {
+        self.table.contains(key)
+    }
+
+    /// 以 `prefix` 开头的所有键（如按环境区分的 Claude 配置档案）
+    pub fn keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>, String> {
+        self.table.keys_with_prefix(prefix)
+    }
+}
+
+/// 可重建的缓存数据
+pub struct CacheRepository<'c> {
+    table: KeyValueTable<'c>,
+}
+
+impl<'c> CacheRepository<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { table: KeyValueTable { conn, table: "caches" } }
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String> {
+        self.table.get(key)
+    }
+
+    pub fn set<T: Serialize + ?Sized>(&self, key: &str, value: &T) -> Result<(), String> {
+        self.table.set(key, value)
+    }
+
+    pub fn contains(&self, key: &str) -> Result<bool, String> {
+        self.table.contains(key)
+    }
+
+    pub fn remove(&self, key: &str) -> Result<(), String> {
+        self.table.remove(key)
+    }
+}
+
+// ============== 工具箱规则 ==============
+
+/// 工具箱规则类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleKind {
+    ForwardRule,
+    ServerConfig,
+    DownloadTask,
+    NetcatSession,
+}
+
+impl RuleKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleKind::ForwardRule => "forward_rule",
+            RuleKind::ServerConfig => "server_config",
+            RuleKind::DownloadTask => "download_task",
+            RuleKind::NetcatSession => "netcat_session",
+        }
+    }
+}
+
+pub struct RuleRepository<'c> {
+    conn: &'c Connection,
+}
+
+impl<'c> RuleRepository<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    pub fn list<T: DeserializeOwned>(&self, kind: RuleKind) -> Result<Vec<T>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, data, version FROM toolbox_rules WHERE kind = ?1 ORDER BY position, rowid")
+            .map_err(|e| format!("查询 {} 失败: {}", kind.as_str(), e))?;
+
+        let rows = stmt
+            .query_map(params![kind.as_str()], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, u32>(2)?))
+            })
+            .map_err(|e| format!("查询 {} 失败: {}", kind.as_str(), e))?;
+
+        let mut items = Vec::new();
+        for row in rows {
+            let (id, content, version) =
+                row.map_err(|e| format!("读取 {} 失败: {}", kind.as_str(), e))?;
+            let location = format!("toolbox_rules/{}/{}", kind.as_str(), id);
+            items.push(decode(self.conn, kind.as_str(), &location, &content, version)?);
+        }
+        Ok(items)
+    }
+
+    pub fn count(&self, kind: RuleKind) -> Result<usize, String> {
+        self.conn
+            .query_row(
+                "SELECT COUNT(*) FROM toolbox_rules WHERE kind = ?1",
+                params![kind.as_str()],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|n| n as usize)
+            .map_err(|e| format!("统计 {} 失败: {}", kind.as_str(), e))
+    }
+
+    /// 用给定列表整体替换某一类规则
+    pub fn replace_all<'a, T: Record + 'a>(
+        &self,
+        kind: RuleKind,
+        items: impl IntoIterator<Item = &'a T>,
+    ) -> Result<(), String> {
+        self.conn
+            .execute("DELETE FROM toolbox_rules WHERE kind = ?1", params![kind.as_str()])
+            .map_err(|e| format!("清空 {} 失败: {}", kind.as_str(), e))?;
+
+        let version = migration::current_version(kind.as_str());
+        for (position, item) in items.into_iter().enumerate() {
+            let data = to_json(item, kind.as_str())?;
+            self.conn
+                .execute(
+                    "INSERT INTO toolbox_rules (kind, id, position, data, version) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![kind.as_str(), item.record_id(), position as i64, data, version],
+                )
+                .map_err(|e| format!("保存 {} 失败: {}", kind.as_str(), e))?;
+        }
+        Ok(())
+    }
+}
+
+// ============== 元数据 ==============
+
+pub struct MetaRepository<'c> {
+    conn: &'c Connection,
+}
+
+impl<'c> MetaRepository<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<String>, String> {
+        self.conn
+            .query_row("SELECT value FROM meta WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("读取元数据 {} 失败: {}", key, e))
+    }
+
+    pub fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT INTO meta (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                params![key, value],
+            )
+            .map_err(|e| format!("保存元数据 {} 失败: {}", key, e))?;
+        Ok(())
+    }
+}
+
+// ============== 便捷函数（单条读写，使用全局连接） ==============
+
+/// 读取设置项，不存在时返回 None
+pub fn load_setting<T: DeserializeOwned>(key: &str) -> Result<Option<T>, String> {
+    super::db::with_connection(|conn| SettingsRepository::new(conn).get(key))
+}
+
+/// 保存设置项
+pub fn save_setting<T: Serialize + ?Sized>(key: &str, value: &T) -> Result<(), String> {
+    super::db::with_connection(|conn| SettingsRepository::new(conn).set(key, value))
+}
+
+/// 读取缓存，不存在时返回 None
+pub fn load_cache<T: DeserializeOwned>(key: &str) -> Result<Option<T>, String> {
+    super::db::with_connection(|conn| CacheRepository::new(conn).get(key))
+}
+
+/// 保存缓存
+pub fn save_cache<T: Serialize + ?Sized>(key: &str, value: &T) -> Result<(), String> {
+    super::db::with_connection(|conn| CacheRepository::new(conn).set(key, value))
+}
+
+/// 删除缓存
+pub fn remove_cache(key: &str) -> Result<(), String> {
+    super::db::with_connection(|conn| CacheRepository::new(conn).remove(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_records_without_id() {
+        let values = vec![serde_json::json!({"id": "a"}), serde_json::json!({"name": "no id"}), serde_json::json!({"id": 1})];
+        let (records, skipped) = JsonRecord::from_values(values);
+        assert_eq!(records.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["a"]);
+        assert_eq!(skipped, 2);
+    }
+}
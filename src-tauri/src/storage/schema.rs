@@ -1,4 +1,7 @@
-// 数据结构定义 - 简洁的数据格式，无版本包装
+// 数据结构定义 - 持久化数据的当前版本结构
+//
+// 数据库中的每条文档都记录了版本号，旧版本数据在读取时由 `migration` 逐步升级。
+// 修改这里的持久化结构时，需要在 `migration::steps` 中为对应文档追加一步迁移。
 
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +20,161 @@ pub struct Project {
     pub created_at: String,
     pub updated_at: String,
     pub last_opened: Option<String>,
+    /// 相对路径根目录记录的路径（如 ${WORK}/codeshelf），不在任何根目录下时为 None
+    pub portable_path: Option<String>,
+    /// 根据项目清单文件识别的技术栈，未识别时为 None
+    pub stack: Option<ProjectStack>,
+    /// 项目的运行脚本（dev、test、build 等），在项目目录下执行
+    pub scripts: Vec<ProjectScript>,
+}
+
+/// 项目运行脚本
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectScript {
+    pub name: String,
+    /// 交给系统 shell 执行的命令行（Windows 为 cmd /C，其他系统为 sh -c）
+    pub command: String,
+}
+
+/// 项目技术栈
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStack {
+    /// 主要语言：rust、javascript、typescript、java、kotlin、go、python
+    pub language: String,
+    /// 识别到的框架，如 tauri、react、spring-boot
+    pub framework: Option<String>,
+    /// 清单文件中声明的项目名
+    pub declared_name: Option<String>,
+    pub version: Option<String>,
+    /// 识别依据的清单文件名，如 Cargo.toml
+    pub manifest: String,
+}
+
+// ============== 项目环境变量数据 ==============
+
+/// 项目的环境变量配置（保存在 project_env 设置项中，按项目 ID 索引）
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectEnv {
+    /// 当前使用的配置名，打开编辑器、终端和运行脚本时注入
+    pub active: Option<String>,
+    pub profiles: Vec<EnvProfile>,
+}
+
+/// 一组命名的环境变量（如 local、staging、test）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvProfile {
+    pub name: String,
+    pub vars: Vec<EnvVar>,
+    /// 关联的 .env 文件名（项目根目录下），可从中读取或写回
+    pub file: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvVar {
+    pub key: String,
+    pub value: String,
+    /// 敏感值：界面上不显示，也不会写入日志
+    pub secret: bool,
+}
+
+// ============== 路径根目录数据 ==============
+
+/// 路径根目录：名称（在项目路径中写作 ${NAME}）到本机目录的映射
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PathRoot {
+    pub name: String,
+    pub path: String,
+}
+
+// ============== 监听目录数据 ==============
+
+/// 监听目录：目录下新出现的 Git 仓库会被自动发现
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchedRoot {
+    pub id: String,
+    pub path: String,
+    /// 仓库目录在监听目录下的最大层数（1 表示只发现直接子目录）
+    pub depth: u32,
+    /// 发现后直接导入，否则作为待导入项交给用户确认
+    pub auto_import: bool,
+    /// 自动导入时使用的分类和标签
+    pub category: Option<String>,
+    pub labels: Vec<String>,
+}
+
+// ============== 项目模板数据 ==============
+
+/// 项目模板：新建项目时复制或克隆的骨架
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub source: TemplateSource,
+    pub created_at: String,
+}
+
+/// 模板来源
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TemplateSource {
+    /// 本机的骨架目录
+    Local { path: String },
+    /// Git 仓库，可指定分支、标签或提交
+    Git {
+        url: String,
+        #[serde(rename = "ref")]
+        reference: Option<String>,
+    },
+}
+
+// ============== 定时获取远程更新 ==============
+
+/// 后台定时 fetch 的设置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchSchedule {
+    pub enabled: bool,
+    pub interval_minutes: u32,
+    /// 要检查的项目，为空时检查所有项目
+    pub project_ids: Vec<String>,
+    /// 免打扰时段内不执行 fetch
+    pub quiet_hours: Option<QuietHours>,
+    /// 当前分支落后远程时发送通知
+    pub notify_behind: bool,
+    /// 远程出现新标签时发送通知
+    pub notify_tags: bool,
+}
+
+/// 默认每 30 分钟检查一次
+pub const DEFAULT_FETCH_INTERVAL_MINUTES: u32 = 30;
+
+impl Default for FetchSchedule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: DEFAULT_FETCH_INTERVAL_MINUTES,
+            project_ids: Vec::new(),
+            quiet_hours: None,
+            notify_behind: true,
+            notify_tags: true,
+        }
+    }
+}
+
+/// 免打扰时段（本地时间 HH:MM，结束早于开始时表示跨过午夜）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
 }
 
 // ============== 编辑器配置数据 ==============
@@ -50,8 +208,27 @@ pub struct AppSettings {
     pub view_mode: String,
     pub sidebar_collapsed: bool,
     pub scan_depth: u32,
+    /// 自动备份保留份数
+    pub backup_keep_count: u32,
+    /// 扫描仓库时跳过的目录（gitignore 风格的 glob）
+    pub scan_excludes: Vec<String>,
 }
 
+/// 默认保留的备份份数
+pub const DEFAULT_BACKUP_KEEP_COUNT: u32 = 10;
+
+/// 默认跳过的依赖和构建产物目录，这些目录通常很大且不会包含需要管理的仓库
+pub const DEFAULT_SCAN_EXCLUDES: &[&str] = &[
+    "node_modules",
+    "target",
+    "vendor",
+    "bower_components",
+    "__pycache__",
+    "venv",
+    "dist",
+    "build",
+];
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
@@ -59,6 +236,8 @@ impl Default for AppSettings {
             view_mode: "grid".to_string(),
             sidebar_collapsed: false,
             scan_depth: 3,
+            backup_keep_count: DEFAULT_BACKUP_KEEP_COUNT,
+            scan_excludes: DEFAULT_SCAN_EXCLUDES.iter().map(|s| s.to_string()).collect(),
         }
     }
 }
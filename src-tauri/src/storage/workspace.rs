@@ -0,0 +1,511 @@
+// 工作区导入导出 - 把全部用户数据打包成一个归档，交给新同事或新电脑一次性导入
+//
+// 归档是 zip 文件：manifest.json 记录归档格式版本和文档清单，其余每个文档一个 JSON 文件，
+// 文件名与旧版数据文件相同，内容为 { "schemaVersion": N, "data": ... }。
+// 导入时按各文档的 schemaVersion 经迁移升级，旧版本程序导出的归档也能导入。
+// 缓存可以重建，不导出。
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::atomic;
+use super::backup::{self, BackupInfo};
+use super::config::{get_storage_config, StorageConfig};
+use super::db;
+use super::documents::{
+    claude_profiles_file_name, claude_profiles_target, into_array, json_documents, DocumentTarget,
+    CLAUDE_PROFILES_PREFIX,
+};
+use super::migration;
+use super::paths;
+use super::repository::{keys, JsonRecord, ProjectRepository, RuleRepository, SettingsRepository};
+use super::schema::{current_iso_time, generate_id, PathRoot, Project};
+
+/// 归档格式标识
+pub const FORMAT_NAME: &str = "codeshelf-workspace";
+/// 归档格式版本（manifest 结构或文件布局变化时递增）
+pub const FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// 归档清单
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceManifest {
+    pub format: String,
+    pub format_version: u32,
+    pub app_version: String,
+    pub exported_at: String,
+    pub documents: Vec<ManifestEntry>,
+}
+
+/// 清单中的一个文档
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub file: String,
+    pub document: String,
+    pub schema_version: u32,
+    /// 数组文档（项目、规则）的记录数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+}
+
+/// 归档中单个文档文件的内容
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DocumentFile {
+    schema_version: u32,
+    data: Value,
+}
+
+/// 导入方式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportMode {
+    /// 与现有数据合并
+    Merge,
+    /// 用归档中的文档整体替换现有文档（归档中没有的文档保持不变）
+    Replace,
+}
+
+/// 合并时遇到冲突（项目按路径、规则按 id 判断）的处理方式
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictStrategy {
+    #[default]
+    KeepExisting,
+    UseImported,
+}
+
+/// 导出结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceExportReport {
+    pub path: String,
+    pub documents: usize,
+    pub projects: usize,
+    pub size: u64,
+}
+
+/// 单个文档的导入结果
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentImportResult {
+    pub file: String,
+    pub added: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+/// 导入结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceImportReport {
+    pub app_version: String,
+    pub exported_at: String,
+    pub documents: Vec<DocumentImportResult>,
+    /// 导入前自动创建的备份，可用于撤销导入
+    pub backup: BackupInfo,
+}
+
+/// 要导出的文档：固定文档（缓存除外）加上数据库中各环境的 Claude 配置档案
+fn exportable_documents(
+    conn: &Connection,
+    config: &StorageConfig,
+) -> Result<Vec<(String, DocumentTarget)>, String> {
+    let mut documents: Vec<(String, DocumentTarget)> = json_documents(config)
+        .into_iter()
+        .filter(|(_, target)| !target.is_cache())
+        .filter_map(|(path, target)| Some((path.file_name()?.to_string_lossy().to_string(), target)))
+        .collect();
+
+    for key in SettingsRepository::new(conn).keys_with_prefix(CLAUDE_PROFILES_PREFIX)? {
+        if let Some(file) = claude_profiles_file_name(&key) {
+            documents.push((file, DocumentTarget::Setting(key)));
+        }
+    }
+    Ok(documents)
+}
+
+/// 读取一个文档的当前内容，不存在时返回 None
+fn read_document(conn: &Connection, target: &DocumentTarget) -> Result<Option<Value>, String> {
+    let to_value = |v| serde_json::to_value(v).map_err(|e| format!("序列化失败: {}", e));
+    match target {
+        DocumentTarget::Projects => to_value(ProjectRepository::new(conn).list()?).map(Some),
+        DocumentTarget::Setting(key) => SettingsRepository::new(conn).get(key),
+        DocumentTarget::Cache(_) => Ok(None),
+        DocumentTarget::Rules(kind) => Ok(Some(Value::Array(RuleRepository::new(conn).list(*kind)?))),
+    }
+}
+
+/// 把整个工作区导出为归档文件
+pub fn export_workspace(path: &Path) -> Result<WorkspaceExportReport, String> {
+    let config = get_storage_config()?;
+
+    let (manifest, files) = db::with_connection(|conn| {
+        let mut entries = Vec::new();
+        let mut files = Vec::new();
+        for (file, target) in exportable_documents(conn, config)? {
+            let data = match read_document(conn, &target)? {
+                Some(data) => data,
+                None => continue,
+            };
+            let document = target.document_name().to_string();
+            let schema_version = migration::current_version(&document);
+            let content = serde_json::to_vec_pretty(&DocumentFile { schema_version, data: data.clone() })
+                .map_err(|e| format!("序列化 {} 失败: {}", file, e))?;
+
+            entries.push(ManifestEntry {
+                file: file.clone(),
+                document,
+                schema_version,
+                count: data.as_array().map(Vec::len),
+            });
+            files.push((file, content));
+        }
+
+        let manifest = WorkspaceManifest {
+            format: FORMAT_NAME.to_string(),
+            format_version: FORMAT_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: current_iso_time(),
+            documents: entries,
+        };
+        Ok((manifest, files))
+    })?;
+
+    let manifest_content =
+        serde_json::to_vec_pretty(&manifest).map_err(|e| format!("序列化归档清单失败: {}", e))?;
+
+    atomic::write_atomic_with(path, |file| {
+        let mut zip = ZipWriter::new(file);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        for (name, content) in std::iter::once((MANIFEST_FILE, &manifest_content))
+            .chain(files.iter().map(|(name, content)| (name.as_str(), content)))
+        {
+            zip.start_file(name, options).map_err(io::Error::other)?;
+            zip.write_all(content)?;
+        }
+        zip.finish().map_err(io::Error::other)?;
+        Ok(())
+    })?;
+
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let projects = manifest
+        .documents
+        .iter()
+        .find(|entry| entry.document == migration::PROJECT_DOCUMENT)
+        .and_then(|entry| entry.count)
+        .unwrap_or(0);
+    log::info!("已导出工作区: {:?}（{} 个文档）", path, manifest.documents.len());
+
+    Ok(WorkspaceExportReport {
+        path: path.to_string_lossy().to_string(),
+        documents: manifest.documents.len(),
+        projects,
+        size,
+    })
+}
+
+/// 从归档中解析出的文档（已升级到当前版本）
+struct ImportedDocument {
+    file: String,
+    target: DocumentTarget,
+    data: Value,
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> Result<String, String> {
+    let mut entry = archive
+        .by_name(name)
+        .map_err(|e| format!("归档中缺少 {}: {}", name, e))?;
+    let mut content = String::new();
+    entry
+        .read_to_string(&mut content)
+        .map_err(|e| format!("读取归档中的 {} 失败: {}", name, e))?;
+    Ok(content)
+}
+
+/// 读取并校验归档，所有文档都能解析并升级后才返回，避免导入到一半才发现归档有问题
+fn read_archive(
+    path: &Path,
+    config: &StorageConfig,
+) -> Result<(WorkspaceManifest, Vec<ImportedDocument>), String> {
+    let file = File::open(path).map_err(|e| format!("打开归档失败 {:?}: {}", path, e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("不是有效的归档文件 {:?}: {}", path, e))?;
+
+    let manifest: WorkspaceManifest = serde_json::from_str(&read_entry(&mut archive, MANIFEST_FILE)?)
+        .map_err(|e| format!("解析归档清单失败: {}", e))?;
+    if manifest.format != FORMAT_NAME {
+        return Err(format!("不是 CodeShelf 工作区归档: {:?}", path));
+    }
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(format!(
+            "归档格式版本为 {}，当前程序只支持到 {}，请升级 CodeShelf 后再导入",
+            manifest.format_version, FORMAT_VERSION
+        ));
+    }
+
+    let fixed: Vec<(String, DocumentTarget)> = json_documents(config)
+        .into_iter()
+        .filter_map(|(p, target)| Some((p.file_name()?.to_string_lossy().to_string(), target)))
+        .collect();
+
+    let mut documents = Vec::new();
+    for entry in &manifest.documents {
+        let target = fixed
+            .iter()
+            .find(|(name, _)| *name == entry.file)
+            .map(|(_, target)| target.clone())
+            .or_else(|| claude_profiles_target(&entry.file));
+        let target = match target {
+            Some(target) if !target.is_cache() => target,
+            _ => {
+                log::warn!("跳过归档中无法识别的文件: {}", entry.file);
+                continue;
+            }
+        };
+
+        let file: DocumentFile = serde_json::from_str(&read_entry(&mut archive, &entry.file)?)
+            .map_err(|e| format!("解析归档中的 {} 失败: {}", entry.file, e))?;
+        let location = format!("归档 {:?} 中的 {}", path, entry.file);
+        let document = target.document_name();
+
+        // 项目和规则按记录存储版本，逐条升级；设置整体升级
+        let data = match target {
+            DocumentTarget::Projects | DocumentTarget::Rules(_) => Value::Array(
+                into_array(file.data)
+                    .map_err(|e| format!("{}: {}", location, e))?
+                    .into_iter()
+                    .map(|item| migration::upgrade(document, &location, file.schema_version, item))
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => migration::upgrade(document, &location, file.schema_version, file.data)?,
+        };
+
+        documents.push(ImportedDocument { file: entry.file.clone(), target, data });
+    }
+
+    Ok((manifest, documents))
+}
+
+/// 按 `key` 合并两个列表，冲突时按 `strategy` 处理，`adopt` 决定采用导入记录时如何生成新记录
+fn merge_by_key<T>(
+    existing: Vec<T>,
+    imported: Vec<T>,
+    key: impl Fn(&T) -> String,
+    strategy: ConflictStrategy,
+    result: &mut DocumentImportResult,
+    adopt: impl Fn(&T, T) -> T,
+) -> Vec<T> {
+    let mut merged = existing;
+    for item in imported {
+        match merged.iter().position(|current| key(current) == key(&item)) {
+            Some(index) => match strategy {
+                ConflictStrategy::KeepExisting => result.skipped += 1,
+                ConflictStrategy::UseImported => {
+                    merged[index] = adopt(&merged[index], item);
+                    result.updated += 1;
+                }
+            },
+            None => {
+                merged.push(item);
+                result.added += 1;
+            }
+        }
+    }
+    merged
+}
+
+fn record_id(value: &Value) -> String {
+    value.get("id").and_then(Value::as_str).unwrap_or_default().to_string()
+}
+
+/// 合并设置值：对象数组按 id 合并，其他数组取并集，其余值视为一个整体
+fn merge_setting(
+    existing: Value,
+    imported: Value,
+    strategy: ConflictStrategy,
+    result: &mut DocumentImportResult,
+) -> Value {
+    match (existing, imported) {
+        (Value::Array(existing), Value::Array(imported)) => {
+            let keyed = existing.iter().chain(&imported).all(|item| item.get("id").is_some());
+            if keyed {
+                Value::Array(merge_by_key(existing, imported, record_id, strategy, result, |_, item| item))
+            } else {
+                let mut merged = existing;
+                for item in imported {
+                    if merged.contains(&item) {
+                        result.skipped += 1;
+                    } else {
+                        merged.push(item);
+                        result.added += 1;
+                    }
+                }
+                Value::Array(merged)
+            }
+        }
+        (existing, imported) if existing == imported => {
+            result.skipped += 1;
+            existing
+        }
+        (existing, imported) => match strategy {
+            ConflictStrategy::KeepExisting => {
+                result.skipped += 1;
+                existing
+            }
+            ConflictStrategy::UseImported => {
+                result.updated += 1;
+                imported
+            }
+        },
+    }
+}
+
+/// 合并后只保留第一个默认编辑器
+fn dedupe_default_editor(editors: &mut Value) {
+    let mut seen = false;
+    for editor in editors.as_array_mut().into_iter().flatten() {
+        if editor.get("is_default").and_then(Value::as_bool) == Some(true) {
+            if seen {
+                editor["is_default"] = Value::Bool(false);
+            }
+            seen = true;
+        }
+    }
+}
+
+/// 把一个文档写入数据库
+fn apply_document(
+    conn: &Connection,
+    document: ImportedDocument,
+    mode: ImportMode,
+    strategy: ConflictStrategy,
+) -> Result<DocumentImportResult, String> {
+    let mut result = DocumentImportResult { file: document.file, ..Default::default() };
+
+    match document.target {
+        DocumentTarget::Projects => {
+            let repo = ProjectRepository::new(conn);
+            let mut imported: Vec<Project> = serde_json::from_value(document.data)
+                .map_err(|e| format!("解析 {} 失败: {}", result.file, e))?;
+
+            // 归档来自其他电脑时，按本机的路径根目录还原项目路径后再比较
+            let roots: Vec<PathRoot> = SettingsRepository::new(conn).get(keys::PATH_ROOTS)?.unwrap_or_default();
+            paths::bind_project_paths(&mut imported, &roots);
+
+            let projects = match mode {
+                ImportMode::Replace => {
+                    result.added = imported.len();
+                    imported
+                }
+                ImportMode::Merge => {
+                    let existing = repo.list()?;
+                    let existing_paths: HashSet<String> = existing.iter().map(|p| paths::path_key(&p.path)).collect();
+                    let mut used_ids: HashSet<String> = existing.iter().map(|p| p.id.clone()).collect();
+
+                    // 新项目的 id 与本机其他项目重复时重新分配
+                    let imported = imported
+                        .into_iter()
+                        .map(|mut project| {
+                            if !existing_paths.contains(&paths::path_key(&project.path)) {
+                                while !used_ids.insert(project.id.clone()) {
+                                    project.id = generate_id();
+                                }
+                            }
+                            project
+                        })
+                        .collect();
+
+                    // 同一路径的项目保留本机的 id，其余字段采用归档中的内容
+                    merge_by_key(existing, imported, |p| paths::path_key(&p.path), strategy, &mut result, |current, item| {
+                        Project { id: current.id.clone(), ..item }
+                    })
+                }
+            };
+            repo.replace_all(&projects)?;
+        }
+        DocumentTarget::Setting(key) => {
+            let repo = SettingsRepository::new(conn);
+            let mut value = match (mode, repo.get::<Value>(&key)?) {
+                (ImportMode::Merge, Some(existing)) => merge_setting(existing, document.data, strategy, &mut result),
+                (ImportMode::Replace, Some(_)) => {
+                    result.updated += 1;
+                    document.data
+                }
+                (_, None) => {
+                    result.added += 1;
+                    document.data
+                }
+            };
+            if key == keys::EDITORS {
+                dedupe_default_editor(&mut value);
+            }
+            repo.set(&key, &value)?;
+        }
+        DocumentTarget::Cache(_) => {}
+        DocumentTarget::Rules(kind) => {
+            let repo = RuleRepository::new(conn);
+            let (imported, invalid) = JsonRecord::from_values(into_array(document.data)?);
+            if invalid > 0 {
+                log::warn!("{} 中有 {} 条记录缺少 id，未导入", result.file, invalid);
+            }
+            result.skipped += invalid;
+
+            let records = match mode {
+                ImportMode::Replace => {
+                    result.added = imported.len();
+                    imported
+                }
+                ImportMode::Merge => {
+                    let existing: Vec<JsonRecord> = repo
+                        .list::<Value>(kind)?
+                        .into_iter()
+                        .filter_map(JsonRecord::from_value)
+                        .collect();
+                    merge_by_key(existing, imported, |r| r.id.clone(), strategy, &mut result, |_, item| item)
+                }
+            };
+            repo.replace_all(kind, &records)?;
+        }
+    }
+
+    Ok(result)
+}
+
+/// 从归档导入工作区
+///
+/// 导入前自动创建一份备份；所有文档在同一个事务中写入，任一文档失败则整体回滚
+pub fn import_workspace(
+    path: &Path,
+    mode: ImportMode,
+    strategy: ConflictStrategy,
+) -> Result<WorkspaceImportReport, String> {
+    let config = get_storage_config()?;
+    let (manifest, documents) = read_archive(path, config)?;
+
+    let backup = backup::create_backup(backup::REASON_PRE_IMPORT)?;
+    let results = db::with_transaction(|tx| {
+        documents
+            .into_iter()
+            .map(|document| apply_document(tx, document, mode, strategy))
+            .collect::<Result<Vec<_>, String>>()
+    })?;
+
+    log::info!("已导入工作区: {:?}（{:?}，{} 个文档）", path, mode, results.len());
+
+    Ok(WorkspaceImportReport {
+        app_version: manifest.app_version,
+        exported_at: manifest.exported_at,
+        documents: results,
+        backup,
+    })
+}
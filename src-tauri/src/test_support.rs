@@ -0,0 +1,68 @@
+// 测试辅助 - 临时目录和测试仓库
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// 测试用的临时目录，离开作用域时删除
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("codeshelf-test-{}-{}-{}", name, std::process::id(), id));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).expect("创建临时目录失败");
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// 在临时目录下创建文件（自动创建上级目录）
+    pub fn write(&self, relative: &str, content: &str) -> PathBuf {
+        let path = self.0.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).expect("创建目录失败");
+        }
+        std::fs::write(&path, content).expect("写入文件失败");
+        path
+    }
+
+    /// 在临时目录下创建目录
+    pub fn mkdir(&self, relative: &str) -> PathBuf {
+        let path = self.0.join(relative);
+        std::fs::create_dir_all(&path).expect("创建目录失败");
+        path
+    }
+
+    /// 在临时目录初始化仓库并提交给定的文件（仓库配置中写入提交者，git 命令也能提交）
+    pub fn init_repo(&self, files: &[(&str, &str)]) -> git2::Repository {
+        let repo = git2::Repository::init(&self.0).expect("初始化仓库失败");
+        let mut config = repo.config().expect("读取仓库配置失败");
+        config.set_str("user.name", "Test").expect("写入仓库配置失败");
+        config.set_str("user.email", "test@example.com").expect("写入仓库配置失败");
+
+        let mut index = repo.index().expect("读取暂存区失败");
+        for (file, content) in files {
+            self.write(file, content);
+            index.add_path(Path::new(file)).expect("暂存文件失败");
+        }
+        index.write().expect("写入暂存区失败");
+        let tree_id = index.write_tree().expect("写入树对象失败");
+        {
+            let tree = repo.find_tree(tree_id).expect("读取树对象失败");
+            let signature = git2::Signature::now("Test", "test@example.com").expect("创建签名失败");
+            repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[]).expect("提交失败");
+        }
+        repo
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
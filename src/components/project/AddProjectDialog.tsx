@@ -1,8 +1,7 @@
 import { useState, useEffect } from "react";
 import { open } from "@tauri-apps/plugin-dialog";
-import { invoke } from "@tauri-apps/api/core";
 import { addProject } from "@/services/db";
-import { isGitRepo, gitInit } from "@/services/git";
+import { isGitRepo, gitInit, gitClone } from "@/services/git";
 import { useAppStore } from "@/stores/appStore";
 import type { Project } from "@/types";
 
@@ -164,11 +163,7 @@ export function AddProjectDialog({ onConfirm, onCancel }: AddProjectDialogProps)
           name = match ? match[1] : "Unknown";
         }
 
-        const clonePath = await invoke<string>("git_clone", {
-          url: gitUrl.trim(),
-          targetDir: gitTargetPath,
-          repoName: name,
-        });
+        const clonePath = await gitClone(gitUrl.trim(), gitTargetPath, name);
 
         const project = await addProject({
           name,
@@ -180,7 +175,7 @@ export function AddProjectDialog({ onConfirm, onCancel }: AddProjectDialogProps)
         onConfirm(project);
       }
     } catch (err: any) {
-      setError(err.toString());
+      setError(String(err));
     } finally {
       setLoading(false);
     }
@@ -6,14 +6,46 @@ import type {
   BranchInfo,
   RemoteInfo,
   GitRepo,
+  GitErrorCode,
+  GitErrorPayload,
+  CheckoutResult,
 } from "@/types";
 
+/** Git 命令的错误，String(error) 仍然是可读的提示，code 用于提供对应的处理方式 */
+export class GitCommandError extends Error {
+  code: GitErrorCode;
+  detail?: string;
+
+  constructor(payload: GitErrorPayload) {
+    super(payload.detail ? `${payload.message}: ${payload.detail}` : payload.message);
+    this.name = "GitCommandError";
+    this.code = payload.code;
+    this.detail = payload.detail;
+  }
+
+  toString() {
+    return this.message;
+  }
+}
+
+function isGitErrorPayload(error: unknown): error is GitErrorPayload {
+  return typeof error === "object" && error !== null && "code" in error && "message" in error;
+}
+
+async function invokeGit<T>(command: string, args?: Record<string, unknown>): Promise<T> {
+  try {
+    return await invoke<T>(command, args);
+  } catch (error) {
+    throw isGitErrorPayload(error) ? new GitCommandError(error) : error;
+  }
+}
+
 export async function scanDirectory(path: string, depth?: number): Promise<GitRepo[]> {
   return invoke("scan_directory", { path, depth });
 }
 
 export async function getGitStatus(path: string): Promise<GitStatus> {
-  return invoke("get_git_status", { path });
+  return invokeGit("get_git_status", { path });
 }
 
 export async function getCommitHistory(
@@ -21,21 +53,21 @@ export async function getCommitHistory(
   limit?: number,
   refName?: string
 ): Promise<CommitInfo[]> {
-  return invoke("get_commit_history", { path, limit, refName });
+  return invokeGit("get_commit_history", { path, limit, refName });
 }
 
 export async function getCommitDetail(
   path: string,
   commitHash: string
 ): Promise<CommitInfo> {
-  return invoke("get_commit_detail", { path, commitHash });
+  return invokeGit("get_commit_detail", { path, commitHash });
 }
 
 export async function getCommitFiles(
   path: string,
   commitHash: string
 ): Promise<CommitFileChange[]> {
-  return invoke("get_commit_files", { path, commitHash });
+  return invokeGit("get_commit_files", { path, commitHash });
 }
 
 export async function searchCommits(
@@ -44,15 +76,15 @@ export async function searchCommits(
   searchType?: "author" | "message" | "hash",
   limit?: number
 ): Promise<CommitInfo[]> {
-  return invoke("search_commits", { path, query, searchType, limit });
+  return invokeGit("search_commits", { path, query, searchType, limit });
 }
 
 export async function getBranches(path: string): Promise<BranchInfo[]> {
-  return invoke("get_branches", { path });
+  return invokeGit("get_branches", { path });
 }
 
 export async function getRemotes(path: string): Promise<RemoteInfo[]> {
-  return invoke("get_remotes", { path });
+  return invokeGit("get_remotes", { path });
 }
 
 export async function addRemote(
@@ -60,15 +92,15 @@ export async function addRemote(
   name: string,
   url: string
 ): Promise<void> {
-  return invoke("add_remote", { path, name, url });
+  return invokeGit("add_remote", { path, name, url });
 }
 
 export async function verifyRemoteUrl(url: string): Promise<void> {
-  return invoke("verify_remote_url", { url });
+  return invokeGit("verify_remote_url", { url });
 }
 
 export async function removeRemote(path: string, name: string): Promise<void> {
-  return invoke("remove_remote", { path, name });
+  return invokeGit("remove_remote", { path, name });
 }
 
 export async function gitPush(
@@ -77,7 +109,7 @@ export async function gitPush(
   branch: string,
   force: boolean = false
 ): Promise<string> {
-  return invoke("git_push", { path, remote, branch, force });
+  return invokeGit("git_push", { path, remote, branch, force });
 }
 
 export async function gitPull(
@@ -85,14 +117,14 @@ export async function gitPull(
   remote: string,
   branch: string
 ): Promise<string> {
-  return invoke("git_pull", { path, remote, branch });
+  return invokeGit("git_pull", { path, remote, branch });
 }
 
 export async function gitFetch(
   path: string,
   remote?: string
 ): Promise<string> {
-  return invoke("git_fetch", { path, remote });
+  return invokeGit("git_fetch", { path, remote });
 }
 
 export async function syncToRemote(
@@ -102,7 +134,7 @@ export async function syncToRemote(
   syncAllBranches: boolean,
   force: boolean = false
 ): Promise<string> {
-  return invoke("sync_to_remote", {
+  return invokeGit("sync_to_remote", {
     path,
     sourceRemote,
     targetRemote,
@@ -113,9 +145,10 @@ export async function syncToRemote(
 
 export async function checkoutBranch(
   path: string,
-  branch: string
-): Promise<string> {
-  return invoke("checkout_branch", { path, branch });
+  branch: string,
+  autoStash: boolean = false
+): Promise<CheckoutResult> {
+  return invokeGit("checkout_branch", { path, branch, autoStash });
 }
 
 export async function createBranch(
@@ -123,28 +156,28 @@ export async function createBranch(
   branch: string,
   checkout: boolean = true
 ): Promise<string> {
-  return invoke("create_branch", { path, branch, checkout });
+  return invokeGit("create_branch", { path, branch, checkout });
 }
 
 export async function gitAdd(
   path: string,
   files: string[] = []
 ): Promise<string> {
-  return invoke("git_add", { path, files });
+  return invokeGit("git_add", { path, files });
 }
 
 export async function gitUnstage(
   path: string,
   files: string[] = []
 ): Promise<string> {
-  return invoke("git_unstage", { path, files });
+  return invokeGit("git_unstage", { path, files });
 }
 
 export async function gitCommit(
   path: string,
   message: string
 ): Promise<string> {
-  return invoke("git_commit", { path, message });
+  return invokeGit("git_commit", { path, message });
 }
 
 export async function gitAddAndCommit(
@@ -152,13 +185,22 @@ export async function gitAddAndCommit(
   files: string[],
   message: string
 ): Promise<string> {
-  return invoke("git_add_and_commit", { path, files, message });
+  return invokeGit("git_add_and_commit", { path, files, message });
 }
 
 export async function isGitRepo(path: string): Promise<boolean> {
-  return invoke("is_git_repo", { path });
+  return invokeGit("is_git_repo", { path });
 }
 
 export async function gitInit(path: string): Promise<string> {
-  return invoke("git_init", { path });
+  return invokeGit("git_init", { path });
+}
+
+/** 克隆到 targetDir/repoName，返回克隆后的目录 */
+export async function gitClone(
+  url: string,
+  targetDir: string,
+  repoName: string
+): Promise<string> {
+  return invokeGit("git_clone", { url, targetDir, repoName });
 }
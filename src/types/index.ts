@@ -75,6 +75,35 @@ export interface RemoteInfo {
   pushUrl?: string;
 }
 
+// Git 命令失败时后端返回的错误
+export type GitErrorCode =
+  | "notARepo"
+  | "noUpstream"
+  | "authRequired"
+  | "mergeConflict"
+  | "networkUnreachable"
+  | "dirtyWorktree"
+  | "detachedHead"
+  | "gitNotInstalled"
+  | "failed";
+
+export interface GitErrorPayload {
+  code: GitErrorCode;
+  message: string;
+  detail?: string;
+}
+
+export interface StashApplyResult {
+  /** 有冲突的文件，为空表示应用成功 */
+  conflicts: string[];
+  dropped: boolean;
+}
+
+export interface CheckoutResult {
+  output: string;
+  autoStash?: StashApplyResult;
+}
+
 export interface GitRepo {
   path: string;
   name: string;